@@ -0,0 +1,14 @@
+#![no_main]
+
+use cli_wrapped::parser::{parse_stats_log_line, parse_zsh_line, ParseMode};
+use libfuzzer_sys::fuzz_target;
+
+// History files aren't guaranteed to be UTF-8, so decode the same way
+// history::get_history_entries does
+fuzz_target!(|data: &[u8]| {
+    let line = String::from_utf8_lossy(data);
+    for mode in [ParseMode::Tolerant, ParseMode::Strict] {
+        let _ = parse_zsh_line(&line, mode);
+        let _ = parse_stats_log_line(&line, mode);
+    }
+});
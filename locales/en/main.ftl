@@ -0,0 +1,292 @@
+# English UI strings. To translate, copy this file to locales/<lang>/main.ftl
+# (e.g. locales/de/main.ftl), translate the values and list the new file in
+# BUNDLED in src/i18n.rs. Messages missing from a translation fall back to
+# English. To try a translation without rebuilding, save it as
+# ~/.config/cli-tracker/locales/<lang>.ftl and set CLI_TRACKER_LANG=<lang>.
+
+## Shared
+
+terminal-too-small = Terminal too small. Please resize to at least { $width }x{ $height }
+app-title = CLI Wrapped
+app-title-view = CLI Wrapped: { $view }
+commands-count = commands: { $count }
+
+## Stats and Today views
+
+stats-controls = <?: help, ←/→, w/m/y/p: step, r: range, tab: panel, s: save, q: exit>
+commands-hint = {" "}↑/↓, enter: show runs{" "}
+today-controls = <?: help, ←/h, →/l, tab: panel, s: save, esc/q: exit>
+view-all-time = All-time Stats
+view-all-time-approximate = All-time Stats (approximate)
+view-week = Week { $week } [{ $month }]
+view-month = { $month } { $year }
+view-year = { $year }
+view-quarter = Q{ $quarter } { $year }
+view-fiscal-quarter = FY{ $year } Q{ $quarter }
+box-general = General Statistics
+stat-today = Today
+stat-this-week = This week
+stat-this-month = This month
+stat-weekly-average = Weekly average
+stat-per-day = Commands per day
+stat-unique = Unique commands
+stat-project-switches = Project switches
+stat-streak = Streak
+streak-value = { $current } days, best { $longest }
+trend-vs-average = { $trend } vs { $weeks }-wk avg
+panel-categories = Command Categories
+panel-pipeline-tools = Pipeline Tools
+panel-edited-types = Edited File Types
+panel-edited-areas = Most Edited Areas
+panel-contexts = Contexts
+panel-projects = Projects
+box-directories = Most Used Directories
+box-commands = Most Used Commands
+box-time-patterns = Time Patterns
+box-goals = Goals for Week { $week }
+peak-hour = Peak hour: { $hour } ({ $count } commands)
+peak-hour-none = Peak hour: None
+peak-day = Peak day: { $day } ({ $count } commands)
+peak-day-none = Peak day: None
+low-data = low data
+day-distribution = Day distribution:
+
+## Date range picker
+
+range-title = Date Range
+range-all-time = All time
+range-today = Today
+range-yesterday = Yesterday
+range-last-7-days = Last 7 days
+range-last-30-days = Last 30 days
+range-this-week = This week
+range-last-week = Last week
+range-this-month = This month
+range-last-month = Last month
+range-commands = { $range }: { $count } commands
+range-empty = No commands in this range
+range-controls-presets = ↑/↓: preset, tab: calendar, enter: apply, esc: cancel
+range-controls-calendar = arrows: day, [/]: month, space: mark, enter: apply
+
+## History list and detail view
+
+list-title = Command History
+list-controls = (?: help, ↑/↓/PgUp/PgDn/g/G: move, :N: go to, Enter: details, Space: preview, /: search, b: pin, d: delete, u: unique, v: split, c: columns, r: range, t: time, s: save, q: quit)
+favorite-pinned = Pinned; `cli-wrapped favorites` lists pins
+favorite-unpinned = Unpinned
+goto-prompt = Go to entry :{ $number }  (Enter: go, Esc: cancel)
+goto-missing = No such entry; the list has 1 to { $count }
+delete-confirm = Delete this command from the stats log when you quit? (y/n)
+delete-marked = Marked for deletion (U: undo)
+delete-undone = Deletion undone
+delete-none = Nothing to undo
+delete-not-stats-log = Only the stats log can be deleted from; this is another program's history
+vocabulary-title = Unique Commands ({ $count })
+vocabulary-controls = (?: help, ↑/↓: move, 1-5: sort, Enter: runs, q: back)
+vocabulary-count = Count
+vocabulary-first = First
+vocabulary-last = Last
+vocabulary-trend = Trend
+vocabulary-command = Command
+search-prompt = /{ $query }  ({ $count } matches, Enter: select, Esc: cancel)
+search-active = /{ $query }  (n/N: next/previous match, Esc: clear)
+history-count = history count: { $count }
+detail-controls = ?: help, <esc>: back, ↑/↓: navigate, t: time, g: timeline, s: save
+detail-no-previous = No previous command
+detail-no-next = No next command
+detail-previous = Previous command
+detail-command = Command
+detail-next = Next command
+detail-stats = Command stats
+detail-history-number = History number
+detail-user = User
+detail-user-hidden = hidden
+detail-host = Host
+detail-time = Time
+detail-time-estimated = { $time } (estimated)
+detail-directory = Directory
+detail-total-runs = Total runs
+detail-recent-runs = Recent runs
+detail-first-used = First used
+detail-last-used = Last used
+detail-since-last = Since last use
+detail-today = today
+detail-days-ago = { $days ->
+    [one] 1 day
+   *[other] { $days } days
+}
+detail-per-week = Per active week
+detail-what = What it is
+detail-duration = Duration
+detail-exit-code = Exit code
+detail-commit-before = Commit before
+detail-commit-after = Commit after
+detail-similar = Similar commands
+similar-spelling = spelled alike
+similar-arguments = same arguments
+similar-together = run together
+detail-directories = Where you run this
+detail-no-directories = No directories recorded
+detail-frequency = Command frequency by hour
+detail-hours = [Hours]
+detail-peak-times = Peak times: { $hours }
+detail-usage = Command usage over time
+detail-most-active = Most active: { $day } ({ $count } times)
+detail-back = Press ESC to go back to command list
+
+## First-run tour
+
+tour-title = Welcome to CLI Wrapped ({ $step }/{ $steps })
+tour-controls = →/enter: next, ←: back, esc: skip
+tour-detail =
+    This is the detail view for your latest command.
+    ↑/↓ step through your history one command at a time.
+    t switches between dates and "2h ago", g charts the
+    command over its whole lifetime, and esc opens the list.
+tour-list =
+    The list view shows your history around the selection.
+    ↑/↓ or j/k move, enter opens the details, q quits.
+tour-stats =
+    For the bigger picture, run `cli-wrapped stats`.
+    ←/→ move between weeks, p switches to quarters and tab
+    changes the side panel. `cli-wrapped today` shows today.
+timeline-back = Press any key to go back
+hook-stale-banner = No commands logged for { $hours }h while { $shell } history kept growing. Run `cli-wrapped doctor`.
+preview-title = Command
+preview-close = any key: close
+
+## Help overlay, opened with ?
+
+help-title = Keys
+help-step-period = Earlier or later period, all time after the latest
+help-step-week-month-year = Step by weeks, months or years
+help-quarters = Switch between weeks and quarters
+help-range = Pick a date range
+help-panel = Next side panel
+help-select-command = Select a command in Most Used Commands
+help-command-runs = List every run of the command
+help-step-week = Earlier or later week, all time after this one
+help-move = Move the selection
+help-page = Up or down a page
+help-half-page = Up or down half a page
+help-first-last = First or last entry
+help-go-to = Go to entry number N
+help-details = Open the command's details
+help-preview = Show a cut-off command in full
+help-search = Search; enter keeps the matches, esc drops them
+help-next-match = Next or previous match
+help-pin = Pin or unpin the command
+help-delete = Delete the entry
+help-undo-delete = Undo the last delete
+help-unique = Unique commands
+help-split = Details beside the list
+help-columns = Time, time and directory, or no columns before the command
+help-time = Dates or times ago
+help-detail-step = Newer or older command
+help-timeline = Chart the command's lifetime
+help-sort = Sort by a column, again to reverse
+help-heatmap-week = Earlier or later week
+help-heatmap-day = Earlier or later day
+help-heatmap-list = List the day's commands
+help-next-page = Next page
+help-previous-page = Previous page
+help-save = Save the view as Markdown or JSON
+help-screens = History, stats or today
+help-help = This help
+help-back = Back, or quit from the first screen
+help-back-to-list = Back to the list
+help-quit = Quit
+
+## Saving a view
+
+snapshot-saved = Saved to { $path }
+snapshot-failed = Couldn't save: { $error }
+snapshot-peak-hour = Peak hour
+snapshot-peak-day = Peak day
+
+## Dates
+
+# chrono strftime pattern for absolute timestamps
+timestamp-format = %b %d %Y at %I:%M %P
+# Shorter pattern for the history list
+timestamp-format-compact = %m/%d %H:%M
+timestamp-unavailable = Timestamp not available
+timestamp-invalid = Invalid timestamp
+
+relative-just-now = just now
+relative-minutes = { $count }m ago
+relative-hours = { $count }h ago
+relative-days = { $count }d ago
+relative-weeks = { $count }w ago
+relative-months = { $count }mo ago
+relative-years = { $count }y ago
+
+weekday-mon = Monday
+weekday-tue = Tuesday
+weekday-wed = Wednesday
+weekday-thu = Thursday
+weekday-fri = Friday
+weekday-sat = Saturday
+weekday-sun = Sunday
+weekday-initial-mon = M
+weekday-initial-tue = T
+weekday-initial-wed = W
+weekday-initial-thu = T
+weekday-initial-fri = F
+weekday-initial-sat = S
+weekday-initial-sun = S
+
+month-jan = Jan
+month-feb = Feb
+month-mar = Mar
+month-apr = Apr
+month-may = May
+month-jun = Jun
+month-jul = Jul
+month-aug = Aug
+month-sep = Sep
+month-oct = Oct
+month-nov = Nov
+month-dec = Dec
+
+heatmap-title = Commands in the last year: { $total }
+heatmap-day = { $date }: { $count } commands
+heatmap-controls = ?: help, ←/→: week, ↑/↓: day, enter: list the day, q: quit
+heatmap-less = Less
+heatmap-more = More
+
+## Timeline, from g in the detail view or `cli-wrapped timeline`
+
+timeline-summary = { $query }: { $count } runs from { $first } to { $last } (one { $bucket } per column)
+timeline-bucket-day = day
+timeline-bucket-week = week
+timeline-bucket-days = { $days } days
+timeline-bucket-of = { $bucket } of { $start }
+timeline-first-used = First used
+timeline-peak = Peak
+timeline-peak-value = { $peak } ({ $count } runs)
+timeline-since-peak = Since peak
+timeline-since-peak-value = { $average } runs a { $bucket }, { $share }% of peak
+timeline-still-at-peak = still at its peak
+timeline-last-used = Last used
+timeline-gaps = Longest gaps
+timeline-gap = { $number }. { $days } days, { $from } to { $to }
+timeline-no-runs = No timestamped runs of '{ $query }'.
+
+## Wrapped
+wrapped-intro = Your { $year } in the terminal
+wrapped-total = commands run
+wrapped-unique = { $count } different commands
+wrapped-top = Your top commands
+wrapped-top-runs = runs of { $command }, your number one
+wrapped-busiest-day = Your busiest day
+wrapped-busiest-day-on = commands on { $date }
+wrapped-peak-hour = Your peak hour
+wrapped-peak-hour-at = commands between { $from } and { $to }
+wrapped-new-tools = New tools
+wrapped-new-tools-count = programs you ran for the first time
+wrapped-new-tools-unknown = Your history starts in { $year }, so every tool is new
+wrapped-streak = Your longest streak
+wrapped-streak-days = days in a row with commands
+wrapped-streak-span = { $from } to { $to }
+wrapped-controls = ?: help, ←/→: page, q: quit
@@ -0,0 +1,1329 @@
+use chrono::{DateTime, Datelike, Days, IsoWeek, Local, Months, NaiveDate, TimeZone, Timelike};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use crate::categories::categories;
+use crate::config::config;
+use crate::days::{day_counts, local_day};
+use crate::history::{repository_root, HistoryEntry};
+use crate::normalize;
+use crate::time_format::local_midnight;
+
+// Headline counts and rankings for a set of entries
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatsSummary {
+    pub total: usize,
+    pub with_timestamps: usize,
+    pub unique: usize,
+    pub oldest: i64,
+    pub newest: i64,
+    pub today: usize,
+    pub this_week: usize,
+    pub this_month: usize,
+    pub categories: Vec<(String, usize)>,
+    // Commands run on the right-hand side of a pipe, e.g. grep in `ps | grep`
+    pub pipeline_tools: Vec<(String, usize)>,
+    // Files opened with an editor, by extension (or file name when there is
+    // none) and by the directory they live in
+    pub edited_extensions: Vec<(String, usize)>,
+    pub edited_areas: Vec<(String, usize)>,
+    // Commands by the context label the hook recorded, if any
+    pub contexts: Vec<(String, usize)>,
+    pub directories: Vec<(String, usize)>,
+    // Directories gathered up by the git repository they're in
+    pub projects: Vec<(String, usize)>,
+    pub commands: Vec<(String, usize)>,
+}
+
+// When commands were run, by hour of day and by day of week (Monday first)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimePatterns {
+    pub hour_counts: [usize; 24],
+    pub day_of_week_counts: [usize; 7],
+}
+
+// Timestamp ranges used for the Today / This week / This month counts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeriodBounds {
+    pub today_start: i64,
+    pub week: (i64, i64),
+    pub month: (i64, i64),
+}
+
+// Below this many commands (or timestamped ones, for when they ran) a
+// view's shares and trends say more about chance than habit
+const SPARSE_COMMANDS: usize = 20;
+
+// How far a view's numbers can be trusted. The stats and today screens ask
+// this before showing shares, time patterns or trends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataQuality {
+    Enough,
+    FewCommands,
+    // Enough commands, but too few with a timestamp
+    FewTimestamps,
+}
+
+impl DataQuality {
+    pub fn of(total: usize, with_timestamps: usize) -> DataQuality {
+        if total < SPARSE_COMMANDS {
+            DataQuality::FewCommands
+        } else if with_timestamps < SPARSE_COMMANDS {
+            DataQuality::FewTimestamps
+        } else {
+            DataQuality::Enough
+        }
+    }
+
+    // Percentages of the view's commands, as in the ranking panel
+    pub fn shares_reliable(self) -> bool {
+        self != DataQuality::FewCommands
+    }
+
+    // When commands ran: the time patterns and trends
+    pub fn timing_reliable(self) -> bool {
+        self == DataQuality::Enough
+    }
+}
+
+impl StatsSummary {
+    pub fn quality(&self) -> DataQuality {
+        DataQuality::of(self.total, self.with_timestamps)
+    }
+
+    // Days between the oldest and newest timestamped entries, inclusive.
+    // Entries without timestamps are assumed to cover at least one day.
+    pub fn days(&self) -> i64 {
+        if self.newest > 0 && self.oldest > 0 {
+            ((self.newest - self.oldest) / 86400) + 1
+        } else if self.total > 0 {
+            1
+        } else {
+            0
+        }
+    }
+
+    // Timestamped commands per week since the first command
+    pub fn weekly_average(&self) -> f64 {
+        let days = self.days();
+        if days == 0 {
+            return 0.0;
+        }
+        let weeks = (days as f64 / 7.0).ceil().max(1.0);
+        self.with_timestamps as f64 / weeks
+    }
+
+    pub fn per_day(&self) -> f64 {
+        let days = self.days();
+        if days == 0 {
+            return 0.0;
+        }
+        self.total as f64 / days as f64
+    }
+
+    // Share of all commands taken by a count, as a whole percentage
+    pub fn percentage(&self, count: usize) -> usize {
+        if self.total == 0 {
+            0
+        } else {
+            (count as f64 / self.total as f64 * 100.0) as usize
+        }
+    }
+
+    // Scale counts taken from a sample up to the size of the full history.
+    // Unique commands can't be extrapolated, so that stays a lower bound.
+    pub fn scaled(mut self, factor: f64) -> Self {
+        let scale = |n: usize| (n as f64 * factor).round() as usize;
+        self.total = scale(self.total);
+        self.with_timestamps = scale(self.with_timestamps);
+        self.today = scale(self.today);
+        self.this_week = scale(self.this_week);
+        self.this_month = scale(self.this_month);
+        for (_, count) in self
+            .categories
+            .iter_mut()
+            .chain(self.pipeline_tools.iter_mut())
+            .chain(self.edited_extensions.iter_mut())
+            .chain(self.edited_areas.iter_mut())
+            .chain(self.contexts.iter_mut())
+            .chain(self.directories.iter_mut())
+            .chain(self.projects.iter_mut())
+            .chain(self.commands.iter_mut())
+        {
+            *count = scale(*count);
+        }
+        self
+    }
+}
+
+impl TimePatterns {
+    // Busiest hour of day as (hour, count), if anything was timestamped
+    pub fn peak_hour(&self) -> Option<(usize, usize)> {
+        peak(&self.hour_counts)
+    }
+
+    // Busiest day of week as (index from Monday, count)
+    pub fn peak_day(&self) -> Option<(usize, usize)> {
+        peak(&self.day_of_week_counts)
+    }
+
+    // Day of week shares as whole percentages that always add up to 100
+    pub fn day_percentages(&self) -> [i32; 7] {
+        let mut percentages = [0; 7];
+        let total: usize = self.day_of_week_counts.iter().sum();
+        if total == 0 {
+            return percentages;
+        }
+
+        let mut float_percentages = [0.0; 7];
+        let mut sum = 0;
+        for (i, &count) in self.day_of_week_counts.iter().enumerate() {
+            let pct = (count as f64 / total as f64) * 100.0;
+            float_percentages[i] = pct;
+            percentages[i] = pct.round() as i32;
+            sum += percentages[i];
+        }
+
+        // Push the rounding error onto the day with the largest (or, when
+        // over 100, smallest) remainder
+        let diffs = float_percentages
+            .iter()
+            .enumerate()
+            .map(|(i, &f)| (i, f - percentages[i] as f64));
+        if sum < 100 {
+            if let Some((i, _)) = diffs.rev().max_by(|a, b| a.1.total_cmp(&b.1)) {
+                percentages[i] += 100 - sum;
+            }
+        } else if sum > 100 {
+            if let Some((i, _)) = diffs.min_by(|a, b| a.1.total_cmp(&b.1)) {
+                percentages[i] -= sum - 100;
+            }
+        }
+        percentages
+    }
+
+    pub fn scaled(mut self, factor: f64) -> Self {
+        let scale = |n: usize| (n as f64 * factor).round() as usize;
+        for count in self
+            .hour_counts
+            .iter_mut()
+            .chain(self.day_of_week_counts.iter_mut())
+        {
+            *count = scale(*count);
+        }
+        self
+    }
+}
+
+// Index and value of the largest non-zero count, preferring the earliest
+fn peak(counts: &[usize]) -> Option<(usize, usize)> {
+    counts
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(_, count)| count > 0)
+        .fold(
+            None,
+            |best: Option<(usize, usize)>, (i, count)| match best {
+                Some((_, best_count)) if best_count >= count => best,
+                _ => Some((i, count)),
+            },
+        )
+}
+
+pub fn count_between<'a>(
+    entries: impl Iterator<Item = &'a HistoryEntry>,
+    start: i64,
+    end: i64,
+) -> usize {
+    entries
+        .filter(|e| e.timestamp >= start && e.timestamp <= end)
+        .count()
+}
+
+pub fn sorted_counts<K: ToString>(counts: HashMap<K, usize>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = counts
+        .into_iter()
+        .map(|(key, count)| (key.to_string(), count))
+        .collect();
+    // Ties are broken alphabetically so rankings are stable between runs
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+// The program a command runs, as its first word
+pub fn program(command: &str) -> &str {
+    command.split_whitespace().next().unwrap_or("other")
+}
+
+// The category a command's program is in, built in or from the config, or
+// else the program itself
+pub fn category(command: &str) -> &str {
+    categories()
+        .of(command)
+        .map_or_else(|| program(command), |(name, _)| name)
+}
+
+// First program named in a pipeline segment, skipping `VAR=value` prefixes
+fn segment_program(segment: &str) -> Option<&str> {
+    let end = segment.find(['|', ';', '&', ')']).unwrap_or(segment.len());
+    segment[..end]
+        .split_whitespace()
+        .find(|word| !word.contains('='))
+}
+
+// Tools that receive piped input, in order. Pipes inside quotes and `||`
+// are ignored.
+pub fn pipeline_tools(command: &str) -> Vec<&str> {
+    let mut tools = Vec::new();
+    let mut quote = None;
+    let mut chars = command.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '|') => {
+                match chars.peek() {
+                    Some(&(_, '|')) => {
+                        chars.next();
+                        continue;
+                    }
+                    // `|&` pipes stderr as well
+                    Some(&(_, '&')) => {
+                        chars.next();
+                    }
+                    _ => {}
+                }
+                let start = chars.peek().map_or(command.len(), |&(i, _)| i);
+                tools.extend(segment_program(&command[start..]));
+            }
+            _ => {}
+        }
+    }
+    tools
+}
+
+pub(crate) const EDITORS: &[&str] = &[
+    "vim", "nvim", "vi", "code", "nano", "emacs", "hx", "helix", "micro", "subl", "kak",
+];
+
+// Whether a command opens an editor, with or without `sudo`
+pub fn is_editor_command(command: &str) -> bool {
+    let mut words = command.split_whitespace();
+    let program = match words.next() {
+        Some("sudo") => words.next(),
+        program => program,
+    };
+    program
+        .map(|program| program.rsplit('/').next().unwrap_or(program))
+        .is_some_and(|program| EDITORS.contains(&program))
+}
+
+// Paths passed to an editor, e.g. `src/main.rs` in `nvim -p src/main.rs`.
+// Options and `+line` arguments are skipped, as is a leading `sudo`.
+pub fn edited_paths(command: &str) -> Vec<&str> {
+    if !is_editor_command(command) {
+        return Vec::new();
+    }
+
+    let mut words = command.split_whitespace();
+    if command.starts_with("sudo ") {
+        words.next();
+    }
+    words
+        .skip(1)
+        .take_while(|word| !matches!(*word, "|" | ";" | ">" | "2>" | "&"))
+        .filter(|word| !word.starts_with('-') && !word.starts_with('+'))
+        .map(|word| word.trim_matches(['\'', '"']))
+        .map(strip_line_number)
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+// `code -g file:12:3` jumps to a line and column
+fn strip_line_number(mut path: &str) -> &str {
+    while let Some((head, tail)) = path.rsplit_once(':') {
+        if tail.is_empty() || !tail.chars().all(|c| c.is_ascii_digit()) {
+            break;
+        }
+        path = head;
+    }
+    path
+}
+
+// Opening `.` or `src/` edits the directory itself rather than a file
+fn is_directory_arg(path: &str) -> bool {
+    path.ends_with('/')
+        || matches!(
+            Path::new(path).components().next_back(),
+            Some(Component::CurDir | Component::ParentDir)
+        )
+}
+
+// Extension of an edited file, or its name when it has none (`Makefile`)
+fn edited_extension(path: &str) -> Option<String> {
+    if is_directory_arg(path) {
+        return None;
+    }
+    let path = Path::new(path);
+    path.extension()
+        .or_else(|| path.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+// Directory an edited path lives in, resolved against where the editor ran
+fn edited_area(directory: Option<&str>, path: &str) -> String {
+    let full = match directory {
+        Some(dir) if Path::new(path).is_relative() && !path.starts_with('~') => {
+            Path::new(dir).join(path)
+        }
+        _ => PathBuf::from(path),
+    };
+    let full: PathBuf = full
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+    let area = if is_directory_arg(path) {
+        full.as_path()
+    } else {
+        full.parent().unwrap_or(&full)
+    };
+
+    if area.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        area.to_string_lossy().into_owned()
+    }
+}
+
+// The git repository each directory of a history is in, looked up once when
+// the history loads so that summarizing never touches the disk
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProjectRoots {
+    roots: HashMap<String, String>,
+}
+
+impl ProjectRoots {
+    pub fn resolve<'a>(entries: impl IntoIterator<Item = &'a HistoryEntry>) -> ProjectRoots {
+        let mut roots = HashMap::new();
+        let mut checked = HashSet::new();
+        for directory in entries.into_iter().filter_map(|e| e.directory.as_deref()) {
+            if !checked.insert(directory) {
+                continue;
+            }
+            if let Some(root) = repository_root(Path::new(directory)) {
+                roots.insert(directory.to_string(), root.display().to_string());
+            }
+        }
+        ProjectRoots { roots }
+    }
+
+    pub fn root(&self, directory: &str) -> Option<&str> {
+        self.roots.get(directory).map(String::as_str)
+    }
+}
+
+pub fn summarize(
+    entries: &[&HistoryEntry],
+    bounds: &PeriodBounds,
+    roots: &ProjectRoots,
+) -> StatsSummary {
+    let mut categories: HashMap<&str, usize> = HashMap::new();
+    let mut tools: HashMap<&str, usize> = HashMap::new();
+    let mut extensions: HashMap<String, usize> = HashMap::new();
+    let mut areas: HashMap<String, usize> = HashMap::new();
+    let mut contexts: HashMap<&str, usize> = HashMap::new();
+    let mut directories: HashMap<&str, usize> = HashMap::new();
+    let mut commands: HashMap<Cow<str>, usize> = HashMap::new();
+    let rules = normalize::rules();
+
+    for entry in entries {
+        *categories.entry(category(&entry.command)).or_insert(0) += 1;
+        for tool in pipeline_tools(&entry.command) {
+            *tools.entry(tool).or_insert(0) += 1;
+        }
+        for path in edited_paths(&entry.command) {
+            if let Some(extension) = edited_extension(path) {
+                *extensions.entry(extension).or_insert(0) += 1;
+            }
+            let area = edited_area(entry.directory.as_deref(), path);
+            *areas.entry(area).or_insert(0) += 1;
+        }
+        if let Some(dir) = &entry.directory {
+            *directories.entry(dir).or_insert(0) += 1;
+        }
+        if let Some(context) = &entry.context {
+            *contexts.entry(context).or_insert(0) += 1;
+        }
+        *commands.entry(rules.apply(&entry.command)).or_insert(0) += 1;
+    }
+
+    let timestamps = || entries.iter().map(|e| e.timestamp).filter(|&ts| ts > 0);
+    let directories = sorted_counts(directories);
+
+    StatsSummary {
+        total: entries.len(),
+        with_timestamps: timestamps().count(),
+        unique: commands.len(),
+        oldest: timestamps().min().unwrap_or(0),
+        newest: timestamps().max().unwrap_or(0),
+        today: count_between(entries.iter().copied(), bounds.today_start, i64::MAX),
+        this_week: count_between(entries.iter().copied(), bounds.week.0, bounds.week.1),
+        this_month: count_between(entries.iter().copied(), bounds.month.0, bounds.month.1),
+        categories: sorted_counts(categories),
+        pipeline_tools: sorted_counts(tools),
+        edited_extensions: sorted_counts(extensions),
+        edited_areas: sorted_counts(areas),
+        contexts: sorted_counts(contexts),
+        projects: project_counts(&directories, roots),
+        directories,
+        commands: sorted_counts(commands),
+    }
+}
+
+// Directory counts summed by the git repository each directory is in.
+// Directories outside any repository, or no longer there, are left out.
+fn project_counts(directories: &[(String, usize)], roots: &ProjectRoots) -> Vec<(String, usize)> {
+    let mut projects: HashMap<&str, usize> = HashMap::new();
+    for (directory, count) in directories {
+        if let Some(root) = roots.root(directory) {
+            *projects.entry(root).or_insert(0) += count;
+        }
+    }
+    sorted_counts(projects)
+}
+
+pub fn time_patterns(entries: &[&HistoryEntry]) -> TimePatterns {
+    let mut patterns = TimePatterns::default();
+    for entry in entries.iter().filter(|e| e.timestamp > 0) {
+        if let chrono::LocalResult::Single(dt) = Local.timestamp_opt(entry.timestamp, 0) {
+            patterns.hour_counts[dt.hour() as usize] += 1;
+            patterns.day_of_week_counts[dt.weekday().num_days_from_monday() as usize] += 1;
+        }
+    }
+    patterns
+}
+
+// Start and end of the week `week_offset` weeks before the one containing `now`
+pub fn week_bounds(now: DateTime<Local>, week_offset: i64) -> (i64, i64) {
+    let days_since_monday = now.weekday().num_days_from_monday() as i64;
+    let start_of_week = now
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_local_timezone(chrono::Local)
+        .unwrap()
+        - chrono::Duration::days(days_since_monday)
+        - chrono::Duration::days(7 * week_offset);
+
+    // End of week is start of next week minus 1 second
+    let end_of_week = start_of_week + chrono::Duration::days(7) - chrono::Duration::seconds(1);
+
+    (start_of_week.timestamp(), end_of_week.timestamp())
+}
+
+// Start and end of the month containing the given day
+pub fn month_bounds(day: DateTime<Local>) -> (i64, i64) {
+    let start_of_month = day
+        .with_day(1)
+        .unwrap()
+        .with_hour(0)
+        .unwrap()
+        .with_minute(0)
+        .unwrap()
+        .with_second(0)
+        .unwrap();
+
+    // End of month is start of next month minus 1 second
+    let next_month = if start_of_month.month() == 12 {
+        start_of_month
+            .with_month(1)
+            .unwrap()
+            .with_year(start_of_month.year() + 1)
+            .unwrap()
+    } else {
+        start_of_month
+            .with_month(start_of_month.month() + 1)
+            .unwrap()
+    };
+
+    let end_of_month = next_month - chrono::Duration::seconds(1);
+
+    (start_of_month.timestamp(), end_of_month.timestamp())
+}
+
+// Period bounds for a view: the selected week and its month, or the current
+// week and month when `week_offset` is negative (the all-time view)
+pub fn period_bounds(now: DateTime<Local>, week_offset: i64) -> PeriodBounds {
+    let week_offset = week_offset.max(0);
+    let days_since_monday = now.weekday().num_days_from_monday() as i64;
+    let selected_week_day =
+        now - chrono::Duration::days(days_since_monday) - chrono::Duration::days(7 * week_offset);
+
+    PeriodBounds {
+        today_start: now
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_local_timezone(chrono::Local)
+            .unwrap()
+            .timestamp(),
+        week: week_bounds(now, week_offset),
+        month: month_bounds(selected_week_day),
+    }
+}
+
+// A quarter of a fiscal year. Fiscal years starting in any month but
+// January are named for the calendar year they end in, so with an April
+// start FY2025 runs from April 2024 to March 2025.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Quarter {
+    pub year: i32,
+    // 1 to 4
+    pub quarter: u32,
+}
+
+impl Quarter {
+    // The quarter containing `day` for a fiscal year starting in month
+    // `fiscal_start` (1 = January)
+    pub fn containing(day: NaiveDate, fiscal_start: u32) -> Quarter {
+        let months_in = (day.month() + 12 - fiscal_start) % 12;
+        let year = if fiscal_start > 1 && day.month() >= fiscal_start {
+            day.year() + 1
+        } else {
+            day.year()
+        };
+        Quarter {
+            year,
+            quarter: months_in / 3 + 1,
+        }
+    }
+
+    // The quarter `quarters` before this one
+    pub fn back(self, quarters: i64) -> Quarter {
+        let index = self.year as i64 * 4 + (self.quarter as i64 - 1) - quarters;
+        Quarter {
+            year: index.div_euclid(4) as i32,
+            quarter: index.rem_euclid(4) as u32 + 1,
+        }
+    }
+
+    pub fn first_day(self, fiscal_start: u32) -> NaiveDate {
+        let start_year = if fiscal_start > 1 {
+            self.year - 1
+        } else {
+            self.year
+        };
+        let month0 = (fiscal_start - 1) + (self.quarter - 1) * 3;
+        NaiveDate::from_ymd_opt(start_year + (month0 / 12) as i32, month0 % 12 + 1, 1).unwrap()
+    }
+
+    // Start and end of the quarter as local timestamps
+    pub fn bounds(self, fiscal_start: u32) -> (i64, i64) {
+        let start = local_midnight(self.first_day(fiscal_start));
+        let end = local_midnight(self.back(-1).first_day(fiscal_start)) - 1;
+        (start, end)
+    }
+}
+
+impl fmt::Display for Quarter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "q{}-{}", self.quarter, self.year)
+    }
+}
+
+// `q3-2024`, as given to --period
+impl FromStr for Quarter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected a quarter like q3-2024, got '{}'", s);
+        let (quarter, year) = s
+            .strip_prefix(['q', 'Q'])
+            .and_then(|rest| rest.split_once('-'))
+            .ok_or_else(invalid)?;
+        let quarter: u32 = quarter.parse().map_err(|_| invalid())?;
+        let year: i32 = year.parse().map_err(|_| invalid())?;
+        if !(1..=4).contains(&quarter) {
+            return Err(invalid());
+        }
+        Ok(Quarter { year, quarter })
+    }
+}
+
+// A calendar week (from Monday), month or year, for the `week`, `month` and
+// `year` commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalendarPeriod {
+    Week,
+    Month,
+    Year,
+}
+
+impl CalendarPeriod {
+    // First day of the period `offset` periods before the one containing
+    // `today`
+    pub fn first_day(self, today: NaiveDate, offset: u32) -> NaiveDate {
+        match self {
+            CalendarPeriod::Week => {
+                let monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+                monday - Days::new(7 * offset as u64)
+            }
+            CalendarPeriod::Month => today.with_day(1).unwrap() - Months::new(offset),
+            CalendarPeriod::Year => {
+                NaiveDate::from_ymd_opt(today.year() - offset as i32, 1, 1).unwrap()
+            }
+        }
+    }
+
+    // Start and end of that period as local timestamps
+    pub fn bounds(self, today: NaiveDate, offset: u32) -> (i64, i64) {
+        let first = self.first_day(today, offset);
+        let next = match self {
+            CalendarPeriod::Week => first + Days::new(7),
+            CalendarPeriod::Month => first + Months::new(1),
+            CalendarPeriod::Year => first + Months::new(12),
+        };
+        (local_midnight(first), local_midnight(next) - 1)
+    }
+
+    // `2024-W42`, `2024-10` or `2024`
+    pub fn name(self, today: NaiveDate, offset: u32) -> String {
+        let first = self.first_day(today, offset);
+        match self {
+            CalendarPeriod::Week => {
+                let week = first.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            CalendarPeriod::Month => first.format("%Y-%m").to_string(),
+            CalendarPeriod::Year => first.year().to_string(),
+        }
+    }
+}
+
+// Commands on each day of the month containing `day`, indexed from the 1st
+pub fn month_day_counts<'a>(
+    entries: impl Iterator<Item = &'a HistoryEntry>,
+    day: DateTime<Local>,
+) -> Vec<usize> {
+    let (start, end) = month_bounds(day);
+    let days_in_month = Local.timestamp_opt(end, 0).unwrap().day() as usize;
+    let mut counts = vec![0; days_in_month];
+    let in_month = entries.filter(|e| e.timestamp >= start && e.timestamp <= end);
+    for (day, count) in day_counts(in_month) {
+        counts[day.day0() as usize] += count;
+    }
+    counts
+}
+
+// Weeks before a period that its trend is measured against
+const TREND_WEEKS: i64 = 4;
+
+// The baseline for trends, in weeks. ui.trend_weeks in the config changes it.
+pub fn trend_weeks() -> i64 {
+    config().trend_weeks.unwrap_or(TREND_WEEKS)
+}
+
+// How a figure compares with its average over the weeks before
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trend {
+    // Percentage change from the baseline, rounded
+    pub change: i64,
+    pub weeks: i64,
+}
+
+impl Trend {
+    // None when there's no baseline to compare with
+    pub fn between(current: f64, baseline: f64, weeks: i64) -> Option<Trend> {
+        (baseline > 0.0).then(|| Trend {
+            change: ((current - baseline) * 100.0 / baseline).round() as i64,
+            weeks,
+        })
+    }
+
+    pub fn arrow(&self) -> &'static str {
+        match self.change.cmp(&0) {
+            Ordering::Greater => "↑",
+            Ordering::Less => "↓",
+            Ordering::Equal => "→",
+        }
+    }
+
+    // `↑ 23%`, for where the baseline goes without saying
+    pub fn short(&self) -> String {
+        format!("{} {}%", self.arrow(), self.change.abs())
+    }
+}
+
+// Commands in `period` against the average for the same stretch of the
+// `weeks` weeks before it. A period still under way is compared with the
+// share of the average that had passed by `now`. `count` gives the commands
+// between two timestamps, both included.
+pub fn usage_trend(
+    count: impl Fn(i64, i64) -> usize,
+    (start, end): (i64, i64),
+    now: i64,
+    weeks: i64,
+) -> Option<Trend> {
+    let baseline_start = start - weeks * 7 * 24 * 3600;
+    let baseline = count(baseline_start, start - 1);
+    // Too few before to say what's usual
+    if !DataQuality::of(baseline, baseline).timing_reliable() {
+        return None;
+    }
+    let elapsed = (now.min(end) - start) as f64 / (end - start).max(1) as f64;
+    let usual = baseline as f64 / weeks as f64 * ((end - start) as f64 / (7.0 * 24.0 * 3600.0));
+    Trend::between(count(start, end) as f64, usual * elapsed, weeks)
+}
+
+// Moves between projects today, per hour of activity, next to the same
+// rate over the weeks before
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ContextSwitches {
+    pub today: usize,
+    pub per_hour: f64,
+    pub usual_per_hour: f64,
+    pub weeks: i64,
+}
+
+impl ContextSwitches {
+    pub fn trend(&self) -> Option<Trend> {
+        Trend::between(self.per_hour, self.usual_per_hour, self.weeks)
+    }
+}
+
+// When commands moved to another project. History doesn't know projects,
+// so directories stand in for them: going into a subdirectory stays in the
+// project, and so does going back up, e.g. to ~ on the way somewhere else.
+pub fn project_switches<'a>(entries: impl Iterator<Item = &'a HistoryEntry>) -> Vec<i64> {
+    let mut moves: Vec<(i64, &Path)> = entries
+        .filter(|e| e.timestamp > 0)
+        .filter_map(|e| Some((e.timestamp, Path::new(e.directory.as_deref()?))))
+        .collect();
+    moves.sort_by_key(|&(ts, _)| ts);
+
+    let mut switches = Vec::new();
+    let mut project: Option<&Path> = None;
+    for (ts, dir) in moves {
+        match project {
+            Some(current) if current.starts_with(dir) => {}
+            Some(current) if dir.starts_with(current) => project = Some(dir),
+            Some(_) => {
+                switches.push(ts);
+                project = Some(dir);
+            }
+            None => project = Some(dir),
+        }
+    }
+    switches
+}
+
+pub fn context_switches(entries: &[HistoryEntry], today_start: i64, weeks: i64) -> ContextSwitches {
+    let week_start = today_start - weeks * 7 * 24 * 3600;
+    let recent: Vec<&HistoryEntry> = entries
+        .iter()
+        .filter(|e| e.timestamp >= week_start)
+        .collect();
+    let switches = project_switches(recent.iter().copied());
+
+    // Switches per hour that had any commands in it
+    let rate = |start: i64, end: i64| {
+        let switched = switches
+            .iter()
+            .filter(|&&ts| ts >= start && ts < end)
+            .count();
+        let mut hours: Vec<i64> = recent
+            .iter()
+            .filter(|e| e.timestamp >= start && e.timestamp < end)
+            .map(|e| e.timestamp.div_euclid(3600))
+            .collect();
+        hours.sort_unstable();
+        hours.dedup();
+        (switched, switched as f64 / hours.len().max(1) as f64)
+    };
+    let (today, per_hour) = rate(today_start, i64::MAX);
+    let (_, usual_per_hour) = rate(week_start, today_start);
+    ContextSwitches {
+        today,
+        per_hour,
+        usual_per_hour,
+        weeks,
+    }
+}
+
+// Shade for a calendar cell: 0 for no commands, then 1-3 by thirds of the
+// busiest day's count
+pub fn activity_level(count: usize, max: usize) -> usize {
+    if count == 0 || max == 0 {
+        0
+    } else {
+        (count * 3).div_ceil(max).clamp(1, 3)
+    }
+}
+
+// Where a command has been run, busiest directory first. Runs without a
+// recorded directory are left out.
+pub fn command_directories(entries: &[HistoryEntry], command: &str) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for entry in entries.iter().filter(|e| e.command == command) {
+        if let Some(directory) = &entry.directory {
+            *counts.entry(directory.as_str()).or_insert(0) += 1;
+        }
+    }
+    sorted_counts(counts)
+}
+
+// A command's whole life in the history, from runs with a timestamp
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CommandLifetime {
+    pub first: i64,
+    pub last: i64,
+    pub runs: usize,
+    // ISO weeks with at least one run
+    pub active_weeks: usize,
+}
+
+impl CommandLifetime {
+    pub fn per_active_week(&self) -> f64 {
+        self.runs as f64 / self.active_weeks.max(1) as f64
+    }
+
+    // Whole days from the last run's day to `today`
+    pub fn days_since_last(&self, today: NaiveDate) -> i64 {
+        local_day(self.last).map_or(0, |day| (today - day).num_days().max(0))
+    }
+}
+
+// None when no run of `command` has a timestamp
+pub fn command_lifetime(entries: &[HistoryEntry], command: &str) -> Option<CommandLifetime> {
+    let runs: Vec<&HistoryEntry> = entries
+        .iter()
+        .filter(|e| e.command == command && e.timestamp > 0)
+        .collect();
+    let weeks: HashSet<IsoWeek> = runs
+        .iter()
+        .filter_map(|e| local_day(e.timestamp))
+        .map(|day| day.iso_week())
+        .collect();
+    Some(CommandLifetime {
+        first: runs.iter().map(|e| e.timestamp).min()?,
+        last: runs.iter().map(|e| e.timestamp).max()?,
+        runs: runs.len(),
+        active_weeks: weeks.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: i64, command: &str, directory: Option<&str>) -> HistoryEntry {
+        HistoryEntry {
+            timestamp,
+            command: command.to_string(),
+            directory: directory.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn local(y: i32, m: u32, d: u32, h: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn summarize_ranks_commands_categories_and_directories() {
+        let entries = [
+            entry(0, "git status", Some("/repo")),
+            entry(0, "git status", Some("/repo")),
+            entry(0, "git push", Some("/other")),
+            entry(0, "ls", None),
+        ];
+        let refs: Vec<&HistoryEntry> = entries.iter().collect();
+        let roots = ProjectRoots {
+            roots: HashMap::from([("/repo".to_string(), "/repo".to_string())]),
+        };
+        let summary = summarize(&refs, &period_bounds(Local::now(), -1), &roots);
+
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.unique, 3);
+        assert_eq!(summary.with_timestamps, 0);
+        assert_eq!(
+            summary.commands,
+            vec![
+                ("git status".to_string(), 2),
+                ("git push".to_string(), 1),
+                ("ls".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            summary.categories,
+            vec![("vcs".to_string(), 3), ("files".to_string(), 1)]
+        );
+        assert_eq!(
+            summary.directories,
+            vec![("/repo".to_string(), 2), ("/other".to_string(), 1)]
+        );
+        assert_eq!(summary.projects, vec![("/repo".to_string(), 2)]);
+        // No timestamps still counts as a single day
+        assert_eq!(summary.days(), 1);
+        assert_eq!(summary.percentage(3), 75);
+    }
+
+    #[test]
+    fn project_switches_ignore_moves_within_a_project() {
+        let at = |minute: i64, dir| entry(minute * 60, "ls", Some(dir));
+        let entries = [
+            at(1, "/home/u/api"),
+            at(2, "/home/u/api/src"),
+            at(3, "/home/u"),
+            at(4, "/home/u/web"),
+            at(5, "/home/u/api/tests"),
+            entry(6 * 60, "ls", None),
+            at(7, "/home/u/api-old"),
+        ];
+        assert_eq!(project_switches(entries.iter()), [4 * 60, 5 * 60, 7 * 60]);
+
+        // Three switches in the one active hour today, one in each of two
+        // hours the day before
+        let today = 10 * 24 * 3600;
+        let mut week = vec![
+            entry(today - 7200, "ls", Some("/a")),
+            entry(today - 7100, "ls", Some("/b")),
+            entry(today - 3600, "ls", Some("/a")),
+        ];
+        week.extend(entries.iter().map(|e| HistoryEntry {
+            timestamp: today + e.timestamp,
+            ..e.clone()
+        }));
+        let switches = context_switches(&week, today, 1);
+        // Coming back to /home/u/api from /a counts too
+        assert_eq!(switches.today, 4);
+        assert_eq!(switches.per_hour, 4.0);
+        assert_eq!(switches.usual_per_hour, 1.0);
+        assert_eq!(
+            switches.trend(),
+            Some(Trend {
+                change: 300,
+                weeks: 1
+            })
+        );
+    }
+
+    #[test]
+    fn usage_trend_against_prior_weeks() {
+        let week = 7 * 24 * 3600;
+        // 40 commands in each of the two weeks before, 60 this week
+        let mut timestamps: Vec<i64> = (0..80).map(|i| i * (2 * week / 80)).collect();
+        timestamps.extend((0..60).map(|i| 2 * week + i));
+        let count = |start: i64, end: i64| {
+            timestamps
+                .iter()
+                .filter(|&&ts| ts >= start && ts <= end)
+                .count()
+        };
+        let this_week = (2 * week, 3 * week - 1);
+        let trend = usage_trend(count, this_week, 3 * week, 2).unwrap();
+        assert_eq!((trend.change, trend.short()), (50, "↑ 50%".to_string()));
+        // Halfway through the week, 60 is already three times the usual 20
+        let halfway = usage_trend(count, this_week, 2 * week + week / 2, 2).unwrap();
+        assert_eq!(halfway.change, 200);
+        // The first week has nothing before it to compare with
+        assert_eq!(usage_trend(count, (0, week - 1), week, 2), None);
+    }
+
+    #[test]
+    fn sparse_views_are_flagged() {
+        assert_eq!(DataQuality::of(5, 5), DataQuality::FewCommands);
+        assert_eq!(DataQuality::of(500, 3), DataQuality::FewTimestamps);
+        assert_eq!(DataQuality::of(500, 40), DataQuality::Enough);
+        assert!(DataQuality::FewTimestamps.shares_reliable());
+        assert!(!DataQuality::FewTimestamps.timing_reliable());
+        assert!(!DataQuality::FewCommands.shares_reliable());
+    }
+
+    #[test]
+    fn pipeline_tools_follow_unquoted_pipes() {
+        assert_eq!(
+            pipeline_tools("ps aux | grep zsh | awk '{print $2}'"),
+            ["grep", "awk"]
+        );
+        assert_eq!(pipeline_tools("make 2>&1 |& LC_ALL=C sort"), ["sort"]);
+        assert_eq!(
+            pipeline_tools("grep 'a|b' file || echo none"),
+            Vec::<&str>::new()
+        );
+        assert_eq!(pipeline_tools("cat x |"), Vec::<&str>::new());
+
+        let entries = [
+            entry(0, "cat a.json | jq .", None),
+            entry(0, "curl x | jq .name", None),
+            entry(0, "history | fzf", None),
+        ];
+        let refs: Vec<&HistoryEntry> = entries.iter().collect();
+        let summary = summarize(
+            &refs,
+            &period_bounds(Local::now(), -1),
+            &ProjectRoots::default(),
+        );
+        assert_eq!(
+            summary.pipeline_tools,
+            [("jq".to_string(), 2), ("fzf".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn editor_arguments_become_extensions_and_areas() {
+        assert_eq!(
+            edited_paths("nvim -p src/main.rs +12 README.md"),
+            ["src/main.rs", "README.md"]
+        );
+        assert_eq!(edited_paths("code -g src/lib.rs:10:4"), ["src/lib.rs"]);
+        assert_eq!(edited_paths("vim notes2"), ["notes2"]);
+        assert_eq!(edited_paths("sudo /usr/bin/vim /etc/hosts"), ["/etc/hosts"]);
+        assert_eq!(edited_paths("git commit -m vim"), Vec::<&str>::new());
+
+        let entries = [
+            entry(0, "nvim src/main.rs", Some("/repo")),
+            entry(0, "vim ./src/parser.rs Makefile", Some("/repo")),
+            entry(0, "code .", Some("/repo")),
+            entry(0, "nano /etc/hosts", None),
+        ];
+        let refs: Vec<&HistoryEntry> = entries.iter().collect();
+        let summary = summarize(
+            &refs,
+            &period_bounds(Local::now(), -1),
+            &ProjectRoots::default(),
+        );
+        assert_eq!(
+            summary.edited_extensions,
+            [
+                ("rs".to_string(), 2),
+                ("Makefile".to_string(), 1),
+                ("hosts".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            summary.edited_areas,
+            [
+                ("/repo".to_string(), 2),
+                ("/repo/src".to_string(), 2),
+                ("/etc".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn summarize_counts_periods_and_span() {
+        let monday = local(2024, 3, 4, 9);
+        let bounds = period_bounds(monday, 0);
+        let entries = [
+            entry(monday.timestamp(), "cargo build", None),
+            entry(
+                (monday - chrono::Duration::days(1)).timestamp(),
+                "cargo test",
+                None,
+            ),
+            entry(
+                (monday - chrono::Duration::days(10)).timestamp(),
+                "cargo run",
+                None,
+            ),
+        ];
+        let refs: Vec<&HistoryEntry> = entries.iter().collect();
+        let summary = summarize(&refs, &bounds, &ProjectRoots::default());
+
+        assert_eq!(summary.today, 1);
+        assert_eq!(summary.this_week, 1);
+        assert_eq!(summary.this_month, 2);
+        assert_eq!(summary.days(), 11);
+        assert!((summary.weekly_average() - 1.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn time_patterns_bucket_by_hour_and_weekday() {
+        let entries = [
+            entry(local(2024, 3, 4, 9).timestamp(), "a", None),
+            entry(local(2024, 3, 4, 9).timestamp(), "b", None),
+            entry(local(2024, 3, 6, 22).timestamp(), "c", None),
+            entry(0, "untimed", None),
+        ];
+        let refs: Vec<&HistoryEntry> = entries.iter().collect();
+        let patterns = time_patterns(&refs);
+
+        assert_eq!(patterns.hour_counts[9], 2);
+        assert_eq!(patterns.hour_counts[22], 1);
+        assert_eq!(patterns.peak_hour(), Some((9, 2)));
+        assert_eq!(patterns.peak_day(), Some((0, 2)));
+        assert_eq!(patterns.day_of_week_counts[2], 1);
+    }
+
+    #[test]
+    fn empty_patterns_have_no_peaks() {
+        let patterns = TimePatterns::default();
+        assert_eq!(patterns.peak_hour(), None);
+        assert_eq!(patterns.peak_day(), None);
+        assert_eq!(patterns.day_percentages(), [0; 7]);
+    }
+
+    #[test]
+    fn day_percentages_sum_to_one_hundred() {
+        let patterns = TimePatterns {
+            hour_counts: [0; 24],
+            day_of_week_counts: [1, 1, 1, 1, 1, 1, 1],
+        };
+        let percentages = patterns.day_percentages();
+        assert_eq!(percentages.iter().sum::<i32>(), 100);
+        assert_eq!(percentages[1], 14);
+
+        let patterns = TimePatterns {
+            hour_counts: [0; 24],
+            day_of_week_counts: [1, 1, 1, 0, 0, 0, 0],
+        };
+        assert_eq!(patterns.day_percentages().iter().sum::<i32>(), 100);
+    }
+
+    #[test]
+    fn scaling_extrapolates_counts_but_not_unique() {
+        let summary = StatsSummary {
+            total: 10,
+            unique: 4,
+            commands: vec![("ls".to_string(), 3)],
+            projects: vec![("/repo".to_string(), 2)],
+            ..Default::default()
+        }
+        .scaled(2.5);
+        assert_eq!(summary.total, 25);
+        assert_eq!(summary.unique, 4);
+        assert_eq!(summary.commands[0].1, 8);
+        assert_eq!(summary.projects[0].1, 5);
+    }
+
+    #[test]
+    fn month_bounds_roll_over_december() {
+        let (start, end) = month_bounds(local(2023, 12, 15, 12));
+        assert_eq!(start, local(2023, 12, 1, 0).timestamp());
+        assert_eq!(end, local(2024, 1, 1, 0).timestamp() - 1);
+    }
+
+    #[test]
+    fn month_calendar_counts_and_levels() {
+        let entries = [
+            entry(local(2024, 2, 1, 9).timestamp(), "ls", None),
+            entry(local(2024, 2, 29, 9).timestamp(), "ls", None),
+            entry(local(2024, 2, 29, 10).timestamp(), "pwd", None),
+            entry(local(2024, 3, 1, 9).timestamp(), "ls", None),
+        ];
+        let counts = month_day_counts(entries.iter(), local(2024, 2, 14, 12));
+        assert_eq!(counts.len(), 29);
+        assert_eq!((counts[0], counts[1], counts[28]), (1, 0, 2));
+
+        assert_eq!(
+            [0, 1, 4, 6, 12].map(|count| activity_level(count, 12)),
+            [0, 1, 1, 2, 3]
+        );
+        assert_eq!(activity_level(0, 0), 0);
+    }
+
+    #[test]
+    fn fiscal_quarters() {
+        let day = NaiveDate::from_ymd_opt(2024, 5, 15).unwrap();
+        assert_eq!(
+            Quarter::containing(day, 1),
+            Quarter {
+                year: 2024,
+                quarter: 2
+            }
+        );
+        let april = Quarter::containing(day, 4);
+        assert_eq!(
+            april,
+            Quarter {
+                year: 2025,
+                quarter: 1
+            }
+        );
+        assert_eq!(
+            april.first_day(4),
+            NaiveDate::from_ymd_opt(2024, 4, 1).unwrap()
+        );
+        assert_eq!(
+            april.back(1).first_day(4),
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()
+        );
+
+        let (start, end) = "q4-2024".parse::<Quarter>().unwrap().bounds(1);
+        assert_eq!(start, local(2024, 10, 1, 0).timestamp());
+        assert_eq!(end, local(2025, 1, 1, 0).timestamp() - 1);
+
+        assert!("q5-2024".parse::<Quarter>().is_err());
+        assert!("2024".parse::<Quarter>().is_err());
+    }
+
+    #[test]
+    fn calendar_periods_back_from_today() {
+        // A Wednesday
+        let today = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+        let week = CalendarPeriod::Week;
+        assert_eq!(week.name(today, 0), "2024-W02");
+        assert_eq!(week.name(today, 2), "2023-W52");
+        assert_eq!(
+            week.bounds(today, 1),
+            (
+                local(2024, 1, 1, 0).timestamp(),
+                local(2024, 1, 8, 0).timestamp() - 1
+            )
+        );
+        assert_eq!(CalendarPeriod::Month.name(today, 1), "2023-12");
+        assert_eq!(
+            CalendarPeriod::Month.bounds(today, 0),
+            (
+                local(2024, 1, 1, 0).timestamp(),
+                local(2024, 2, 1, 0).timestamp() - 1
+            )
+        );
+        assert_eq!(CalendarPeriod::Year.name(today, 3), "2021");
+        assert_eq!(
+            CalendarPeriod::Year.bounds(today, 1).1,
+            local(2024, 1, 1, 0).timestamp() - 1
+        );
+    }
+
+    #[test]
+    fn directories_of_one_command() {
+        let entries = [
+            entry(1, "make", Some("/src/b")),
+            entry(2, "make", Some("/src/a")),
+            entry(3, "make", Some("/src/b")),
+            entry(4, "make", None),
+            entry(5, "make test", Some("/src/c")),
+        ];
+        assert_eq!(
+            command_directories(&entries, "make"),
+            [("/src/b".to_string(), 2), ("/src/a".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn command_lifetime_spans_every_run() {
+        let entries = [
+            entry(local(2024, 1, 1, 9).timestamp(), "make", None),
+            entry(local(2024, 1, 3, 9).timestamp(), "make", None),
+            entry(local(2024, 1, 10, 9).timestamp(), "make", None),
+            entry(local(2024, 1, 11, 9).timestamp(), "ls", None),
+            entry(0, "make", None),
+        ];
+        let lifetime = command_lifetime(&entries, "make").unwrap();
+        assert_eq!(lifetime.first, local(2024, 1, 1, 9).timestamp());
+        assert_eq!(lifetime.last, local(2024, 1, 10, 9).timestamp());
+        assert_eq!((lifetime.runs, lifetime.active_weeks), (3, 2));
+        assert_eq!(lifetime.per_active_week(), 1.5);
+        let today = NaiveDate::from_ymd_opt(2024, 1, 17).unwrap();
+        assert_eq!(lifetime.days_since_last(today), 7);
+        assert_eq!(command_lifetime(&entries, "cd"), None);
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::{Context, Result};
+use crossterm::{cursor, event::KeyCode, execute, terminal};
+use std::io;
+use std::sync::Arc;
+
+use crate::aggregate::{ProjectRoots, Quarter};
+use crate::doctor;
+use crate::error::ErrorKind;
+use crate::history::HistoryEntry;
+use crate::interactive::HistoryScreen;
+use crate::journal;
+use crate::range_picker::{pick_range, DayRange, Picked};
+use crate::stats::StatsScreen;
+use crate::time_format::TimeDisplay;
+use crate::timing;
+use crate::today::TodayScreen;
+
+// The full-screen views of one session, which share the loaded history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    History,
+    Stats,
+    Today,
+}
+
+impl Screen {
+    // F1, F2 and F3 switch screens from any of them
+    pub fn for_key(code: KeyCode) -> Option<Screen> {
+        match code {
+            KeyCode::F(1) => Some(Screen::History),
+            KeyCode::F(2) => Some(Screen::Stats),
+            KeyCode::F(3) => Some(Screen::Today),
+            _ => None,
+        }
+    }
+}
+
+// How a screen was left
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenExit {
+    // q or Esc, back to whatever opened it. For the screens themselves
+    // that means quitting.
+    Back,
+    // Ctrl-C, which leaves the whole program
+    Quit,
+    Switch(Screen),
+    // r, to open the date range picker over the screen
+    PickRange,
+}
+
+// How each screen starts out the first time it's shown
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AppOptions {
+    pub time_display: TimeDisplay,
+    // Quarter `stats` opens on instead of all time
+    pub period: Option<Quarter>,
+    pub fiscal_start: u32,
+    // History list and details side by side
+    pub split: bool,
+}
+
+// Screens are created the first time they're shown and kept, so switching
+// back returns to the same place without recomputing anything
+struct App {
+    // Everything loaded, and the part of it in the picked range
+    all: Arc<Vec<HistoryEntry>>,
+    entries: Arc<Vec<HistoryEntry>>,
+    // Looked up for everything loaded, so ranges reuse it
+    roots: Arc<ProjectRoots>,
+    range: Option<DayRange>,
+    options: AppOptions,
+    history: Option<HistoryScreen>,
+    stats: Option<StatsScreen>,
+    today: Option<TodayScreen>,
+}
+
+impl App {
+    // Narrow every screen to `range`. They're rebuilt from the new entries
+    // the next time they're shown.
+    fn set_range(&mut self, range: Option<DayRange>) {
+        if range == self.range {
+            return;
+        }
+        self.range = range;
+        self.entries = match range {
+            Some(range) => {
+                let (start, end) = range.bounds();
+                Arc::new(
+                    self.all
+                        .iter()
+                        .filter(|e| e.timestamp >= start && e.timestamp <= end)
+                        .cloned()
+                        .collect(),
+                )
+            }
+            None => Arc::clone(&self.all),
+        };
+        self.history = None;
+        self.stats = None;
+        self.today = None;
+    }
+
+    fn show(&mut self, screen: Screen, stdout: &mut io::Stdout) -> Result<ScreenExit> {
+        let entries = &self.entries;
+        let roots = &self.roots;
+        let options = self.options;
+        let range = self.range;
+        match screen {
+            Screen::History => self
+                .history
+                .get_or_insert_with(|| {
+                    timing::time("aggregate", || {
+                        HistoryScreen::new(entries, options.time_display, range)
+                            .with_split(options.split)
+                    })
+                })
+                .run(stdout, entries),
+            Screen::Stats => self
+                .stats
+                .get_or_insert_with(|| {
+                    timing::time("aggregate", || {
+                        StatsScreen::new(
+                            Arc::clone(entries),
+                            Arc::clone(roots),
+                            options.period,
+                            options.fiscal_start,
+                            range,
+                        )
+                    })
+                })
+                .run(stdout),
+            Screen::Today => self
+                .today
+                .get_or_insert_with(TodayScreen::default)
+                .run(stdout, entries, roots),
+        }
+    }
+}
+
+// Open the terminal UI on `first`, switching screens until the user quits
+pub fn run_app(entries: Vec<HistoryEntry>, first: Screen, options: AppOptions) -> Result<()> {
+    doctor::watch();
+    let mut stdout = io::stdout();
+    execute!(stdout, terminal::EnterAlternateScreen)
+        .and_then(|_| terminal::enable_raw_mode())
+        .and_then(|_| execute!(stdout, cursor::Hide))
+        .context(ErrorKind::Terminal)?;
+
+    let roots = Arc::new(ProjectRoots::resolve(&entries));
+    let entries = Arc::new(entries);
+    let mut app = App {
+        all: Arc::clone(&entries),
+        entries,
+        roots,
+        range: None,
+        options,
+        history: None,
+        stats: None,
+        today: None,
+    };
+    let mut screen = first;
+    let result = loop {
+        match app.show(screen, &mut stdout) {
+            Ok(ScreenExit::Switch(next)) => screen = next,
+            // Back to the same screen, rebuilt if the range changed
+            Ok(ScreenExit::PickRange) => match pick_range(&mut stdout, &app.all, app.range) {
+                Ok(Picked::Range(range)) => app.set_range(Some(range)),
+                Ok(Picked::AllTime) => app.set_range(None),
+                Ok(Picked::Cancelled) => {}
+                Err(err) => break Err(err),
+            },
+            Ok(ScreenExit::Back | ScreenExit::Quit) => break Ok(()),
+            Err(err) => break Err(err),
+        }
+    };
+
+    // Raw mode is left even if the screen can't be
+    let restored = execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)
+        .and(terminal::disable_raw_mode())
+        .context(ErrorKind::Terminal);
+
+    // Deletions are only written now, so U could undo them until here
+    journal::finish(result, restored)
+}
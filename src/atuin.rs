@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+
+use crate::error::ErrorKind;
+use crate::history::{HistoryEntry, UserDirs};
+use crate::parser::is_valid_directory;
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+
+// Atuin's database, unless its config moves it elsewhere
+pub fn atuin_db_path(dirs: &UserDirs) -> PathBuf {
+    dirs.data.join("atuin/history.db")
+}
+
+// Atuin stores times in nanoseconds and uses -1 for a duration or exit code
+// it never saw, e.g. when the shell was killed mid-command
+fn entry_from_row(
+    timestamp: i64,
+    duration: i64,
+    exit: i64,
+    command: String,
+    cwd: String,
+) -> HistoryEntry {
+    HistoryEntry {
+        timestamp: timestamp / NANOS_PER_SECOND,
+        command,
+        directory: is_valid_directory(&cwd).then_some(cwd),
+        duration: (duration >= 0).then_some(duration / NANOS_PER_SECOND),
+        exit_code: (exit >= 0).then_some(exit as i32),
+        context: None,
+        session_id: None,
+        commit: None,
+        host: None,
+        recorded_timestamp: None,
+    }
+}
+
+// Commands are kept whole rather than split on `&&` like the text formats,
+// since the duration and exit code belong to the whole line
+fn read_entries(conn: &Connection) -> rusqlite::Result<Vec<HistoryEntry>> {
+    // Databases from before atuin soft-deleted entries have no deleted_at
+    let has_deleted_at: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('history') WHERE name = 'deleted_at'",
+        [],
+        |row| row.get(0),
+    )?;
+    let filter = if has_deleted_at {
+        "WHERE deleted_at IS NULL"
+    } else {
+        ""
+    };
+    let mut statement = conn.prepare(&format!(
+        "SELECT timestamp, duration, exit, command, cwd FROM history {} ORDER BY timestamp",
+        filter
+    ))?;
+    let rows = statement.query_map([], |row| {
+        Ok(entry_from_row(
+            row.get(0)?,
+            row.get(1)?,
+            row.get(2)?,
+            row.get(3)?,
+            row.get(4)?,
+        ))
+    })?;
+    rows.collect()
+}
+
+pub fn read_atuin_history(path: &Path) -> Result<Vec<HistoryEntry>> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open atuin database {}", path.display()))
+        .context(ErrorKind::NoData)?;
+    read_entries(&conn).context("Failed to read atuin history")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = "CREATE TABLE history (
+        id TEXT PRIMARY KEY, timestamp INTEGER, duration INTEGER, exit INTEGER,
+        command TEXT, cwd TEXT, session TEXT, hostname TEXT, deleted_at INTEGER
+    )";
+
+    #[test]
+    fn reads_duration_exit_code_and_directory() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(SCHEMA, []).unwrap();
+        conn.execute_batch(
+            "INSERT INTO history VALUES
+                ('b', 1681234600000000000, -1, -1, 'vim', 'unknown', 's', 'h', NULL),
+                ('a', 1681234567000000000, 2500000000, 1, 'cargo test', '/src/app', 's', 'h', NULL),
+                ('c', 1681234700000000000, 0, 0, 'echo secret', '/tmp', 's', 'h', 1681234800)",
+        )
+        .unwrap();
+
+        let entries = read_entries(&conn).unwrap();
+        assert_eq!(
+            entries,
+            [
+                HistoryEntry {
+                    timestamp: 1681234567,
+                    command: "cargo test".to_string(),
+                    directory: Some("/src/app".to_string()),
+                    duration: Some(2),
+                    exit_code: Some(1),
+                    context: None,
+                    session_id: None,
+                    commit: None,
+                    host: None,
+                    recorded_timestamp: None,
+                },
+                HistoryEntry {
+                    timestamp: 1681234600,
+                    command: "vim".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::UNIX_EPOCH,
+};
+
+use crate::history::HistoryEntry;
+use crate::timing;
+
+// Bumped whenever the layout below or HistoryEntry changes, so old caches
+// are ignored rather than misread
+const MAGIC: &[u8] = b"cli-wrapped cache 1\n";
+
+// Turned off with --no-cache
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+// What a cache file was made from. Any change to the history file's size or
+// modification time means it has to be parsed again.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    path: String,
+    // How the file was parsed, e.g. as zsh or bash history
+    format: String,
+    size: u64,
+    modified: u128,
+}
+
+impl Key {
+    fn of(path: &Path, format: &str, file: &File) -> Option<Key> {
+        let meta = file.metadata().ok()?;
+        let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Key {
+            path: path.to_string_lossy().into_owned(),
+            format: format.to_string(),
+            size: meta.len(),
+            modified: modified.as_nanos(),
+        })
+    }
+
+    // One cache file per history file and format
+    fn file_name(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        (&self.path, &self.format).hash(&mut hasher);
+        format!("{:016x}.bin", hasher.finish())
+    }
+}
+
+// $XDG_CACHE_HOME/cli-tracker, usually ~/.cache/cli-tracker
+pub fn cache_dir() -> Option<PathBuf> {
+    match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir).join("cli-tracker")),
+        _ => home::home_dir().map(|home| home.join(".cache/cli-tracker")),
+    }
+}
+
+// Little-endian integers and length-prefixed strings, with a flag byte in
+// front of each optional field
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u64).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn put_opt_str(out: &mut Vec<u8>, s: &Option<String>) {
+    out.push(s.is_some() as u8);
+    if let Some(s) = s {
+        put_str(out, s);
+    }
+}
+
+fn encode(key: &Key, entries: &[HistoryEntry]) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    put_str(&mut out, &key.path);
+    put_str(&mut out, &key.format);
+    out.extend_from_slice(&key.size.to_le_bytes());
+    out.extend_from_slice(&key.modified.to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+    for entry in entries {
+        out.extend_from_slice(&entry.timestamp.to_le_bytes());
+        put_str(&mut out, &entry.command);
+        put_opt_str(&mut out, &entry.directory);
+        out.push(entry.duration.is_some() as u8);
+        out.extend_from_slice(&entry.duration.unwrap_or_default().to_le_bytes());
+        out.push(entry.exit_code.is_some() as u8);
+        out.extend_from_slice(&entry.exit_code.unwrap_or_default().to_le_bytes());
+        put_opt_str(&mut out, &entry.context);
+        put_opt_str(&mut out, &entry.session_id);
+        put_opt_str(&mut out, &entry.commit);
+    }
+    out
+}
+
+// Reads back what `encode` wrote. Every read is checked, so a truncated or
+// corrupt file comes out as None rather than a panic.
+struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl Decoder<'_> {
+    fn take(&mut self, n: usize) -> Option<&[u8]> {
+        if n > self.bytes.len() {
+            return None;
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Some(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N)?.try_into().ok()
+    }
+
+    fn flag(&mut self) -> Option<bool> {
+        Some(self.array::<1>()?[0] != 0)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn str(&mut self) -> Option<String> {
+        let len = usize::try_from(self.u64()?).ok()?;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+
+    fn opt_str(&mut self) -> Option<Option<String>> {
+        if self.flag()? {
+            self.str().map(Some)
+        } else {
+            Some(None)
+        }
+    }
+}
+
+fn decode(bytes: &[u8], key: &Key) -> Option<Vec<HistoryEntry>> {
+    decode_any(bytes)
+        .filter(|(cached, _)| cached == key)
+        .map(|(_, entries)| entries)
+}
+
+// What a cache file holds, whatever it was made from
+fn decode_any(bytes: &[u8]) -> Option<(Key, Vec<HistoryEntry>)> {
+    let mut d = Decoder {
+        bytes: bytes.strip_prefix(MAGIC)?,
+    };
+    let key = Key {
+        path: d.str()?,
+        format: d.str()?,
+        size: d.u64()?,
+        modified: u128::from_le_bytes(d.array()?),
+    };
+    let count = usize::try_from(d.u64()?).ok()?;
+    // Don't trust the count further than the bytes there are
+    let mut entries = Vec::with_capacity(count.min(bytes.len()));
+    for _ in 0..count {
+        let timestamp = i64::from_le_bytes(d.array()?);
+        let command = d.str()?;
+        let directory = d.opt_str()?;
+        let has_duration = d.flag()?;
+        let duration = i64::from_le_bytes(d.array()?);
+        let has_exit_code = d.flag()?;
+        let exit_code = i32::from_le_bytes(d.array()?);
+        entries.push(HistoryEntry {
+            timestamp,
+            command,
+            directory,
+            duration: has_duration.then_some(duration),
+            exit_code: has_exit_code.then_some(exit_code),
+            context: d.opt_str()?,
+            session_id: d.opt_str()?,
+            commit: d.opt_str()?,
+            host: None,
+            recorded_timestamp: None,
+        });
+    }
+    d.bytes.is_empty().then_some((key, entries))
+}
+
+// Delete the cache files in `dir` holding a command `removes` picks, or
+// that can't be read to tell, for `purge`. Each is made again from its
+// history file when next needed. Returns the files deleted.
+pub fn forget(dir: &Path, removes: impl Fn(&HistoryEntry) -> bool) -> io::Result<Vec<PathBuf>> {
+    let mut deleted = Vec::new();
+    for file in fs::read_dir(dir)? {
+        let path = file?.path();
+        let holds = match fs::read(&path).ok().as_deref().and_then(decode_any) {
+            Some((_, entries)) => entries.iter().any(&removes),
+            None => true,
+        };
+        if holds {
+            fs::remove_file(&path)?;
+            deleted.push(path);
+        }
+    }
+    Ok(deleted)
+}
+
+// Parse `file` (opened from `path`) with `parse`, or return what the last
+// parse of the same unchanged file gave. The cache is best effort: if it
+// can't be read or written the file is simply parsed.
+pub fn parse_cached(
+    path: &Path,
+    format: &str,
+    file: File,
+    parse: impl FnOnce(&[u8]) -> Vec<HistoryEntry>,
+) -> Vec<HistoryEntry> {
+    // Read in full before parsing, so --timing can tell the two apart. A
+    // read error part way through leaves what was read, as a failed line
+    // would.
+    let read = |mut file: File| {
+        let mut bytes = Vec::new();
+        let _ = file.read_to_end(&mut bytes);
+        bytes
+    };
+    let key = Key::of(path, format, &file);
+    let cache_file = cache_dir()
+        .zip(key.as_ref())
+        .map(|(dir, key)| dir.join(key.file_name()));
+    let (Some(key), Some(cache_file), true) = (key, cache_file, ENABLED.load(Ordering::Relaxed))
+    else {
+        let bytes = timing::time("read", || read(file));
+        return timing::time("parse", || parse(&bytes));
+    };
+
+    let cached = timing::time("read cache", || File::open(&cache_file).map(read));
+    if let Ok(bytes) = cached {
+        if let Some(entries) = timing::time("decode cache", || decode(&bytes, &key)) {
+            return entries;
+        }
+    }
+
+    let bytes = timing::time("read", || read(file));
+    let entries = timing::time("parse", || parse(&bytes));
+    // Written aside and renamed, so a concurrent run never reads half a file
+    if let Some(dir) = cache_file.parent() {
+        let tmp = cache_file.with_extension(format!("tmp{}", std::process::id()));
+        let written = timing::time("write cache", || {
+            fs::create_dir_all(dir)
+                .and_then(|_| fs::write(&tmp, encode(&key, &entries)))
+                .and_then(|_| fs::rename(&tmp, &cache_file))
+        });
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+
+    fn key(size: u64) -> Key {
+        Key {
+            path: "/home/me/.zsh_history".to_string(),
+            format: "zsh".to_string(),
+            size,
+            modified: 1_700_000_000_123_456_789,
+        }
+    }
+
+    #[test]
+    fn entries_survive_a_round_trip() {
+        let entries = vec![
+            HistoryEntry {
+                timestamp: 1700000000,
+                command: "cargo test -- --nocapture".to_string(),
+                directory: Some("/src/app".to_string()),
+                duration: Some(12),
+                exit_code: Some(-1),
+                context: Some("work".to_string()),
+                session_id: Some("4242".to_string()),
+                commit: Some("abc123".to_string()),
+                host: None,
+                recorded_timestamp: None,
+            },
+            HistoryEntry {
+                command: "ls ünïcödé".to_string(),
+                ..Default::default()
+            },
+        ];
+        let bytes = encode(&key(100), &entries);
+        assert_eq!(decode(&bytes, &key(100)), Some(entries));
+    }
+
+    #[test]
+    fn changed_or_damaged_caches_are_ignored() {
+        let entries = vec![HistoryEntry {
+            timestamp: 1,
+            command: "ls".to_string(),
+            ..Default::default()
+        }];
+        let bytes = encode(&key(100), &entries);
+        assert_eq!(decode(&bytes, &key(101)), None);
+        assert_eq!(decode(&bytes[..bytes.len() - 1], &key(100)), None);
+        assert_eq!(decode(b"something else", &key(100)), None);
+    }
+
+    #[test]
+    fn forgetting_deletes_only_caches_holding_the_command() {
+        let dir = ScratchDir::new("cache");
+        let cache = |name: &str, command: &str| {
+            let entries = vec![HistoryEntry {
+                command: command.to_string(),
+                ..Default::default()
+            }];
+            fs::write(dir.join(name), encode(&key(100), &entries)).unwrap();
+        };
+        cache("a.bin", "ls");
+        cache("b.bin", "export TOKEN=hunter2");
+
+        let deleted = forget(&dir, |e| e.command.contains("hunter2")).unwrap();
+        assert_eq!(deleted, [dir.join("b.bin")]);
+        assert!(dir.join("a.bin").exists());
+    }
+}
@@ -0,0 +1,185 @@
+use crossterm::style::Color;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::aggregate::EDITORS;
+use crate::config::config;
+
+// Built-in categories, each with the programs in it and the color the
+// history list draws them in
+const BUILT_IN: &[(&str, Color, &[&str])] = &[
+    ("editor", Color::Green, EDITORS),
+    (
+        "vcs",
+        Color::Magenta,
+        &["git", "gh", "hg", "svn", "tig", "lazygit", "jj", "glab"],
+    ),
+    (
+        "container",
+        Color::Blue,
+        &[
+            "docker",
+            "docker-compose",
+            "podman",
+            "kubectl",
+            "k9s",
+            "helm",
+            "minikube",
+            "kind",
+            "nerdctl",
+        ],
+    ),
+    (
+        "build",
+        Color::Cyan,
+        &[
+            "cargo", "make", "cmake", "ninja", "gradle", "mvn", "go", "rustc", "gcc", "clang",
+            "bazel", "just",
+        ],
+    ),
+    (
+        "package",
+        Color::DarkCyan,
+        &[
+            "npm", "yarn", "pnpm", "pip", "pip3", "uv", "brew", "apt", "apt-get", "dnf", "pacman",
+            "gem", "bundle", "rustup",
+        ],
+    ),
+    (
+        "network",
+        Color::DarkGreen,
+        &[
+            "ssh", "scp", "sftp", "rsync", "curl", "wget", "ping", "dig", "nc", "mosh", "http",
+        ],
+    ),
+    (
+        "system",
+        Color::DarkMagenta,
+        &[
+            "ps",
+            "top",
+            "htop",
+            "btop",
+            "kill",
+            "pkill",
+            "systemctl",
+            "journalctl",
+            "df",
+            "du",
+            "free",
+            "uptime",
+        ],
+    ),
+    (
+        "files",
+        Color::Grey,
+        &[
+            "ls", "cd", "cp", "mv", "rm", "mkdir", "rmdir", "touch", "ln", "chmod", "chown", "cat",
+            "less", "tree", "find", "fd", "grep", "rg", "tar", "unzip", "z", "zoxide",
+        ],
+    ),
+];
+
+// Colors for categories the config adds, in the order they're defined
+const USER_COLORS: &[Color] = &[
+    Color::Red,
+    Color::DarkYellow,
+    Color::DarkRed,
+    Color::DarkGrey,
+];
+
+// The program a command runs, without a leading `sudo` or its path
+fn base_program(command: &str) -> Option<&str> {
+    let mut words = command.split_whitespace();
+    let program = match words.next() {
+        Some("sudo") => words.next(),
+        program => program,
+    }?;
+    Some(program.rsplit('/').next().unwrap_or(program))
+}
+
+// Which category each program is in, the config's [categories] over the
+// built-in ones, and the color of each category
+pub struct Categories {
+    programs: HashMap<String, usize>,
+    names: Vec<(String, Color)>,
+}
+
+impl Categories {
+    pub fn new(extra: &[(String, Vec<String>)]) -> Categories {
+        let mut categories = Categories {
+            programs: HashMap::new(),
+            names: Vec::new(),
+        };
+        for &(name, color, programs) in BUILT_IN {
+            categories.add(name, color, programs.iter().copied());
+        }
+        let mut palette = USER_COLORS.iter().cycle();
+        for (name, programs) in extra {
+            let color = match categories.names.iter().find(|(known, _)| known == name) {
+                Some(&(_, color)) => color,
+                None => *palette.next().unwrap_or(&Color::Reset),
+            };
+            categories.add(name, color, programs.iter().map(String::as_str));
+        }
+        categories
+    }
+
+    fn add<'a>(&mut self, name: &str, color: Color, programs: impl Iterator<Item = &'a str>) {
+        let index = match self.names.iter().position(|(known, _)| known == name) {
+            Some(index) => index,
+            None => {
+                self.names.push((name.to_string(), color));
+                self.names.len() - 1
+            }
+        };
+        for program in programs {
+            self.programs.insert(program.to_string(), index);
+        }
+    }
+
+    // The category `command`'s program is in, and its color
+    pub fn of(&self, command: &str) -> Option<(&str, Color)> {
+        let index = *self.programs.get(base_program(command)?)?;
+        let (name, color) = &self.names[index];
+        Some((name, *color))
+    }
+}
+
+static CATEGORIES: OnceLock<Categories> = OnceLock::new();
+
+// Built from the loaded config
+pub fn categories() -> &'static Categories {
+    CATEGORIES.get_or_init(|| Categories::new(&config().categories))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn programs_map_to_built_in_and_configured_categories() {
+        let categories = Categories::new(&[
+            (
+                "cloud".to_string(),
+                vec!["aws".to_string(), "gcloud".to_string()],
+            ),
+            ("editor".to_string(), vec!["ed".to_string()]),
+            (
+                "vcs".to_string(),
+                vec!["fossil".to_string(), "ls".to_string()],
+            ),
+        ]);
+        assert_eq!(
+            categories.of("sudo /usr/bin/nvim /etc/hosts"),
+            Some(("editor", Color::Green))
+        );
+        assert_eq!(categories.of("git push"), Some(("vcs", Color::Magenta)));
+        assert_eq!(categories.of("aws s3 ls"), Some(("cloud", Color::Red)));
+        assert_eq!(categories.of("ed notes"), Some(("editor", Color::Green)));
+        // The config moves a program to another category
+        assert_eq!(categories.of("ls -la"), Some(("vcs", Color::Magenta)));
+        assert_eq!(categories.of("mytool --help"), None);
+        assert_eq!(categories.of(""), None);
+    }
+}
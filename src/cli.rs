@@ -1,18 +1,417 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::aggregate::Quarter;
+use crate::config::Theme;
+use crate::error::ErrorFormat;
+use crate::estimate::Estimate;
+use crate::export::Rollup;
+use crate::history::{Shell, Source};
+use crate::import::ImportFormat;
+use crate::jump::JumpFormat;
+use crate::output::OutputFormat;
+use crate::time_format::TimeBound;
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    after_help = "Exit codes: 0 success, 1 other error, 2 usage error, 3 no history data, \
+                  4 parse failure, 5 terminal error"
+)]
 pub struct Cli {
+    // The config's default view, or history, when left out
     #[command(subcommand)]
-    pub command: Commands,
+    pub command: Option<Commands>,
+
+    /// How to print errors on stderr
+    #[arg(long, global = true, value_enum, default_value_t = ErrorFormat::Text)]
+    pub error_format: ErrorFormat,
+
+    /// Read this shell's history instead of detecting it
+    #[arg(long, global = true, value_enum)]
+    pub shell: Option<Shell>,
+
+    /// Read another tool's history database, or all histories merged
+    #[arg(long, global = true, value_enum, conflicts_with = "shell")]
+    pub source: Option<Source>,
+
+    /// Read history from this file instead, in the format --shell or its name suggests
+    #[arg(long, global = true, value_name = "PATH", conflicts_with = "source")]
+    pub history_file: Option<PathBuf>,
+
+    /// Parse history files again instead of reusing the cached result
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+
+    /// Print where startup time went (reading, parsing, counting, drawing) on stderr
+    #[arg(long, global = true)]
+    pub timing: bool,
+
+    /// Month the fiscal year starts in (1-12), for quarter views
+    #[arg(
+        long,
+        global = true,
+        value_name = "MONTH",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..=12)
+    )]
+    pub fiscal_year_start: u32,
+
+    /// Add noise to the counts printed by stats, today, week, month, year, top and wrapped,
+    /// and leave out rare commands, for sharing them
+    #[arg(long, global = true)]
+    pub private: bool,
+
+    /// Show times only relative to now, directories as project-1, project-2, ... and no user
+    /// name in the views, for screenshots and screen sharing
+    #[arg(long, global = true)]
+    pub present: bool,
+
+    /// Color the views with this preset instead of the config's, even with NO_COLOR set
+    #[arg(long, global = true, value_enum)]
+    pub theme: Option<Theme>,
+
+    /// Count the commands the config file's history.ignore leaves out
+    #[arg(long, global = true)]
+    pub include_ignored: bool,
+
+    /// Only use commands from this date, span or period on, e.g. 2024-03-01, 7d or last-month
+    #[arg(long, global = true, value_name = "WHEN")]
+    pub since: Option<TimeBound>,
+
+    /// Only use commands up to the end of this date or period, or this long ago
+    #[arg(long, global = true, value_name = "WHEN")]
+    pub until: Option<TimeBound>,
+
+    /// Only use the N most recent commands
+    #[arg(long, global = true, value_name = "N")]
+    pub last: Option<usize>,
+
+    /// Give commands saved without a time one from the commands around them, so they count
+    /// in the charts; estimated times are marked with ~
+    #[arg(
+        long,
+        global = true,
+        value_name = "HOW",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "interpolate"
+    )]
+    pub estimate_timestamps: Option<Estimate>,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
     /// Show command history in an interactive viewer
-    History,
+    History {
+        /// Show times as "2h ago" instead of dates (toggle with t)
+        #[arg(long)]
+        relative_time: bool,
+        /// Only commands run in this directory or below it; `.` is the current repository
+        #[arg(long, value_name = "PATH")]
+        dir: Option<PathBuf>,
+        /// Show the list with the selected command's details beside it (toggle with v)
+        #[arg(long)]
+        split: bool,
+    },
     /// Show summary statistics about command usage
-    Stats,
+    Stats {
+        /// Open on a fiscal quarter, e.g. q3-2024
+        #[arg(long, value_name = "QUARTER")]
+        period: Option<Quarter>,
+        /// Print the stats in this format instead of opening the TUI
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+        /// Print the stats as plain text instead of opening the TUI, like --format table
+        #[arg(long, conflicts_with = "format")]
+        plain: bool,
+        /// Only commands run in this directory or below it; `.` is the current repository
+        #[arg(long, value_name = "PATH")]
+        dir: Option<PathBuf>,
+    },
     /// Show today's stats
-    Today,
+    Today {
+        /// Print today's stats in this format instead of opening the TUI
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+    /// Print this week's stats, from Monday
+    Week {
+        /// Go back this many weeks, e.g. 1 for last week
+        #[arg(long, default_value_t = 0)]
+        offset: u32,
+        /// Print the stats in this format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Print this month's stats
+    Month {
+        /// Go back this many months, e.g. 1 for last month
+        #[arg(long, default_value_t = 0)]
+        offset: u32,
+        /// Print the stats in this format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Print this year's stats
+    Year {
+        /// Go back this many years, e.g. 1 for last year
+        #[arg(long, default_value_t = 0)]
+        offset: u32,
+        /// Print the stats in this format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Compare two periods side by side, with the commands that rose, fell or are new
+    Compare {
+        /// The earlier period: a date, a span like 7d, or a period like last-week
+        #[arg(long, value_name = "WHEN", default_value = "last-week")]
+        a: TimeBound,
+        /// The later period, in the same forms
+        #[arg(long, value_name = "WHEN", default_value = "this-week")]
+        b: TimeBound,
+    },
+    /// Chart a command's use over its whole lifetime
+    Timeline {
+        /// The command, or its first words to include every variant
+        command: String,
+    },
+    /// Find commands in the history by regular expression or fuzzy match
+    Search {
+        /// A regular expression, or with --fuzzy the characters to look for in order
+        pattern: String,
+        /// Match the pattern's characters in order, with anything between them
+        #[arg(long, short)]
+        fuzzy: bool,
+        /// Open the matching commands in the history viewer
+        #[arg(long, short)]
+        interactive: bool,
+        /// Print the matches in this format
+        #[arg(long, value_enum, conflicts_with = "interactive")]
+        format: Option<OutputFormat>,
+    },
+    /// Print the most used commands as a plain table
+    Top {
+        /// How many commands to list
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+        /// Print the commands in this format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Rank working directories by how many commands ran in them
+    Dirs {
+        /// How many directories to list
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+        /// Print the directories in this format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Rank git repositories by how many commands ran anywhere inside them
+    Projects {
+        /// How many projects to list
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+        /// Print the projects in this format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// List the directories you use most and most recently, for cd-jump tools
+    Jump {
+        /// How many directories to list
+        #[arg(short = 'n', long, default_value_t = 100)]
+        limit: usize,
+        /// Write the list as z or autojump keep theirs, or one path per line
+        #[arg(long, value_enum, default_value_t = JumpFormat::Plain)]
+        format: JumpFormat,
+    },
+    /// Show how many days in a row you've run commands, and the longest run and gap
+    Streak,
+    /// Print today's count, the current streak and this week's top command, e.g. from .zshrc
+    Summary,
+    /// List the commands pinned with b in the history view, with their stats
+    Favorites,
+    /// Draw a calendar of the last year, shaded by how many commands ran each day
+    Heatmap {
+        /// Move between days with the arrow keys to see each one's top commands
+        #[arg(long, short)]
+        interactive: bool,
+    },
+    /// Recap a year of commands a page at a time, like Spotify Wrapped
+    Wrapped {
+        /// The year to recap (default: this year)
+        #[arg(long)]
+        year: Option<i32>,
+    },
+    /// Write every history entry to a file for analysis elsewhere
+    Export {
+        /// File to write, as CSV, JSON or (for .db files) SQLite going by its name
+        #[arg(long, short, value_name = "PATH")]
+        output: Option<PathBuf>,
+        /// Write CSV or JSON whatever the output file is called
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+        /// Write a CSV row per day, week or month with its totals instead of every entry
+        #[arg(long, value_enum, value_name = "PERIOD")]
+        rollup: Option<Rollup>,
+    },
+    /// Save the current stats, or compare them with ones saved earlier
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+    /// Merge history from another machine or tool into the stats log
+    Import {
+        /// An `export` CSV or JSON file, or a zsh or bash history file
+        file: PathBuf,
+        /// How the file is written, if its name doesn't tell
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+    },
+    /// Compare the programs run here with another machine's history
+    DiffSources {
+        /// The other machine's `export` CSV or JSON, or its zsh or bash history
+        file: PathBuf,
+        /// How the file is written, if its name doesn't tell
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+    },
+    /// Check that the logging hook is still recording commands
+    Doctor,
+    /// Read or change settings in the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Import new history lines into the local database used by the views
+    Ingest {
+        /// Keep importing every few minutes, pausing while the battery is low
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Print a shell hook that records every command
+    Init {
+        #[arg(value_enum)]
+        shell: Shell,
+        /// Also record the git commit checked out after each command
+        #[arg(long)]
+        git_head: bool,
+        /// Also record a context label, printed by this command after each one
+        #[arg(long, value_name = "COMMAND")]
+        context_command: Option<String>,
+    },
+    /// Append one command to the stats log (called by the shell hook)
+    Record {
+        /// The command line as typed
+        #[arg(long, allow_hyphen_values = true)]
+        cmd: String,
+        /// Directory the command ran in
+        #[arg(long)]
+        cwd: Option<String>,
+        /// Unix time the command started (default: now)
+        #[arg(long, value_name = "TIMESTAMP")]
+        start: Option<i64>,
+        /// How long the command ran, in seconds
+        #[arg(long, value_name = "SECONDS")]
+        duration: Option<i64>,
+        /// The command's exit status
+        #[arg(long, value_name = "CODE", allow_negative_numbers = true)]
+        exit: Option<i32>,
+        /// Identifies the shell session, so its commands can be grouped
+        #[arg(long, value_name = "ID")]
+        session: Option<String>,
+        /// Git HEAD after the command ran
+        #[arg(long, value_name = "SHA")]
+        commit: Option<String>,
+        /// Where the command ran, e.g. office or home-vpn
+        #[arg(long, value_name = "LABEL")]
+        context: Option<String>,
+    },
+    /// Suggest shell functions for commands you retype
+    #[command(group(ArgGroup::new("kind").required(true).args(["functions"])))]
+    Suggest {
+        /// Print zsh function stubs for long commands you retype often
+        #[arg(long)]
+        functions: bool,
+    },
+    /// Print a plain-text report
+    Report {
+        /// Only include a fiscal quarter, e.g. q3-2024
+        #[arg(long, global = true, value_name = "QUARTER")]
+        period: Option<Quarter>,
+        #[command(subcommand)]
+        report: ReportKind,
+    },
+    /// Permanently delete tracked data
+    #[command(group(ArgGroup::new("target").required(true).args(["all", "matching"])))]
+    Purge {
+        /// Delete everything the tracker has recorded
+        #[arg(long)]
+        all: bool,
+        /// Delete commands whose text, directory or context matches this regular expression
+        #[arg(long, value_name = "PATTERN")]
+        matching: Option<String>,
+        /// Don't ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Delete every recorded command matching a regular expression, e.g. a pasted secret
+    Scrub {
+        #[arg(value_name = "PATTERN")]
+        pattern: String,
+        /// Don't ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotAction {
+    /// Save the current stats under a name, replacing any saved under it before
+    Save { name: String },
+    /// Show how the stats changed since the snapshot saved under a name
+    Diff { name: String },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print a setting, e.g. `ui.theme`
+    Get { key: String },
+    /// Change a setting, e.g. `ui.theme solarized`; history.ignore takes a comma-separated list
+    Set { key: String, value: String },
+    /// Print where the config file is
+    Path,
+}
+
+#[derive(Subcommand)]
+pub enum ReportKind {
+    /// Edit/build/test cycles per project
+    Loops,
+    /// Commands and active time per git branch
+    Branches,
+    /// Commands most often re-run straight after failing
+    Retries,
+    /// Shell sessions with their length, commands and directories
+    Sessions,
+    /// How many tmux panes were in use each day, and which ran the most commands
+    Panes,
+    /// The command that made a git commit, and the commands leading up to it
+    Commit {
+        /// Commit hash, full or abbreviated
+        #[arg(value_name = "SHA")]
+        commit: String,
+    },
+    /// Compare the histories of several users on a shared host
+    Users {
+        /// A user's home directory, repeated for each user
+        #[arg(long = "home", value_name = "DIR", required = true)]
+        homes: Vec<PathBuf>,
+        /// Replace user names with user-1, user-2, ...
+        #[arg(long)]
+        anonymize: bool,
+    },
 }
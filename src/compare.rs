@@ -0,0 +1,275 @@
+use chrono::{Local, TimeZone};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::days::day_counts;
+use crate::history::HistoryEntry;
+use crate::normalize;
+
+// Rows in each of the risers, fallers and new commands
+const CHANGES: usize = 5;
+
+// One side of a comparison
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeriodFigures {
+    pub start: i64,
+    pub end: i64,
+    pub total: usize,
+    pub unique: usize,
+    pub active_days: usize,
+}
+
+impl PeriodFigures {
+    pub fn per_active_day(&self) -> f64 {
+        if self.active_days == 0 {
+            0.0
+        } else {
+            self.total as f64 / self.active_days as f64
+        }
+    }
+}
+
+// Two periods side by side, with the commands whose use changed the most.
+// Commands are told apart after the normalization rules, as in `top`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Comparison {
+    pub a: PeriodFigures,
+    pub b: PeriodFigures,
+    // (command, runs in A, runs in B), by how much they rose or fell
+    pub rose: Vec<(String, usize, usize)>,
+    pub fell: Vec<(String, usize, usize)>,
+    // Commands B ran that A didn't, busiest first, and how many there were
+    pub new: Vec<(String, usize)>,
+    pub new_count: usize,
+}
+
+fn period_counts<'a>(
+    entries: &'a [HistoryEntry],
+    (start, end): (i64, i64),
+) -> (PeriodFigures, HashMap<Cow<'a, str>, usize>) {
+    let rules = normalize::rules();
+    let in_period: Vec<&HistoryEntry> = entries
+        .iter()
+        .filter(|e| e.timestamp >= start && e.timestamp <= end)
+        .collect();
+    let mut counts = HashMap::new();
+    for entry in &in_period {
+        *counts.entry(rules.apply(&entry.command)).or_insert(0) += 1;
+    }
+    let figures = PeriodFigures {
+        start,
+        end,
+        total: in_period.len(),
+        unique: counts.len(),
+        active_days: day_counts(in_period.iter().copied()).len(),
+    };
+    (figures, counts)
+}
+
+pub fn compare_periods(entries: &[HistoryEntry], a: (i64, i64), b: (i64, i64)) -> Comparison {
+    let (a, a_counts) = period_counts(entries, a);
+    let (b, b_counts) = period_counts(entries, b);
+
+    let mut changes: Vec<(String, usize, usize)> = a_counts
+        .keys()
+        .chain(b_counts.keys())
+        .map(|command| {
+            let before = a_counts.get(command).copied().unwrap_or(0);
+            let after = b_counts.get(command).copied().unwrap_or(0);
+            (command.to_string(), before, after)
+        })
+        .collect();
+    changes.sort();
+    changes.dedup();
+    let change = |&(_, before, after): &(String, usize, usize)| after as i64 - before as i64;
+    // Biggest change first, then the busier command
+    changes.sort_by(|x, y| {
+        change(y)
+            .cmp(&change(x))
+            .then_with(|| (y.1 + y.2).cmp(&(x.1 + x.2)))
+            .then_with(|| x.0.cmp(&y.0))
+    });
+    // New commands have their own list
+    let rose = changes
+        .iter()
+        .filter(|c| c.1 > 0 && change(c) > 0)
+        .take(CHANGES)
+        .cloned()
+        .collect();
+    let mut fell: Vec<(String, usize, usize)> =
+        changes.iter().filter(|c| change(c) < 0).cloned().collect();
+    fell.sort_by_key(|c| (change(c), Reverse(c.1 + c.2)));
+    fell.truncate(CHANGES);
+    let mut new: Vec<(String, usize)> = changes
+        .iter()
+        .filter(|c| c.1 == 0)
+        .map(|(command, _, after)| (command.clone(), *after))
+        .collect();
+    let new_count = new.len();
+    new.truncate(CHANGES);
+
+    Comparison {
+        a,
+        b,
+        rose,
+        fell,
+        new,
+        new_count,
+    }
+}
+
+// How far `after` is from `before`, e.g. `+25%`, or `new` from nothing
+pub fn percent_change(before: f64, after: f64) -> String {
+    if before == 0.0 {
+        return if after == 0.0 { "0%" } else { "new" }.to_string();
+    }
+    let change = ((after - before) / before * 100.0).round() as i64;
+    if change > 0 {
+        format!("+{}%", change)
+    } else {
+        format!("{}%", change)
+    }
+}
+
+fn span(figures: &PeriodFigures) -> String {
+    let day = |timestamp: i64| {
+        Local
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    };
+    let (first, last) = (day(figures.start), day(figures.end));
+    if first == last {
+        first
+    } else {
+        format!("{} – {}", first, last)
+    }
+}
+
+// The comparison as `compare` prints it
+pub fn compare_report(comparison: &Comparison) -> String {
+    let (a, b) = (&comparison.a, &comparison.b);
+    let mut out = String::new();
+    let _ = writeln!(out, "A  {}", span(a));
+    let _ = writeln!(out, "B  {}\n", span(b));
+
+    let _ = writeln!(out, "{:<16}{:>8}{:>8}{:>9}", "", "A", "B", "Change");
+    let rows = [
+        (
+            "Commands",
+            a.total.to_string(),
+            b.total.to_string(),
+            a.total as f64,
+            b.total as f64,
+        ),
+        (
+            "Unique",
+            a.unique.to_string(),
+            b.unique.to_string(),
+            a.unique as f64,
+            b.unique as f64,
+        ),
+        (
+            "Active days",
+            a.active_days.to_string(),
+            b.active_days.to_string(),
+            a.active_days as f64,
+            b.active_days as f64,
+        ),
+        (
+            "Per active day",
+            format!("{:.1}", a.per_active_day()),
+            format!("{:.1}", b.per_active_day()),
+            a.per_active_day(),
+            b.per_active_day(),
+        ),
+    ];
+    for (name, shown_a, shown_b, before, after) in rows {
+        let _ = writeln!(
+            out,
+            "{:<16}{:>8}{:>8}{:>9}",
+            name,
+            shown_a,
+            shown_b,
+            percent_change(before, after)
+        );
+    }
+
+    let width = comparison
+        .rose
+        .iter()
+        .chain(&comparison.fell)
+        .map(|(command, _, _)| command.chars().count())
+        .chain(
+            comparison
+                .new
+                .iter()
+                .map(|(command, _)| command.chars().count()),
+        )
+        .max()
+        .unwrap_or(0);
+    for (title, changes) in [("Rose", &comparison.rose), ("Fell", &comparison.fell)] {
+        if changes.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "\n{}", title);
+        for (command, before, after) in changes {
+            let _ = writeln!(
+                out,
+                "  {:<width$}  {:>5} → {:<5} {}",
+                command,
+                before,
+                after,
+                percent_change(*before as f64, *after as f64),
+                width = width
+            );
+        }
+    }
+    if !comparison.new.is_empty() {
+        let _ = writeln!(out, "\nNew in B ({})", comparison.new_count);
+        for (command, count) in &comparison.new {
+            let _ = writeln!(out, "  {:<width$}  {:>5}", command, count, width = width);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_that_rose_fell_or_are_new() {
+        let day = 86400;
+        let run = |timestamp: i64, command: &str| HistoryEntry {
+            timestamp,
+            command: command.to_string(),
+            ..Default::default()
+        };
+        let mut entries = Vec::new();
+        // Week A: 4 builds and 3 tests over two days
+        entries.extend((0..4).map(|i| run(day + i, "cargo build")));
+        entries.extend((0..3).map(|i| run(2 * day + i, "cargo test")));
+        // Week B: 6 builds, 1 test and something new
+        entries.extend((0..6).map(|i| run(8 * day + i, "cargo build")));
+        entries.push(run(8 * day + 10, "cargo test"));
+        entries.push(run(8 * day + 20, "rg todo"));
+
+        let comparison = compare_periods(&entries, (0, 7 * day - 1), (7 * day, 14 * day - 1));
+        assert_eq!((comparison.a.total, comparison.b.total), (7, 8));
+        assert_eq!(comparison.b.unique, 3);
+        assert_eq!(comparison.rose, [("cargo build".to_string(), 4, 6)]);
+        assert_eq!(comparison.fell, [("cargo test".to_string(), 3, 1)]);
+        assert_eq!(comparison.new, [("rg todo".to_string(), 1)]);
+
+        assert_eq!(percent_change(4.0, 6.0), "+50%");
+        assert_eq!(percent_change(3.0, 1.0), "-67%");
+        assert_eq!(percent_change(0.0, 2.0), "new");
+        let report = compare_report(&comparison);
+        assert!(report.contains("\nCommands               7       8     +14%\n"));
+        assert!(report.contains("New in B (1)"));
+    }
+}
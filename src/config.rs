@@ -0,0 +1,734 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::ValueEnum;
+use crossterm::style::Color;
+use regex::Regex;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
+};
+
+use crate::app::Screen;
+use crate::error::ErrorKind;
+use crate::estimate::Estimate;
+use crate::frames::MAX_FPS;
+use crate::goals::Goal;
+use crate::history::{Shell, Source};
+use crate::normalize::parse_rules;
+use crate::parser::is_context_label;
+use crate::tagging::Tagger;
+use crate::theme::{parse_color, Palette};
+use crate::timeline::matches_command;
+use crate::ui_utils::{parse_size, PathStyle};
+
+// The built-in color presets for the TUIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Theme {
+    #[default]
+    #[value(alias = "color")]
+    Default,
+    Solarized,
+    // No colors at all, as with NO_COLOR
+    #[value(name = "monochrome", alias = "mono")]
+    Mono,
+}
+
+// How the history viewer lays out its list and details
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HistoryLayout {
+    // One at a time, Enter and Esc moving between them
+    #[default]
+    Separate,
+    // Side by side
+    Split,
+}
+
+// Settings from the config file. Command-line flags win over them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    // [history]: where to read from, as --shell, --source and --history-file
+    pub shell: Option<Shell>,
+    pub source: Option<Source>,
+    pub history_file: Option<PathBuf>,
+    // Commands left out of everything, matched like `timeline` matches them
+    // or, with `*` or `?` in them, as a pattern for the whole command
+    pub ignore: Vec<String>,
+    // Regexes for secrets to mask, on top of the built-in ones
+    pub redact: Vec<String>,
+    // How untimed commands get a time, as --estimate-timestamps
+    pub estimate_timestamps: Option<Estimate>,
+    // Commands a history can hold before loading it asks first, 0 for never
+    pub max_entries: Option<usize>,
+    // [ui]
+    pub theme: Theme,
+    // What runs when no subcommand is given
+    pub default_view: Option<Screen>,
+    // strftime pattern for dates in the views, in place of the locale's
+    pub date_format: Option<String>,
+    pub history_layout: HistoryLayout,
+    // Most redraws a second
+    pub fps: Option<u32>,
+    // Weeks before a period that its trend is measured against
+    pub trend_weeks: Option<i64>,
+    // Smallest terminals for the full and compact stats layouts
+    pub full_size: Option<(u16, u16)>,
+    pub compact_size: Option<(u16, u16)>,
+    pub path_style: PathStyle,
+    // [theme]: colors replacing the preset's, by their key
+    pub colors: Vec<(String, Color)>,
+    // [goals]: weekly targets shown in the stats screen
+    pub goals: Vec<Goal>,
+    // [tags]: `<regex> => <tag>` and `<directory> => <tag>` rules for
+    // commands recorded without a tag
+    pub tag_commands: Vec<String>,
+    pub tag_directories: Vec<String>,
+    // [categories]: `<name> = ["<program>", ...]`, adding to or moving
+    // programs between the built-in categories
+    pub categories: Vec<(String, Vec<String>)>,
+    // [normalize]: `<regex> => <replacement>` rewrites, in order, for the
+    // command rankings
+    pub normalize: Vec<String>,
+    // [ingest]: seconds between the imports of `ingest --watch`, and the
+    // battery percentage below which it pauses
+    pub ingest_interval: Option<u64>,
+    pub min_battery: Option<u8>,
+}
+
+impl Config {
+    pub fn ignores(&self, command: &str) -> bool {
+        self.ignore.iter().any(|ignored| {
+            if ignored.contains(['*', '?']) {
+                glob_matches(ignored.trim().as_bytes(), command.as_bytes())
+            } else {
+                matches_command(command, ignored)
+            }
+        })
+    }
+}
+
+// Shell-style: `*` for any run of characters, `?` for any one byte
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| glob_matches(rest, &text[skip..])),
+        Some((&p, rest)) => text
+            .split_first()
+            .is_some_and(|(&t, text)| (p == b'?' || p == t) && glob_matches(rest, text)),
+    }
+}
+
+// A quoted string, with the escapes a config is likely to need
+fn parse_string(value: &str) -> Option<(String, &str)> {
+    let mut chars = value.strip_prefix('"')?.char_indices();
+    let mut parsed = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((parsed, &value[i + 2..])),
+            '\\' => match chars.next()?.1 {
+                'n' => parsed.push('\n'),
+                't' => parsed.push('\t'),
+                escaped @ ('"' | '\\') => parsed.push(escaped),
+                _ => return None,
+            },
+            c => parsed.push(c),
+        }
+    }
+    None
+}
+
+// A string or an array of strings, then at most a comment
+fn parse_value(value: &str) -> Option<Vec<String>> {
+    let (values, rest) = match value.strip_prefix('[') {
+        Some(mut rest) => {
+            let mut values = Vec::new();
+            loop {
+                rest = rest.trim_start();
+                if let Some(after) = rest.strip_prefix(']') {
+                    break (values, after);
+                }
+                let (item, after) = parse_string(rest)?;
+                values.push(item);
+                rest = after.trim_start();
+                rest = rest.strip_prefix(',').unwrap_or(rest);
+            }
+        }
+        None => {
+            let (item, rest) = parse_string(value)?;
+            (vec![item], rest)
+        }
+    };
+    let rest = rest.trim_start();
+    (rest.is_empty() || rest.starts_with('#')).then_some(values)
+}
+
+fn one(key: &str, mut values: Vec<String>) -> Result<String> {
+    match values.len() {
+        1 => Ok(values.remove(0)),
+        _ => bail!("`{}` takes a single string", key),
+    }
+}
+
+// A number written as a string, like every other value, within `valid`
+fn parse_number<T: FromStr>(
+    key: &str,
+    values: Vec<String>,
+    valid: impl Fn(&T) -> bool,
+    what: &str,
+) -> Result<T> {
+    one(key, values)?
+        .trim()
+        .parse()
+        .ok()
+        .filter(valid)
+        .ok_or_else(|| anyhow!("`{}` must be {}", key, what))
+}
+
+fn size(key: &str, values: Vec<String>) -> Result<(u16, u16)> {
+    parse_size(&one(key, values)?)
+        .ok_or_else(|| anyhow!("`{}` must be a terminal size like 120x30", key))
+}
+
+fn choice<T: ValueEnum>(key: &str, value: &str) -> Result<T> {
+    T::from_str(value, true).map_err(|_| {
+        let choices: Vec<String> = T::value_variants()
+            .iter()
+            .filter_map(|v| v.to_possible_value())
+            .map(|v| v.get_name().to_string())
+            .collect();
+        anyhow!("`{}` must be one of {}", key, choices.join(", "))
+    })
+}
+
+// The TOML the config uses: `[history]`, `[ui]`, `[theme]`, `[goals]`,
+// `[tags]`, `[categories]`, `[normalize]` and `[ingest]` tables of strings and arrays of strings
+pub fn parse_config(source: &str) -> Result<Config> {
+    let mut config = Config::default();
+    let mut table = String::new();
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parsed = (|| -> Result<()> {
+            if let Some(name) = line.strip_prefix('[') {
+                let (name, rest) = name
+                    .split_once(']')
+                    .ok_or_else(|| anyhow!("Unclosed table"))?;
+                if !(rest.trim().is_empty() || rest.trim().starts_with('#')) {
+                    bail!("Unexpected text after [{}]", name);
+                }
+                table = name.trim().to_string();
+                if !matches!(
+                    table.as_str(),
+                    "history"
+                        | "ui"
+                        | "theme"
+                        | "goals"
+                        | "tags"
+                        | "categories"
+                        | "normalize"
+                        | "ingest"
+                ) {
+                    bail!("Unknown table [{}]", table);
+                }
+                return Ok(());
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Expected `key = value`"))?;
+            let key = key.trim();
+            let values = parse_value(value.trim())
+                .ok_or_else(|| anyhow!("`{}` needs a quoted string or a list of them", key))?;
+            match (table.as_str(), key) {
+                ("history", "shell") => config.shell = Some(choice(key, &one(key, values)?)?),
+                ("history", "source") => config.source = Some(choice(key, &one(key, values)?)?),
+                ("history", "file") => {
+                    let file = one(key, values)?;
+                    config.history_file = Some(match file.strip_prefix("~/") {
+                        Some(rest) => home::home_dir().unwrap_or_default().join(rest),
+                        None => PathBuf::from(file),
+                    });
+                }
+                ("history", "ignore") => config.ignore = values,
+                ("history", "redact") => {
+                    for pattern in &values {
+                        Regex::new(pattern)
+                            .map_err(|_| anyhow!("`redact` has an invalid regex: {}", pattern))?;
+                    }
+                    config.redact = values;
+                }
+                ("history", "estimate_timestamps") => {
+                    config.estimate_timestamps = Some(choice(key, &one(key, values)?)?)
+                }
+                ("history", "max_entries") => {
+                    config.max_entries = Some(parse_number(key, values, |_| true, "a number")?)
+                }
+                ("ui", "theme") => config.theme = choice(key, &one(key, values)?)?,
+                ("ui", "default_view") => {
+                    config.default_view = Some(match one(key, values)?.as_str() {
+                        "history" => Screen::History,
+                        "stats" => Screen::Stats,
+                        "today" => Screen::Today,
+                        _ => bail!("`default_view` must be history, stats or today"),
+                    })
+                }
+                ("ui", "date_format") => config.date_format = Some(one(key, values)?),
+                ("ui", "history_layout") => {
+                    config.history_layout = match one(key, values)?.as_str() {
+                        "separate" => HistoryLayout::Separate,
+                        "split" => HistoryLayout::Split,
+                        _ => bail!("`history_layout` must be separate or split"),
+                    }
+                }
+                ("ui", "fps") => {
+                    let what = format!("a number from 1 to {}", MAX_FPS);
+                    config.fps = Some(parse_number(
+                        key,
+                        values,
+                        |fps| (1..=MAX_FPS).contains(fps),
+                        &what,
+                    )?)
+                }
+                ("ui", "trend_weeks") => {
+                    config.trend_weeks = Some(parse_number(
+                        key,
+                        values,
+                        |&weeks| weeks > 0,
+                        "a number above 0",
+                    )?)
+                }
+                ("ui", "full_size") => config.full_size = Some(size(key, values)?),
+                ("ui", "compact_size") => config.compact_size = Some(size(key, values)?),
+                ("ui", "path_style") => config.path_style = choice(key, &one(key, values)?)?,
+                ("theme", name) => {
+                    let value = one(key, values)?;
+                    let color = parse_color(&value).ok_or_else(|| {
+                        anyhow!("`{}` needs a color name like dark_blue, or #rrggbb", key)
+                    })?;
+                    Palette::preset(Theme::Default).set(name, color)?;
+                    config.colors.push((name.to_string(), color));
+                }
+                ("goals", "weekly") => {
+                    config.goals = values
+                        .iter()
+                        .map(|goal| Goal::parse(goal))
+                        .collect::<Result<_>>()?
+                }
+                ("tags", "commands") => {
+                    Tagger::new(&values, &[])?;
+                    config.tag_commands = values;
+                }
+                ("tags", "directories") => {
+                    Tagger::new(&[], &values)?;
+                    config.tag_directories = values;
+                }
+                ("normalize", "rules") => {
+                    parse_rules(&values)?;
+                    config.normalize = values;
+                }
+                ("ingest", "interval") => {
+                    config.ingest_interval = Some(parse_number(
+                        key,
+                        values,
+                        |&seconds| seconds > 0,
+                        "a number of seconds above 0",
+                    )?)
+                }
+                ("ingest", "min_battery") => {
+                    config.min_battery = Some(parse_number(
+                        key,
+                        values,
+                        |&percent| percent <= 100,
+                        "a percentage from 0 to 100",
+                    )?)
+                }
+                ("categories", name) => {
+                    if !is_context_label(name) {
+                        bail!(
+                            "Category `{}` may only have letters, digits, -, _ and .",
+                            name
+                        );
+                    }
+                    config.categories.push((name.to_string(), values));
+                }
+                _ if table.is_empty() => bail!(
+                    "`{}` must be under [history], [ui], [theme], [goals], [tags], [categories], [normalize] or [ingest]",
+                    key
+                ),
+                _ => bail!("Unknown setting `{}` in [{}]", key, table),
+            }
+            Ok(())
+        })();
+        parsed
+            .map_err(|err| anyhow!("{} on line {}", err, number + 1))
+            .context(ErrorKind::Parse)?;
+    }
+    Ok(config)
+}
+
+// Every setting `config get` and `config set` take, as `table.key`
+pub const SETTINGS: &[&str] = &[
+    "history.shell",
+    "history.source",
+    "history.file",
+    "history.ignore",
+    "history.estimate_timestamps",
+    "history.max_entries",
+    "ui.theme",
+    "ui.default_view",
+    "ui.date_format",
+    "ui.history_layout",
+    "ui.fps",
+    "ui.trend_weeks",
+    "ui.full_size",
+    "ui.compact_size",
+    "ui.path_style",
+    "goals.weekly",
+    "ingest.interval",
+    "ingest.min_battery",
+];
+
+fn split_setting(setting: &str) -> Result<(&str, &str)> {
+    if !SETTINGS.contains(&setting) {
+        return Err(anyhow!(
+            "Unknown setting `{}`; one of {}",
+            setting,
+            SETTINGS.join(", ")
+        ))
+        .context(ErrorKind::Parse);
+    }
+    Ok(setting.split_once('.').unwrap_or_default())
+}
+
+// The key a `key = value` line sets, or the table a `[table]` line opens
+enum Line<'a> {
+    Table(&'a str),
+    Key(&'a str, &'a str),
+    Other,
+}
+
+fn classify(line: &str) -> Line<'_> {
+    let line = line.trim();
+    if let Some(name) = line.strip_prefix('[') {
+        return name
+            .split_once(']')
+            .map_or(Line::Other, |(name, _)| Line::Table(name.trim()));
+    }
+    match line.split_once('=') {
+        Some((key, value)) if !line.starts_with('#') => Line::Key(key.trim(), value.trim()),
+        _ => Line::Other,
+    }
+}
+
+// `setting` as written in the config file `source`, arrays joined with
+// commas, or None when it isn't set
+pub fn get_setting(source: &str, setting: &str) -> Result<Option<String>> {
+    let (table, key) = split_setting(setting)?;
+    let mut current = "";
+    for line in source.lines() {
+        match classify(line) {
+            Line::Table(name) => current = name,
+            Line::Key(name, value) if current == table && name == key => {
+                return Ok(parse_value(value).map(|values| values.join(", ")));
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+fn quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+// `source` with `setting` set to `value`, replacing the line that set it
+// before or adding one to its table, so comments and the rest of the file
+// stay as they were. `history.ignore` and `goals.weekly` take a
+// comma-separated list.
+pub fn set_setting(source: &str, setting: &str, value: &str) -> Result<String> {
+    let (table, key) = split_setting(setting)?;
+    let value = if matches!(setting, "history.ignore" | "goals.weekly") {
+        let items: Vec<String> = value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(quote)
+            .collect();
+        format!("[{}]", items.join(", "))
+    } else {
+        quote(value)
+    };
+    let assignment = format!("{} = {}", key, value);
+
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+    let mut current = String::new();
+    // Where the table's last setting is, to add the new one after
+    let mut table_end = None;
+    let mut replaced = false;
+    for (i, line) in lines.iter_mut().enumerate() {
+        match classify(line) {
+            Line::Table(name) => {
+                current = name.to_string();
+                if current == table {
+                    table_end = Some(i);
+                }
+            }
+            Line::Key(name, _) if current == table => {
+                if name == key {
+                    *line = assignment.clone();
+                    replaced = true;
+                    break;
+                }
+                table_end = Some(i);
+            }
+            _ => {}
+        }
+    }
+    if !replaced {
+        match table_end {
+            Some(i) => lines.insert(i + 1, assignment),
+            None => {
+                if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                    lines.push(String::new());
+                }
+                lines.push(format!("[{}]", table));
+                lines.push(assignment);
+            }
+        }
+    }
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    // Never write a file the next run would refuse
+    parse_config(&updated)?;
+    Ok(updated)
+}
+
+// Next to user translations
+pub fn config_path() -> Option<PathBuf> {
+    home::home_dir().map(|home| home.join(".config/cli-tracker/config.toml"))
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+// Read the config file, if there is one, for `config` to hand out. Done
+// once at startup so a mistake in it is reported before anything runs.
+pub fn load() -> Result<()> {
+    let Some(path) = config_path() else {
+        return Ok(());
+    };
+    let config = match fs::read_to_string(&path) {
+        Ok(source) => parse_config(&source).with_context(|| format!("In {}", path.display()))?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Config::default(),
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let _ = CONFIG.set(config);
+    Ok(())
+}
+
+fn read_config_file(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(source) => Ok(source),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(err).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+// `config get`
+pub fn read_setting(setting: &str) -> Result<Option<String>> {
+    let path = config_path().ok_or_else(|| anyhow!("Could not find home directory"))?;
+    get_setting(&read_config_file(&path)?, setting)
+}
+
+// `config set`, creating the file and its directory if need be
+pub fn write_setting(setting: &str, value: &str) -> Result<PathBuf> {
+    let path = config_path().ok_or_else(|| anyhow!("Could not find home directory"))?;
+    let updated = set_setting(&read_config_file(&path)?, setting, value)
+        .with_context(|| format!("In {}", path.display()))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    fs::write(&path, updated).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+// The loaded config, or the defaults if `load` wasn't called
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_history_and_ui_settings() {
+        let config = parse_config(
+            r#"
+            # Everything in one place
+            [history]
+            shell = "bash"
+            file = "/tmp/history" # not ~/.bash_history
+            ignore = ["ls", "git status", ]
+            estimate_timestamps = "previous"
+            max_entries = "0"
+
+            [ui]
+            theme = "mono"
+            default_view = "stats"
+            history_layout = "split"
+            fps = "60"
+            trend_weeks = "8"
+            full_size = "120x30"
+            path_style = "fish"
+            date_format = "%Y-%m-%d \"%H:%M\""
+
+            [theme]
+            selected = "dark_magenta"
+
+            [goals]
+            weekly = ["cargo test >= 30", "git push --force <= 5"]
+
+            [tags]
+            commands = ["^(kubectl|helm) => ops"]
+            directories = ["~/work => work"]
+
+            [categories]
+            cloud = ["aws", "gcloud"]
+
+            [normalize]
+            rules = ["^git commit -m .* => git commit -m <msg>"]
+
+            [ingest]
+            interval = "60"
+            min_battery = "0"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.shell, Some(Shell::Bash));
+        assert_eq!(config.history_file, Some(PathBuf::from("/tmp/history")));
+        assert_eq!(config.estimate_timestamps, Some(Estimate::Previous));
+        assert_eq!(
+            (config.theme, config.default_view),
+            (Theme::Mono, Some(Screen::Stats))
+        );
+        assert_eq!(config.date_format.as_deref(), Some("%Y-%m-%d \"%H:%M\""));
+        assert_eq!(config.history_layout, HistoryLayout::Split);
+        assert_eq!(config.fps, Some(60));
+        assert_eq!(config.max_entries, Some(0));
+        assert_eq!(config.trend_weeks, Some(8));
+        assert_eq!(
+            (config.full_size, config.compact_size),
+            (Some((120, 30)), None)
+        );
+        assert_eq!(config.path_style, PathStyle::Abbreviate);
+        assert_eq!(
+            config.colors,
+            [("selected".to_string(), Color::DarkMagenta)]
+        );
+        assert_eq!(config.goals[1].command, "git push --force");
+        assert_eq!(config.tag_directories, ["~/work => work"]);
+        assert_eq!(
+            config.normalize,
+            ["^git commit -m .* => git commit -m <msg>"]
+        );
+        assert_eq!(
+            config.categories,
+            [(
+                "cloud".to_string(),
+                vec!["aws".to_string(), "gcloud".to_string()]
+            )]
+        );
+        assert!(config.ignores("ls -la"));
+        assert!(config.ignores("git status"));
+        assert!(!config.ignores("git push"));
+        assert!(!config.ignores("lsof"));
+
+        let config = Config {
+            ignore: vec![
+                "cd *".to_string(),
+                "clear".to_string(),
+                "git ?ull*".to_string(),
+            ],
+            ..Default::default()
+        };
+        assert!(config.ignores("cd ~/src"));
+        assert!(!config.ignores("cd"));
+        assert!(config.ignores("clear"));
+        assert!(config.ignores("git pull --rebase"));
+        assert!(!config.ignores("git push"));
+
+        assert!(parse_config("theme = \"mono\"").is_err());
+        assert!(parse_config("[ui]\ntheme = mono").is_err());
+        assert!(parse_config("[history]\nshell = \"tcsh\"").is_err());
+        assert!(parse_config("[colors]").is_err());
+        assert_eq!(
+            parse_config("[ui]\ntheme = \"solarized\"").unwrap().theme,
+            Theme::Solarized
+        );
+        assert_eq!(
+            parse_config("[theme]\nheader = \"#268bd2\"")
+                .unwrap()
+                .colors,
+            [(
+                "header".to_string(),
+                Color::Rgb {
+                    r: 0x26,
+                    g: 0x8b,
+                    b: 0xd2
+                }
+            )]
+        );
+        assert!(parse_config("[theme]\nbackground = \"red\"").is_err());
+        assert!(parse_config("[ui]\nfps = \"0\"").is_err());
+        assert!(parse_config("[ui]\nfps = \"fast\"").is_err());
+        assert!(parse_config("[ui]\ntrend_weeks = \"0\"").is_err());
+        assert!(parse_config("[ui]\nfull_size = \"wide\"").is_err());
+        assert!(parse_config("[ui]\npath_style = \"short\"").is_err());
+        assert!(parse_config("[history]\nmax_entries = \"-1\"").is_err());
+        assert!(parse_config("[theme]\nheader = \"mauve\"").is_err());
+        assert!(parse_config("[history]\nredact = [\"(\"]").is_err());
+        assert!(parse_config("[goals]\nweekly = [\"make\"]").is_err());
+        assert!(parse_config("[tags]\ncommands = [\"^ssh\"]").is_err());
+        assert!(parse_config("[normalize]\nrules = [\"( => x\"]").is_err());
+        assert!(parse_config("[ingest]\ninterval = \"0\"").is_err());
+        assert!(parse_config("[ingest]\nmin_battery = \"101\"").is_err());
+    }
+
+    #[test]
+    fn gets_and_sets_settings_in_place() {
+        let source = "# mine\n[ui]\ntheme = \"mono\" # no colors\n\n[history]\nshell = \"zsh\"\n";
+        assert_eq!(
+            get_setting(source, "ui.theme").unwrap().as_deref(),
+            Some("mono")
+        );
+        assert_eq!(get_setting(source, "ui.date_format").unwrap(), None);
+        assert!(get_setting(source, "ui.colour").is_err());
+
+        let updated = set_setting(source, "ui.theme", "color").unwrap();
+        assert_eq!(
+            updated,
+            "# mine\n[ui]\ntheme = \"color\"\n\n[history]\nshell = \"zsh\"\n"
+        );
+        let updated = set_setting(&updated, "ui.date_format", "%d \"%b\"").unwrap();
+        assert!(updated.starts_with("# mine\n[ui]\ntheme = \"color\"\ndate_format = "));
+        assert_eq!(
+            parse_config(&updated).unwrap().date_format.as_deref(),
+            Some("%d \"%b\"")
+        );
+        let updated = set_setting(&updated, "history.ignore", "ls, git status").unwrap();
+        assert_eq!(
+            get_setting(&updated, "history.ignore").unwrap().as_deref(),
+            Some("ls, git status")
+        );
+
+        let created = set_setting("", "ui.default_view", "stats").unwrap();
+        assert_eq!(created, "[ui]\ndefault_view = \"stats\"\n");
+        assert!(set_setting(source, "ui.theme", "neon").is_err());
+    }
+}
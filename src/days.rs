@@ -1,800 +1,188 @@
-use anyhow::Result;
-use chrono::{Datelike, Local, TimeZone, Timelike};
-use crossterm::{
-    cursor,
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
-    execute,
-    style::{Color, Stylize},
-    terminal::{self, ClearType},
-};
-use std::io::{self, Write};
-use unicode_width::UnicodeWidthStr;
-
-use crate::history::{format_timestamp, HistoryEntry};
-use crate::ui_utils::draw_box;
-
-pub fn display_today_stats(entries: &[HistoryEntry]) -> Result<()> {
-    let mut stdout = io::stdout();
-
-    // Set up terminal
-    execute!(stdout, terminal::EnterAlternateScreen)?;
-    terminal::enable_raw_mode()?;
-    execute!(stdout, cursor::Hide)?;
-
-    // Track current view: -1 = lifetime stats, 0 = current week, 1 = last week, etc.
-    let mut week_offset: i64 = -1;
+use chrono::{Local, NaiveDate, TimeZone, Timelike};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::aggregate::sorted_counts;
+use crate::history::HistoryEntry;
+
+// How many of a day's commands `DayStats` ranks
+const TOP_COMMANDS: usize = 5;
+
+// Everything known about one local calendar day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayStats {
+    pub date: NaiveDate,
+    pub count: usize,
+    pub unique: usize,
+    // The day's most run commands, busiest first
+    pub top_commands: Vec<(String, usize)>,
+    // Hours of the day (0-23) with at least one command, earliest first
+    pub active_hours: Vec<u32>,
+}
 
-    loop {
-        // Get terminal size
-        let (term_width, term_height) = terminal::size()?;
+// The local date a command ran on. Entries without a timestamp have none.
+pub fn local_day(timestamp: i64) -> Option<NaiveDate> {
+    if timestamp <= 0 {
+        return None;
+    }
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|dt| dt.date_naive())
+}
 
-        // Check minimum terminal size requirements
-        let min_width = 100;
-        let min_height = 20;
-        if term_width < min_width || term_height < min_height {
-            execute!(
-                stdout,
-                terminal::Clear(ClearType::All),
-                cursor::MoveTo(0, 0)
-            )?;
-            write!(
-                stdout,
-                "Terminal too small. Please resize to at least {}x{}",
-                min_width, min_height
-            )?;
-            stdout.flush()?;
+// Commands per local day, for callers that only need the totals
+pub fn day_counts<'a>(
+    entries: impl IntoIterator<Item = &'a HistoryEntry>,
+) -> BTreeMap<NaiveDate, usize> {
+    let mut counts = BTreeMap::new();
+    for day in entries.into_iter().filter_map(|e| local_day(e.timestamp)) {
+        *counts.entry(day).or_insert(0) += 1;
+    }
+    counts
+}
 
-            // Wait for input and check if terminal has been resized
-            if let Event::Key(KeyEvent {
-                code, modifiers, ..
-            }) = event::read()?
-            {
-                match code {
-                    KeyCode::Esc => break,
-                    KeyCode::Char('c') => {
-                        if modifiers.contains(KeyModifiers::CONTROL) {
-                            break;
-                        }
-                    }
-                    _ => {}
-                }
-            }
+// One entry per day with any timestamped commands, oldest day first
+pub fn daily_stats<'a>(entries: impl IntoIterator<Item = &'a HistoryEntry>) -> Vec<DayStats> {
+    let mut days: BTreeMap<NaiveDate, (HashMap<&str, usize>, [bool; 24])> = BTreeMap::new();
+    for entry in entries {
+        if entry.timestamp <= 0 {
             continue;
         }
-
-        // Clear screen
-        execute!(stdout, terminal::Clear(ClearType::All))?;
-
-        // Calculate line allocation based on available height
-        // 1 line for header
-        // 6 lines for borders (3 box layers * 2 border lines each)
-        // Remaining lines for content
-
-        // Total available height
-        let usable_height = term_height;
-        let header_lines = 1;
-        let border_lines = 6; // 3 box layers * 2 border lines each
-
-        // Calculate remaining lines for content
-        let content_lines = usable_height
-            .saturating_sub(header_lines)
-            .saturating_sub(border_lines);
-
-        // Time patterns content (reduced from 4 to 2 since we removed a line)
-        let time_patterns_min = 2;
-        let time_patterns_max = 3;
-
-        // Middle layer content (start with 3, max 10)
-        let middle_layer_min = 3;
-        let middle_layer_max = 10;
-
-        // Apply priority-based allocation:
-        // 1. Ensure we have enough lines for minimum allocation
-        // 2. First allocate minimum to each layer
-        // 3. Then grow Time Patterns to max if possible
-        // 4. Then grow middle layer up to max
-        // 5. Any extra goes to top layer (though it's capped at its max)
-
-        // Update top layer max to 6 to accommodate additional content line
-        let top_layer_max = 5;
-
-        // When terminal height is limited, reduce middle box height
-        let adjusted_middle_layer_min = if term_height <= 20 {
-            2 // Reduce by 1 when height is limited
-        } else {
-            middle_layer_min
-        };
-
-        let adjusted_middle_layer_max = if term_height <= 20 {
-            middle_layer_max - 1 // Reduce max by 1 for limited height
-        } else {
-            middle_layer_max
-        };
-
-        // Start with minimum allocation using adjusted values
-        let base_allocation = top_layer_max + adjusted_middle_layer_min + time_patterns_min;
-
-        // Determine how many extra lines we have beyond base allocation
-        let extra_lines = content_lines.saturating_sub(base_allocation).min(20); // Cap extra at 20 to avoid excessive growth
-
-        // Allocate additional lines according to priority
-        let time_patterns_extra = (time_patterns_max - time_patterns_min).min(extra_lines);
-        let time_patterns_content = time_patterns_min + time_patterns_extra;
-
-        let middle_extra = if extra_lines > time_patterns_extra {
-            (adjusted_middle_layer_max - adjusted_middle_layer_min)
-                .min(extra_lines - time_patterns_extra)
-        } else {
-            0
-        };
-        let middle_layer_content = adjusted_middle_layer_min + middle_extra;
-
-        // Top layer stays at max (already allocated in base_allocation)
-        let top_layer_content = top_layer_max;
-
-        // Calculate box heights (content + borders)
-        let top_box_height = top_layer_content + 2; // +2 for borders
-        let middle_box_height = middle_layer_content + 2; // +2 for borders
-        let bottom_box_height = time_patterns_content + 2; // +2 for borders
-
-        // Set command list limits based on available space
-        let commands_box_height = middle_box_height;
-        let max_commands = middle_layer_content as usize;
-        let max_categories = max_commands;
-
-        // Calculate widths to use the full terminal width
-        // Account for the border between columns (1 character)
-        let usable_width = term_width;
-        let half_width = usable_width / 2;
-
-        // Calculate precise widths for left and right boxes
-        let left_box_width = half_width;
-        let right_box_width = usable_width - half_width;
-
-        // Define the active entries based on current view
-        let (view_name, active_entries): (String, Vec<&HistoryEntry>) = if week_offset < 0 {
-            // Lifetime stats view
-            ("All-time Stats".to_string(), entries.iter().collect())
-        } else {
-            // Week-specific view
-            let now = chrono::Local::now();
-
-            // Calculate the start of the current week (Monday at 00:00:00)
-            let days_since_monday = now.weekday().num_days_from_monday() as i64;
-            let start_of_week = now
-                .date_naive()
-                .and_hms_opt(0, 0, 0)
-                .unwrap()
-                .and_local_timezone(chrono::Local)
-                .unwrap()
-                - chrono::Duration::days(days_since_monday)
-                - chrono::Duration::days(7 * week_offset);
-
-            // End of week is start of next week minus 1 second
-            let end_of_week =
-                start_of_week + chrono::Duration::days(7) - chrono::Duration::seconds(1);
-
-            // Get ISO week number of the year (1-52/53)
-            let week_number = start_of_week.iso_week().week();
-
-            // Format month abbreviation
-            let month_name = start_of_week.format("%b").to_string();
-
-            // Create view name in format "Week # [Month]"
-            let view_name = format!("Week {} [{}]", week_number, month_name);
-
-            // Filter entries for specific week
-            let week_entries = entries
-                .iter()
-                .filter(|e| {
-                    let ts = e.timestamp;
-                    ts >= start_of_week.timestamp() && ts <= end_of_week.timestamp()
-                })
-                .collect();
-
-            (view_name, week_entries)
-        };
-
-        // Header with view name
-        execute!(stdout, cursor::MoveTo(0, 0))?;
-
-        // Get the terminal width to properly center the controls text
-        let controls_text = "<←/h: prev, →/l: next, esc/q: exit>".dark_grey();
-        let left_text = format!("CLI Wrapped: {}", view_name).cyan().bold();
-        let right_text = format!("commands: {}", active_entries.len()).cyan();
-
-        // Calculate positions to ensure proper centering
-        let right_start = term_width.saturating_sub(right_text.to_string().width() as u16);
-        let center_start = half_width - (controls_text.to_string().width() as u16 / 2);
-
-        // Write the left part
-        write!(stdout, "{}", left_text)?;
-
-        // Write the centered controls
-        execute!(stdout, cursor::MoveTo(center_start, 0))?;
-        write!(stdout, "{}", controls_text)?;
-
-        // Write the right part
-        execute!(stdout, cursor::MoveTo(right_start, 0))?;
-        write!(stdout, "{}", right_text)?;
-
-        // Calculate time span and metrics for the active view
-        let oldest = active_entries
-            .iter()
-            .map(|e| e.timestamp)
-            .filter(|&ts| ts > 0)
-            .min()
-            .unwrap_or(0);
-        let newest = active_entries
-            .iter()
-            .map(|e| e.timestamp)
-            .filter(|&ts| ts > 0)
-            .max()
-            .unwrap_or(0);
-        let days = if newest > 0 && oldest > 0 {
-            ((newest - oldest) / 86400) + 1
-        } else if active_entries.len() > 0 {
-            // If we have entries but no valid timestamps, assume at least 1 day
-            1
-        } else {
-            0
-        };
-
-        // Count commands with valid timestamps
-        let commands_with_timestamps = active_entries.iter().filter(|e| e.timestamp > 0).count();
-
-        // Total commands including those without timestamps
-        let total_commands = active_entries.len();
-
-        // Time metrics for the current week
-        let now = chrono::Local::now();
-
-        // For specific week view, calculate the start/end of the selected week
-        let (this_week_start, this_week_end) = if week_offset >= 0 {
-            let days_since_monday = now.weekday().num_days_from_monday() as i64;
-            let start_of_week = now
-                .date_naive()
-                .and_hms_opt(0, 0, 0)
-                .unwrap()
-                .and_local_timezone(chrono::Local)
-                .unwrap()
-                - chrono::Duration::days(days_since_monday)
-                - chrono::Duration::days(7 * week_offset);
-
-            let end_of_week =
-                start_of_week + chrono::Duration::days(7) - chrono::Duration::seconds(1);
-
-            (start_of_week.timestamp(), end_of_week.timestamp())
-        } else {
-            // For all-time view, use current week
-            let days_since_monday = now.weekday().num_days_from_monday() as i64;
-            let start_of_week = now
-                .date_naive()
-                .and_hms_opt(0, 0, 0)
-                .unwrap()
-                .and_local_timezone(chrono::Local)
-                .unwrap()
-                - chrono::Duration::days(days_since_monday);
-
-            let end_of_week =
-                start_of_week + chrono::Duration::days(7) - chrono::Duration::seconds(1);
-
-            (start_of_week.timestamp(), end_of_week.timestamp())
-        };
-
-        // For specific week view, calculate the start/end of the month containing the selected week
-        let (this_month_start, this_month_end) = if week_offset >= 0 {
-            let days_since_monday = now.weekday().num_days_from_monday() as i64;
-            let selected_week_day = now
-                - chrono::Duration::days(days_since_monday)
-                - chrono::Duration::days(7 * week_offset);
-
-            let start_of_month = selected_week_day
-                .with_day(1)
-                .unwrap()
-                .with_hour(0)
-                .unwrap()
-                .with_minute(0)
-                .unwrap()
-                .with_second(0)
-                .unwrap();
-
-            // End of month is start of next month minus 1 second
-            let next_month = if start_of_month.month() == 12 {
-                start_of_month
-                    .with_month(1)
-                    .unwrap()
-                    .with_year(start_of_month.year() + 1)
-                    .unwrap()
-            } else {
-                start_of_month
-                    .with_month(start_of_month.month() + 1)
-                    .unwrap()
-            };
-
-            let end_of_month = next_month - chrono::Duration::seconds(1);
-
-            (start_of_month.timestamp(), end_of_month.timestamp())
-        } else {
-            // For all-time view, use current month
-            let start_of_month = now
-                .with_day(1)
-                .unwrap()
-                .with_hour(0)
-                .unwrap()
-                .with_minute(0)
-                .unwrap()
-                .with_second(0)
-                .unwrap();
-
-            // End of month is start of next month minus 1 second
-            let next_month = if start_of_month.month() == 12 {
-                start_of_month
-                    .with_month(1)
-                    .unwrap()
-                    .with_year(start_of_month.year() + 1)
-                    .unwrap()
-            } else {
-                start_of_month
-                    .with_month(start_of_month.month() + 1)
-                    .unwrap()
-            };
-
-            let end_of_month = next_month - chrono::Duration::seconds(1);
-
-            (start_of_month.timestamp(), end_of_month.timestamp())
+        let Some(dt) = Local.timestamp_opt(entry.timestamp, 0).single() else {
+            continue;
         };
+        let (commands, hours) = days.entry(dt.date_naive()).or_default();
+        *commands.entry(entry.command.as_str()).or_insert(0) += 1;
+        hours[dt.hour() as usize] = true;
+    }
 
-        // Get today's date for the "today" metric
-        let today_start = now
-            .date_naive()
-            .and_hms_opt(0, 0, 0)
-            .unwrap()
-            .and_local_timezone(chrono::Local)
-            .unwrap()
-            .timestamp();
-
-        // Count commands for different time periods, specific to the view
-        let commands_today = entries
-            .iter()
-            .filter(|e| e.timestamp >= today_start)
-            .count();
-
-        let commands_this_week = entries
-            .iter()
-            .filter(|e| e.timestamp >= this_week_start && e.timestamp <= this_week_end)
-            .count();
-
-        let commands_this_month = entries
-            .iter()
-            .filter(|e| e.timestamp >= this_month_start && e.timestamp <= this_month_end)
-            .count();
+    days.into_iter()
+        .map(|(date, (commands, hours))| {
+            let count = commands.values().sum();
+            let unique = commands.len();
+            let mut top_commands = sorted_counts(commands);
+            top_commands.truncate(TOP_COMMANDS);
+            DayStats {
+                date,
+                count,
+                unique,
+                top_commands,
+                active_hours: (0..24).filter(|&hour| hours[hour as usize]).collect(),
+            }
+        })
+        .collect()
+}
 
-        // Top Left Box - General Statistics
-        draw_box(
-            &mut stdout,
-            0,
-            1,
-            left_box_width,
-            top_box_height,
-            Some("General Statistics"),
-        )?;
+// Runs of days with and without commands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Streaks {
+    // Days in a row up to today, or up to yesterday while today has none yet
+    pub current: usize,
+    pub longest: usize,
+    // First day of the longest streak, the earliest if there are several
+    pub longest_from: Option<NaiveDate>,
+    // Most days in a row without a command, between two days with some
+    pub longest_gap: usize,
+    pub gap_from: Option<NaiveDate>,
+}
 
-        // Different stats depending on view
-        let general_stats = if week_offset < 0 {
-            // Lifetime stats
-            [
-                ("Today", commands_today.to_string()),
-                ("This week", commands_this_week.to_string()),
-                ("This month", commands_this_month.to_string()),
-                ("Weekly average", {
-                    if days == 0 {
-                        "0".to_string()
-                    } else {
-                        // Calculate weeks since first command
-                        let weeks = (days as f64 / 7.0).ceil().max(1.0);
-                        // Use commands_with_timestamps for accurate time-based average
-                        format!("{:.1}", commands_with_timestamps as f64 / weeks)
-                    }
-                }),
-                (
-                    "Unique commands",
-                    active_entries
-                        .iter()
-                        .map(|e| &e.command)
-                        .collect::<std::collections::HashSet<_>>()
-                        .len()
-                        .to_string(),
-                ),
-            ]
+// Streaks in the days that had commands
+pub fn streaks(active: &BTreeMap<NaiveDate, usize>, today: NaiveDate) -> Streaks {
+    let mut streaks = Streaks::default();
+    let mut run = 0;
+    let mut run_from = None;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in active.keys() {
+        let gap = previous.map_or(0, |previous| (day - previous).num_days() as usize - 1);
+        if previous.is_some() && gap == 0 {
+            run += 1;
         } else {
-            // Weekly stats
-            [
-                ("Today", commands_today.to_string()),
-                ("This week", commands_this_week.to_string()),
-                ("This month", commands_this_month.to_string()),
-                ("Commands per day", {
-                    if days > 0 {
-                        format!("{:.1}", active_entries.len() as f64 / days as f64)
-                    } else {
-                        "0".to_string()
-                    }
-                }),
-                (
-                    "Unique commands",
-                    active_entries
-                        .iter()
-                        .map(|e| &e.command)
-                        .collect::<std::collections::HashSet<_>>()
-                        .len()
-                        .to_string(),
-                ),
-            ]
-        };
-
-        for (i, (key, value)) in general_stats.iter().enumerate() {
-            execute!(stdout, cursor::MoveTo(3, 2 + i as u16))?;
-            write!(stdout, "{:<14} {}", key.with(Color::DarkGrey), value)?;
+            run = 1;
+            run_from = Some(day);
         }
-
-        // Top Right Box - Command Categories (Moved from Middle Right)
-        draw_box(
-            &mut stdout,
-            left_box_width,
-            1, // Moved to top row (y=1)
-            right_box_width,
-            top_box_height, // Use height of top row boxes
-            Some("Command Categories"),
-        )?;
-
-        let mut categories: std::collections::HashMap<&str, usize> =
-            std::collections::HashMap::new();
-        for entry in &active_entries {
-            let first_word = entry.command.split_whitespace().next().unwrap_or("other");
-            *categories.entry(first_word).or_insert(0) += 1;
+        if run > streaks.longest {
+            streaks.longest = run;
+            streaks.longest_from = run_from;
         }
-
-        // Sort by frequency
-        let mut categories: Vec<_> = categories.into_iter().collect();
-        categories.sort_by(|a, b| b.1.cmp(&a.1));
-
-        // Display top categories with percentage bars (limited by top_layer_content)
-        for (i, (category, count)) in categories
-            .iter()
-            .take(top_layer_content as usize)
-            .enumerate()
-        {
-            let percentage = if active_entries.is_empty() {
-                0
-            } else {
-                (*count as f64 / active_entries.len() as f64 * 100.0) as usize
-            };
-
-            // Ensure we have a fixed width for the category name
-            let category_display = if category.len() > 10 {
-                format!("{}...", &category[..7])
-            } else {
-                format!("{:<10}", category)
-            };
-
-            execute!(
-                stdout,
-                cursor::MoveTo(left_box_width + 3, 2 + i as u16) // Use top row y coordinate base (2)
-            )?;
-            write!(stdout, "{} ", category_display)?;
-
-            // Calculate bar width based on available space
-            let max_bar_width = (right_box_width as usize).saturating_sub(20);
-            let bar_width = (percentage * max_bar_width / 100).min(max_bar_width);
-            // Use a clearer bar character for better visibility
-            let dots = "█".repeat(bar_width);
-            write!(stdout, "{} {}%", dots, percentage)?;
-        }
-
-        // Middle Left Box - Most Used Directories (Moved from Middle Right)
-        draw_box(
-            &mut stdout,
-            0, // Moved to left column (x=0)
-            top_box_height + 1,
-            left_box_width, // Use width of left column
-            commands_box_height,
-            Some("Most Used Directories"),
-        )?;
-
-        // Count directory frequency
-        let mut directory_counts: std::collections::HashMap<String, usize> =
-            std::collections::HashMap::new();
-        for entry in &active_entries {
-            if let Some(dir) = &entry.directory {
-                *directory_counts.entry(dir.clone()).or_insert(0) += 1;
-            }
+        if gap > streaks.longest_gap {
+            streaks.longest_gap = gap;
+            streaks.gap_from = previous.and_then(|previous| previous.succ_opt());
         }
+        previous = Some(day);
+    }
+    let ongoing = previous.is_some_and(|last| last == today || last.succ_opt() == Some(today));
+    if ongoing {
+        streaks.current = run;
+    }
+    streaks
+}
 
-        // Sort by frequency
-        let mut directory_counts: Vec<_> = directory_counts.into_iter().collect();
-        directory_counts.sort_by(|a, b| b.1.cmp(&a.1));
-
-        // Display top directories (limited by max_commands)
-        for (i, (dir, count)) in directory_counts.iter().take(max_commands).enumerate() {
-            let display_width = left_box_width.saturating_sub(15) as usize; // Use left_box_width for truncation
-            let truncated_dir = if dir.len() > display_width {
-                format!("{}...", &dir[0..display_width - 3])
-            } else {
-                dir.to_string()
-            };
-
-            execute!(stdout, cursor::MoveTo(3, top_box_height + 2 + i as u16))?;
-            write!(stdout, "{:2}. {} ", i + 1, truncated_dir)?;
-
-            execute!(
-                stdout,
-                cursor::MoveTo(left_box_width - 10, top_box_height + 2 + i as u16) // Position count relative to left_box_width
-            )?;
-            write!(stdout, "{}", count.to_string().with(Color::DarkGrey))?;
-        }
-
-        // Middle Right Box - Most Used Commands (Moved from Middle Left)
-        draw_box(
-            &mut stdout,
-            left_box_width, // Moved to right column
-            top_box_height + 1,
-            right_box_width, // Use width of right column
-            commands_box_height,
-            Some("Most Used Commands"),
-        )?;
-
-        // Count command frequency
-        let mut command_counts: std::collections::HashMap<&str, usize> =
-            std::collections::HashMap::new();
-        for entry in &active_entries {
-            *command_counts.entry(&entry.command).or_insert(0) += 1;
-        }
-
-        // Sort by frequency
-        let mut command_counts: Vec<_> = command_counts.into_iter().collect();
-        command_counts.sort_by(|a, b| b.1.cmp(&a.1));
-
-        // Display top commands (limited by max_commands)
-        for (i, (cmd, count)) in command_counts.iter().take(max_commands).enumerate() {
-            let display_width = right_box_width.saturating_sub(15) as usize; // Use right_box_width for truncation
-            let truncated_cmd = if cmd.len() > display_width {
-                format!("{}...", &cmd[0..display_width - 3])
-            } else {
-                cmd.to_string()
-            };
-
-            execute!(
-                stdout,
-                cursor::MoveTo(left_box_width + 3, top_box_height + 2 + i as u16)
-            )?;
-            write!(stdout, "{:2}. {} ", i + 1, truncated_cmd)?;
-
-            execute!(
-                stdout,
-                cursor::MoveTo(
-                    left_box_width + right_box_width - 10,
-                    top_box_height + 2 + i as u16
-                ) // Position count relative to total width
-            )?;
-            write!(stdout, "{}", count.to_string().with(Color::DarkGrey))?;
-        }
-
-        // Bottom Box - Time Patterns
-        let bottom_y = 1 + top_box_height + commands_box_height;
-        draw_box(
-            &mut stdout,
-            0,
-            bottom_y,
-            usable_width, // Use the full width for the bottom box
-            bottom_box_height,
-            Some("Time Patterns"),
-        )?;
-
-        // Count by hour of day
-        let mut hour_counts = vec![0; 24];
-        for entry in active_entries.iter().filter(|e| e.timestamp > 0) {
-            let dt = Local.timestamp_opt(entry.timestamp, 0);
-            if let chrono::LocalResult::Single(dt) = dt {
-                let hour = dt.hour() as usize;
-                if hour < 24 {
-                    hour_counts[hour] += 1;
-                }
-            }
-        }
-
-        // Calculate average usage per hour
-        let total_usage: i32 = hour_counts.iter().sum();
-        let active_hours = hour_counts.iter().filter(|&&count| count > 0).count();
-        let avg_usage = if active_hours > 0 {
-            total_usage as f64 / active_hours as f64
-        } else {
-            0.0
-        };
-
-        // Find peak hour of day
-        let (peak_hour, peak_count) = hour_counts
-            .iter()
-            .enumerate()
-            .max_by_key(|&(_, count)| count)
-            .unwrap_or((0, &0));
-
-        // Find peak day of week
-        let mut day_of_week_counts = vec![0; 7];
-
-        // Filter to get only entries with valid timestamps
-        let entries_with_timestamps: Vec<&HistoryEntry> = active_entries
-            .iter()
-            .filter(|e| e.timestamp > 0)
-            .copied()
-            .collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        for entry in &entries_with_timestamps {
-            let dt = Local.timestamp_opt(entry.timestamp, 0);
-            if let chrono::LocalResult::Single(dt) = dt {
-                let weekday = dt.weekday().num_days_from_monday() as usize;
-                if weekday < 7 {
-                    day_of_week_counts[weekday] += 1;
-                }
-            }
+    fn run(day: u32, hour: u32, command: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: Local
+                .with_ymd_and_hms(2024, 3, day, hour, 15, 0)
+                .unwrap()
+                .timestamp(),
+            command: command.to_string(),
+            ..Default::default()
         }
+    }
 
-        let (peak_day_idx, peak_day_count) = day_of_week_counts
-            .iter()
-            .enumerate()
-            .max_by_key(|&(_, count)| count)
-            .unwrap_or((0, &0));
-
-        let weekdays = [
-            "Monday",
-            "Tuesday",
-            "Wednesday",
-            "Thursday",
-            "Friday",
-            "Saturday",
-            "Sunday",
+    #[test]
+    fn days_are_counted_ranked_and_ordered() {
+        let entries = [
+            run(5, 9, "git status"),
+            run(4, 23, "make"),
+            run(5, 9, "cargo test"),
+            run(5, 17, "git status"),
+            // No timestamp, so no day
+            HistoryEntry {
+                command: "ls".to_string(),
+                ..Default::default()
+            },
         ];
-        let peak_day = weekdays[peak_day_idx];
-
-        // Display peak times with consistent spacing
-        execute!(stdout, cursor::MoveTo(3, bottom_y + 1))?;
-        if *peak_count > 0 {
-            write!(
-                stdout,
-                "Peak hour: {:02}:00 ({} commands)",
-                peak_hour, peak_count
-            )?;
-        } else {
-            write!(stdout, "Peak hour: None")?;
-        }
-
-        execute!(stdout, cursor::MoveTo(3, bottom_y + 2))?;
-        if *peak_day_count > 0 {
-            write!(
-                stdout,
-                "Peak day: {} ({} commands)",
-                peak_day, peak_day_count
-            )?;
-        } else {
-            write!(stdout, "Peak day: None")?;
-        }
-
-        // Day of week distribution with better alignment
-        execute!(stdout, cursor::MoveTo(3, bottom_y + 3))?;
-        write!(stdout, "Day distribution: ")?;
-
-        let days = ["M", "T", "W", "T", "F", "S", "S"];
-        let distribution_start_x = 22; // Slightly adjust the starting position
-        let day_spacing = 7; // Consistent spacing between day percentages
-
-        // Calculate total from day_of_week_counts to ensure percentages add up to 100%
-        let total_days_count: usize = day_of_week_counts.iter().sum();
-        let mut percentages = vec![0; 7];
-        let mut float_percentages = vec![0.0; 7];
-        let mut sum = 0;
-
-        if total_days_count > 0 {
-            for (i, &count) in day_of_week_counts.iter().enumerate() {
-                let pct = (count as f64 / total_days_count as f64) * 100.0;
-                float_percentages[i] = pct;
-                percentages[i] = pct.round() as i32;
-                sum += percentages[i];
-            }
-            // Adjust so total is exactly 100%
-            if sum != 100 {
-                // Find the index with the largest fractional part
-                let mut diffs: Vec<(usize, f64)> = float_percentages
-                    .iter()
-                    .enumerate()
-                    .map(|(i, &f)| (i, f - (percentages[i] as f64)))
-                    .collect();
-                if sum < 100 {
-                    // Add to the day with the largest positive remainder
-                    diffs.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-                    for _ in 0..(100 - sum) {
-                        percentages[diffs[0].0] += 1;
-                    }
-                } else if sum > 100 {
-                    // Subtract from the day with the smallest (most negative) remainder
-                    diffs.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-                    for _ in 0..(sum - 100) {
-                        percentages[diffs[0].0] -= 1;
-                    }
-                }
+        let days = daily_stats(&entries);
+        let date = |day| NaiveDate::from_ymd_opt(2024, 3, day).unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!((days[0].date, days[0].count), (date(4), 1));
+        assert_eq!(
+            days[1],
+            DayStats {
+                date: date(5),
+                count: 3,
+                unique: 2,
+                top_commands: vec![("git status".to_string(), 2), ("cargo test".to_string(), 1)],
+                active_hours: vec![9, 17],
             }
-        }
-
-        for (i, &pct) in percentages.iter().enumerate() {
-            execute!(
-                stdout,
-                cursor::MoveTo(distribution_start_x + i as u16 * day_spacing, bottom_y + 3)
-            )?;
-            write!(stdout, "{}:{}%", days[i], pct)?;
-        }
+        );
 
-        // Wait for user input
-        stdout.flush()?;
+        let counts = day_counts(&entries);
+        assert_eq!(
+            counts.into_iter().collect::<Vec<_>>(),
+            [(date(4), 1), (date(5), 3)]
+        );
+    }
 
-        // Handle key presses
-        match event::read()? {
-            Event::Key(KeyEvent {
-                code: KeyCode::Esc, ..
-            })
-            | Event::Key(KeyEvent {
-                code: KeyCode::Char('q'),
-                ..
-            }) => break,
-            Event::Key(KeyEvent {
-                code: KeyCode::Left,
-                ..
-            })
-            | Event::Key(KeyEvent {
-                code: KeyCode::Char('h'),
-                ..
-            }) => {
-                // Go back (all-time -> current week -> previous weeks)
-                if week_offset < 0 {
-                    // When in all-time view, switch to current week
-                    week_offset = 0;
-                } else {
-                    // When in a week view, go back one week (increase offset)
-                    week_offset += 1;
-                }
-                continue; // Force immediate refresh of the display
-            }
-            Event::Key(KeyEvent {
-                code: KeyCode::Right,
-                ..
-            })
-            | Event::Key(KeyEvent {
-                code: KeyCode::Char('l'),
-                ..
-            }) => {
-                // Go forward (previous weeks -> current week -> all-time)
-                if week_offset > 0 {
-                    // When viewing past weeks, move forward one week (decrease offset)
-                    week_offset -= 1;
-                } else if week_offset == 0 {
-                    // When viewing current week, go to all-time view
-                    week_offset = -1;
-                }
-                continue; // Force immediate refresh of the display
+    #[test]
+    fn streaks_and_gaps() {
+        let date = |day| NaiveDate::from_ymd_opt(2024, 3, day).unwrap();
+        let active: BTreeMap<NaiveDate, usize> =
+            [1, 2, 3, 7, 8, 12, 13].map(|day| (date(day), 1)).into();
+        assert_eq!(
+            streaks(&active, date(14)),
+            Streaks {
+                current: 2,
+                longest: 3,
+                longest_from: Some(date(1)),
+                longest_gap: 3,
+                gap_from: Some(date(4)),
             }
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('c'),
-                modifiers,
-                ..
-            }) => {
-                if modifiers.contains(KeyModifiers::CONTROL) {
-                    break;
-                }
-            }
-            _ => {}
-        }
+        );
+        // Broken once a whole day goes by without a command
+        assert_eq!(streaks(&active, date(15)).current, 0);
     }
-
-    // Clean up
-    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
-    terminal::disable_raw_mode()?;
-
-    Ok(())
 }
@@ -0,0 +1,175 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+
+use crate::history::{Shell, UserDirs};
+use crate::report::format_time;
+use crate::tr;
+use crate::ui_utils::home_relative;
+
+// Hours the stats log can fall behind the shell's own history before the
+// hook is taken to be broken
+const STALE_HOURS: i64 = 6;
+
+// CLI_TRACKER_HOOK_STALE_HOURS, or 6
+pub fn stale_hours() -> i64 {
+    std::env::var("CLI_TRACKER_HOOK_STALE_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse().ok())
+        .filter(|&hours| hours > 0)
+        .unwrap_or(STALE_HOURS)
+}
+
+// When the stats log and the shell's history were last written. Shells
+// append to their history as commands run (or at the latest on exit), so a
+// history written long after the log means commands went unrecorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookCheck {
+    pub log: PathBuf,
+    pub log_written: i64,
+    pub shell: Shell,
+    pub history: PathBuf,
+    pub history_written: i64,
+}
+
+impl HookCheck {
+    // Seconds the history kept being written after the log stopped, when
+    // that's over `hours`
+    pub fn stale(&self, hours: i64) -> Option<i64> {
+        let behind = self.history_written - self.log_written;
+        (behind > hours * 3600).then_some(behind)
+    }
+}
+
+fn written(path: &Path) -> Option<i64> {
+    let modified = path.metadata().ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64)
+}
+
+// The stats log against whichever shell history was written last. None
+// without a stats log, as then there's no hook to break.
+pub fn hook_check(dirs: &UserDirs) -> Option<HookCheck> {
+    let log = dirs.stats_log();
+    if log.metadata().map_or(true, |meta| meta.len() == 0) {
+        return None;
+    }
+    let log_written = written(&log)?;
+    let (shell, history, history_written) = Shell::ALL
+        .into_iter()
+        .filter_map(|shell| {
+            let path = shell.history_path(dirs);
+            let written = written(&path)?;
+            Some((shell, path, written))
+        })
+        .max_by_key(|(_, _, written)| *written)?;
+    Some(HookCheck {
+        log,
+        log_written,
+        shell,
+        history,
+        history_written,
+    })
+}
+
+// Set once the TUI starts, for the screens to show
+static BANNER: OnceLock<Option<String>> = OnceLock::new();
+
+// Check the hook for the current user, keeping a warning for `banner`
+pub fn watch() {
+    BANNER.get_or_init(|| {
+        let dirs = UserDirs::current().ok()?;
+        let check = hook_check(&dirs)?;
+        let behind = check.stale(stale_hours())?;
+        Some(tr!(
+            "hook-stale-banner",
+            hours = behind / 3600,
+            shell = check.shell.name()
+        ))
+    });
+}
+
+// The warning the screens show on their last row while the hook looks broken
+pub fn banner() -> Option<&'static str> {
+    BANNER.get()?.as_deref()
+}
+
+// What `doctor` prints
+pub fn doctor_report(check: Option<&HookCheck>, hours: i64) -> String {
+    let mut out = String::new();
+    let Some(check) = check else {
+        let _ = writeln!(
+            out,
+            "Logging hook   not set up: there is no stats log yet. Add `eval \"$(cli-wrapped init zsh)\"` to ~/.zshrc to record commands."
+        );
+        return out;
+    };
+    let home = home::home_dir();
+    let path = |path: &Path| home_relative(&path.to_string_lossy(), home.as_deref());
+    let _ = writeln!(
+        out,
+        "Stats log      {}, last written {}",
+        path(&check.log),
+        format_time(check.log_written)
+    );
+    let label = format!("{} history", check.shell.name());
+    let _ = writeln!(
+        out,
+        "{:<15}{}, last written {}",
+        label,
+        path(&check.history),
+        format_time(check.history_written)
+    );
+    match check.stale(hours) {
+        None => {
+            let _ = writeln!(out, "Logging hook   OK");
+        }
+        Some(behind) => {
+            let _ = writeln!(
+                out,
+                "Logging hook   BROKEN? Nothing was logged for {}h while {} history kept growing.",
+                behind / 3600,
+                check.shell.name()
+            );
+            let _ = writeln!(
+                out,
+                "               A dotfiles change may have dropped it; make sure ~/.zshrc still has `eval \"$(cli-wrapped init zsh)\"`."
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+    use std::fs::{self, File};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn history_written_long_after_the_log_is_stale() {
+        let home = ScratchDir::new("doctor");
+        let dirs = UserDirs::at(home.to_path_buf());
+        assert_eq!(hook_check(&dirs), None);
+
+        let now = SystemTime::now();
+        let write = |name: &str, hours_ago: u64| {
+            let path = home.join(name);
+            fs::write(&path, "ls\n").unwrap();
+            let file = File::options().write(true).open(&path).unwrap();
+            file.set_modified(now - Duration::from_secs(hours_ago * 3600))
+                .unwrap();
+        };
+        write(".cli_stats_log", 10);
+        write(".bash_history", 8);
+        write(".zsh_history", 1);
+
+        let check = hook_check(&dirs).unwrap();
+        assert_eq!(check.shell, Shell::Zsh);
+        assert_eq!(check.stale(12), None);
+        assert_eq!(check.stale(6), Some(9 * 3600));
+        assert!(doctor_report(Some(&check), 6).contains("Nothing was logged for 9h"));
+        assert!(doctor_report(Some(&check), 12).contains("Logging hook   OK"));
+    }
+}
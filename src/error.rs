@@ -0,0 +1,139 @@
+use clap::ValueEnum;
+use std::fmt;
+
+// Failure classes that scripts can tell apart by exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Other,
+    // No history file, or nothing in it
+    NoData,
+    // History lines or user-supplied patterns that couldn't be parsed
+    Parse,
+    // Reading from or drawing to the terminal failed
+    Terminal,
+}
+
+impl ErrorKind {
+    // Exit code for the process. 2 is left to clap for usage errors.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::NoData => 3,
+            ErrorKind::Parse => 4,
+            ErrorKind::Terminal => 5,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::Other => "other",
+            ErrorKind::NoData => "no_data",
+            ErrorKind::Parse => "parse",
+            ErrorKind::Terminal => "terminal",
+        }
+    }
+
+    // Attach with `.context(ErrorKind::...)`; otherwise the kind is worked
+    // out from the underlying error. I/O errors say nothing of where they
+    // came from, so terminal failures are marked where the terminal is set
+    // up, drawn to and read from.
+    pub fn of(err: &anyhow::Error) -> ErrorKind {
+        // anyhow looks through every layer of context here
+        if let Some(kind) = err.downcast_ref::<ErrorKind>() {
+            return *kind;
+        }
+        for cause in err.chain() {
+            if cause.is::<crate::parser::ParseError>() || cause.is::<regex::Error>() {
+                return ErrorKind::Parse;
+            }
+        }
+        ErrorKind::Other
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            ErrorKind::Other => "Error",
+            ErrorKind::NoData => "No history data",
+            ErrorKind::Parse => "Parse failure",
+            ErrorKind::Terminal => "Terminal error",
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for ErrorKind {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ErrorFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+// Render an error for stderr in the requested format
+pub fn format_error(err: &anyhow::Error, format: ErrorFormat) -> String {
+    let kind = ErrorKind::of(err);
+    match format {
+        ErrorFormat::Text => format!("Error: {:?}", err),
+        ErrorFormat::Json => {
+            let causes: Vec<String> = err.chain().map(|cause| cause.to_string()).collect();
+            serde_json::json!({
+                "error": {
+                    "kind": kind.name(),
+                    "exit_code": kind.exit_code(),
+                    "message": causes.join(": "),
+                    "causes": causes,
+                }
+            })
+            .to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn kind_comes_from_context_or_cause() {
+        let missing = std::fs::File::open("/nonexistent/history")
+            .context("Failed to open zsh history file")
+            .context(ErrorKind::NoData)
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&missing), ErrorKind::NoData);
+
+        let unclosed = String::from("(");
+        let pattern = regex::Regex::new(&unclosed)
+            .context("Invalid pattern")
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&pattern), ErrorKind::Parse);
+
+        assert_eq!(ErrorKind::of(&anyhow::anyhow!("boom")), ErrorKind::Other);
+
+        // A file that can't be written isn't the terminal's fault
+        let unwritable = std::fs::write("/nonexistent/stats-log", "ls")
+            .context("Failed to write the stats log")
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&unwritable), ErrorKind::Other);
+        let closed = Err::<(), _>(std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+            .context(ErrorKind::Terminal)
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&closed), ErrorKind::Terminal);
+    }
+
+    #[test]
+    fn json_errors_carry_kind_and_exit_code() {
+        let err = anyhow::anyhow!("Failed to open zsh history file").context(ErrorKind::NoData);
+        let json: serde_json::Value =
+            serde_json::from_str(&format_error(&err, ErrorFormat::Json)).unwrap();
+        assert_eq!(json["error"]["kind"], "no_data");
+        assert_eq!(json["error"]["exit_code"], 3);
+        assert_eq!(
+            json["error"]["message"],
+            "No history data: Failed to open zsh history file"
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use clap::ValueEnum;
+
+use crate::history::HistoryEntry;
+
+// How commands saved without a time (timestamp 0, as in plain bash and old
+// zsh histories) get one, so they count in the time-based views
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Estimate {
+    /// Spread evenly between the nearest timed commands before and after
+    Interpolate,
+    /// The time of the last timed command before them
+    Previous,
+}
+
+// Give every untimed entry a time from its timed neighbours, in history
+// order, marking it estimated. Those before the first timed entry take its
+// time and those after the last take that one. Without any timed entry
+// there's nothing to go on and they're left alone. Returns how many got one.
+pub fn estimate_timestamps(entries: &mut [HistoryEntry], how: Estimate) -> usize {
+    let timed: Vec<usize> = (0..entries.len())
+        .filter(|&i| entries[i].timestamp > 0)
+        .collect();
+    let (Some(&first), Some(&last)) = (timed.first(), timed.last()) else {
+        return 0;
+    };
+    let mut estimated = 0;
+    let mut set = |entry: &mut HistoryEntry, timestamp: i64| {
+        entry.recorded_timestamp = Some(entry.timestamp);
+        entry.timestamp = timestamp;
+        estimated += 1;
+    };
+
+    let first_time = entries[first].timestamp;
+    for entry in &mut entries[..first] {
+        set(entry, first_time);
+    }
+    for pair in timed.windows(2) {
+        let (before, after) = (pair[0], pair[1]);
+        let (start, end) = (entries[before].timestamp, entries[after].timestamp);
+        let gap = (after - before) as i64;
+        for (step, entry) in entries[before + 1..after].iter_mut().enumerate() {
+            let timestamp = match how {
+                // Clocks and merged histories go backwards now and then; the
+                // guess then stays at the earlier time
+                Estimate::Interpolate => start + (end - start).max(0) * (step as i64 + 1) / gap,
+                Estimate::Previous => start,
+            };
+            set(entry, timestamp);
+        }
+    }
+    let last_time = entries[last].timestamp;
+    for entry in &mut entries[last + 1..] {
+        set(entry, last_time);
+    }
+    estimated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_gaps_from_timed_neighbours() {
+        let run = |timestamp: i64| HistoryEntry {
+            timestamp,
+            command: "ls".to_string(),
+            ..Default::default()
+        };
+        let history = [run(0), run(100), run(0), run(0), run(0), run(500), run(0)];
+        let times = |entries: &[HistoryEntry]| -> Vec<i64> {
+            entries.iter().map(|e| e.timestamp).collect()
+        };
+
+        let mut entries = history.to_vec();
+        assert_eq!(estimate_timestamps(&mut entries, Estimate::Interpolate), 5);
+        assert_eq!(times(&entries), [100, 100, 200, 300, 400, 500, 500]);
+        let marked: Vec<bool> = entries.iter().map(|e| e.estimated()).collect();
+        assert_eq!(marked, [true, false, true, true, true, false, true]);
+
+        let mut entries = history.to_vec();
+        estimate_timestamps(&mut entries, Estimate::Previous);
+        assert_eq!(times(&entries), [100, 100, 100, 100, 100, 500, 500]);
+
+        let mut untimed = vec![run(0), run(0)];
+        assert_eq!(estimate_timestamps(&mut untimed, Estimate::Interpolate), 0);
+        assert!(!untimed[0].estimated());
+    }
+}
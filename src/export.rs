@@ -0,0 +1,309 @@
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, Days, NaiveDate};
+use clap::ValueEnum;
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::aggregate::{category, sorted_counts};
+use crate::days::local_day;
+use crate::history::HistoryEntry;
+use crate::output::{csv_field, OutputFormat};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Sqlite,
+}
+
+impl ExportFormat {
+    // --format json or csv when given, otherwise whatever the output file is
+    // named for. SQLite is only written to a `.db`, `.sqlite` or `.sqlite3`
+    // file, since it can't go to stdout.
+    pub fn choose(format: Option<OutputFormat>, output: Option<&Path>) -> Result<ExportFormat> {
+        let extension = output
+            .and_then(|path| path.extension())
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        Ok(match (format, extension.as_deref()) {
+            (Some(OutputFormat::Csv), _) => ExportFormat::Csv,
+            (Some(OutputFormat::Json), _) => ExportFormat::Json,
+            (Some(OutputFormat::Table), _) => {
+                bail!("export writes csv or json, or SQLite to a .db file")
+            }
+            (None, Some("json")) => ExportFormat::Json,
+            (None, Some("db" | "sqlite" | "sqlite3")) => ExportFormat::Sqlite,
+            (None, _) => ExportFormat::Csv,
+        })
+    }
+}
+
+const CSV_HEADER: &str =
+    "timestamp,command,directory,duration,exit_code,context,session_id,commit\n";
+
+const SCHEMA: &str = "
+    CREATE TABLE history (
+        timestamp INTEGER,
+        command TEXT NOT NULL,
+        directory TEXT,
+        duration INTEGER,
+        exit_code INTEGER,
+        context TEXT,
+        session_id TEXT,
+        git_commit TEXT
+    );
+";
+
+// Entries without a timestamp have 0, which exports as missing
+fn timestamp(entry: &HistoryEntry) -> Option<i64> {
+    (entry.timestamp > 0).then_some(entry.timestamp)
+}
+
+// One row per entry, oldest first. Missing values are empty fields.
+pub fn entries_csv(entries: &[HistoryEntry]) -> String {
+    let mut out = String::from(CSV_HEADER);
+    let text = |value: &Option<String>| csv_field(value.as_deref().unwrap_or("")).into_owned();
+    let number = |value: Option<i64>| value.map(|n| n.to_string()).unwrap_or_default();
+    for entry in entries {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            number(timestamp(entry)),
+            csv_field(&entry.command),
+            text(&entry.directory),
+            number(entry.duration),
+            number(entry.exit_code.map(i64::from)),
+            text(&entry.context),
+            text(&entry.session_id),
+            text(&entry.commit)
+        );
+    }
+    out
+}
+
+// An array of objects with the same fields as the CSV, missing ones null
+pub fn entries_json(entries: &[HistoryEntry]) -> String {
+    let rows: Vec<Value> = entries
+        .iter()
+        .map(|entry| {
+            json!({
+                "timestamp": timestamp(entry),
+                "command": entry.command,
+                "directory": entry.directory,
+                "duration": entry.duration,
+                "exit_code": entry.exit_code,
+                "context": entry.context,
+                "session_id": entry.session_id,
+                "commit": entry.commit,
+            })
+        })
+        .collect();
+    format!("{:#}\n", Value::Array(rows))
+}
+
+// What `export --rollup` adds commands up by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Rollup {
+    Day,
+    Week,
+    Month,
+}
+
+impl Rollup {
+    // The first day of the bucket `day` falls in, weeks starting on Monday
+    fn bucket(self, day: NaiveDate) -> NaiveDate {
+        match self {
+            Rollup::Day => day,
+            Rollup::Week => day - Days::new(day.weekday().num_days_from_monday() as u64),
+            Rollup::Month => day.with_day(1).unwrap_or(day),
+        }
+    }
+}
+
+const ROLLUP_HEADER: &str = "date,total,unique,top_command,top_category\n";
+
+// One row per day, week or month with commands, oldest first, for
+// spreadsheets to pivot on. Entries without a timestamp are left out.
+pub fn rollup_csv(entries: &[HistoryEntry], rollup: Rollup) -> String {
+    let mut buckets: BTreeMap<NaiveDate, Vec<&str>> = BTreeMap::new();
+    for entry in entries {
+        if let Some(day) = local_day(entry.timestamp) {
+            buckets
+                .entry(rollup.bucket(day))
+                .or_default()
+                .push(&entry.command);
+        }
+    }
+    let mut out = String::from(ROLLUP_HEADER);
+    for (date, commands) in buckets {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        let mut categories: HashMap<&str, usize> = HashMap::new();
+        for &command in &commands {
+            *counts.entry(command).or_insert(0) += 1;
+            *categories.entry(category(command)).or_insert(0) += 1;
+        }
+        let unique = counts.len();
+        let top = |counts| {
+            sorted_counts(counts)
+                .into_iter()
+                .next()
+                .map(|(name, _)| name)
+                .unwrap_or_default()
+        };
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{}",
+            date,
+            commands.len(),
+            unique,
+            csv_field(&top(counts)),
+            csv_field(&top(categories))
+        );
+    }
+    out
+}
+
+// A fresh database at `path` with one `history` table, replacing any file
+// already there
+pub fn write_sqlite(entries: &[HistoryEntry], path: &Path) -> Result<()> {
+    if path.exists() {
+        fs::remove_file(path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    }
+    let mut conn = Connection::open(path)
+        .with_context(|| format!("Failed to create database {}", path.display()))?;
+    conn.execute_batch(SCHEMA)?;
+    let tx = conn.transaction()?;
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO history (timestamp, command, directory, duration, exit_code, context,
+                session_id, git_commit)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        for entry in entries {
+            insert.execute(params![
+                timestamp(entry),
+                entry.command,
+                entry.directory,
+                entry.duration,
+                entry.exit_code,
+                entry.context,
+                entry.session_id,
+                entry.commit,
+            ])?;
+        }
+    }
+    tx.commit()
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+    use chrono::TimeZone;
+
+    fn entries() -> Vec<HistoryEntry> {
+        vec![
+            HistoryEntry {
+                timestamp: 1_700_000_000,
+                command: "git commit -m \"a, b\"".to_string(),
+                directory: Some("/repo".to_string()),
+                duration: Some(2),
+                exit_code: Some(0),
+                ..Default::default()
+            },
+            HistoryEntry {
+                command: "ls".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn format_follows_the_flag_then_the_file_name() {
+        let choose =
+            |format, output: &str| ExportFormat::choose(format, Some(Path::new(output))).unwrap();
+        assert_eq!(choose(None, "history.db"), ExportFormat::Sqlite);
+        assert_eq!(choose(None, "history.JSON"), ExportFormat::Json);
+        assert_eq!(choose(None, "history.txt"), ExportFormat::Csv);
+        assert_eq!(
+            choose(Some(OutputFormat::Json), "history.csv"),
+            ExportFormat::Json
+        );
+        assert_eq!(ExportFormat::choose(None, None).unwrap(), ExportFormat::Csv);
+        assert!(ExportFormat::choose(Some(OutputFormat::Table), None).is_err());
+    }
+
+    #[test]
+    fn every_format_has_every_entry() {
+        let csv = entries_csv(&entries());
+        assert_eq!(
+            csv,
+            format!(
+                "{}1700000000,\"git commit -m \"\"a, b\"\"\",/repo,2,0,,,\n,ls,,,,,,\n",
+                CSV_HEADER
+            )
+        );
+
+        let json: Value = serde_json::from_str(&entries_json(&entries())).unwrap();
+        assert_eq!(json[0]["exit_code"], 0);
+        assert_eq!(json[1]["timestamp"], Value::Null);
+
+        let dir = ScratchDir::new("export");
+        let path = dir.join("history.db");
+        write_sqlite(&entries(), &path).unwrap();
+        // Writing again replaces rather than appends
+        write_sqlite(&entries(), &path).unwrap();
+        let conn = Connection::open(&path).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT count(*) FROM history", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn rollups_add_up_each_bucket() {
+        let at = |day: u32, command: &str| {
+            let time = chrono::Local
+                .with_ymd_and_hms(2024, 3, day, 12, 0, 0)
+                .unwrap();
+            HistoryEntry {
+                timestamp: time.timestamp(),
+                command: command.to_string(),
+                ..Default::default()
+            }
+        };
+        // Tuesday the 5th to Monday the 11th
+        let entries = vec![
+            at(5, "git status"),
+            at(5, "git commit -m \"a, b\""),
+            at(5, "git status"),
+            at(6, "cargo test"),
+            at(11, "ls"),
+            HistoryEntry {
+                command: "undated".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            rollup_csv(&entries, Rollup::Day),
+            format!(
+                "{}2024-03-05,3,2,git status,vcs\n2024-03-06,1,1,cargo test,build\n2024-03-11,1,1,ls,files\n",
+                ROLLUP_HEADER
+            )
+        );
+        assert_eq!(
+            rollup_csv(&entries, Rollup::Week),
+            format!(
+                "{}2024-03-04,4,3,git status,vcs\n2024-03-11,1,1,ls,files\n",
+                ROLLUP_HEADER
+            )
+        );
+        assert!(rollup_csv(&entries, Rollup::Month).ends_with("\n2024-03-01,5,4,git status,vcs\n"));
+        assert_eq!(rollup_csv(&[], Rollup::Month), ROLLUP_HEADER);
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use unicode_width::UnicodeWidthStr;
+
+use crate::aggregate::command_lifetime;
+use crate::days::local_day;
+use crate::history::{HistoryEntry, UserDirs};
+
+// Commands pinned with b in the history view, as a JSON array in the order
+// they were pinned
+pub fn favorites_path(dirs: &UserDirs) -> PathBuf {
+    dirs.data.join("cli-tracker/favorites.json")
+}
+
+// Nothing pinned yet is an empty list
+pub fn load_favorites(dirs: &UserDirs) -> Result<Vec<String>> {
+    let path = favorites_path(dirs);
+    match fs::read_to_string(&path) {
+        Ok(source) => serde_json::from_str(&source)
+            .with_context(|| format!("Failed to read favorites from {}", path.display())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+pub fn save_favorites(dirs: &UserDirs, favorites: &[String]) -> Result<()> {
+    let path = favorites_path(dirs);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let json = serde_json::to_string_pretty(favorites)?;
+    fs::write(&path, json + "\n").with_context(|| format!("Failed to write {}", path.display()))
+}
+
+// Pin `command`, or unpin it if it was. True when it's pinned now.
+pub fn toggle_favorite(dirs: &UserDirs, command: &str) -> Result<bool> {
+    let mut favorites = load_favorites(dirs)?;
+    let pinned = match favorites.iter().position(|pinned| pinned == command) {
+        Some(i) => {
+            favorites.remove(i);
+            false
+        }
+        None => {
+            favorites.push(command.to_string());
+            true
+        }
+    };
+    save_favorites(dirs, &favorites)?;
+    Ok(pinned)
+}
+
+// The pinned commands, for highlighting them in the list
+pub fn pinned_set(dirs: &UserDirs) -> HashSet<String> {
+    load_favorites(dirs)
+        .unwrap_or_default()
+        .into_iter()
+        .collect()
+}
+
+// `favorites`: each pinned command with how often and how recently it ran
+pub fn favorites_report(favorites: &[String], entries: &[HistoryEntry]) -> String {
+    let width = favorites
+        .iter()
+        .map(|command| command.width())
+        .chain(std::iter::once("Command".len()))
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:<width$}  {:>5}  {:<10}  {:>8}",
+        "Command",
+        "Runs",
+        "Last used",
+        "Per week",
+        width = width
+    );
+    for command in favorites {
+        let padding = " ".repeat(width - command.width());
+        let _ = match command_lifetime(entries, command) {
+            Some(lifetime) => writeln!(
+                out,
+                "{}{}  {:>5}  {:<10}  {:>8.1}",
+                command,
+                padding,
+                lifetime.runs,
+                local_day(lifetime.last).map_or_else(|| "-".to_string(), |day| day.to_string()),
+                lifetime.per_active_week()
+            ),
+            None => writeln!(
+                out,
+                "{}{}  {:>5}  {:<10}  {:>8}",
+                command, padding, 0, "-", "-"
+            ),
+        };
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+
+    #[test]
+    fn pins_toggle_and_report_their_runs() {
+        let root = ScratchDir::new("favorites");
+        let dirs = UserDirs::at(root.to_path_buf());
+        assert!(load_favorites(&dirs).unwrap().is_empty());
+        assert!(toggle_favorite(&dirs, "cargo build").unwrap());
+        assert!(toggle_favorite(&dirs, "make").unwrap());
+        assert!(!toggle_favorite(&dirs, "cargo build").unwrap());
+        assert_eq!(load_favorites(&dirs).unwrap(), ["make"]);
+        assert!(pinned_set(&dirs).contains("make"));
+
+        let run = |timestamp: i64| HistoryEntry {
+            timestamp,
+            command: "make".to_string(),
+            ..Default::default()
+        };
+        let favorites = ["make".to_string(), "htop".to_string()];
+        let report = favorites_report(&favorites, &[run(1_700_000_000), run(1_700_000_100)]);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "Command   Runs  Last used   Per week");
+        assert!(lines[1].starts_with("make         2  2023-11-1"));
+        assert!(lines[1].ends_with("     2.0"));
+        assert_eq!(lines[2], "htop         0  -                  -");
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event};
+use std::io;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::config;
+use crate::error::ErrorKind;
+
+// Redraws a second when the config doesn't set ui.fps
+pub const DEFAULT_FPS: u32 = 30;
+
+// The most ui.fps takes
+pub const MAX_FPS: u32 = 240;
+
+// When the last event was handed to a screen, which redraws right after,
+// and an event read while collapsing resizes that still has to be handed out
+struct Frames {
+    last: Option<Instant>,
+    held: Option<Event>,
+}
+
+static FRAMES: Mutex<Frames> = Mutex::new(Frames {
+    last: None,
+    held: None,
+});
+
+// How long a frame lasts at `fps`
+fn interval(fps: u32) -> Duration {
+    Duration::from_secs(1) / fps.clamp(1, MAX_FPS)
+}
+
+pub fn frame_interval() -> Duration {
+    interval(config().fps.unwrap_or(DEFAULT_FPS))
+}
+
+// What's left of the frame begun at `last`, to wait before the next redraw
+fn rest_of_frame(last: Option<Instant>, now: Instant, frame: Duration) -> Duration {
+    last.map_or(Duration::ZERO, |last| {
+        frame.saturating_sub(now.duration_since(last))
+    })
+}
+
+// Whether an event is waiting, as event::poll, counting one held back
+pub fn poll(timeout: Duration) -> Result<bool> {
+    if FRAMES.lock().unwrap().held.is_some() {
+        return Ok(true);
+    }
+    event::poll(timeout).context(ErrorKind::Terminal)
+}
+
+// The next event for a screen to handle and redraw after, in place of
+// event::read. Waiting blocks rather than polls, so a screen left open in a
+// tmux pane uses no CPU. Resizes coming within a frame of each other, as
+// dragging a pane sends them, come back as the last one, and events never
+// come back faster than ui.fps a second, so a held key or a burst of input
+// can't keep a core busy redrawing.
+pub fn next_event() -> Result<Event> {
+    read_event().context(ErrorKind::Terminal)
+}
+
+fn read_event() -> io::Result<Event> {
+    let frame = frame_interval();
+    let held = FRAMES.lock().unwrap().held.take();
+    let mut next = match held {
+        Some(event) => event,
+        None => event::read()?,
+    };
+    while matches!(next, Event::Resize(..)) && event::poll(frame)? {
+        let event = event::read()?;
+        if !matches!(event, Event::Resize(..)) {
+            FRAMES.lock().unwrap().held = Some(event);
+            break;
+        }
+        next = event;
+    }
+    let last = FRAMES.lock().unwrap().last;
+    thread::sleep(rest_of_frame(last, Instant::now(), frame));
+    FRAMES.lock().unwrap().last = Some(Instant::now());
+    Ok(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_spaced_by_the_rate() {
+        assert_eq!(interval(DEFAULT_FPS), Duration::from_nanos(33_333_333));
+        assert_eq!(interval(0), Duration::from_secs(1));
+        assert_eq!(interval(1000), interval(MAX_FPS));
+
+        let frame = interval(25);
+        let start = Instant::now();
+        assert_eq!(rest_of_frame(None, start, frame), Duration::ZERO);
+        assert_eq!(
+            rest_of_frame(Some(start), start + Duration::from_millis(10), frame),
+            Duration::from_millis(30)
+        );
+        // Idle for longer than a frame, the next redraw is immediate
+        assert_eq!(
+            rest_of_frame(Some(start), start + Duration::from_secs(5), frame),
+            Duration::ZERO
+        );
+    }
+}
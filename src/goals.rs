@@ -0,0 +1,125 @@
+use anyhow::{anyhow, Result};
+
+use crate::history::HistoryEntry;
+use crate::timeline::matches_command;
+
+// A weekly target from the config's [goals] table, e.g. `cargo test >= 30`
+// or `git push --force <= 5`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Goal {
+    // Matched like `timeline` matches commands, so `git push` takes in
+    // `git push origin main`
+    pub command: String,
+    pub limit: Limit,
+    pub target: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    AtLeast,
+    AtMost,
+}
+
+impl Goal {
+    // `<command> >= N` or `<command> <= N`, with ≥ and ≤ taken as well
+    pub fn parse(goal: &str) -> Result<Goal> {
+        let (split, limit, sign) = [
+            (">=", Limit::AtLeast),
+            ("≥", Limit::AtLeast),
+            ("<=", Limit::AtMost),
+            ("≤", Limit::AtMost),
+        ]
+        .into_iter()
+        .filter_map(|(sign, limit)| goal.rfind(sign).map(|at| (at, limit, sign)))
+        .max_by_key(|&(at, _, _)| at)
+        .ok_or_else(|| anyhow!("Goal `{}` needs >= or <= and a number", goal))?;
+        let command = goal[..split].trim();
+        let target = goal[split + sign.len()..]
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("Goal `{}` needs a whole number after {}", goal, sign))?;
+        if command.is_empty() {
+            return Err(anyhow!("Goal `{}` has no command", goal));
+        }
+        Ok(Goal {
+            command: command.to_string(),
+            limit,
+            target,
+        })
+    }
+
+    // `≥30` or `≤5`
+    pub fn label(&self) -> String {
+        match self.limit {
+            Limit::AtLeast => format!("≥{}", self.target),
+            Limit::AtMost => format!("≤{}", self.target),
+        }
+    }
+
+    pub fn count<'a>(&self, entries: impl IntoIterator<Item = &'a HistoryEntry>) -> usize {
+        entries
+            .into_iter()
+            .filter(|e| matches_command(&e.command, &self.command))
+            .count()
+    }
+
+    pub fn met(&self, count: usize) -> bool {
+        match self.limit {
+            Limit::AtLeast => count >= self.target,
+            Limit::AtMost => count <= self.target,
+        }
+    }
+
+    // How far `count` is toward the target, 1 once it's there. A limit
+    // fills up as it's used and goes over 1 when broken.
+    pub fn progress(&self, count: usize) -> f64 {
+        match (self.limit, self.target) {
+            (Limit::AtLeast, 0) => 1.0,
+            (Limit::AtLeast, target) => (count as f64 / target as f64).min(1.0),
+            (Limit::AtMost, 0) => count as f64,
+            (Limit::AtMost, target) => count as f64 / target as f64,
+        }
+    }
+}
+
+// `width` cells, filled in proportion to `progress` (capped at full)
+pub fn progress_bar(progress: f64, width: usize) -> String {
+    let filled = ((progress.clamp(0.0, 1.0) * width as f64).round() as usize).min(width);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_scores_weekly_goals() {
+        let tests = Goal::parse("cargo test >= 30").unwrap();
+        assert_eq!(tests.command, "cargo test");
+        assert_eq!((tests.limit, tests.target), (Limit::AtLeast, 30));
+        let pushes = Goal::parse("git push --force≤5").unwrap();
+        assert_eq!((pushes.limit, pushes.target), (Limit::AtMost, 5));
+        assert_eq!(pushes.label(), "≤5");
+        // Only the last comparison splits, so commands can contain one
+        let echo = Goal::parse("echo a>=b >= 2").unwrap();
+        assert_eq!((echo.command.as_str(), echo.target), ("echo a>=b", 2));
+        assert!(Goal::parse("cargo test").is_err());
+        assert!(Goal::parse(">= 3").is_err());
+        assert!(Goal::parse("make >= lots").is_err());
+
+        let run = |command: &str| HistoryEntry {
+            command: command.to_string(),
+            ..Default::default()
+        };
+        let week = [
+            run("cargo test"),
+            run("cargo test --release"),
+            run("cargo testing"),
+        ];
+        assert_eq!(tests.count(&week), 2);
+        assert!(!tests.met(2) && tests.met(30));
+        assert!(pushes.met(5) && !pushes.met(6));
+        assert_eq!(progress_bar(tests.progress(15), 10), "█████░░░░░");
+        assert_eq!(progress_bar(pushes.progress(10), 4), "████");
+    }
+}
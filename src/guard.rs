@@ -0,0 +1,105 @@
+use anyhow::{bail, Result};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::config;
+use crate::history::{default_history_file, Shell, Source, UserDirs};
+
+// More commands than this are worth asking about before loading them
+const MAX_ENTRIES: usize = 1_000_000;
+
+// A typical history line, timestamp and all
+const BYTES_PER_ENTRY: u64 = 50;
+
+// history.max_entries from the config, or a million. 0 turns the check off.
+pub fn max_entries() -> usize {
+    config().max_entries.unwrap_or(MAX_ENTRIES)
+}
+
+// Roughly how many commands a history file of `bytes` holds, without
+// reading it
+pub fn estimate_entries(bytes: u64) -> usize {
+    (bytes / BYTES_PER_ENTRY) as usize
+}
+
+// The file a load would read, when it's a single file. Atuin and histdb
+// databases are left alone, as they can't be sized the same way.
+fn history_path(
+    source: Option<Source>,
+    shell: Option<Shell>,
+    history_file: Option<&Path>,
+) -> Option<PathBuf> {
+    if source.is_some() {
+        return None;
+    }
+    if let Some(path) = history_file {
+        return Some(path.to_path_buf());
+    }
+    let dirs = UserDirs::current().ok()?;
+    default_history_file(&dirs, shell, Shell::detect()).map(|(path, _)| path)
+}
+
+fn rounded(count: usize) -> String {
+    if count >= 1_000_000 {
+        format!("{:.1} million", count as f64 / 1e6)
+    } else {
+        count.to_string()
+    }
+}
+
+// Before loading a history too big to hold comfortably, say so and ask
+// whether to go on. `narrowed` is set when --last, --since or --until will
+// cut it down anyway. Without a terminal to ask on, only warns.
+pub fn check_history_size(
+    source: Option<Source>,
+    shell: Option<Shell>,
+    history_file: Option<&Path>,
+    narrowed: bool,
+) -> Result<()> {
+    let max = max_entries();
+    if narrowed || max == 0 {
+        return Ok(());
+    }
+    let Some(path) = history_path(source, shell, history_file) else {
+        return Ok(());
+    };
+    let Ok(meta) = path.metadata() else {
+        return Ok(());
+    };
+    let estimate = estimate_entries(meta.len());
+    if estimate <= max {
+        return Ok(());
+    }
+    let warning = format!(
+        "{} holds about {} commands ({} MB). Loading them all can take a lot of memory; \
+         narrow them with --last N, --since or --until.",
+        path.display(),
+        rounded(estimate),
+        meta.len() / 1_000_000
+    );
+    eprintln!("{}", warning);
+    if !io::stdin().is_terminal() {
+        return Ok(());
+    }
+    eprint!("Load everything anyway? [y/N] ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        bail!("Not loaded; try --last 100000 or --since 90d");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_from_the_file_size() {
+        assert_eq!(estimate_entries(0), 0);
+        assert_eq!(estimate_entries(50_000_000), 1_000_000);
+        assert_eq!(rounded(2_345_678), "2.3 million");
+        assert_eq!(rounded(1_200), "1200");
+    }
+}
@@ -0,0 +1,281 @@
+use anyhow::{Context, Result};
+use chrono::{Datelike, Days, NaiveDate};
+use crossterm::{
+    cursor,
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    style::Stylize,
+    terminal::{self, ClearType},
+};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::aggregate::activity_level;
+use crate::app::ScreenExit;
+use crate::days::{local_day, DayStats};
+use crate::error::ErrorKind;
+use crate::frames;
+use crate::help::{show_help, HelpView};
+use crate::history::HistoryEntry;
+use crate::i18n::{month_abbrev, weekday_initial, weekday_name};
+use crate::interactive::browse_history_list;
+use crate::journal;
+use crate::time_format::TimeDisplay;
+use crate::tr;
+
+// Columns in the heatmap, the last one being the current week
+pub const HEATMAP_WEEKS: u64 = 52;
+
+// Room for the weekday labels left of the grid
+const LABEL_WIDTH: usize = 4;
+
+// Block for each activity level, so the scale reads without color too
+const BLOCKS: [char; 4] = ['·', '░', '▒', '▓'];
+
+// Commands per day over the last year, a column per week from Monday
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heatmap {
+    // Monday of the first column
+    pub first: NaiveDate,
+    pub today: NaiveDate,
+    // Commands on each day from `first` to `today`
+    pub counts: Vec<usize>,
+    pub busiest: usize,
+}
+
+impl Heatmap {
+    pub fn new(day_counts: &BTreeMap<NaiveDate, usize>, today: NaiveDate) -> Heatmap {
+        let monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+        let first = monday - Days::new((HEATMAP_WEEKS - 1) * 7);
+        let counts: Vec<usize> = first
+            .iter_days()
+            .take_while(|&day| day <= today)
+            .map(|day| day_counts.get(&day).copied().unwrap_or(0))
+            .collect();
+        let busiest = counts.iter().copied().max().unwrap_or(0);
+        Heatmap {
+            first,
+            today,
+            counts,
+            busiest,
+        }
+    }
+
+    // None for days outside the heatmap
+    pub fn count(&self, day: NaiveDate) -> Option<usize> {
+        let index = (day - self.first).num_days();
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.counts.get(index).copied())
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    fn day(&self, week: u64, weekday: u64) -> NaiveDate {
+        self.first + Days::new(week * 7 + weekday)
+    }
+}
+
+fn cell(level: usize, color: bool) -> String {
+    let block = BLOCKS[level.min(3)].to_string();
+    if !color {
+        return block;
+    }
+    // The same scale as the today screen's calendar
+    match level {
+        0 => block.dark_grey().to_string(),
+        1 => block.dark_green().to_string(),
+        2 => block.green().to_string(),
+        _ => block.yellow().to_string(),
+    }
+}
+
+// The heatmap as lines of text: month names, a row per weekday and a
+// legend. `cursor` marks one day, for the interactive view.
+pub fn heatmap_lines(heatmap: &Heatmap, color: bool, cursor: Option<NaiveDate>) -> Vec<String> {
+    // Month names over the week each month starts in, where they fit
+    let mut months = " ".repeat(LABEL_WIDTH);
+    for week in 0..HEATMAP_WEEKS {
+        let column = LABEL_WIDTH + week as usize;
+        let monday = heatmap.day(week, 0);
+        let starts_month = week == 0 || (monday + Days::new(6)).day() <= 7;
+        if months.chars().count() <= column && starts_month {
+            let sunday = monday + Days::new(6);
+            months.push_str(&" ".repeat(column - months.chars().count()));
+            months.push_str(&month_abbrev(sunday.month()));
+        }
+    }
+    let mut lines = vec![months.trim_end().to_string()];
+
+    for weekday in 0..7 {
+        // Every other weekday is labelled, as on GitHub
+        let label = if weekday % 2 == 0 {
+            weekday_initial(weekday as usize)
+        } else {
+            String::new()
+        };
+        let mut line = format!("{:<width$}", label, width = LABEL_WIDTH);
+        for week in 0..HEATMAP_WEEKS {
+            let day = heatmap.day(week, weekday);
+            let Some(count) = heatmap.count(day) else {
+                break;
+            };
+            let level = activity_level(count, heatmap.busiest);
+            if cursor == Some(day) {
+                let block = BLOCKS[level].to_string();
+                line.push_str(&if color {
+                    block.black().on_cyan().to_string()
+                } else {
+                    "◆".to_string()
+                });
+            } else {
+                line.push_str(&cell(level, color));
+            }
+        }
+        lines.push(line);
+    }
+
+    let scale: String = (0..4).map(|level| cell(level, color)).collect();
+    lines.push(format!(
+        "{}{} {} {}",
+        " ".repeat(LABEL_WIDTH),
+        tr!("heatmap-less"),
+        scale,
+        tr!("heatmap-more")
+    ));
+    lines
+}
+
+// The interactive heatmap: the arrow keys move between days and the one
+// picked is shown with its most run commands below. Enter lists the day's
+// commands in the history view.
+pub fn browse_heatmap(
+    heatmap: &Heatmap,
+    days: &[DayStats],
+    entries: &[HistoryEntry],
+) -> Result<()> {
+    let mut stdout = io::stdout();
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)
+        .and_then(|_| terminal::enable_raw_mode())
+        .context(ErrorKind::Terminal)?;
+    let result = heatmap_loop(&mut stdout, heatmap, days, entries);
+    let restored = execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)
+        .and(terminal::disable_raw_mode())
+        .context(ErrorKind::Terminal);
+    // Deleted in the day lists opened from here
+    journal::finish(result, restored)
+}
+
+fn heatmap_loop(
+    stdout: &mut io::Stdout,
+    heatmap: &Heatmap,
+    days: &[DayStats],
+    entries: &[HistoryEntry],
+) -> Result<()> {
+    let mut picked = heatmap.today;
+    loop {
+        execute!(
+            stdout,
+            terminal::Clear(ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+        write!(
+            stdout,
+            "{}",
+            tr!("heatmap-title", total = heatmap.total()).cyan().bold()
+        )?;
+        let lines = heatmap_lines(heatmap, true, Some(picked));
+        for (i, line) in lines.iter().enumerate() {
+            execute!(stdout, cursor::MoveTo(0, 2 + i as u16))?;
+            write!(stdout, "{}", line)?;
+        }
+
+        let mut y = 3 + lines.len() as u16;
+        let count = heatmap.count(picked).unwrap_or(0);
+        execute!(stdout, cursor::MoveTo(0, y))?;
+        let weekday = weekday_name(picked.weekday().num_days_from_monday() as usize);
+        let date = format!("{} {}", weekday, picked.format("%Y-%m-%d"));
+        write!(
+            stdout,
+            "{}",
+            tr!("heatmap-day", date = date, count = count).bold()
+        )?;
+        if let Some(day) = days.iter().find(|day| day.date == picked) {
+            for (command, runs) in &day.top_commands {
+                y += 1;
+                execute!(stdout, cursor::MoveTo(2, y))?;
+                write!(stdout, "{:>4}  {}", runs, command)?;
+            }
+        }
+        execute!(stdout, cursor::MoveTo(0, y + 2))?;
+        write!(stdout, "{}", tr!("heatmap-controls").dark_grey())?;
+        stdout.flush().context(ErrorKind::Terminal)?;
+
+        let Event::Key(KeyEvent {
+            code, modifiers, ..
+        }) = frames::next_event()?
+        else {
+            continue;
+        };
+        let moved = match code {
+            KeyCode::Left | KeyCode::Char('h') => picked.checked_sub_days(Days::new(7)),
+            KeyCode::Right | KeyCode::Char('l') => picked.checked_add_days(Days::new(7)),
+            KeyCode::Up | KeyCode::Char('k') => picked.pred_opt(),
+            KeyCode::Down | KeyCode::Char('j') => picked.succ_opt(),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Char('?') => {
+                show_help(stdout, HelpView::Heatmap)?;
+                None
+            }
+            KeyCode::Enter => {
+                let day: Vec<HistoryEntry> = entries
+                    .iter()
+                    .filter(|e| local_day(e.timestamp) == Some(picked))
+                    .cloned()
+                    .collect();
+                if !day.is_empty()
+                    && browse_history_list(stdout, &day, TimeDisplay::Absolute)? == ScreenExit::Quit
+                {
+                    return Ok(());
+                }
+                None
+            }
+            _ => None,
+        };
+        // The cursor stays on the heatmap
+        if let Some(day) = moved.filter(|&day| heatmap.count(day).is_some()) {
+            picked = day;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_year_of_weeks_up_to_today() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+        // A Wednesday
+        let today = date(3, 13);
+        let counts = BTreeMap::from([(date(3, 11), 9), (date(3, 13), 3), (date(1, 1), 1)]);
+        let heatmap = Heatmap::new(&counts, today);
+        assert_eq!(heatmap.first, date(3, 13) - Days::new(51 * 7 + 2));
+        assert_eq!(heatmap.first.weekday(), chrono::Weekday::Mon);
+        assert_eq!(heatmap.counts.len(), 51 * 7 + 3);
+        assert_eq!((heatmap.busiest, heatmap.total()), (9, 13));
+        assert_eq!(heatmap.count(date(3, 14)), None);
+
+        let lines = heatmap_lines(&heatmap, false, Some(date(3, 12)));
+        assert_eq!(lines.len(), 9);
+        // Monday's row ends with the busy day, Tuesday's with the cursor,
+        // and Thursday's stops a week early
+        assert!(lines[1].ends_with("·▓"));
+        assert!(lines[2].ends_with("·◆"));
+        assert!(lines[3].ends_with("·░"));
+        assert_eq!(lines[4].chars().count(), LABEL_WIDTH + 51);
+    }
+}
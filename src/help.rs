@@ -0,0 +1,193 @@
+use anyhow::{Context, Result};
+use crossterm::{execute, terminal};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Clear, Row, Table};
+use std::io::{self, Write};
+
+use crate::error::ErrorKind;
+use crate::render::{titled, Canvas};
+use crate::tr;
+use crate::ui_utils::wait_to_dismiss;
+
+// The views `?` lists the keys of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelpView {
+    Stats,
+    Today,
+    History,
+    Detail,
+    Vocabulary,
+    Heatmap,
+    Wrapped,
+}
+
+// Widest the overlay gets, and the column the descriptions start in
+const HELP_WIDTH: u16 = 72;
+const KEYS_WIDTH: u16 = 14;
+
+// Every key the view takes, with what it does, the keys shared with the
+// other screens last
+pub fn bindings(view: HelpView) -> Vec<(&'static str, String)> {
+    let mut keys: Vec<(&str, String)> = match view {
+        HelpView::Stats => vec![
+            ("←/h →/l", tr!("help-step-period")),
+            ("w m y", tr!("help-step-week-month-year")),
+            ("p", tr!("help-quarters")),
+            ("r", tr!("help-range")),
+            ("tab", tr!("help-panel")),
+            ("↑/k ↓/j", tr!("help-select-command")),
+            ("enter", tr!("help-command-runs")),
+        ],
+        HelpView::Today => vec![
+            ("←/h →/l", tr!("help-step-week")),
+            ("tab", tr!("help-panel")),
+        ],
+        HelpView::History => vec![
+            ("↑/k ↓/j", tr!("help-move")),
+            ("PgUp PgDn", tr!("help-page")),
+            ("ctrl-u ctrl-d", tr!("help-half-page")),
+            ("g/Home G/End", tr!("help-first-last")),
+            (":N", tr!("help-go-to")),
+            ("enter/l", tr!("help-details")),
+            ("space", tr!("help-preview")),
+            ("/", tr!("help-search")),
+            ("n N", tr!("help-next-match")),
+            ("b", tr!("help-pin")),
+            ("d", tr!("help-delete")),
+            ("U", tr!("help-undo-delete")),
+            ("u", tr!("help-unique")),
+            ("v", tr!("help-split")),
+            ("c", tr!("help-columns")),
+            ("r", tr!("help-range")),
+            ("t", tr!("help-time")),
+        ],
+        HelpView::Detail => vec![
+            ("↑/k ↓/j", tr!("help-detail-step")),
+            ("t", tr!("help-time")),
+            ("g", tr!("help-timeline")),
+            ("b", tr!("help-pin")),
+        ],
+        HelpView::Vocabulary => vec![
+            ("↑/k ↓/j", tr!("help-move")),
+            ("PgUp PgDn", tr!("help-page")),
+            ("1-5", tr!("help-sort")),
+            ("enter", tr!("help-command-runs")),
+        ],
+        HelpView::Heatmap => vec![
+            ("←/h →/l", tr!("help-heatmap-week")),
+            ("↑/k ↓/j", tr!("help-heatmap-day")),
+            ("enter", tr!("help-heatmap-list")),
+        ],
+        HelpView::Wrapped => vec![
+            ("→/l/space", tr!("help-next-page")),
+            ("←/h", tr!("help-previous-page")),
+        ],
+    };
+    if matches!(
+        view,
+        HelpView::Stats | HelpView::Today | HelpView::History | HelpView::Detail
+    ) {
+        keys.push(("s S", tr!("help-save")));
+    }
+    if !matches!(view, HelpView::Heatmap | HelpView::Wrapped) {
+        keys.push(("F1 F2 F3", tr!("help-screens")));
+    }
+    keys.push(("?", tr!("help-help")));
+    keys.push(match view {
+        HelpView::Detail => ("esc/q", tr!("help-back-to-list")),
+        _ => ("esc/q", tr!("help-back")),
+    });
+    keys.push(("ctrl-c", tr!("help-quit")));
+    keys
+}
+
+// The keys split evenly into as many columns as it takes to fit `rows` high
+fn columns<T>(keys: &[T], rows: usize) -> Vec<&[T]> {
+    let count = keys.len().div_ceil(rows.max(1)).max(1);
+    keys.chunks(keys.len().div_ceil(count).max(1)).collect()
+}
+
+// A box over the view listing its keys, closed by any key. A resize draws
+// it again alone, as the view below no longer fits.
+pub fn show_help(stdout: &mut io::Stdout, view: HelpView) -> Result<()> {
+    while !draw_help(stdout, view).context(ErrorKind::Terminal)? {
+        execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
+    }
+    Ok(())
+}
+
+// True once a key closes it
+fn draw_help(stdout: &mut io::Stdout, view: HelpView) -> Result<bool> {
+    let keys = bindings(view);
+    let mut canvas = Canvas::screen()?;
+    let screen = canvas.area();
+    // Room for the borders, the hint sitting on the bottom one
+    let columns = columns(&keys, (screen.height as usize).saturating_sub(2));
+    let rows = columns.first().map_or(0, |column| column.len());
+    let width = (HELP_WIDTH * columns.len() as u16).min(screen.width);
+    let height = (rows as u16 + 2).min(screen.height);
+    let area = Rect::new(
+        screen.width.saturating_sub(width) / 2,
+        screen.height.saturating_sub(height) / 2,
+        width,
+        height,
+    );
+
+    let block = titled(&tr!("help-title")).title_bottom(
+        Line::from(tr!("preview-close"))
+            .right_aligned()
+            .fg(Color::DarkGray),
+    );
+    let inner = block.inner(area);
+    canvas.render(Clear, area);
+    canvas.render(block, area);
+    let areas = Layout::horizontal(vec![Constraint::Fill(1); columns.len()]).split(inner);
+    for (column, area) in columns.iter().zip(areas.iter()) {
+        let rows = column.iter().map(|(key, description)| {
+            Row::new([key.to_string().light_cyan(), description.clone().into()])
+        });
+        let table = Table::new(rows, [Constraint::Length(KEYS_WIDTH), Constraint::Fill(1)]);
+        canvas.render(
+            table,
+            Rect {
+                x: area.x + 1,
+                width: area.width.saturating_sub(1),
+                ..*area
+            },
+        );
+    }
+    canvas.flush(stdout)?;
+    stdout.flush().context(ErrorKind::Terminal)?;
+    wait_to_dismiss()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_view_lists_help_and_leaving() {
+        for view in [
+            HelpView::Stats,
+            HelpView::Today,
+            HelpView::History,
+            HelpView::Detail,
+            HelpView::Vocabulary,
+            HelpView::Heatmap,
+            HelpView::Wrapped,
+        ] {
+            let keys: Vec<&str> = bindings(view).iter().map(|(key, _)| *key).collect();
+            assert!(keys.contains(&"?"), "{:?}", view);
+            assert!(keys.contains(&"esc/q"), "{:?}", view);
+            assert_eq!(keys.last(), Some(&"ctrl-c"));
+        }
+        // Too many for the screen's height go in more columns
+        let keys = bindings(HelpView::History);
+        let split = columns(&keys, 12);
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[0].len() + split[1].len(), keys.len());
+        assert!(split[0].len() - split[1].len() <= 1);
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+
+use crate::error::ErrorKind;
+use crate::history::{HistoryEntry, UserDirs};
+use crate::parser::is_valid_directory;
+
+// zsh-histdb's database, unless $HISTDB_FILE moves it elsewhere
+pub fn histdb_path(dirs: &UserDirs) -> PathBuf {
+    dirs.home.join(".histdb/zsh-history.db")
+}
+
+// Commands still running when the shell exited have no duration or exit
+// status. The host is kept apart from the context, which is the user's own
+// label.
+fn read_entries(conn: &Connection) -> rusqlite::Result<Vec<HistoryEntry>> {
+    let mut statement = conn.prepare(
+        "SELECT history.start_time, history.duration, history.exit_status,
+                commands.argv, places.dir, places.host
+         FROM history
+         JOIN commands ON commands.id = history.command_id
+         LEFT JOIN places ON places.id = history.place_id
+         ORDER BY history.start_time",
+    )?;
+    let rows = statement.query_map([], |row| {
+        let directory: Option<String> = row.get(4)?;
+        let host: Option<String> = row.get(5)?;
+        Ok(HistoryEntry {
+            timestamp: row.get::<_, Option<i64>>(0)?.unwrap_or(0),
+            command: row.get(3)?,
+            directory: directory.filter(|dir| is_valid_directory(dir)),
+            duration: row.get(1)?,
+            exit_code: row.get(2)?,
+            context: None,
+            session_id: None,
+            commit: None,
+            host: host.filter(|host| !host.is_empty()),
+            recorded_timestamp: None,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn read_histdb_history(path: &Path) -> Result<Vec<HistoryEntry>> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open zsh-histdb database {}", path.display()))
+        .context(ErrorKind::NoData)?;
+    read_entries(&conn).context("Failed to read zsh-histdb history")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_commands_and_places() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE commands (id INTEGER PRIMARY KEY, argv TEXT);
+             CREATE TABLE places (id INTEGER PRIMARY KEY, host TEXT, dir TEXT);
+             CREATE TABLE history (id INTEGER PRIMARY KEY, session INT, command_id INT,
+                 place_id INT, exit_status INT, start_time INT, duration INT);
+             INSERT INTO commands VALUES (1, 'cargo test'), (2, 'sleep 100');
+             INSERT INTO places VALUES (1, 'laptop', '/src/app');
+             INSERT INTO history VALUES (1, 7, 2, 1, NULL, 1681234600, NULL);
+             INSERT INTO history VALUES (2, 7, 1, 1, 101, 1681234567, 12);",
+        )
+        .unwrap();
+
+        let entries = read_entries(&conn).unwrap();
+        assert_eq!(
+            entries[0],
+            HistoryEntry {
+                timestamp: 1681234567,
+                command: "cargo test".to_string(),
+                directory: Some("/src/app".to_string()),
+                duration: Some(12),
+                exit_code: Some(101),
+                context: None,
+                session_id: None,
+                commit: None,
+                host: Some("laptop".to_string()),
+                recorded_timestamp: None,
+            }
+        );
+        assert_eq!((entries[1].duration, entries[1].exit_code), (None, None));
+    }
+}
@@ -1,235 +1,749 @@
-use anyhow::{Context, Result};
-use chrono::{Local, TimeZone};
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
 use std::{
+    collections::HashMap,
     fs::File,
-    io::{BufRead, BufReader},
-    path::PathBuf,
+    io::{self, BufRead},
+    path::{Component, Path, PathBuf},
 };
 
-#[derive(Debug, Clone)]
+use crate::atuin::{atuin_db_path, read_atuin_history};
+use crate::cache::parse_cached;
+use crate::error::ErrorKind;
+use crate::histdb::{histdb_path, read_histdb_history};
+use crate::parser::{
+    parse_bash_history, parse_fish_history, parse_powershell_line, parse_stats_log_line,
+    parse_zsh_line, split_commands, ParseMode,
+};
+use crate::timing;
+
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct HistoryEntry {
     pub timestamp: i64,
     pub command: String,
     pub directory: Option<String>,
-    pub duration: Option<i64>,  // Keep for potential future use
-    pub exit_code: Option<i32>, // Keep for potential future use
+    pub duration: Option<i64>,      // Seconds, when the source records it
+    pub exit_code: Option<i32>,     // When the source records it
+    pub context: Option<String>,    // User-supplied label such as "office" or "home"
+    pub session_id: Option<String>, // Shell session the command ran in
+    pub commit: Option<String>,     // Git HEAD once the command finished, if recorded
+    pub host: Option<String>,       // Machine it ran on, when the source records it
+    // The timestamp as saved, when --estimate-timestamps replaced it with a
+    // guess
+    pub recorded_timestamp: Option<i64>,
 }
 
-fn get_zsh_history_path() -> Result<PathBuf> {
-    let home = home::home_dir().context("Could not find home directory")?;
-    Ok(home.join(".zsh_history"))
+impl HistoryEntry {
+    pub fn estimated(&self) -> bool {
+        self.recorded_timestamp.is_some()
+    }
+
+    // The timestamp the entry's line has, to find it there again
+    pub fn saved_timestamp(&self) -> i64 {
+        self.recorded_timestamp.unwrap_or(self.timestamp)
+    }
 }
 
-fn get_cli_stats_log_path() -> Result<PathBuf> {
-    let home = home::home_dir().context("Could not find home directory")?;
-    Ok(home.join(".cli_stats_log"))
+// Databases kept by other history tools, read only when asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Source {
+    Atuin,
+    Histdb,
+    /// Every history found, merged without duplicates
+    All,
 }
 
-fn parse_history_line(line: &str) -> Vec<HistoryEntry> {
-    let mut entries = Vec::new();
-    // Handle Zsh history format: ": timestamp:0;command"
-    if line.starts_with(": ") {
-        let parts: Vec<&str> = line.splitn(3, ';').collect();
-        if parts.len() < 2 {
-            return entries;
-        }
-        let ts_part = match parts[0].strip_prefix(": ") {
-            Some(s) => s.trim(),
-            None => return entries,
-        };
-        let timestamp = match ts_part.splitn(2, ':').next().and_then(|s| s.parse().ok()) {
-            Some(ts) => ts,
-            None => return entries,
+// Where one user's history files live
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserDirs {
+    pub home: PathBuf,
+    // $XDG_DATA_HOME, where fish and atuin keep their history
+    pub data: PathBuf,
+    // $HISTFILE, which moves the history of the shell that set it
+    pub histfile: Option<(Shell, PathBuf)>,
+}
+
+impl UserDirs {
+    pub fn current() -> Result<UserDirs> {
+        let home = home::home_dir().context("Could not find home directory")?;
+        let data = match std::env::var_os("XDG_DATA_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => home.join(".local/share"),
         };
-        let command = parts[1].trim();
-        for subcmd in command.split("&&") {
-            let clean = subcmd.trim();
-            if !clean.is_empty() {
-                entries.push(HistoryEntry {
-                    timestamp,
-                    command: clean.to_string(),
-                    directory: None,
-                    duration: None,
-                    exit_code: None,
-                });
+        // Only zsh and bash use $HISTFILE, and it's usually only exported
+        // by the login shell
+        let histfile = match (Shell::detect(), std::env::var_os("HISTFILE")) {
+            (Some(shell @ (Shell::Zsh | Shell::Bash)), Some(path)) if !path.is_empty() => {
+                Some((shell, PathBuf::from(path)))
             }
+            _ => None,
+        };
+        Ok(UserDirs {
+            home,
+            data,
+            histfile,
+        })
+    }
+
+    // Another user's home, assuming their files are in the default places
+    pub fn at(home: PathBuf) -> UserDirs {
+        UserDirs {
+            data: home.join(".local/share"),
+            home,
+            histfile: None,
         }
-        return entries;
-    } else {
-        // Plain command
-        for subcmd in line.trim().split("&&") {
-            let clean = subcmd.trim();
-            if !clean.is_empty() {
-                entries.push(HistoryEntry {
-                    timestamp: 0,
-                    command: clean.to_string(),
-                    directory: None,
-                    duration: None,
-                    exit_code: None,
-                });
+    }
+
+    pub fn stats_log(&self) -> PathBuf {
+        self.home.join(".cli_stats_log")
+    }
+}
+
+// Shells whose own history file can be read when there's no stats log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Zsh,
+    Bash,
+    Fish,
+    #[value(alias = "pwsh")]
+    Powershell,
+}
+
+impl Shell {
+    pub(crate) const ALL: [Shell; 4] = [Shell::Zsh, Shell::Bash, Shell::Fish, Shell::Powershell];
+
+    // The login shell from $SHELL, e.g. /usr/bin/bash
+    pub(crate) fn detect() -> Option<Shell> {
+        let shell = std::env::var("SHELL").ok()?;
+        match shell.rsplit('/').next()? {
+            "zsh" => Some(Shell::Zsh),
+            "bash" => Some(Shell::Bash),
+            "fish" => Some(Shell::Fish),
+            "pwsh" | "powershell" => Some(Shell::Powershell),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Shell::Zsh => "zsh",
+            Shell::Bash => "bash",
+            Shell::Fish => "fish",
+            Shell::Powershell => "PowerShell",
+        }
+    }
+
+    pub(crate) fn history_path(self, dirs: &UserDirs) -> PathBuf {
+        if let Some((shell, path)) = &dirs.histfile {
+            if *shell == self {
+                return path.clone();
             }
         }
-        return entries;
+        match self {
+            Shell::Zsh => dirs.home.join(".zsh_history"),
+            Shell::Bash => dirs.home.join(".bash_history"),
+            Shell::Fish => dirs.data.join("fish/fish_history"),
+            Shell::Powershell => powershell_history_path(dirs),
+        }
     }
-}
 
-fn parse_cli_stats_line(line: &str) -> Vec<HistoryEntry> {
-    let mut entries = Vec::new();
-    // Pipe-delimited format
-    let pipe_parts: Vec<&str> = line.split('|').collect();
-    if pipe_parts.len() == 3 {
-        let timestamp = pipe_parts[0].parse::<i64>().unwrap_or(0);
-        let directory = if is_valid_directory(pipe_parts[2].trim()) {
-            Some(pipe_parts[2].trim().to_string())
+    // Guess the shell from a history file's name, e.g. `.bash_history`
+    pub(crate) fn from_file_name(path: &Path) -> Option<Shell> {
+        let name = path.file_name()?.to_str()?;
+        if name.contains("zsh") || name == ".histfile" {
+            Some(Shell::Zsh)
+        } else if name.contains("bash") {
+            Some(Shell::Bash)
+        } else if name.contains("fish") {
+            Some(Shell::Fish)
+        } else if name.starts_with("ConsoleHost_history") {
+            Some(Shell::Powershell)
         } else {
             None
-        };
-        for subcmd in pipe_parts[1].split("&&") {
-            let clean = subcmd.trim();
-            if !clean.is_empty() {
-                entries.push(HistoryEntry {
-                    timestamp,
-                    command: clean.to_string(),
-                    directory: directory.clone(),
-                    duration: None,
-                    exit_code: None,
-                });
+        }
+    }
+
+    pub(crate) fn parse(self, reader: impl BufRead) -> Vec<HistoryEntry> {
+        match self {
+            Shell::Zsh => read_lines(reader)
+                .flat_map(|line| parse_zsh_line(&line, ParseMode::Tolerant).unwrap_or_default())
+                .collect(),
+            Shell::Bash => {
+                parse_bash_history(read_lines(reader), ParseMode::Tolerant).unwrap_or_default()
+            }
+            Shell::Fish => {
+                parse_fish_history(read_lines(reader), ParseMode::Tolerant).unwrap_or_default()
             }
+            Shell::Powershell => read_lines(reader)
+                .flat_map(|line| {
+                    parse_powershell_line(&line, ParseMode::Tolerant).unwrap_or_default()
+                })
+                .collect(),
         }
-        return entries;
-    }
-    // Colon-delimited format
-    if !line.starts_with(": ") {
-        let parts: Vec<&str> = line.splitn(3, ':').collect();
-        if parts.len() >= 3 && parts[0].chars().all(|c| c.is_digit(10)) {
-            let timestamp = parts[0].parse::<i64>().unwrap_or(0);
-            let dir_str = parts.last().unwrap().trim();
-            let directory = if is_valid_directory(dir_str) {
-                Some(dir_str.to_string())
-            } else {
-                None
-            };
-            let command_parts = &parts[1..parts.len() - 1];
-            let command = command_parts.join(":").trim().to_string();
-            for subcmd in command.split("&&") {
-                let clean = subcmd.trim();
-                if !clean.is_empty() {
-                    entries.push(HistoryEntry {
-                        timestamp,
-                        command: clean.to_string(),
-                        directory: directory.clone(),
-                        duration: None,
-                        exit_code: None,
-                    });
-                }
+    }
+}
+
+// PSReadLine keeps its history under %APPDATA% on Windows and in the XDG
+// data directory elsewhere
+#[cfg(windows)]
+fn powershell_history_path(dirs: &UserDirs) -> PathBuf {
+    dirs.home
+        .join("AppData/Roaming/Microsoft/Windows/PowerShell/PSReadLine/ConsoleHost_history.txt")
+}
+
+#[cfg(not(windows))]
+fn powershell_history_path(dirs: &UserDirs) -> PathBuf {
+    dirs.data
+        .join("powershell/PSReadLine/ConsoleHost_history.txt")
+}
+
+pub fn get_cli_stats_log_path() -> Result<PathBuf> {
+    Ok(UserDirs::current()?.stats_log())
+}
+
+// Zsh metafies non-ASCII bytes, so history files aren't always valid UTF-8.
+// Decode lossily rather than dropping those lines.
+fn read_lines(reader: impl BufRead) -> impl Iterator<Item = String> {
+    reader
+        .split(b'\n')
+        .map_while(|line| line.ok())
+        .map(|line| String::from_utf8_lossy(&line).into_owned())
+}
+
+fn read_shell_history(dirs: &UserDirs, shell: Shell) -> Result<Vec<HistoryEntry>> {
+    let path = shell.history_path(dirs);
+    let file = File::open(&path)
+        .with_context(|| format!("Failed to open {} history file", shell.name()))
+        .context(ErrorKind::NoData)?;
+    let mut entries = parse_cached(&path, shell.name(), file, |bytes| shell.parse(bytes));
+    timing::time("resolve directories", || {
+        resolve_directories(&mut entries, &dirs.home)
+    });
+    Ok(entries)
+}
+
+// Names the stats log's format in the parse cache, next to the shells'
+const STATS_LOG_FORMAT: &str = "stats log";
+
+pub(crate) fn parse_stats_log(reader: impl BufRead) -> Vec<HistoryEntry> {
+    read_lines(reader)
+        .flat_map(|line| parse_stats_log_line(&line, ParseMode::Tolerant).unwrap_or_default())
+        .collect()
+}
+
+// A history file given on the command line, read in the format of `shell`,
+// or the one its name suggests. Anything else is read like the stats log,
+// which also understands zsh history lines.
+fn read_history_file(path: &Path, shell: Option<Shell>) -> Result<Vec<HistoryEntry>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open history file {}", path.display()))
+        .context(ErrorKind::NoData)?;
+    let mut entries = match shell.or_else(|| Shell::from_file_name(path)) {
+        Some(shell) => parse_cached(path, shell.name(), file, |bytes| shell.parse(bytes)),
+        None => parse_cached(path, STATS_LOG_FORMAT, file, |bytes| parse_stats_log(bytes)),
+    };
+    if let Some(home) = home::home_dir() {
+        timing::time("resolve directories", || {
+            resolve_directories(&mut entries, &home)
+        });
+    }
+    Ok(entries)
+}
+
+// The stats log's entries, if it exists and has any
+fn read_stats_log(dirs: &UserDirs) -> Option<Vec<HistoryEntry>> {
+    let path = dirs.stats_log();
+    let file = File::open(&path).ok()?;
+    let mut entries = parse_cached(&path, STATS_LOG_FORMAT, file, |bytes| {
+        parse_stats_log(bytes)
+    });
+    // Older log lines may not have the directory
+    timing::time("resolve directories", || {
+        resolve_directories(&mut entries, &dirs.home)
+    });
+    (!entries.is_empty()).then_some(entries)
+}
+
+// Where one shell session is, as far as its history tells
+#[derive(Debug, Clone, Default)]
+struct WorkingDirectory {
+    // None until something pins it down, e.g. `cd /src` or a recorded cwd
+    current: Option<PathBuf>,
+    // $OLDPWD, for `cd -`
+    previous: Option<PathBuf>,
+    // The pushd/popd stack
+    stack: Vec<Option<PathBuf>>,
+}
+
+// `base` joined with `path`, with `.` and `..` resolved without touching the
+// filesystem, as the shell does by default
+fn join_lexically(base: &Path, path: &Path) -> PathBuf {
+    let mut joined = base.to_path_buf();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                joined.pop();
             }
-            return entries;
+            other => joined.push(other),
         }
     }
-    // Zsh history format
-    if line.starts_with(": ") {
-        let timestamp_part = line.strip_prefix(": ").unwrap_or("");
-        let parts: Vec<&str> = timestamp_part.splitn(2, ';').collect();
-        if parts.len() < 2 {
-            return entries;
+    joined
+}
+
+impl WorkingDirectory {
+    // Where `target` leads from here, if that can be told from the text
+    fn resolve(&self, target: &str, home: &Path) -> Option<PathBuf> {
+        let target = target.trim_matches(|c| c == '"' || c == '\'');
+        if target.contains('$') || target.contains('`') {
+            return None;
+        }
+        if target == "~" {
+            return Some(home.to_path_buf());
+        }
+        if let Some(rest) = target.strip_prefix("~/") {
+            return Some(join_lexically(home, Path::new(rest)));
+        }
+        if target.starts_with('~') {
+            // Another user's home
+            return None;
         }
-        let ts_parts: Vec<&str> = parts[0].splitn(2, ':').collect();
-        let timestamp = ts_parts[0].parse::<i64>().unwrap_or(0);
-        let cmd_dir: Vec<&str> = parts[1].splitn(2, ':').collect();
-        let directory = if cmd_dir.len() > 1 && is_valid_directory(cmd_dir[1].trim()) {
-            Some(cmd_dir[1].trim().to_string())
+        let path = Path::new(target);
+        if path.is_absolute() {
+            Some(join_lexically(Path::new("/"), path))
         } else {
-            None
+            self.current
+                .as_deref()
+                .map(|current| join_lexically(current, path))
+        }
+    }
+
+    fn change_to(&mut self, directory: Option<PathBuf>) {
+        self.previous = std::mem::replace(&mut self.current, directory);
+    }
+
+    // Follow the cd, pushd and popd calls in one simple command
+    fn run(&mut self, words: &[&str], home: &Path) {
+        let Some((&program, args)) = words.split_first() else {
+            return;
         };
-        for subcmd in cmd_dir[0].split("&&") {
-            let clean = subcmd.trim();
-            if !clean.is_empty() {
-                entries.push(HistoryEntry {
-                    timestamp,
-                    command: clean.to_string(),
-                    directory: directory.clone(),
-                    duration: None,
-                    exit_code: None,
-                });
+        // Options such as `cd -P`, but `-` alone is an argument
+        let mut args = args
+            .iter()
+            .filter(|arg| **arg == "-" || !arg.starts_with('-'));
+        match (program, args.next()) {
+            ("cd", None) => self.change_to(Some(home.to_path_buf())),
+            ("cd", Some(&"-")) => {
+                let previous = self.previous.take();
+                self.change_to(previous);
             }
-        }
-        return entries;
-    } else {
-        // Plain command
-        for subcmd in line.trim().split("&&") {
-            let clean = subcmd.trim();
-            if !clean.is_empty() {
-                entries.push(HistoryEntry {
-                    timestamp: 0,
-                    command: clean.to_string(),
-                    directory: None,
-                    duration: None,
-                    exit_code: None,
-                });
+            ("cd", Some(target)) => {
+                let directory = self.resolve(target, home);
+                self.change_to(directory);
             }
+            ("pushd", Some(target)) if !target.starts_with('+') => {
+                let directory = self.resolve(target, home);
+                self.stack.push(self.current.clone());
+                self.change_to(directory);
+            }
+            ("pushd", None) => {
+                // Swaps with the top of the stack
+                if let Some(top) = self.stack.pop() {
+                    self.stack.push(self.current.clone());
+                    self.change_to(top);
+                }
+            }
+            ("pushd", _) | ("popd", Some(_)) => {
+                // Rotating the stack isn't followed
+                self.stack.clear();
+                self.change_to(None);
+            }
+            ("popd", None) => {
+                let top = self.stack.pop().flatten();
+                self.change_to(top);
+            }
+            _ => {}
         }
-        return entries;
     }
 }
 
-// Helper function to validate if a string looks like a valid directory path
-fn is_valid_directory(path: &str) -> bool {
-    // Valid directories should:
-    // 1. Start with / (absolute path) or ~ (home directory)
-    // 2. Not contain characters that are invalid in paths
+// Fill in the directory of commands that don't record one, by following
+// cd, pushd and popd through each session's history. Directories that were
+// recorded are kept and anchor the ones after them. Commands in a
+// subshell, a pipeline or after `||` don't move the shell, and neither do
+// commands that failed.
+pub fn resolve_directories(entries: &mut [HistoryEntry], home: &Path) {
+    let mut sessions: HashMap<Option<String>, WorkingDirectory> = HashMap::new();
+    for entry in entries.iter_mut() {
+        let cwd = sessions.entry(entry.session_id.clone()).or_default();
+        match &entry.directory {
+            Some(directory) => cwd.current = Some(PathBuf::from(directory)),
+            None => {
+                entry.directory = cwd
+                    .current
+                    .as_ref()
+                    .map(|current| current.to_string_lossy().into_owned());
+            }
+        }
+        if entry.exit_code.is_some_and(|code| code != 0) {
+            continue;
+        }
+        for command in entry.command.split(['\n', ';']).flat_map(|c| c.split("&&")) {
+            // Whatever comes after `||` may or may not have run
+            let (command, alternative) = match command.split_once("||") {
+                Some((first, _)) => (first.trim(), true),
+                None => (command.trim(), false),
+            };
+            if !command.starts_with('(') && !command.contains('|') {
+                let words: Vec<&str> = command.split_whitespace().collect();
+                cwd.run(&words, home);
+            }
+            if alternative {
+                break;
+            }
+        }
+    }
+}
 
-    if path.is_empty() {
-        return false;
+// The directory `--dir` names. `.` is the git repository the shell is in,
+// or just the current directory outside one; other relative paths are
+// taken from the current directory.
+pub fn project_dir(dir: &Path) -> Result<PathBuf> {
+    let cwd = std::env::current_dir().context("Could not read the current directory")?;
+    let path = join_lexically(&cwd, dir);
+    if dir == Path::new(".") {
+        if let Some(root) = repository_root(&path) {
+            return Ok(root.to_path_buf());
+        }
     }
+    Ok(path)
+}
+
+// The git repository `dir` is in: the nearest directory up from it with a
+// `.git`, which is a file rather than a directory in worktrees
+pub fn repository_root(dir: &Path) -> Option<&Path> {
+    dir.ancestors().find(|a| a.join(".git").exists())
+}
 
-    // Check if starts with / or ~
-    if path.starts_with('/') || path.starts_with('~') {
-        // Do additional checks to exclude URLs
-        if path.contains("://") || path.contains("github.com") {
-            return false;
+// Whether `entry` ran in `dir` or somewhere below it
+pub fn ran_in(entry: &HistoryEntry, dir: &Path) -> bool {
+    entry
+        .directory
+        .as_deref()
+        .is_some_and(|directory| Path::new(directory).starts_with(dir))
+}
+
+// Combine histories into one, oldest first. A command at the same time in
+// two histories was recorded by both, so each (timestamp, command) pair is
+// kept as many times as the history with the most of it has it; repeats
+// within one history, like `ls && ls`, survive. Earlier histories win, so
+// list the ones recording more about each command first. The shell
+// histories split `a && b` into its commands while atuin and the stats log
+// keep the line whole, so every history is split the same way first.
+pub fn merge_histories(histories: Vec<Vec<HistoryEntry>>) -> Vec<HistoryEntry> {
+    let mut kept: HashMap<(i64, String), usize> = HashMap::new();
+    let mut merged = Vec::new();
+    for history in histories {
+        let mut seen: HashMap<(i64, String), usize> = HashMap::new();
+        for entry in history.into_iter().flat_map(split_line) {
+            let key = (entry.timestamp, entry.command.clone());
+            let count = seen.entry(key.clone()).or_insert(0);
+            *count += 1;
+            let kept = kept.entry(key).or_insert(0);
+            if *count > *kept {
+                *kept += 1;
+                merged.push(entry);
+            }
         }
-        return true;
     }
+    merged.sort_by_key(|entry| entry.timestamp);
+    merged
+}
 
-    false
+// `entry` a command at a time, as the shell histories save `a && b`
+fn split_line(entry: HistoryEntry) -> Vec<HistoryEntry> {
+    if !entry.command.contains("&&") {
+        return vec![entry];
+    }
+    match split_commands(entry.clone(), &entry.command, ParseMode::Tolerant) {
+        Ok(split) if !split.is_empty() => split,
+        _ => vec![entry],
+    }
+}
+
+// Every history that exists for the user: databases first, since they
+// record durations and exit codes, then the stats log and shell histories
+fn read_all_history(dirs: &UserDirs) -> Result<Vec<HistoryEntry>> {
+    let mut histories = Vec::new();
+    let atuin = atuin_db_path(dirs);
+    if atuin.exists() {
+        histories.push(read_atuin_history(&atuin)?);
+    }
+    let histdb = histdb_path(dirs);
+    if histdb.exists() {
+        histories.push(read_histdb_history(&histdb)?);
+    }
+    histories.extend(read_stats_log(dirs));
+    for shell in Shell::ALL {
+        if shell.history_path(dirs).exists() {
+            histories.push(read_shell_history(dirs, shell)?);
+        }
+    }
+    if histories.is_empty() {
+        return Err(anyhow!("No history found to merge")).context(ErrorKind::NoData);
+    }
+    Ok(merge_histories(histories))
 }
 
-pub fn get_history_entries() -> Result<Vec<HistoryEntry>> {
+// Read one user's history. With an explicit `source` or `shell` only that
+// history is read. Otherwise the stats log wins, then the `preferred`
+// shell's history, then whichever other shell history exists.
+pub fn read_history(
+    dirs: &UserDirs,
+    source: Option<Source>,
+    shell: Option<Shell>,
+    preferred: Option<Shell>,
+) -> Result<Vec<HistoryEntry>> {
+    match source {
+        Some(Source::Atuin) => {
+            return timing::time("read", || read_atuin_history(&atuin_db_path(dirs)))
+        }
+        Some(Source::Histdb) => {
+            return timing::time("read", || read_histdb_history(&histdb_path(dirs)))
+        }
+        Some(Source::All) => return read_all_history(dirs),
+        None => {}
+    }
+    if let Some(shell) = shell {
+        return read_shell_history(dirs, shell);
+    }
+
     // Try to read from CLI stats log first
-    let stats_path = get_cli_stats_log_path()?;
-    if let Ok(file) = File::open(&stats_path) {
-        let reader = BufReader::new(file);
-        let entries: Vec<HistoryEntry> = reader
-            .lines()
-            .filter_map(|line| line.ok())
-            .flat_map(|line| parse_cli_stats_line(&line))
-            .collect();
+    if let Some(entries) = read_stats_log(dirs) {
+        return Ok(entries);
+    }
 
-        if !entries.is_empty() {
-            return Ok(entries);
+    // Fall back to shell history if stats log is empty or not available
+    let first = preferred.unwrap_or(Shell::Zsh);
+    let fallbacks = Shell::ALL.into_iter().filter(|&shell| shell != first);
+    let mut result = read_shell_history(dirs, first);
+    for shell in fallbacks {
+        if result.is_ok() {
+            break;
+        }
+        if shell.history_path(dirs).exists() {
+            result = read_shell_history(dirs, shell);
         }
     }
+    result
+}
 
-    // Fall back to zsh history if stats log is empty or not available
-    let history_path = get_zsh_history_path()?;
-    let file = File::open(history_path).context("Failed to open zsh history file")?;
-    let reader = BufReader::new(file);
+// The file `read_history` reads when no source is given, with the shell
+// whose format it's in (None for the stats log), if one exists
+pub(crate) fn default_history_file(
+    dirs: &UserDirs,
+    shell: Option<Shell>,
+    preferred: Option<Shell>,
+) -> Option<(PathBuf, Option<Shell>)> {
+    if let Some(shell) = shell {
+        let path = shell.history_path(dirs);
+        return path.exists().then_some((path, Some(shell)));
+    }
+    let log = dirs.stats_log();
+    if log.metadata().is_ok_and(|meta| meta.len() > 0) {
+        return Some((log, None));
+    }
+    let first = preferred.unwrap_or(Shell::Zsh);
+    let fallbacks = Shell::ALL.into_iter().filter(|&shell| shell != first);
+    std::iter::once(first)
+        .chain(fallbacks)
+        .map(|shell| (shell.history_path(dirs), Some(shell)))
+        .find(|(path, _)| path.exists())
+}
 
-    let entries: Vec<HistoryEntry> = reader
-        .lines()
-        .filter_map(|line| line.ok())
-        .flat_map(|line| parse_history_line(&line))
-        .collect();
+// Whether the history these settings load is the current user's stats log,
+// the only one entries can be deleted from
+pub fn reads_stats_log(
+    source: Option<Source>,
+    shell: Option<Shell>,
+    history_file: Option<&Path>,
+) -> bool {
+    let Ok(dirs) = UserDirs::current() else {
+        return false;
+    };
+    match (source, history_file) {
+        (Some(_), _) => false,
+        (None, Some(path)) => path == dirs.stats_log(),
+        (None, None) => default_history_file(&dirs, shell, Shell::detect())
+            .is_some_and(|(_, format)| format.is_none()),
+    }
+}
 
-    Ok(entries)
+// The current user's history, preferring the shell in $SHELL, or just
+// `history_file` when one is given
+pub fn get_history_entries(
+    source: Option<Source>,
+    shell: Option<Shell>,
+    history_file: Option<&Path>,
+) -> Result<Vec<HistoryEntry>> {
+    if let Some(path) = history_file {
+        return read_history_file(path, shell);
+    }
+    read_history(&UserDirs::current()?, source, shell, Shell::detect())
 }
 
-pub fn format_timestamp(timestamp: i64) -> String {
-    if timestamp == 0 {
-        return "Timestamp not available".to_string();
+// Another user's history, read with the settings `get_history_entries`
+// reads the current user's with. A history file in the current user's home
+// is taken from the same place in theirs. Having no history at all isn't an
+// error, but a history that can't be read is.
+pub fn user_history(
+    dirs: &UserDirs,
+    source: Option<Source>,
+    shell: Option<Shell>,
+    history_file: Option<&Path>,
+) -> Result<Vec<HistoryEntry>> {
+    let read = match history_file {
+        Some(path) => {
+            let own = UserDirs::current()?.home;
+            let relative = path.strip_prefix(&own).map_err(|_| {
+                anyhow!(
+                    "{} is outside your home, so other users have no copy of it",
+                    path.display()
+                )
+            })?;
+            read_history_file(&dirs.home.join(relative), shell)
+        }
+        None => read_history(dirs, source, shell, Shell::detect()),
+    };
+    match read {
+        Err(err)
+            if err.chain().any(|cause| {
+                cause
+                    .downcast_ref::<io::Error>()
+                    .is_some_and(|err| err.kind() == io::ErrorKind::NotFound)
+            }) =>
+        {
+            Ok(Vec::new())
+        }
+        read => read,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: i64, command: &str, exit_code: Option<i32>) -> HistoryEntry {
+        HistoryEntry {
+            timestamp,
+            command: command.to_string(),
+            exit_code,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn history_files_are_recognised_by_name() {
+        let shell = |name: &str| Shell::from_file_name(Path::new(name));
+        assert_eq!(shell("/tmp/.zsh_history"), Some(Shell::Zsh));
+        assert_eq!(shell("backup/.bash_history.old"), Some(Shell::Bash));
+        assert_eq!(shell("/data/fish/fish_history"), Some(Shell::Fish));
+        assert_eq!(shell("ConsoleHost_history.txt"), Some(Shell::Powershell));
+        assert_eq!(shell("/home/me/.cli_stats_log"), None);
     }
-    match Local.timestamp_opt(timestamp, 0) {
-        chrono::LocalResult::Single(dt) => dt.format("%b %d %Y at %I:%M %P").to_string(),
-        _ => "Invalid timestamp".to_string(),
+
+    #[test]
+    fn merged_histories_drop_duplicates() {
+        let atuin = vec![entry(20, "ls", Some(0)), entry(10, "make", Some(2))];
+        let zsh = vec![
+            entry(10, "make", None),
+            entry(30, "ls", None),
+            entry(30, "ls", None),
+        ];
+        let log = vec![entry(30, "ls", None), entry(20, "ls", None)];
+        assert_eq!(
+            merge_histories(vec![atuin, zsh, log]),
+            [
+                entry(10, "make", Some(2)),
+                entry(20, "ls", Some(0)),
+                entry(30, "ls", None),
+                entry(30, "ls", None),
+            ]
+        );
+
+        // zsh saved the line as two commands, atuin as one
+        let atuin = vec![entry(40, "cargo build && cargo test", Some(1))];
+        let zsh = vec![
+            entry(40, "cargo build", None),
+            entry(40, "cargo test", None),
+        ];
+        assert_eq!(
+            merge_histories(vec![atuin, zsh]),
+            [
+                entry(40, "cargo build", Some(1)),
+                entry(40, "cargo test", Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn directories_follow_cd_pushd_and_popd() {
+        let run = |command: &str| HistoryEntry {
+            command: command.to_string(),
+            ..Default::default()
+        };
+        let mut entries = vec![
+            run("ls"),
+            run("cd ~/src/app"),
+            run("cd .."),
+            run("cd -"),
+            run("pushd /etc/nginx && cat nginx.conf"),
+            HistoryEntry {
+                exit_code: Some(1),
+                ..run("cd missing")
+            },
+            run("popd"),
+            run("(cd /tmp && make)"),
+            HistoryEntry {
+                directory: Some("/opt".to_string()),
+                ..run("cd ./lib/../bin")
+            },
+            run("cd $PROJECT"),
+            run("ls"),
+        ];
+        resolve_directories(&mut entries, Path::new("/home/me"));
+        let directories: Vec<Option<&str>> =
+            entries.iter().map(|e| e.directory.as_deref()).collect();
+        assert_eq!(
+            directories,
+            [
+                None,
+                None,
+                Some("/home/me/src/app"),
+                Some("/home/me/src"),
+                Some("/home/me/src/app"),
+                Some("/etc/nginx"),
+                Some("/etc/nginx"),
+                Some("/home/me/src/app"),
+                Some("/opt"),
+                Some("/opt/bin"),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn dir_filters_by_path_prefix() {
+        let at = |directory: Option<&str>| HistoryEntry {
+            command: "ls".to_string(),
+            directory: directory.map(str::to_string),
+            ..Default::default()
+        };
+        let project = project_dir(Path::new("/home/me/src/app/../app")).unwrap();
+        assert_eq!(project, Path::new("/home/me/src/app"));
+        assert!(ran_in(&at(Some("/home/me/src/app")), &project));
+        assert!(ran_in(&at(Some("/home/me/src/app/tests")), &project));
+        assert!(!ran_in(&at(Some("/home/me/src/app-old")), &project));
+        assert!(!ran_in(&at(None), &project));
     }
 }
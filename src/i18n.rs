@@ -0,0 +1,168 @@
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::FluentResource;
+use std::{fs, path::PathBuf, sync::OnceLock};
+use unic_langid::LanguageIdentifier;
+
+pub use fluent_bundle::{FluentArgs, FluentValue};
+
+// Translations compiled into the binary, see locales/en/main.ftl for how to
+// add one
+const BUNDLED: &[(&str, &str)] = &[("en", include_str!("../locales/en/main.ftl"))];
+
+const FALLBACK: &str = "en";
+
+// Message lookup over a chain of bundles, most specific first, always ending
+// in the English one
+pub struct Localizer {
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+// The UI language from CLI_TRACKER_LANG, falling back to the usual locale
+// variables. `de_DE.UTF-8` becomes `de-DE`; `C` and `POSIX` mean English.
+fn requested_language() -> String {
+    ["CLI_TRACKER_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| {
+            let tag = value.split(['.', '@']).next().unwrap_or_default();
+            tag.replace('_', "-")
+        })
+        .filter(|tag| !matches!(tag.as_str(), "" | "C" | "POSIX"))
+        .unwrap_or_else(|| FALLBACK.to_string())
+}
+
+// Translations the user is working on, which take precedence over bundled
+// ones so they can be tried without rebuilding
+fn user_locale_dir() -> Option<PathBuf> {
+    home::home_dir().map(|home| home.join(".config/cli-tracker/locales"))
+}
+
+fn load_sources(tag: &str) -> Vec<String> {
+    let mut sources = Vec::new();
+    if let Some(dir) = user_locale_dir() {
+        if let Ok(source) = fs::read_to_string(dir.join(format!("{}.ftl", tag))) {
+            sources.push(source);
+        }
+    }
+    if let Some((_, source)) = BUNDLED.iter().find(|(bundled, _)| *bundled == tag) {
+        sources.push(source.to_string());
+    }
+    sources
+}
+
+impl Localizer {
+    pub fn new(requested: &str) -> Self {
+        // `de-DE`, then `de`, then English
+        let mut tags = vec![requested.to_string()];
+        if let Some((language, _)) = requested.split_once('-') {
+            tags.push(language.to_string());
+        }
+        tags.push(FALLBACK.to_string());
+        tags.dedup();
+
+        let mut bundles = Vec::new();
+        for tag in tags {
+            let langid: LanguageIdentifier = tag.parse().unwrap_or_default();
+            for source in load_sources(&tag) {
+                // Messages with syntax errors are skipped; the rest of the
+                // file still loads
+                let resource = FluentResource::try_new(source).unwrap_or_else(|(res, _)| res);
+                let mut bundle = FluentBundle::new_concurrent(vec![langid.clone()]);
+                // Unicode isolation marks show up as junk in most terminals
+                bundle.set_use_isolating(false);
+                bundle.add_resource_overriding(resource);
+                bundles.push(bundle);
+            }
+        }
+        Localizer { bundles }
+    }
+
+    // The message for `id` in the first bundle that has it, or the id itself
+    pub fn format(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        for bundle in &self.bundles {
+            if let Some(pattern) = bundle.get_message(id).and_then(|m| m.value()) {
+                let mut errors = Vec::new();
+                return bundle
+                    .format_pattern(pattern, args, &mut errors)
+                    .into_owned();
+            }
+        }
+        id.to_string()
+    }
+}
+
+pub fn localizer() -> &'static Localizer {
+    static LOCALIZER: OnceLock<Localizer> = OnceLock::new();
+    LOCALIZER.get_or_init(|| Localizer::new(&requested_language()))
+}
+
+// Look up a UI string: `tr!("stat-today")` or
+// `tr!("peak-day", day = name, count = 12)`
+#[macro_export]
+macro_rules! tr {
+    ($id:expr) => {
+        $crate::i18n::localizer().format($id, None)
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = $crate::i18n::FluentArgs::new();
+        $(args.set(stringify!($name), $crate::i18n::FluentValue::from($value));)+
+        $crate::i18n::localizer().format($id, Some(&args))
+    }};
+}
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+// Day name from its index counting from Monday
+pub fn weekday_name(index: usize) -> String {
+    tr!(&format!("weekday-{}", WEEKDAYS[index % 7]))
+}
+
+pub fn weekday_initial(index: usize) -> String {
+    tr!(&format!("weekday-initial-{}", WEEKDAYS[index % 7]))
+}
+
+// Abbreviated month name, January being 1
+pub fn month_abbrev(month: u32) -> String {
+    tr!(&format!("month-{}", MONTHS[(month as usize + 11) % 12]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_messages_with_arguments() {
+        let en = Localizer::new("en");
+        assert_eq!(en.format("stat-today", None), "Today");
+
+        let mut args = FluentArgs::new();
+        args.set("width", 100);
+        args.set("height", 20);
+        assert_eq!(
+            en.format("terminal-too-small", Some(&args)),
+            "Terminal too small. Please resize to at least 100x20"
+        );
+    }
+
+    #[test]
+    fn unknown_languages_and_messages_fall_back() {
+        let localizer = Localizer::new("xx-YY");
+        assert_eq!(localizer.format("weekday-sun", None), "Sunday");
+        assert_eq!(localizer.format("no-such-message", None), "no-such-message");
+    }
+
+    #[test]
+    fn every_bundled_translation_parses() {
+        for (tag, source) in BUNDLED {
+            assert!(
+                FluentResource::try_new(source.to_string()).is_ok(),
+                "{} has syntax errors",
+                tag
+            );
+        }
+    }
+}
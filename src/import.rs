@@ -0,0 +1,409 @@
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+
+use crate::error::ErrorKind;
+use crate::history::{default_history_file, parse_stats_log, HistoryEntry, Shell, UserDirs};
+use crate::parser::{parse_stats_log_line, ParseMode};
+use crate::record::format_record;
+
+// What `import` can read: `export`'s CSV and JSON, or a shell's own history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    Json,
+    Csv,
+    Zsh,
+    Bash,
+}
+
+impl ImportFormat {
+    // Going by the extension, then the shell a history file is named for
+    pub fn guess(path: &Path) -> Option<ImportFormat> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => Some(ImportFormat::Json),
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => Some(ImportFormat::Csv),
+            _ => match Shell::from_file_name(path)? {
+                Shell::Zsh => Some(ImportFormat::Zsh),
+                Shell::Bash => Some(ImportFormat::Bash),
+                _ => None,
+            },
+        }
+    }
+
+    // `format` if given, or else a guess from the file's name
+    pub fn choose(format: Option<ImportFormat>, path: &Path) -> Result<ImportFormat> {
+        format.or_else(|| ImportFormat::guess(path)).ok_or_else(|| {
+            anyhow!(
+                "Can't tell how {} is written; pass --format",
+                path.display()
+            )
+        })
+    }
+}
+
+// How an import went
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub added: usize,
+    // Already in the log
+    pub duplicates: usize,
+    // Without a timestamp a command can't be told apart from the same one
+    // imported before, so these are left out
+    pub undated: usize,
+}
+
+// Split CSV text into records of fields, with quoted fields able to hold
+// commas, doubled quotes and line breaks
+fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+// CSV with a header row naming `export`'s columns. Only `command` is needed,
+// and the columns can come in any order.
+fn parse_csv(text: &str) -> Result<Vec<HistoryEntry>> {
+    let mut records = csv_records(text).into_iter();
+    let header = records.next().unwrap_or_default();
+    let column = |name: &str| header.iter().position(|field| field.trim() == name);
+    let command = column("command").ok_or_else(|| anyhow!("No command column in the header"))?;
+    let columns = [
+        "timestamp",
+        "directory",
+        "duration",
+        "exit_code",
+        "context",
+        "session_id",
+        "commit",
+    ]
+    .map(column);
+
+    let mut entries = Vec::new();
+    for (number, record) in records.enumerate() {
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|i| record.get(i))
+                .filter(|value| !value.is_empty())
+        };
+        let text = |index| field(index).cloned();
+        let number_at = |index: Option<usize>| -> Result<Option<i64>> {
+            field(index)
+                .map(|value| value.parse())
+                .transpose()
+                .with_context(|| format!("Expected a number on row {}", number + 2))
+        };
+        let Some(command) = field(Some(command)) else {
+            continue;
+        };
+        entries.push(HistoryEntry {
+            timestamp: number_at(columns[0])?.unwrap_or(0),
+            command: command.clone(),
+            directory: text(columns[1]),
+            duration: number_at(columns[2])?,
+            exit_code: number_at(columns[3])?.map(|code| code as i32),
+            context: text(columns[4]),
+            session_id: text(columns[5]),
+            commit: text(columns[6]),
+            host: None,
+            recorded_timestamp: None,
+        });
+    }
+    Ok(entries)
+}
+
+// An array of objects with `export`'s fields
+fn parse_json(text: &str) -> Result<Vec<HistoryEntry>> {
+    let value: Value = serde_json::from_str(text).context("Not valid JSON")?;
+    let rows = value
+        .as_array()
+        .ok_or_else(|| anyhow!("Expected an array of entries"))?;
+    let mut entries = Vec::new();
+    for row in rows {
+        let text = |key: &str| row[key].as_str().map(str::to_string);
+        let Some(command) = text("command") else {
+            continue;
+        };
+        entries.push(HistoryEntry {
+            timestamp: row["timestamp"].as_i64().unwrap_or(0),
+            command,
+            directory: text("directory"),
+            duration: row["duration"].as_i64(),
+            exit_code: row["exit_code"].as_i64().map(|code| code as i32),
+            context: text("context"),
+            session_id: text("session_id"),
+            commit: text("commit"),
+            host: None,
+            recorded_timestamp: None,
+        });
+    }
+    Ok(entries)
+}
+
+pub fn read_import(path: &Path, format: ImportFormat) -> Result<Vec<HistoryEntry>> {
+    let entries = match format {
+        ImportFormat::Zsh | ImportFormat::Bash => {
+            let file =
+                File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+            let shell = if format == ImportFormat::Zsh {
+                Shell::Zsh
+            } else {
+                Shell::Bash
+            };
+            shell.parse(BufReader::new(file))
+        }
+        ImportFormat::Json | ImportFormat::Csv => {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let parsed = if format == ImportFormat::Json {
+                parse_json(&text)
+            } else {
+                parse_csv(&text)
+            };
+            parsed
+                .with_context(|| format!("In {}", path.display()))
+                .context(ErrorKind::Parse)?
+        }
+    };
+    Ok(entries)
+}
+
+// The entries not in `existing` yet. Like `merge_histories`, a command at
+// the same second counts as the same run, but each one is only matched
+// once, so repeats like `ls && ls` survive.
+fn new_entries(
+    existing: &[HistoryEntry],
+    imported: Vec<HistoryEntry>,
+) -> (Vec<HistoryEntry>, ImportSummary) {
+    let mut known: HashMap<(i64, &str), usize> = HashMap::new();
+    for entry in existing {
+        *known
+            .entry((entry.timestamp, entry.command.as_str()))
+            .or_insert(0) += 1;
+    }
+    let mut summary = ImportSummary::default();
+    let mut seen: HashMap<(i64, String), usize> = HashMap::new();
+    let mut added = Vec::new();
+    for entry in imported {
+        if entry.timestamp <= 0 {
+            summary.undated += 1;
+            continue;
+        }
+        let count = seen
+            .entry((entry.timestamp, entry.command.clone()))
+            .or_insert(0);
+        *count += 1;
+        let before = known
+            .get(&(entry.timestamp, entry.command.as_str()))
+            .copied()
+            .unwrap_or(0);
+        if *count > before {
+            added.push(entry);
+        } else {
+            summary.duplicates += 1;
+        }
+    }
+    summary.added = added.len();
+    (added, summary)
+}
+
+// The log's lines with the new records slotted in by time. Existing lines
+// are kept byte for byte, in place; lines without a timestamp stay after
+// the line before them.
+fn merged_lines(log: &str, mut added: Vec<HistoryEntry>) -> String {
+    added.sort_by_key(|entry| entry.timestamp);
+    let mut added = added.into_iter().peekable();
+    let mut out = String::with_capacity(log.len());
+    let mut previous = 0;
+    for line in log.lines() {
+        let timestamp = parse_stats_log_line(line, ParseMode::Tolerant)
+            .ok()
+            .and_then(|entries| entries.first().map(|entry| entry.timestamp))
+            .filter(|&timestamp| timestamp > 0)
+            .unwrap_or(previous);
+        while let Some(entry) = added.next_if(|entry| entry.timestamp < timestamp) {
+            out.push_str(&format_record(&entry));
+            out.push('\n');
+        }
+        out.push_str(line);
+        out.push('\n');
+        previous = timestamp;
+    }
+    for entry in added {
+        out.push_str(&format_record(&entry));
+        out.push('\n');
+    }
+    out
+}
+
+// Replace the log with `text` through a temporary file, as `purge` does,
+// so a crash never leaves it half written
+fn write_log(log: &Path, text: &str) -> Result<()> {
+    let tmp_path = log.with_extension("import-tmp");
+    let mut tmp = io::BufWriter::new(
+        File::create(&tmp_path)
+            .with_context(|| format!("Failed to create {}", tmp_path.display()))?,
+    );
+    tmp.write_all(text.as_bytes())?;
+    tmp.into_inner()?.sync_all()?;
+    fs::rename(&tmp_path, log).with_context(|| format!("Failed to replace {}", log.display()))
+}
+
+// Without a stats log the views read the shell's history, and once there
+// is one they read only that. So before an import creates the log, it's
+// started with the shell history, as it was written rather than with the
+// directories worked out from `cd`s. Returns how many commands that took.
+pub fn seed_log(log: &Path, dirs: &UserDirs) -> Result<usize> {
+    if log.metadata().is_ok_and(|meta| meta.len() > 0) {
+        return Ok(0);
+    }
+    let Some((path, Some(shell))) = default_history_file(dirs, None, Shell::detect()) else {
+        return Ok(0);
+    };
+    let file = File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let local = shell.parse(BufReader::new(file));
+    if !local.is_empty() {
+        write_log(log, &merged_lines("", local.clone()))?;
+    }
+    Ok(local.len())
+}
+
+// Add the entries the stats log doesn't have yet, rewriting it with
+// `write_log`
+pub fn import_into_log(log: &Path, imported: Vec<HistoryEntry>) -> Result<ImportSummary> {
+    let current = match fs::read(log) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {}", log.display())),
+    };
+    let existing = parse_stats_log(current.as_bytes());
+    let (added, summary) = new_entries(&existing, imported);
+    if added.is_empty() {
+        return Ok(summary);
+    }
+
+    write_log(log, &merged_lines(&current, added))?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{entries_csv, entries_json};
+    use crate::scratch::ScratchDir;
+
+    fn run(timestamp: i64, command: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp,
+            command: command.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reads_what_export_writes() {
+        let entries = vec![
+            HistoryEntry {
+                directory: Some("/repo".to_string()),
+                exit_code: Some(1),
+                ..run(100, "printf 'a,\"b\"\\n'\necho done")
+            },
+            run(200, "ls"),
+        ];
+        assert_eq!(parse_csv(&entries_csv(&entries)).unwrap(), entries);
+        assert_eq!(parse_json(&entries_json(&entries)).unwrap(), entries);
+
+        // Only the command column is required, in any position
+        let parsed = parse_csv("dir,command\n/tmp,ls\n").unwrap();
+        assert_eq!(parsed, [run(0, "ls")]);
+        assert!(parse_csv("timestamp\n1\n").is_err());
+        assert!(parse_csv("command,timestamp\nls,soon\n").is_err());
+    }
+
+    #[test]
+    fn merges_new_runs_in_order() {
+        let log = "{\"v\":1,\"ts\":100,\"cmd\":\"ls\"}\n300|make|/src\nbroken line\n";
+        let existing = parse_stats_log(log.as_bytes());
+        let (added, summary) = new_entries(
+            &existing,
+            vec![
+                run(100, "ls"),
+                run(200, "git pull"),
+                run(400, "make"),
+                run(400, "make"),
+                run(0, "pwd"),
+            ],
+        );
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: 3,
+                duplicates: 1,
+                undated: 1
+            }
+        );
+
+        let merged = merged_lines(log, added);
+        let lines: Vec<&str> = merged.lines().collect();
+        assert_eq!(lines[0], "{\"v\":1,\"ts\":100,\"cmd\":\"ls\"}");
+        assert!(lines[1].contains("\"git pull\""));
+        assert_eq!(&lines[2..4], ["300|make|/src", "broken line"]);
+        assert_eq!(lines.len(), 6);
+    }
+
+    #[test]
+    fn a_new_log_starts_with_the_shell_history() {
+        let home = ScratchDir::new("seed");
+        fs::write(home.join(".zsh_history"), ": 100:0;cd /src\n: 200:0;make\n").unwrap();
+        let dirs = UserDirs::at(home.to_path_buf());
+        let log = dirs.stats_log();
+
+        assert_eq!(seed_log(&log, &dirs).unwrap(), 2);
+        let seeded = parse_stats_log(fs::read(&log).unwrap().as_slice());
+        assert_eq!(seeded, [run(100, "cd /src"), run(200, "make")]);
+        // Only a missing or empty log is started
+        assert_eq!(seed_log(&log, &dirs).unwrap(), 0);
+        let summary = import_into_log(&log, vec![run(200, "make"), run(300, "ls")]).unwrap();
+        assert_eq!((summary.added, summary.duplicates), (1, 1));
+    }
+
+    #[test]
+    fn guesses_the_format() {
+        assert_eq!(
+            ImportFormat::guess(Path::new("laptop.CSV")),
+            Some(ImportFormat::Csv)
+        );
+        assert_eq!(
+            ImportFormat::guess(Path::new("/backup/.zsh_history")),
+            Some(ImportFormat::Zsh)
+        );
+        assert_eq!(ImportFormat::guess(Path::new("history.txt")), None);
+    }
+}
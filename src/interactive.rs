@@ -2,116 +2,67 @@ use anyhow::{Context, Result};
 use chrono::{Local, TimeZone, Timelike};
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
-    style::{self, Color, Stylize},
+    style::{Color, Stylize},
     terminal::{self, ClearType},
 };
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::io::{self, Write};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::history::{format_timestamp, HistoryEntry};
-use crate::ui_utils::{draw_box, write_in_box};
+use crate::aggregate::{command_directories, command_lifetime};
+use crate::app::{Screen, ScreenExit};
+use crate::categories::categories;
+use crate::days::day_counts;
+use crate::error::ErrorKind;
+use crate::favorites::{pinned_set, toggle_favorite};
+use crate::frames;
+use crate::help::{show_help, HelpView};
+use crate::history::{HistoryEntry, UserDirs};
+use crate::journal::{self, Operation};
+use crate::normalize;
+use crate::panels::render_header;
+use crate::presentation;
+use crate::preview::{needs_preview, show_preview};
+use crate::purge::deleting_allowed;
+use crate::range_picker::DayRange;
+use crate::render::{labeled, titled, Canvas};
+use crate::report::{commits_around, format_duration, short_commit};
+use crate::search::{fuzzy_positions, fuzzy_score};
+use crate::similar::similar_commands;
+use crate::snapshot::{save_status, Snapshot, SnapshotFormat};
+use crate::theme::palette;
+use crate::time_format::TimeDisplay;
+use crate::timeline::timeline_lines;
+use crate::timing;
+use crate::tour::{mark_seen, Tour};
+use crate::tr;
+use crate::ui_utils::{display_path, draw_banner, screen_size, truncate_middle, wait_to_dismiss};
+use crate::vocabulary::browse_vocabulary;
+use crate::whatis;
+use ratatui::backend::FromCrossterm;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color as RatatuiColor, Modifier, Style as RatatuiStyle};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Padding, Paragraph, Wrap};
 
-fn display_detail_view(
-    stdout: &mut io::Stdout,
+// The Command stats rows for `entry`, values that could run long cut to
+// `room` columns
+fn command_stats(
     entry: &HistoryEntry,
     entries: &[HistoryEntry],
     current_index: usize,
-) -> Result<()> {
-    // Clear screen first
-    execute!(stdout, terminal::Clear(ClearType::All))?;
-
-    // Get terminal size
-    let (term_width, term_height) = terminal::size()?;
-
-    // Ensure minimum size requirements
-    let min_width = 80;
-    let min_height = 24;
-    if term_width < min_width || term_height < min_height {
-        execute!(stdout, cursor::MoveTo(0, 0))?;
-        write!(
-            stdout,
-            "Terminal too small. Please resize to at least {}x{}",
-            min_width, min_height
-        )?;
-        stdout.flush()?;
-        return Ok(());
-    }
-
-    // Correctly assign previous and next commands
-    // Previous command comes before current (is newer, has lower index)
-    let prev_cmd = if current_index > 0 {
-        &entries[current_index - 1].command
+    time_display: TimeDisplay,
+    room: usize,
+) -> Vec<(String, String)> {
+    let username = if presentation::enabled() {
+        tr!("detail-user-hidden")
     } else {
-        "No previous command"
+        std::env::var("USER").unwrap_or_else(|_| "unknown".to_string())
     };
 
-    // Next command comes after current (is older, has higher index)
-    let next_cmd = if current_index < entries.len() - 1 {
-        &entries[current_index + 1].command
-    } else {
-        "No next command"
-    };
-
-    // Header
-    execute!(stdout, cursor::MoveTo(0, 0))?;
-    write!(
-        stdout,
-        "{}                                                                    {: <67}                                                               {}",
-        "CLI Wrapped".cyan().bold(),
-        "<esc>: back, ↑/↓: navigate".dark_grey(),
-        format!("history count: {}", entries.len()).cyan()
-    )?;
-
-    // Command navigation section - top row with 3 boxes
-    let box_height = 5;
-    let prev_width = term_width / 3;
-    let cmd_width = term_width / 3;
-    let next_width = term_width - prev_width - cmd_width;
-
-    // Previous command box (older command) - normal styling
-    draw_box(
-        stdout,
-        1,
-        2,
-        prev_width,
-        box_height,
-        Some("Previous command"),
-    )?;
-
-    // Write previous command with normal color
-    write_in_box(stdout, 1, 3, prev_cmd, 1)?;
-
-    // Current command box
-    draw_box(
-        stdout,
-        prev_width + 1,
-        2,
-        cmd_width,
-        box_height,
-        Some("Command"),
-    )?;
-    write_in_box(stdout, prev_width + 1, 3, &entry.command, 1)?;
-
-    // Next command box (newer command) - normal styling
-    draw_box(
-        stdout,
-        prev_width + cmd_width + 1,
-        2,
-        next_width - 1, // Adjust width to fix alignment
-        box_height,
-        Some("Next command"),
-    )?;
-
-    // Write next command with normal color
-    write_in_box(stdout, prev_width + cmd_width + 1, 3, next_cmd, 1)?;
-
-    // Calculate command stats
-    let username = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
-    let hostname = std::env::var("HOSTNAME")
-        .or_else(|_| std::env::var("HOST"))
-        .unwrap_or_else(|_| "unknown".to_string());
-
     // Find the current working directory
     let current_dir = if let Some(dir) = &entry.directory {
         dir.clone()
@@ -120,6 +71,8 @@ fn display_detail_view(
             .map(|p| p.to_string_lossy().to_string())
             .unwrap_or_else(|_| "unknown".to_string())
     };
+    // Leave room for the label
+    let current_dir = display_path(&current_dir, room);
 
     // Count how many times this command appears in history
     let total_runs = entries
@@ -131,14 +84,21 @@ fn display_detail_view(
     let history_position = current_index + 1;
 
     // Gather real stats from the history entries and environment
-    let stats = [
-        ("History number", history_position.to_string()),
-        ("User", username),
-        ("Time", format_timestamp(entry.timestamp)),
-        ("Directory", current_dir),
-        ("Total runs", total_runs.to_string()),
+    let mut stats = vec![
+        (tr!("detail-history-number"), history_position.to_string()),
+        (tr!("detail-user"), username),
+        (tr!("detail-time"), {
+            let time = time_display.format(entry.timestamp, Local::now().timestamp());
+            if entry.estimated() {
+                tr!("detail-time-estimated", time = time)
+            } else {
+                time
+            }
+        }),
+        (tr!("detail-directory"), current_dir),
+        (tr!("detail-total-runs"), total_runs.to_string()),
         (
-            "Recent runs",
+            tr!("detail-recent-runs"),
             format!(
                 "{}",
                 entries
@@ -148,68 +108,238 @@ fn display_detail_view(
             ),
         ),
     ];
+    // Below the user, for sources that record the machine
+    if let Some(host) = &entry.host {
+        let host = if presentation::enabled() {
+            tr!("detail-user-hidden")
+        } else {
+            host.clone()
+        };
+        stats.insert(2, (tr!("detail-host"), host));
+    }
+    // The command's whole life, from every run with a timestamp
+    if let Some(lifetime) = command_lifetime(entries, &entry.command) {
+        let now = Local::now();
+        let since = match lifetime.days_since_last(now.date_naive()) {
+            0 => tr!("detail-today"),
+            days => tr!("detail-days-ago", days = days),
+        };
+        stats.extend([
+            (
+                tr!("detail-first-used"),
+                time_display.format(lifetime.first, now.timestamp()),
+            ),
+            (
+                tr!("detail-last-used"),
+                time_display.format(lifetime.last, now.timestamp()),
+            ),
+            (tr!("detail-since-last"), since),
+            (
+                tr!("detail-per-week"),
+                format!("{:.1}", lifetime.per_active_week()),
+            ),
+        ]);
+    }
+    // What the program does, as far as the bundled list or whatis know
+    if let Some(mut summary) = whatis::summary(&entry.command) {
+        if summary.width() > room {
+            let mut used = 1;
+            summary = summary
+                .chars()
+                .take_while(|c| {
+                    used += c.width().unwrap_or(0);
+                    used <= room
+                })
+                .collect();
+            summary.push('…');
+        }
+        stats.push((tr!("detail-what"), summary));
+    }
+    // Only some sources record how long a command took and how it exited
+    if let Some(duration) = entry.duration {
+        stats.push((tr!("detail-duration"), format_duration(duration)));
+    }
+    if let Some(exit_code) = entry.exit_code {
+        stats.push((tr!("detail-exit-code"), exit_code.to_string()));
+    }
+    // Recorded by the shell hook with --git-head
+    let (commit_before, commit_after) = commits_around(entries, entry);
+    if let Some(commit) = commit_before {
+        stats.push((
+            tr!("detail-commit-before"),
+            short_commit(commit).to_string(),
+        ));
+    }
+    if let Some(commit) = commit_after {
+        stats.push((tr!("detail-commit-after"), short_commit(commit).to_string()));
+    }
+    stats
+}
 
-    // Command stats box - left column
-    let stats_height = 14;
-    let stats_width = term_width / 2;
-    draw_box(
-        stdout,
-        1,
-        box_height + 2,
-        stats_width,
-        stats_height,
-        Some("Command stats"),
-    )?;
-
-    for (i, (key, value)) in stats.iter().enumerate() {
-        let line = box_height + 4 + i as u16;
-        execute!(stdout, cursor::MoveTo(3, line))?;
-        write!(stdout, "{:<14} {}", key.with(Color::DarkGrey), value)?;
+fn display_detail_view(
+    stdout: &mut io::Stdout,
+    entry: &HistoryEntry,
+    entries: &[HistoryEntry],
+    current_index: usize,
+    time_display: TimeDisplay,
+    status: Option<String>,
+) -> Result<Option<Snapshot>> {
+    // Clear screen first
+    execute!(stdout, terminal::Clear(ClearType::All))?;
+
+    // Get terminal size
+    let (term_width, term_height) = screen_size()?;
+
+    // Ensure minimum size requirements
+    let min_width = 80;
+    let min_height = 24;
+    if term_width < min_width || term_height < min_height {
+        execute!(stdout, cursor::MoveTo(0, 0))?;
+        write!(
+            stdout,
+            "{}",
+            tr!("terminal-too-small", width = min_width, height = min_height)
+        )?;
+        stdout.flush().context(ErrorKind::Terminal)?;
+        return Ok(None);
+    }
+
+    // Correctly assign previous and next commands
+    // Previous command comes before current (is newer, has lower index)
+    let prev_cmd = if current_index > 0 {
+        entries[current_index - 1].command.clone()
+    } else {
+        tr!("detail-no-previous")
+    };
+
+    // Next command comes after current (is older, has higher index)
+    let next_cmd = if current_index < entries.len() - 1 {
+        entries[current_index + 1].command.clone()
+    } else {
+        tr!("detail-no-next")
+    };
+
+    let mut canvas = Canvas::screen()?;
+    render_header(
+        &mut canvas,
+        Rect::new(0, 0, term_width, 1),
+        &tr!("app-title"),
+        &tr!("detail-controls"),
+        &tr!("history-count", count = entries.len()),
+    );
+
+    // Command navigation section - top row with 3 boxes, long commands
+    // wrapped inside them
+    let box_height = 5;
+    let [prev_area, cmd_area, next_area] = Layout::horizontal([
+        Constraint::Length(term_width / 3),
+        Constraint::Length(term_width / 3),
+        Constraint::Fill(1),
+    ])
+    .areas(Rect::new(1, 2, term_width - 1, box_height));
+    for (title, command, area) in [
+        (tr!("detail-previous"), prev_cmd.as_str(), prev_area),
+        (tr!("detail-command"), entry.command.as_str(), cmd_area),
+        (tr!("detail-next"), next_cmd.as_str(), next_area),
+    ] {
+        let paragraph = Paragraph::new(command)
+            .wrap(Wrap { trim: false })
+            .block(titled(&title).padding(Padding::left(1)));
+        canvas.render(paragraph, area);
     }
 
-    // List of similar commands - right top
-    draw_box(
-        stdout,
-        stats_width + 1,
+    // Command stats and directories on the left, similar commands and the
+    // hour and day charts on the right, down to the footer
+    let body = Rect::new(
+        1,
         box_height + 2,
-        term_width - stats_width - 1,
-        5,
-        Some("Similar commands"),
-    )?;
-
-    // Find similar commands (commands that start with the same word)
-    let first_word = entry.command.split_whitespace().next().unwrap_or("");
-    let similar_commands: Vec<&HistoryEntry> = entries
+        term_width - 1,
+        term_height - box_height - 3,
+    );
+    let [left, right] =
+        Layout::horizontal([Constraint::Length(term_width / 2), Constraint::Fill(1)]).areas(body);
+
+    let stats = command_stats(
+        entry,
+        entries,
+        current_index,
+        time_display,
+        (term_width / 2).saturating_sub(20) as usize,
+    );
+
+    // Command stats box, sized to its rows, as many as fit; then up to five
+    // of the directories the command runs in
+    let shown_stats = &stats[..stats.len().min(left.height.saturating_sub(2) as usize)];
+    let stats_height = shown_stats.len() as u16 + 2;
+    let dirs_height = left.height.saturating_sub(stats_height).min(7);
+    let [stats_area, dirs_area] = Layout::vertical([
+        Constraint::Length(stats_height),
+        Constraint::Length(dirs_height),
+    ])
+    .areas(left);
+    let stats_lines: Vec<Line> = shown_stats
         .iter()
-        .filter(|e| {
-            let e_first_word = e.command.split_whitespace().next().unwrap_or("");
-            e_first_word == first_word && e.command != entry.command
-        })
-        .take(3)
+        .map(|(key, value)| labeled(key, value.as_str()))
         .collect();
+    canvas.render(
+        Paragraph::new(stats_lines).block(titled(&tr!("detail-stats")).padding(Padding::left(1))),
+        stats_area,
+    );
 
-    for (i, similar) in similar_commands.iter().enumerate() {
-        let line = box_height + 3 + i as u16;
-        let display = if similar.command.len() > 40 {
-            format!("{}...", &similar.command[..37])
+    let directories = command_directories(entries, &entry.command);
+    let shown_directories = directories
+        .iter()
+        .take(dirs_height.saturating_sub(2) as usize);
+    if dirs_height >= 3 {
+        let path_width = (dirs_area.width as usize).saturating_sub(12);
+        let lines: Vec<Line> = if directories.is_empty() {
+            vec![Line::from(tr!("detail-no-directories"))]
         } else {
-            similar.command.clone()
+            shown_directories
+                .clone()
+                .map(|(dir, count)| {
+                    Line::from(format!("{:>6}  {}", count, display_path(dir, path_width)))
+                })
+                .collect()
         };
-        write_in_box(stdout, stats_width + 1, line, &display, 1)?;
+        canvas.render(
+            Paragraph::new(lines)
+                .block(titled(&tr!("detail-directories")).padding(Padding::left(1))),
+            dirs_area,
+        );
     }
 
-    // Command frequency by hour - right middle
-    draw_box(
-        stdout,
-        stats_width + 1,
-        box_height + 7,
-        term_width - stats_width - 1,
-        4,
-        Some("Command frequency by hour"),
-    )?;
+    let [similar_area, frequency_area, usage_area] = Layout::vertical([
+        Constraint::Length(5),
+        Constraint::Length(4),
+        Constraint::Length(5),
+    ])
+    .areas(right);
+
+    // Related commands, each with why it's related on the right
+    let similar_commands = similar_commands(entries, &entry.command, 3);
+    let similar_width = similar_area.width.saturating_sub(4) as usize;
+    let lines: Vec<Line> = similar_commands
+        .iter()
+        .map(|similar| {
+            let reason = similar.relation.label();
+            let room = similar_width.saturating_sub(reason.width() + 2);
+            let display = truncate_middle(&similar.command, room);
+            let padding =
+                " ".repeat(similar_width.saturating_sub(display.width() + reason.width()));
+            Line::from(vec![
+                Span::from(format!("{}{}", display, padding)),
+                Span::styled(reason, RatatuiStyle::new().fg(RatatuiColor::DarkGray)),
+            ])
+        })
+        .collect();
+    canvas.render(
+        Paragraph::new(lines).block(titled(&tr!("detail-similar")).padding(Padding::horizontal(1))),
+        similar_area,
+    );
 
     // Count commands by hour of day (based on timestamps)
-    let mut hour_counts = vec![0; 24];
+    let mut hour_counts = [0; 24];
     for e in entries
         .iter()
         .filter(|e| e.command == entry.command && e.timestamp > 0)
@@ -237,65 +367,45 @@ fn display_detail_view(
 
     // Create a simpler +/- visualization where + is above average and - is below
     let mut hour_viz = String::new();
-    hour_viz.push_str("[Hours] ");
-    for i in 0..24 {
+    hour_viz.push_str(&tr!("detail-hours"));
+    hour_viz.push(' ');
+    for &count in &hour_counts {
         // Use - for below average, + for above average, · for zeros
-        let symbol = if hour_counts[i] == 0 {
+        let symbol = if count == 0 {
             "·"
-        } else if (hour_counts[i] as f64) < avg_usage {
+        } else if (count as f64) < avg_usage {
             "-"
         } else {
             "+"
         };
         hour_viz.push_str(symbol);
     }
+    let peak_times = tr!(
+        "detail-peak-times",
+        hours = hour_counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 2 * max_count / 3)
+            .map(|(hour, _)| format!("{:02}:00", hour))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    canvas.render(
+        Paragraph::new(vec![Line::from(hour_viz), Line::from(peak_times)])
+            .block(titled(&tr!("detail-frequency")).padding(Padding::left(1))),
+        frequency_area,
+    );
 
-    write_in_box(stdout, stats_width + 1, box_height + 8, &hour_viz, 1)?;
-    write_in_box(
-        stdout,
-        stats_width + 1,
-        box_height + 9,
-        &format!(
-            "Peak times: {}",
-            hour_counts
-                .iter()
-                .enumerate()
-                .filter(|(_, &count)| count > 2 * max_count / 3)
-                .map(|(hour, _)| format!("{:02}:00", hour))
-                .collect::<Vec<_>>()
-                .join(", ")
-        ),
-        1,
-    )?;
-
-    // Command usage over time - right bottom
-    draw_box(
-        stdout,
-        stats_width + 1,
-        box_height + 11,
-        term_width - stats_width - 1,
-        5,
-        Some("Command usage over time"),
-    )?;
-
-    // Group commands by day for a simple timeline
-    let mut days = std::collections::HashMap::new();
-    for e in entries
+    // Group commands by day for a simple timeline, keeping the 7 most
+    // recent days, oldest first
+    let days = day_counts(entries.iter().filter(|e| e.command == entry.command));
+    let days: Vec<(String, usize)> = days
         .iter()
-        .filter(|e| e.command == entry.command && e.timestamp > 0)
-    {
-        let dt = Local.timestamp_opt(e.timestamp, 0);
-        if let chrono::LocalResult::Single(dt) = dt {
-            let day = dt.format("%m/%d").to_string();
-            *days.entry(day).or_insert(0) += 1;
-        }
-    }
-
-    // Sort by date and take most recent
-    let mut days: Vec<(String, i32)> = days.into_iter().collect();
-    days.sort_by(|a, b| b.0.cmp(&a.0)); // Sort descending by date
-    days.truncate(7); // Keep only the 7 most recent days
-    days.reverse(); // Show oldest to newest
+        .rev()
+        .take(7)
+        .rev()
+        .map(|(day, count)| (day.format("%m/%d").to_string(), *count))
+        .collect();
 
     // Create a sparkline-style visualization
     let max_day_count = days.iter().map(|(_, count)| *count).max().unwrap_or(1);
@@ -316,159 +426,913 @@ fn display_detail_view(
         .collect::<Vec<_>>()
         .join("  ");
 
-    write_in_box(stdout, stats_width + 1, box_height + 12, &days_viz, 1)?;
-
     // Show most frequent day
-    if !days.is_empty() {
-        let most_frequent = days
+    let most_frequent = days
+        .iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(day, count)| tr!("detail-most-active", day = day.as_str(), count = *count))
+        .unwrap_or_default();
+    canvas.render(
+        Paragraph::new(vec![Line::from(days_viz), Line::from(most_frequent)])
+            .block(titled(&tr!("detail-usage")).padding(Padding::left(1))),
+        usage_area,
+    );
+
+    // Footer. The header has no room for a save message, so it goes here,
+    // and the hook warning takes the place of the hint.
+    canvas.flush(stdout)?;
+    match status {
+        Some(status) => {
+            execute!(stdout, cursor::MoveTo(1, term_height - 1))?;
+            write!(stdout, "{}", status)?;
+        }
+        None if draw_banner(stdout).context(ErrorKind::Terminal)? => {}
+        None => {
+            execute!(stdout, cursor::MoveTo(1, term_height - 1))?;
+            write!(stdout, "{}", tr!("detail-back"))?;
+        }
+    }
+
+    stdout.flush().context("Failed to flush stdout")?;
+
+    let shown_directories = shown_directories
+        .map(|(dir, count)| (dir.clone(), count.to_string()))
+        .collect();
+    let similar = similar_commands
+        .into_iter()
+        .map(|similar| (similar.command, similar.relation.label()))
+        .collect();
+    let hours = hour_counts
+        .iter()
+        .enumerate()
+        .filter(|(_, &count)| count > 0)
+        .map(|(hour, count)| (format!("{:02}:00", hour), count.to_string()))
+        .collect();
+    let usage = days
+        .into_iter()
+        .map(|(day, count)| (day, count.to_string()))
+        .collect();
+    Ok(Some(
+        Snapshot::new(entry.command.as_str())
+            .section(tr!("detail-stats"), stats)
+            .section(tr!("detail-directories"), shown_directories)
+            .command_section(tr!("detail-similar"), similar)
+            .section(tr!("detail-frequency"), hours)
+            .section(tr!("detail-usage"), usage),
+    ))
+}
+
+// The split layout's right pane at column `x`: the selected command, its
+// Command stats and, if there's room, Similar commands, redrawn as the
+// selection moves
+fn draw_detail_pane(
+    stdout: &mut io::Stdout,
+    x: u16,
+    (width, height): (u16, u16),
+    entries: &[HistoryEntry],
+    current_index: usize,
+    time_display: TimeDisplay,
+) -> Result<()> {
+    let entry = &entries[current_index];
+    let inner = width.saturating_sub(4) as usize;
+    let stats = command_stats(
+        entry,
+        entries,
+        current_index,
+        time_display,
+        inner.saturating_sub(15),
+    );
+    let pane = Rect::new(x, 2, width, height.saturating_sub(2));
+    let shown = stats.len().min(height.saturating_sub(5) as usize);
+    let [command_area, stats_area, similar_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(shown as u16 + 2),
+        Constraint::Max(5),
+    ])
+    .areas(pane);
+
+    let mut canvas = Canvas::screen()?;
+    canvas.render(
+        Paragraph::new(truncate_middle(&entry.command, inner))
+            .block(titled(&tr!("detail-command")).padding(Padding::left(1))),
+        command_area,
+    );
+    let lines: Vec<Line> = stats
+        .iter()
+        .take(shown)
+        .map(|(key, value)| labeled(key, truncate_middle(value, inner.saturating_sub(15))))
+        .collect();
+    canvas.render(
+        Paragraph::new(lines).block(titled(&tr!("detail-stats")).padding(Padding::left(1))),
+        stats_area,
+    );
+    if similar_area.height >= 3 {
+        let similar = similar_commands(entries, &entry.command, similar_area.height as usize - 2);
+        let lines: Vec<Line> = similar
+            .iter()
+            .map(|similar| Line::from(truncate_middle(&similar.command, inner)))
+            .collect();
+        canvas.render(
+            Paragraph::new(lines).block(titled(&tr!("detail-similar")).padding(Padding::left(1))),
+            similar_area,
+        );
+    }
+    canvas.flush(stdout)
+}
+
+// Full-screen lifetime chart of the entry's command, until a key is pressed
+fn display_timeline_view(
+    stdout: &mut io::Stdout,
+    entry: &HistoryEntry,
+    entries: &[HistoryEntry],
+) -> Result<()> {
+    loop {
+        execute!(stdout, terminal::Clear(ClearType::All))?;
+        let (term_width, term_height) = screen_size()?;
+        let chart_height = (term_height as usize).saturating_sub(16).clamp(3, 12);
+        let lines = timeline_lines(
+            entries,
+            &entry.command,
+            (term_width as usize).saturating_sub(2),
+            chart_height,
+        );
+        for (i, line) in lines
+            .iter()
+            .take((term_height as usize).saturating_sub(2))
+            .enumerate()
+        {
+            execute!(stdout, cursor::MoveTo(1, i as u16))?;
+            write!(stdout, "{}", line)?;
+        }
+        execute!(stdout, cursor::MoveTo(1, term_height.saturating_sub(1)))?;
+        write!(stdout, "{}", tr!("timeline-back").dark_grey())?;
+        stdout.flush().context(ErrorKind::Terminal)?;
+        if wait_to_dismiss()? {
+            return Ok(());
+        }
+    }
+}
+
+// While the tour is showing it takes every key, leaving the view below as
+// it was. Returns whether it did.
+fn tour_handled_key(stdout: &mut io::Stdout, tour: &mut Option<Tour>) -> Result<bool> {
+    let Some(current) = *tour else {
+        return Ok(false);
+    };
+    current.draw(stdout).context(ErrorKind::Terminal)?;
+    if let Event::Key(KeyEvent { code, .. }) = frames::next_event()? {
+        *tour = current.on_key(code);
+        if tour.is_none() {
+            mark_seen();
+        }
+    }
+    Ok(true)
+}
+
+// The list view's `/` search
+struct ListSearch {
+    query: String,
+    // Still typing, with the list narrowed to the matches
+    editing: bool,
+    // Indices of the entries that match, in list order
+    matches: Vec<usize>,
+    // The selection before the search, restored if it's cancelled
+    origin: usize,
+}
+
+impl ListSearch {
+    fn start(origin: usize, entries: &[HistoryEntry]) -> ListSearch {
+        let mut search = ListSearch {
+            query: String::new(),
+            editing: true,
+            matches: Vec::new(),
+            origin,
+        };
+        search.update(entries);
+        search
+    }
+
+    fn update(&mut self, entries: &[HistoryEntry]) {
+        let pattern = self.query.to_lowercase();
+        self.matches = (0..entries.len())
+            .filter(|&i| fuzzy_score(&pattern, &entries[i].command).is_some())
+            .collect();
+    }
+
+    // The match nearest to `index`
+    fn nearest(&self, index: usize) -> Option<usize> {
+        self.matches
             .iter()
-            .max_by_key(|(_, count)| *count)
-            .map(|(day, count)| format!("Most active: {} ({} times)", day, count))
-            .unwrap_or_else(|| "No data".to_string());
-        write_in_box(stdout, stats_width + 1, box_height + 13, &most_frequent, 1)?;
+            .copied()
+            .min_by_key(|&i| i.abs_diff(index))
     }
 
-    // Footer
-    execute!(stdout, cursor::MoveTo(1, term_height - 1))?;
-    write!(stdout, "Press ESC to go back to command list")?;
+    // The next match after `index`, or the one before with `backwards`,
+    // wrapping around at either end
+    fn step(&self, index: usize, backwards: bool) -> Option<usize> {
+        if backwards {
+            let before = self.matches.iter().rev().find(|&&i| i < index);
+            before.or(self.matches.last()).copied()
+        } else {
+            let after = self.matches.iter().find(|&&i| i > index);
+            after.or(self.matches.first()).copied()
+        }
+    }
+}
 
-    stdout.flush().context("Failed to flush stdout")
+// `command` with the characters the search matched picked out, on the
+// selection's background if given
+fn highlighted(command: &str, query: &str, fg: Color, bg: Option<Color>) -> Vec<Span<'static>> {
+    let palette = palette();
+    let positions = if query.is_empty() {
+        Vec::new()
+    } else {
+        fuzzy_positions(&query.to_lowercase(), command).unwrap_or_default()
+    };
+    command
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let style = if positions.contains(&i) {
+                RatatuiStyle::new()
+                    .fg(tui_color(palette.matched))
+                    .add_modifier(Modifier::UNDERLINED)
+            } else {
+                RatatuiStyle::new().fg(tui_color(fg))
+            };
+            let style = match bg {
+                Some(_) if palette.selection_reversed() => {
+                    style.add_modifier(Modifier::REVERSED | Modifier::BOLD)
+                }
+                Some(bg) => style.bg(tui_color(bg)).add_modifier(Modifier::BOLD),
+                None => style,
+            };
+            Span::styled(c.to_string(), style)
+        })
+        .collect()
 }
 
-pub fn run_interactive_viewer(entries: Vec<HistoryEntry>) -> Result<()> {
-    let mut stdout = io::stdout();
-    execute!(stdout, terminal::EnterAlternateScreen)?;
-    terminal::enable_raw_mode()?;
-    execute!(stdout, cursor::Hide)?;
+// A theme or category color, for drawing with ratatui
+fn tui_color(color: Color) -> RatatuiColor {
+    RatatuiColor::from_crossterm(color)
+}
 
-    let mut current_index = entries.len().saturating_sub(1);
-    // Start directly in detail view mode with the most recent command
-    let mut view_mode: Option<usize> = Some(current_index);
+// The runs of a command as Most Used Commands counts them, i.e. after the
+// normalization rules, optionally only those between two timestamps
+pub fn command_runs(
+    entries: &[HistoryEntry],
+    command: &str,
+    period: Option<(i64, i64)>,
+) -> Vec<HistoryEntry> {
+    let rules = normalize::rules();
+    entries
+        .iter()
+        .filter(|e| period.is_none_or(|(start, end)| e.timestamp >= start && e.timestamp <= end))
+        .filter(|e| rules.apply(&e.command) == command)
+        .cloned()
+        .collect()
+}
 
-    // Theme colors
-    let header_color = Color::Cyan;
-    let selected_bg = Color::DarkBlue;
-    let selected_fg = Color::White;
-    let number_color = Color::DarkGrey;
-    let separator_color = Color::DarkGrey;
-    let command_color = Color::White;
+// The list and detail views over `entries`, on a screen already set up by
+// the caller, until the user quits
+pub fn browse_history(
+    stdout: &mut io::Stdout,
+    entries: &[HistoryEntry],
+    time_display: TimeDisplay,
+) -> Result<ScreenExit> {
+    HistoryScreen::new(entries, time_display, None).run(stdout, entries)
+}
 
-    loop {
-        if let Some(detail_index) = view_mode {
-            // --- Detail View ---
-            display_detail_view(&mut stdout, &entries[detail_index], &entries, detail_index)?;
+// The same, opening on the list rather than the latest command
+pub fn browse_history_list(
+    stdout: &mut io::Stdout,
+    entries: &[HistoryEntry],
+    time_display: TimeDisplay,
+) -> Result<ScreenExit> {
+    let mut screen = HistoryScreen::new(entries, time_display, None);
+    screen.view_mode = None;
+    screen.run(stdout, entries)
+}
 
-            // Input handling for Detail View
-            if let Event::Key(KeyEvent { code, .. }) = event::read()? {
-                match code {
-                    KeyCode::Char('q') | KeyCode::Esc => view_mode = None,
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        // Navigate to previous command in history (newer)
-                        if detail_index > 0 {
-                            view_mode = Some(detail_index - 1);
+// The first row of a `page`-row window over `len` rows that shows row
+// `cursor`, moved from `scroll` as little as possible
+fn scroll_to(scroll: usize, cursor: usize, page: usize, len: usize) -> usize {
+    let scroll = if cursor < scroll {
+        cursor
+    } else if cursor >= scroll + page {
+        cursor + 1 - page
+    } else {
+        scroll
+    };
+    scroll.min(len.saturating_sub(page))
+}
+
+// Columns every list row starts with: marker, number and separator
+const LIST_NUMBER_WIDTH: usize = 9;
+
+// The time column, wide enough for `01/15 14:03` or `~3 weeks ago`
+const LIST_TIME_WIDTH: usize = 12;
+
+// Least room the command keeps before the directory column is left out
+const LIST_COMMAND_MIN_WIDTH: usize = 20;
+
+// What the list shows left of each command, cycled with c
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum ListColumns {
+    #[default]
+    Time,
+    TimeAndDirectory,
+    CommandOnly,
+}
+
+impl ListColumns {
+    fn next(self) -> ListColumns {
+        match self {
+            ListColumns::Time => ListColumns::TimeAndDirectory,
+            ListColumns::TimeAndDirectory => ListColumns::CommandOnly,
+            ListColumns::CommandOnly => ListColumns::Time,
+        }
+    }
+
+    // The time and directory columns' widths in a list `width` wide. The
+    // directory gets a quarter of it, from 10 to 30 columns, and is dropped
+    // when the command would be left too little.
+    fn widths(self, width: usize) -> (Option<usize>, Option<usize>) {
+        let time = (self != ListColumns::CommandOnly).then_some(LIST_TIME_WIDTH);
+        let directory = (width / 4).clamp(10, 30);
+        let room = width.saturating_sub(LIST_NUMBER_WIDTH + LIST_TIME_WIDTH + 3 + directory + 3);
+        let directory = (self == ListColumns::TimeAndDirectory && room >= LIST_COMMAND_MIN_WIDTH)
+            .then_some(directory);
+        (time, directory)
+    }
+
+    // Columns left of the command in a list `width` wide, separators included
+    fn prefix_width(self, width: usize) -> usize {
+        let (time, directory) = self.widths(width);
+        LIST_NUMBER_WIDTH
+            + [time, directory]
+                .iter()
+                .flatten()
+                .map(|w| w + 3)
+                .sum::<usize>()
+    }
+}
+
+// Narrower terminals show the list alone, even in the split layout
+const SPLIT_MIN_WIDTH: u16 = 100;
+
+// The list and detail views over `entries`, kept while switching to
+// other screens
+pub struct HistoryScreen {
+    current_index: usize,
+    // Detail view of that entry, or the list when None
+    view_mode: Option<usize>,
+    // Dates or "2h ago", toggled with t in either view
+    time_display: TimeDisplay,
+    // Picked with r, and shown after the list's title
+    range: Option<DayRange>,
+    // Rows deleted with d here, struck through while the session's journal
+    // still holds them. They leave the stats log when the session ends.
+    deleted: Vec<usize>,
+    // Commands pinned with b, drawn in the favorite color
+    pinned: HashSet<String>,
+    // First row shown in the list, moved only as far as it takes to keep
+    // the selected row on screen
+    scroll: usize,
+    // The list with the selected entry's details beside it, toggled with v
+    split: bool,
+    columns: ListColumns,
+}
+
+impl HistoryScreen {
+    pub fn new(
+        entries: &[HistoryEntry],
+        time_display: TimeDisplay,
+        range: Option<DayRange>,
+    ) -> HistoryScreen {
+        let current_index = entries.len().saturating_sub(1);
+        HistoryScreen {
+            current_index,
+            // Start directly in detail view mode with the most recent command
+            view_mode: Some(current_index),
+            time_display,
+            range,
+            deleted: Vec::new(),
+            scroll: 0,
+            split: false,
+            columns: ListColumns::default(),
+            pinned: UserDirs::current()
+                .map(|dirs| pinned_set(&dirs))
+                .unwrap_or_default(),
+        }
+    }
+
+    // Open on the split layout, which starts from the list
+    pub fn with_split(mut self, split: bool) -> HistoryScreen {
+        if split {
+            self.split = true;
+            self.view_mode = None;
+        }
+        self
+    }
+
+    // b: pin or unpin the command, saved straight away
+    fn toggle_pin(&mut self, command: &str) -> String {
+        let toggled = UserDirs::current().and_then(|dirs| toggle_favorite(&dirs, command));
+        match toggled {
+            Ok(true) => {
+                self.pinned.insert(command.to_string());
+                tr!("favorite-pinned")
+            }
+            Ok(false) => {
+                self.pinned.remove(command);
+                tr!("favorite-unpinned")
+            }
+            Err(err) => err.to_string(),
+        }
+    }
+
+    pub fn run(&mut self, stdout: &mut io::Stdout, entries: &[HistoryEntry]) -> Result<ScreenExit> {
+        // Shown over the views on first launch
+        let mut tour = Tour::start();
+
+        // Shown once in place of the controls after saving the view
+        let mut status: Option<String> = None;
+
+        // Started with / in the list view
+        let mut search: Option<ListSearch> = None;
+
+        // Asking whether to delete the selected entry, after d
+        let mut confirming = false;
+
+        // The number typed after :, to jump to that entry
+        let mut goto: Option<String> = None;
+
+        // The theme's colors, from --theme or the config
+        let palette = palette();
+
+        loop {
+            if let Some(detail_index) = self.view_mode {
+                // --- Detail View ---
+                let snapshot = display_detail_view(
+                    stdout,
+                    &entries[detail_index],
+                    entries,
+                    detail_index,
+                    self.time_display,
+                    status.take(),
+                )?;
+                if tour_handled_key(stdout, &mut tour)? {
+                    continue;
+                }
+
+                // Input handling for Detail View
+                if let Event::Key(KeyEvent { code, .. }) = frames::next_event()? {
+                    if let Some(screen) = Screen::for_key(code) {
+                        return Ok(ScreenExit::Switch(screen));
+                    }
+                    if let Some(format) = SnapshotFormat::for_key(code) {
+                        if let Some(snapshot) = &snapshot {
+                            status = Some(save_status(snapshot, format));
                         }
+                        continue;
                     }
-                    KeyCode::Down | KeyCode::Char('j') => {
+                    match code {
+                        KeyCode::Char('q') | KeyCode::Esc => self.view_mode = None,
+                        KeyCode::Char('t') => self.time_display = self.time_display.toggled(),
+                        KeyCode::Char('b') => {
+                            status = Some(self.toggle_pin(&entries[detail_index].command))
+                        }
+                        KeyCode::Char('g') => {
+                            display_timeline_view(stdout, &entries[detail_index], entries)?
+                        }
+                        KeyCode::Char('?') => show_help(stdout, HelpView::Detail)?,
+                        // Navigate to previous command in history (newer)
+                        KeyCode::Up | KeyCode::Char('k') if detail_index > 0 => {
+                            self.view_mode = Some(detail_index - 1);
+                        }
                         // Navigate to next command in history (older)
-                        if detail_index < entries.len() - 1 {
-                            view_mode = Some(detail_index + 1);
+                        KeyCode::Down | KeyCode::Char('j') if detail_index < entries.len() - 1 => {
+                            self.view_mode = Some(detail_index + 1);
                         }
-                    }
-                    KeyCode::Char('c') | KeyCode::Char('C') => {
-                        if event::poll(std::time::Duration::from_millis(100))? {
+                        KeyCode::Char('c') | KeyCode::Char('C')
+                            if frames::poll(std::time::Duration::from_millis(100))? =>
+                        {
                             if let Event::Key(KeyEvent {
                                 code: KeyCode::Char('c'),
                                 modifiers,
                                 ..
-                            }) = event::read()?
+                            }) = frames::next_event()?
                             {
                                 if modifiers.contains(KeyModifiers::CONTROL) {
-                                    break;
+                                    return Ok(ScreenExit::Quit);
                                 }
                             }
                         }
+                        _ => {}
                     }
-                    _ => {}
                 }
-            }
-        } else {
-            // --- List View ---
-            execute!(
-                stdout,
-                terminal::Clear(ClearType::All),
-                cursor::MoveTo(0, 0)
-            )?;
-            let header = "Command History".with(header_color).bold();
-            let controls = "(↑/k: up, ↓/j: down, Enter: details, q: quit)".with(Color::DarkGrey);
-            writeln!(stdout, "{} {}\n", header, controls)?;
-
-            let window_size = 10;
-            let start_idx = current_index.saturating_sub(window_size / 2);
-            let end_idx = (start_idx + window_size).min(entries.len());
-
-            for (idx, entry) in entries[start_idx..end_idx].iter().enumerate() {
-                let absolute_index = start_idx + idx;
-                let line_num = entries.len() - absolute_index;
-                let is_selected = absolute_index == current_index;
-
-                execute!(stdout, cursor::MoveTo(0, (idx + 3) as u16))?;
-
-                let prefix = if is_selected {
-                    "▶".with(selected_fg).bold()
-                } else {
-                    " ".with(Color::Reset)
+            } else {
+                // --- List View ---
+                // Rows whose deletion was undone from another list
+                self.deleted
+                    .retain(|&index| journal::deletes(&entries[index]));
+                execute!(
+                    stdout,
+                    terminal::Clear(ClearType::All),
+                    cursor::MoveTo(0, 0)
+                )?;
+                let title = match self.range {
+                    Some(range) => format!("{} ({})", tr!("list-title"), range),
+                    None => tr!("list-title"),
                 };
-                let num = format!("{:4}", line_num).with(number_color);
-                let separator = "│".with(separator_color);
-
-                let command_text = if is_selected {
-                    execute!(stdout, style::SetBackgroundColor(selected_bg))?;
-                    entry.command.as_str().with(selected_fg).bold()
-                } else {
-                    entry.command.as_str().with(command_color)
+                let controls = match &search {
+                    _ if confirming => tr!("delete-confirm"),
+                    _ if goto.is_some() => {
+                        tr!("goto-prompt", number = goto.as_deref().unwrap_or_default())
+                    }
+                    Some(search) if search.editing => tr!(
+                        "search-prompt",
+                        query = search.query.as_str(),
+                        count = search.matches.len()
+                    ),
+                    Some(search) => tr!("search-active", query = search.query.as_str()),
+                    None => status.take().unwrap_or_else(|| tr!("list-controls")),
+                };
+
+                // While typing a search, only the matches are listed
+                let rows: Vec<usize> = match &search {
+                    Some(search) if search.editing => search.matches.clone(),
+                    _ => (0..entries.len()).collect(),
                 };
+                let query = search.as_ref().map_or("", |search| search.query.as_str());
+                let selected_row = rows.binary_search(&self.current_index).unwrap_or(0);
+
+                // As many rows as fit between the title and the banner line
+                let (term_width, term_height) = screen_size()?;
+                let page = (term_height as usize).saturating_sub(4).max(1);
+                // Where the detail pane goes, with the list left of it
+                let pane_x = (self.split && term_width >= SPLIT_MIN_WIDTH && !entries.is_empty())
+                    .then_some(term_width / 2);
+                self.scroll = scroll_to(self.scroll, selected_row, page, rows.len());
+                let start_idx = self.scroll;
+                let end_idx = (start_idx + page).min(rows.len());
+                let list_width = pane_x.unwrap_or(term_width) as usize;
+                let (time_width, directory_width) = self.columns.widths(list_width);
+                let now = Local::now().timestamp();
+                let mut shown = Vec::new();
 
-                write!(stdout, "{} {} {} {}", prefix, num, separator, command_text)?;
+                // Rows are cut off at the list's edge, the terminal's or the pane's
+                let mut canvas = Canvas::screen()?;
+                let muted = RatatuiStyle::new().fg(tui_color(palette.muted));
+                canvas.render(
+                    Line::from(vec![
+                        Span::styled(
+                            title,
+                            RatatuiStyle::new()
+                                .fg(tui_color(palette.header))
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(" "),
+                        Span::styled(controls, muted),
+                    ]),
+                    Rect::new(0, 0, term_width, 1),
+                );
+                for (idx, &absolute_index) in rows[start_idx..end_idx].iter().enumerate() {
+                    let entry = &entries[absolute_index];
+                    let line_num = entries.len() - absolute_index;
+                    let is_selected = absolute_index == self.current_index;
 
-                if is_selected {
-                    execute!(stdout, style::ResetColor)?;
+                    let prefix = if is_selected {
+                        Span::styled(
+                            "▶",
+                            RatatuiStyle::new()
+                                .fg(tui_color(palette.selected_text))
+                                .add_modifier(Modifier::BOLD),
+                        )
+                    } else {
+                        Span::raw(" ")
+                    };
+                    let mut time = self.time_display.format_compact(entry.timestamp, now);
+                    if entry.estimated() {
+                        time.insert(0, '~');
+                    }
+                    shown.push((entry.command.clone(), time.clone()));
+                    let mut columns = Vec::new();
+                    if let Some(width) = time_width {
+                        columns.push(format!("{:<width$}", time, width = width));
+                    }
+                    if let Some(width) = directory_width {
+                        let directory = entry
+                            .directory
+                            .as_deref()
+                            .map(|directory| display_path(directory, width))
+                            .unwrap_or_default();
+                        columns.push(format!("{:<width$}", directory, width = width));
+                    }
+
+                    // Beside the pane, long commands are cut to the list's half
+                    let command = match pane_x {
+                        Some(pane_x) => Cow::Owned(truncate_middle(
+                            &entry.command,
+                            (pane_x as usize)
+                                .saturating_sub(self.columns.prefix_width(list_width) + 1),
+                        )),
+                        None => Cow::Borrowed(entry.command.as_str()),
+                    };
+                    // Deleted ones stay listed, struck through, until undone or the session ends
+                    let command_spans = if self.deleted.contains(&absolute_index) {
+                        vec![Span::styled(
+                            command.into_owned(),
+                            RatatuiStyle::new()
+                                .fg(RatatuiColor::DarkGray)
+                                .add_modifier(Modifier::CROSSED_OUT),
+                        )]
+                    } else if is_selected {
+                        highlighted(
+                            &command,
+                            query,
+                            palette.selected_text,
+                            Some(palette.selected),
+                        )
+                    } else if self.pinned.contains(&entry.command) {
+                        highlighted(&command, query, palette.pinned, None)
+                    } else {
+                        // Colored by category, so runs of the same kind of work stand out
+                        let color = categories()
+                            .of(&entry.command)
+                            .map_or(palette.command, |(_, color)| color);
+                        highlighted(&command, query, color, None)
+                    };
+
+                    let separator = || Span::styled("│", muted);
+                    let mut spans = vec![
+                        prefix,
+                        Span::raw(" "),
+                        Span::styled(format!("{:4}", line_num), muted),
+                        Span::raw(" "),
+                        separator(),
+                        Span::raw(" "),
+                    ];
+                    for column in columns {
+                        spans.extend([
+                            Span::styled(column, muted),
+                            Span::raw(" "),
+                            separator(),
+                            Span::raw(" "),
+                        ]);
+                    }
+                    spans.extend(command_spans);
+                    let row = Line::from(spans);
+                    let row = if is_selected {
+                        row.style(RatatuiStyle::new().bg(tui_color(palette.selected)))
+                    } else {
+                        row
+                    };
+                    canvas.render(row, Rect::new(0, (idx + 3) as u16, list_width as u16, 1));
+                }
+                canvas.flush(stdout).context(ErrorKind::Terminal)?;
+                if let Some(pane_x) = pane_x {
+                    draw_detail_pane(
+                        stdout,
+                        pane_x,
+                        (term_width - pane_x, term_height.saturating_sub(3)),
+                        entries,
+                        self.current_index,
+                        self.time_display,
+                    )
+                    .context(ErrorKind::Terminal)?;
+                }
+                draw_banner(stdout).context(ErrorKind::Terminal)?;
+                stdout.flush().context(ErrorKind::Terminal)?;
+                timing::frame_drawn();
+                if tour_handled_key(stdout, &mut tour)? {
+                    continue;
                 }
-            }
-            stdout.flush()?;
 
-            // Input handling for List View
-            if let Event::Key(KeyEvent {
-                code, modifiers, ..
-            }) = event::read()?
-            {
+                // Input handling for List View
+                let Event::Key(KeyEvent {
+                    code, modifiers, ..
+                }) = frames::next_event()?
+                else {
+                    continue;
+                };
+                if code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
+                    return Ok(ScreenExit::Quit);
+                }
+                if let Some(screen) = Screen::for_key(code) {
+                    return Ok(ScreenExit::Switch(screen));
+                }
+                if confirming {
+                    confirming = false;
+                    if matches!(code, KeyCode::Char('y' | 'Y')) {
+                        self.deleted.push(self.current_index);
+                        let entry = entries[self.current_index].clone();
+                        journal::record(Operation::Delete(entry));
+                        status = Some(tr!("delete-marked"));
+                    }
+                    continue;
+                }
+                // The number is the one shown in the list, 1 being the latest
+                if let Some(number) = goto.as_mut() {
+                    match code {
+                        KeyCode::Char(digit @ '0'..='9') => number.push(digit),
+                        KeyCode::Backspace => {
+                            number.pop();
+                        }
+                        KeyCode::Enter => {
+                            match number.parse::<usize>() {
+                                Ok(n) if (1..=entries.len()).contains(&n) => {
+                                    self.current_index = entries.len() - n
+                                }
+                                _ => status = Some(tr!("goto-missing", count = entries.len())),
+                            }
+                            goto = None;
+                        }
+                        KeyCode::Esc => goto = None,
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                // Typing a search takes every other key
+                if let Some(active) = search.as_mut().filter(|search| search.editing) {
+                    match code {
+                        KeyCode::Char(c) => {
+                            active.query.push(c);
+                            active.update(entries);
+                        }
+                        KeyCode::Backspace => {
+                            active.query.pop();
+                            active.update(entries);
+                        }
+                        KeyCode::Up => {
+                            self.current_index = active
+                                .step(self.current_index, true)
+                                .unwrap_or(self.current_index)
+                        }
+                        KeyCode::Down => {
+                            self.current_index = active
+                                .step(self.current_index, false)
+                                .unwrap_or(self.current_index)
+                        }
+                        KeyCode::Enter if !active.matches.is_empty() => active.editing = false,
+                        KeyCode::Enter | KeyCode::Esc => {
+                            self.current_index = active.origin;
+                            search = None;
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    if matches!(code, KeyCode::Char(_) | KeyCode::Backspace) {
+                        self.current_index = active.nearest(active.origin).unwrap_or(active.origin);
+                    }
+                    continue;
+                }
+
+                if let Some(format) = SnapshotFormat::for_key(code) {
+                    let snapshot =
+                        Snapshot::new(tr!("list-title")).command_section(tr!("list-title"), shown);
+                    status = Some(save_status(&snapshot, format));
+                    continue;
+                }
                 match code {
-                    KeyCode::Char('q') | KeyCode::Esc => break, // Exit the loop
+                    // Esc first drops a finished search
+                    KeyCode::Esc if search.is_some() => search = None,
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(ScreenExit::Back),
+                    KeyCode::Char('/') => {
+                        search = Some(ListSearch::start(self.current_index, entries))
+                    }
+                    KeyCode::Char(key @ ('n' | 'N')) => {
+                        if let Some(found) = search
+                            .as_ref()
+                            .and_then(|search| search.step(self.current_index, key == 'N'))
+                        {
+                            self.current_index = found;
+                        }
+                    }
                     KeyCode::Up | KeyCode::Char('k') => {
-                        current_index = current_index.saturating_sub(1);
+                        self.current_index = self.current_index.saturating_sub(1);
                     }
                     KeyCode::Down | KeyCode::Char('j') => {
-                        current_index = (current_index + 1).min(entries.len().saturating_sub(1));
+                        self.current_index =
+                            (self.current_index + 1).min(entries.len().saturating_sub(1));
+                    }
+                    // A page moves the rows with the cursor, so it stays on
+                    // the same line of the screen
+                    KeyCode::PageUp => {
+                        self.current_index = self.current_index.saturating_sub(page);
+                        self.scroll = self.scroll.saturating_sub(page);
+                    }
+                    KeyCode::PageDown => {
+                        self.current_index =
+                            (self.current_index + page).min(entries.len().saturating_sub(1));
+                        self.scroll += page;
                     }
+                    KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.current_index = self.current_index.saturating_sub(page / 2);
+                        self.scroll = self.scroll.saturating_sub(page / 2);
+                    }
+                    KeyCode::Char('d') if modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.current_index =
+                            (self.current_index + page / 2).min(entries.len().saturating_sub(1));
+                        self.scroll += page / 2;
+                    }
+                    KeyCode::Home | KeyCode::Char('g') => self.current_index = 0,
+                    KeyCode::End | KeyCode::Char('G') => {
+                        self.current_index = entries.len().saturating_sub(1)
+                    }
+                    KeyCode::Char(':') => goto = Some(String::new()),
+                    KeyCode::Char('?') => show_help(stdout, HelpView::History)?,
+                    KeyCode::Char('v') => self.split = !self.split,
+                    KeyCode::Char('c') => self.columns = self.columns.next(),
                     KeyCode::Enter | KeyCode::Char('l') => {
-                        view_mode = Some(current_index); // Switch to detail view
+                        self.view_mode = Some(self.current_index); // Switch to detail view
+                    }
+                    // Only commands cut off at the edge have more to show
+                    KeyCode::Char(' ') => {
+                        let command = &entries[self.current_index].command;
+                        let term_width = screen_size()?.0 as usize;
+                        let row_width =
+                            term_width.saturating_sub(self.columns.prefix_width(term_width));
+                        if needs_preview(command, row_width) {
+                            show_preview(stdout, command)?;
+                        }
                     }
+                    KeyCode::Char('t') => self.time_display = self.time_display.toggled(),
+                    KeyCode::Char('b') if !entries.is_empty() => {
+                        let command = entries[self.current_index].command.clone();
+                        status = Some(self.toggle_pin(&command));
+                    }
+                    KeyCode::Char('d') if !deleting_allowed() => {
+                        status = Some(tr!("delete-not-stats-log"));
+                    }
+                    KeyCode::Char('d') if !self.deleted.contains(&self.current_index) => {
+                        confirming = !entries.is_empty();
+                    }
+                    // The session's latest deletion, made here or in another list
+                    KeyCode::Char('U') => {
+                        status = Some(match journal::undo() {
+                            Some(Operation::Delete(entry)) => {
+                                let row = self.deleted.iter().rposition(|&i| entries[i] == entry);
+                                if let Some(row) = row {
+                                    self.current_index = self.deleted.remove(row);
+                                }
+                                tr!("delete-undone")
+                            }
+                            None => tr!("delete-none"),
+                        });
+                    }
+                    KeyCode::Char('u') => match browse_vocabulary(stdout, entries)? {
+                        ScreenExit::Back => {}
+                        exit => return Ok(exit),
+                    },
+                    KeyCode::Char('r') => return Ok(ScreenExit::PickRange),
                     KeyCode::Char('h') => {
                         // In list view, 'h' doesn't do anything special
                     }
-                    KeyCode::Char('c') => {
-                        if modifiers.contains(KeyModifiers::CONTROL) {
-                            break;
-                        }
-                    }
                     _ => {}
                 }
             }
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Cleanup
-    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
-    terminal::disable_raw_mode()?;
+    #[test]
+    fn list_scrolls_only_to_keep_the_cursor_on_screen() {
+        // Starts at the latest entry, on the bottom row
+        assert_eq!(scroll_to(0, 99, 10, 100), 90);
+        // Moving within the window leaves it alone
+        assert_eq!(scroll_to(90, 93, 10, 100), 90);
+        // Past the top or bottom edge, it moves by just enough
+        assert_eq!(scroll_to(90, 88, 10, 100), 88);
+        assert_eq!(scroll_to(50, 62, 10, 100), 53);
+        // A page down past the end stops at the last full page
+        assert_eq!(scroll_to(95, 99, 10, 100), 90);
+        assert_eq!(scroll_to(3, 2, 10, 5), 0);
+    }
 
-    Ok(())
+    #[test]
+    fn list_columns_fit_the_width() {
+        assert_eq!(ListColumns::Time.prefix_width(80), 24);
+        assert_eq!(ListColumns::CommandOnly.widths(80), (None, None));
+        assert_eq!(ListColumns::CommandOnly.prefix_width(80), 9);
+        // A quarter of the width, at most 30
+        assert_eq!(
+            ListColumns::TimeAndDirectory.widths(80),
+            (Some(12), Some(20))
+        );
+        assert_eq!(
+            ListColumns::TimeAndDirectory.widths(200),
+            (Some(12), Some(30))
+        );
+        assert_eq!(ListColumns::TimeAndDirectory.prefix_width(200), 57);
+        // Too narrow to keep 20 columns for the command
+        assert_eq!(ListColumns::TimeAndDirectory.widths(60), (Some(12), None));
+        assert_eq!(ListColumns::CommandOnly.next(), ListColumns::Time);
+    }
 }
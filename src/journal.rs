@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use std::sync::Mutex;
+
+use crate::history::HistoryEntry;
+use crate::purge::{delete_from_stats_log, FileSummary};
+
+// A change made from a viewer, held back until the session ends
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    // d: the entry leaves the stats log
+    Delete(HistoryEntry),
+}
+
+// The session's changes, oldest first. U takes back the latest one from
+// any viewer, and whatever is left is applied to the stats log on exit, so
+// a slip of a key is never written straight away.
+#[derive(Debug, Default)]
+pub struct Journal {
+    operations: Vec<Operation>,
+}
+
+impl Journal {
+    pub fn record(&mut self, operation: Operation) {
+        self.operations.push(operation);
+    }
+
+    pub fn undo(&mut self) -> Option<Operation> {
+        self.operations.pop()
+    }
+
+    // Whether `entry` is still to be deleted
+    pub fn deletes(&self, entry: &HistoryEntry) -> bool {
+        self.operations
+            .iter()
+            .any(|operation| matches!(operation, Operation::Delete(deleted) if deleted == entry))
+    }
+
+    pub fn deletions(&self) -> Vec<HistoryEntry> {
+        self.operations
+            .iter()
+            .map(|operation| match operation {
+                Operation::Delete(entry) => entry.clone(),
+            })
+            .collect()
+    }
+}
+
+static JOURNAL: Mutex<Journal> = Mutex::new(Journal {
+    operations: Vec::new(),
+});
+
+pub fn record(operation: Operation) {
+    JOURNAL.lock().unwrap().record(operation);
+}
+
+pub fn undo() -> Option<Operation> {
+    JOURNAL.lock().unwrap().undo()
+}
+
+pub fn deletes(entry: &HistoryEntry) -> bool {
+    JOURNAL.lock().unwrap().deletes(entry)
+}
+
+// Apply the session's changes, once the terminal is back to normal, and
+// say what became of them
+pub fn apply() -> Result<()> {
+    let journal = std::mem::take(&mut *JOURNAL.lock().unwrap());
+    let deleted = journal.deletions();
+    if deleted.is_empty() {
+        return Ok(());
+    }
+    let FileSummary {
+        removed,
+        shortened,
+        path,
+        ..
+    } = delete_from_stats_log(&deleted)?;
+    println!(
+        "Deleted {} of {} commands from {}",
+        removed + shortened,
+        deleted.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+// What a viewer returns once it has tried to give the terminal back. The
+// session's changes are applied even when the session or the restore
+// failed.
+pub fn finish(session: Result<()>, restored: Result<()>) -> Result<()> {
+    let applied = apply();
+    combine([session, restored, applied])
+}
+
+// The first failure, wrapped in each later one so none is lost
+fn combine(results: impl IntoIterator<Item = Result<()>>) -> Result<()> {
+    results
+        .into_iter()
+        .reduce(|earlier, later| match (earlier, later) {
+            (Ok(()), later) => later,
+            (Err(earlier), Ok(())) => Err(earlier),
+            (Err(earlier), Err(later)) => {
+                Err(earlier).context(format!("{:#}, after an earlier error", later))
+            }
+        })
+        .unwrap_or(Ok(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_takes_back_the_latest_change() {
+        let entry = |command: &str| HistoryEntry {
+            timestamp: 10,
+            command: command.to_string(),
+            ..Default::default()
+        };
+        let mut journal = Journal::default();
+        journal.record(Operation::Delete(entry("export TOKEN=abc")));
+        journal.record(Operation::Delete(entry("ls")));
+        assert!(journal.deletes(&entry("ls")));
+
+        assert_eq!(journal.undo(), Some(Operation::Delete(entry("ls"))));
+        assert!(!journal.deletes(&entry("ls")));
+        assert_eq!(journal.deletions(), [entry("export TOKEN=abc")]);
+        journal.undo();
+        assert_eq!(journal.undo(), None);
+    }
+
+    #[test]
+    fn no_failure_hides_another() {
+        use crate::error::ErrorKind;
+
+        assert!(combine([Ok(()), Ok(())]).is_ok());
+        let session = Err(anyhow::anyhow!("broken pipe")).context(ErrorKind::Terminal);
+        let restored = Err(anyhow::anyhow!("not a terminal"));
+        let applied = Err(anyhow::anyhow!("Failed to write the log"));
+        let err = combine([session, Ok(()), restored, applied]).unwrap_err();
+        let text = format!("{:#}", err);
+        for part in ["broken pipe", "not a terminal", "Failed to write the log"] {
+            assert!(text.contains(part), "{}", text);
+        }
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Terminal);
+    }
+}
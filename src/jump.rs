@@ -0,0 +1,125 @@
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::history::HistoryEntry;
+
+// How `jump` writes its directories, for the tool they're meant for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum JumpFormat {
+    // One path per line, best first, e.g. for fzf
+    Plain,
+    // z's data file, `path|rank|time`, which zoxide can import too
+    Z,
+    // autojump's `weight<TAB>path`
+    Autojump,
+}
+
+// A directory worth jumping to
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpDir {
+    pub path: String,
+    // Commands run there
+    pub visits: usize,
+    // When the last one ran, 0 when none had a timestamp
+    pub last: i64,
+    pub score: f64,
+}
+
+// Frequency weighted by how recently the directory was used, the way z
+// ranks directories: four times the visits within the hour, twice within
+// the day, half within the week and a quarter before that
+fn frecency(visits: usize, last: i64, now: i64) -> f64 {
+    let age = now - last;
+    let weight = if last <= 0 {
+        0.25
+    } else if age < 3600 {
+        4.0
+    } else if age < 86400 {
+        2.0
+    } else if age < 7 * 86400 {
+        0.5
+    } else {
+        0.25
+    };
+    visits as f64 * weight
+}
+
+// Directories commands ran in, best jump first. Directories that no longer
+// exist are left out, as jump tools would only drop them again.
+pub fn jump_list(entries: &[HistoryEntry], now: i64, limit: usize) -> Vec<JumpDir> {
+    let mut dirs: HashMap<&str, (usize, i64)> = HashMap::new();
+    for entry in entries {
+        if let Some(directory) = entry.directory.as_deref() {
+            let (visits, last) = dirs.entry(directory).or_insert((0, 0));
+            *visits += 1;
+            *last = (*last).max(entry.timestamp);
+        }
+    }
+    let mut list: Vec<JumpDir> = dirs
+        .into_iter()
+        .filter(|(path, _)| Path::new(path).is_dir())
+        .map(|(path, (visits, last))| JumpDir {
+            path: path.to_string(),
+            visits,
+            last,
+            score: frecency(visits, last, now),
+        })
+        .collect();
+    list.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| b.last.cmp(&a.last))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    list.truncate(limit);
+    list
+}
+
+pub fn jump_output(list: &[JumpDir], format: JumpFormat) -> String {
+    let mut out = String::new();
+    for dir in list {
+        let _ = match format {
+            JumpFormat::Plain => writeln!(out, "{}", dir.path),
+            // z ages ranks itself, so it gets the plain count
+            JumpFormat::Z => writeln!(out, "{}|{}|{}", dir.path, dir.visits, dir.last),
+            JumpFormat::Autojump => writeln!(out, "{:.1}\t{}", dir.score, dir.path),
+        };
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_by_visits_and_recency() {
+        let tmp = std::env::temp_dir().to_string_lossy().into_owned();
+        let root = "/".to_string();
+        let now = 1_700_000_000;
+        let run = |directory: &str, timestamp: i64| HistoryEntry {
+            timestamp,
+            command: "ls".to_string(),
+            directory: Some(directory.to_string()),
+            ..Default::default()
+        };
+        let mut entries = vec![run("/no/such/directory", now)];
+        // Six visits a month ago lose to two a minute ago
+        entries.extend((0..6).map(|_| run(&root, now - 30 * 86400)));
+        entries.extend((0..2).map(|i| run(&tmp, now - 60 + i)));
+
+        let list = jump_list(&entries, now, 10);
+        assert_eq!(list.len(), 2);
+        assert_eq!((list[0].path.as_str(), list[0].visits), (tmp.as_str(), 2));
+        assert_eq!((list[0].score, list[1].score), (8.0, 1.5));
+
+        assert_eq!(
+            jump_output(&list[1..], JumpFormat::Z),
+            format!("/|6|{}\n", now - 30 * 86400)
+        );
+        assert_eq!(jump_output(&list[1..], JumpFormat::Autojump), "1.5\t/\n");
+        assert_eq!(jump_list(&entries, now, 1).len(), 1);
+    }
+}
@@ -0,0 +1,61 @@
+pub mod aggregate;
+pub mod app;
+pub mod atuin;
+pub mod cache;
+pub mod categories;
+pub mod cli;
+pub mod compare;
+pub mod config;
+pub mod days;
+pub mod doctor;
+pub mod error;
+pub mod estimate;
+pub mod export;
+pub mod favorites;
+pub mod frames;
+pub mod goals;
+pub mod guard;
+pub mod heatmap;
+pub mod help;
+pub mod histdb;
+pub mod history;
+pub mod i18n;
+pub mod import;
+pub mod interactive;
+pub mod journal;
+pub mod jump;
+pub mod machines;
+pub mod metrics;
+pub mod normalize;
+pub mod output;
+pub mod panels;
+pub mod parser;
+pub mod power;
+pub mod presentation;
+pub mod preview;
+pub mod privacy;
+pub mod purge;
+pub mod range_picker;
+pub mod record;
+pub mod redact;
+pub mod render;
+pub mod report;
+#[cfg(test)]
+mod scratch;
+pub mod search;
+pub mod similar;
+pub mod snapshot;
+pub mod stats;
+pub mod store;
+pub mod suggest;
+pub mod tagging;
+pub mod theme;
+pub mod time_format;
+pub mod timeline;
+pub mod timing;
+pub mod today;
+pub mod tour;
+pub mod ui_utils;
+pub mod vocabulary;
+pub mod whatis;
+pub mod wrapped;
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::aggregate::{program, sorted_counts};
+use crate::history::HistoryEntry;
+
+// Rows in each list of the report
+const ROWS: usize = 10;
+
+// A program one machine runs at least this many times as much of, by share
+// of everything it runs, counts as used differently
+const INTENSITY_RATIO: f64 = 2.0;
+
+// How the programs run on this machine and on another one differ
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceDiff {
+    pub here_total: usize,
+    pub there_total: usize,
+    // Programs only one side runs, busiest first, with their runs
+    pub only_here: Vec<(String, usize)>,
+    pub only_there: Vec<(String, usize)>,
+    // (program, share here, share there) in percent, for programs both run
+    // where one share is at least twice the other, biggest gap first
+    pub different: Vec<(String, f64, f64)>,
+}
+
+fn program_counts(entries: &[HistoryEntry]) -> HashMap<&str, usize> {
+    let mut counts = HashMap::new();
+    for entry in entries {
+        *counts.entry(program(&entry.command)).or_insert(0) += 1;
+    }
+    counts
+}
+
+pub fn diff_sources(here: &[HistoryEntry], there: &[HistoryEntry]) -> SourceDiff {
+    let here_counts = program_counts(here);
+    let there_counts = program_counts(there);
+    let only = |from: &HashMap<&str, usize>, other: &HashMap<&str, usize>| {
+        sorted_counts(
+            from.iter()
+                .filter(|(program, _)| !other.contains_key(*program))
+                .map(|(program, count)| (*program, *count))
+                .collect::<HashMap<_, _>>(),
+        )
+    };
+    let share = |count: usize, total: usize| count as f64 * 100.0 / total as f64;
+    let mut different: Vec<(String, f64, f64)> = here_counts
+        .iter()
+        .filter_map(|(program, &count)| {
+            let here_share = share(count, here.len());
+            let there_share = share(*there_counts.get(program)?, there.len());
+            let ratio = here_share.max(there_share) / here_share.min(there_share);
+            (ratio >= INTENSITY_RATIO).then(|| (program.to_string(), here_share, there_share))
+        })
+        .collect();
+    different.sort_by(|a, b| {
+        (b.1 - b.2)
+            .abs()
+            .total_cmp(&(a.1 - a.2).abs())
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    SourceDiff {
+        here_total: here.len(),
+        there_total: there.len(),
+        only_here: only(&here_counts, &there_counts),
+        only_there: only(&there_counts, &here_counts),
+        different,
+    }
+}
+
+// The differences as `diff-sources` prints them, with the other machine
+// called `there`
+pub fn source_diff_report(diff: &SourceDiff, there: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Here   {} commands", diff.here_total);
+    let _ = writeln!(out, "There  {} commands ({})", diff.there_total, there);
+
+    let width = diff
+        .only_here
+        .iter()
+        .chain(&diff.only_there)
+        .take(ROWS * 2)
+        .map(|(program, _)| program.chars().count())
+        .chain(
+            diff.different
+                .iter()
+                .take(ROWS)
+                .map(|(program, _, _)| program.chars().count()),
+        )
+        .max()
+        .unwrap_or(0);
+    for (title, programs) in [
+        ("Only here", &diff.only_here),
+        ("Only there", &diff.only_there),
+    ] {
+        if programs.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "\n{} ({})", title, programs.len());
+        for (program, count) in programs.iter().take(ROWS) {
+            let _ = writeln!(out, "  {:<width$}  {:>5}", program, count, width = width);
+        }
+    }
+    if !diff.different.is_empty() {
+        let _ = writeln!(out, "\nUsed differently");
+        let _ = writeln!(
+            out,
+            "  {:<width$}  {:>6}  {:>6}",
+            "",
+            "Here",
+            "There",
+            width = width
+        );
+        for (program, here, there) in diff.different.iter().take(ROWS) {
+            let _ = writeln!(
+                out,
+                "  {:<width$}  {:>5.1}%  {:>5.1}%",
+                program,
+                here,
+                there,
+                width = width
+            );
+        }
+    }
+    if diff.only_here.is_empty() && diff.only_there.is_empty() && diff.different.is_empty() {
+        let _ = writeln!(out, "\nBoth run the same programs about as much");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn programs_missing_on_one_side_or_used_differently() {
+        let run = |command: &str| HistoryEntry {
+            command: command.to_string(),
+            ..Default::default()
+        };
+        let mut laptop: Vec<HistoryEntry> = ["git status", "git push", "cargo build", "brew up"]
+            .map(run)
+            .to_vec();
+        laptop.extend((0..4).map(|_| run("ls")));
+        let mut server: Vec<HistoryEntry> = ["git pull", "htop", "ls"].map(run).to_vec();
+        server.extend((0..5).map(|_| run("systemctl status nginx")));
+
+        let diff = diff_sources(&laptop, &server);
+        assert_eq!(
+            diff.only_here,
+            [("brew".to_string(), 1), ("cargo".to_string(), 1)]
+        );
+        assert_eq!(
+            diff.only_there,
+            [("systemctl".to_string(), 5), ("htop".to_string(), 1)]
+        );
+        // ls is half the laptop's commands and an eighth of the server's;
+        // git's 25% against 12.5% is exactly twice
+        assert_eq!(
+            diff.different,
+            [
+                ("ls".to_string(), 50.0, 12.5),
+                ("git".to_string(), 25.0, 12.5)
+            ]
+        );
+
+        let report = source_diff_report(&diff, "server.csv");
+        assert!(report.contains("There  8 commands (server.csv)"));
+        assert!(report.contains("Only there (2)\n  systemctl      5"));
+        assert!(report.contains("  ls          50.0%   12.5%"));
+    }
+}
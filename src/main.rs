@@ -1,563 +1,704 @@
-use anyhow::{Context, Result};
-use chrono::{Local, TimeZone, Timelike};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
-use crossterm::{
-    cursor, execute,
-    style::{Color, Stylize},
-    terminal::{self, ClearType},
+use regex::Regex;
+use std::borrow::Cow;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+use cli_wrapped::aggregate::{
+    period_bounds, summarize, time_patterns, CalendarPeriod, ProjectRoots,
 };
-use std::{
-    io::{self, Write},
-    path::PathBuf,
+use cli_wrapped::app::{run_app, AppOptions, Screen};
+use cli_wrapped::cache;
+use cli_wrapped::cli::{Cli, Commands, ConfigAction, ReportKind, SnapshotAction};
+use cli_wrapped::compare::{compare_periods, compare_report};
+use cli_wrapped::config::{self, HistoryLayout};
+use cli_wrapped::days::{daily_stats, day_counts, streaks};
+use cli_wrapped::doctor::{doctor_report, hook_check, stale_hours};
+use cli_wrapped::error::{format_error, ErrorKind};
+use cli_wrapped::estimate::estimate_timestamps;
+use cli_wrapped::export::{entries_csv, entries_json, rollup_csv, write_sqlite, ExportFormat};
+use cli_wrapped::favorites::{favorites_report, load_favorites};
+use cli_wrapped::guard::check_history_size;
+use cli_wrapped::heatmap::{browse_heatmap, heatmap_lines, Heatmap};
+use cli_wrapped::history::{
+    get_cli_stats_log_path, get_history_entries, project_dir, ran_in, reads_stats_log,
+    user_history, HistoryEntry, Shell, UserDirs,
 };
-use unicode_width::UnicodeWidthStr;
-
-// Declare modules
-mod cli;
-mod days;
-mod history;
-mod interactive;
-mod stats;
-mod ui_utils;
-// Use items from modules
-use cli::{Cli, Commands};
-use days::display_today_stats;
-use history::get_history_entries;
-use interactive::run_interactive_viewer;
-use stats::display_stats;
-
-#[derive(Debug, Clone)]
-struct HistoryEntry {
-    timestamp: i64,
-    command: String,
-    directory: Option<String>,
-    duration: Option<i64>,
-    exit_code: Option<i32>,
-}
-
-fn get_zsh_history_path() -> Result<PathBuf> {
-    let home = home::home_dir().context("Could not find home directory")?;
-    Ok(home.join(".zsh_history"))
-}
-
-fn get_cli_stats_log_path() -> Result<PathBuf> {
-    let home = home::home_dir().context("Could not find home directory")?;
-    Ok(home.join(".cli_stats_log"))
-}
-
-fn parse_history_line(line: &str) -> Option<HistoryEntry> {
-    let (timestamp, command_part) = if line.starts_with(": ") {
-        let parts: Vec<&str> = line.splitn(3, ';').collect();
-        if parts.len() < 2 {
-            return None;
-        }
-        let ts_part = parts[0].strip_prefix(": ")?.trim();
-        let timestamp = ts_part.splitn(2, ':').next()?.parse().ok()?;
-        (timestamp, parts.get(1).unwrap_or(&"").to_string())
-    } else {
-        // For basic history entries, use a default timestamp (e.g., 0 or current time)
-        (0, line.to_string()) // Or use chrono::Utc::now().timestamp()
+use cli_wrapped::import::{import_into_log, read_import, seed_log, ImportFormat};
+use cli_wrapped::jump::{jump_list, jump_output};
+use cli_wrapped::machines::{diff_sources, source_diff_report};
+use cli_wrapped::metrics::{diff_report, load_metrics, save_metrics, Metrics};
+use cli_wrapped::output::{
+    dirs_output, projects_output, search_output, summary_output, top_output, OutputFormat,
+};
+use cli_wrapped::parser::is_context_label;
+use cli_wrapped::power::battery_level;
+use cli_wrapped::presentation;
+use cli_wrapped::privacy::Privacy;
+use cli_wrapped::purge::{allow_deleting, data_files, format_summary, purge, PurgeTarget};
+use cli_wrapped::record::{append_record, zsh_hook};
+use cli_wrapped::redact::redactor;
+use cli_wrapped::report::{
+    branches_report, brief_report, commit_report, directory_stats, loops_report, panes_report,
+    project_stats, retries_report, sessions_report, streak_report, top_commands, users_report,
+};
+use cli_wrapped::search::{search, search_report, Matcher};
+use cli_wrapped::store::{ingest_all, ingest_interval, min_battery, store_path, stored_history};
+use cli_wrapped::suggest::functions_report;
+use cli_wrapped::tagging::tagger;
+use cli_wrapped::theme;
+use cli_wrapped::time_format::{TimeDisplay, TimeRange};
+use cli_wrapped::timeline::timeline_lines;
+use cli_wrapped::timing;
+use cli_wrapped::tr;
+use cli_wrapped::wrapped::{browse_recap, recap_pages, recap_text, Recap};
+
+fn confirm_purge(target: &PurgeTarget) -> Result<bool> {
+    let what = match target {
+        PurgeTarget::All => "all tracked data".to_string(),
+        PurgeTarget::Matching(pattern) => format!("every line matching '{}'", pattern),
+        PurgeTarget::Commands(pattern) => format!("every command matching '{}'", pattern),
     };
-
-    let clean_command = command_part.trim().to_string();
-
-    if !clean_command.is_empty() {
-        // Extract directory from cd commands
-        let directory = if clean_command.starts_with("cd ") {
-            clean_command
-                .strip_prefix("cd ")
-                .map(|s| s.trim().to_string())
-        } else {
-            None
-        };
-
-        Some(HistoryEntry {
-            timestamp,
-            command: clean_command,
-            directory,
-            duration: None,  // We don't have duration info in zsh history
-            exit_code: None, // We don't have exit code info in zsh history
-        })
-    } else {
-        None
-    }
-}
-
-fn parse_cli_stats_line(line: &str) -> Option<HistoryEntry> {
-    // Format 1: ": timestamp:0;command:directory"
-    // Format 2: "command" (just the command)
-    // Format 3: ": timestamp:0;command" (no directory)
-
-    if line.starts_with(": ") {
-        // Format 1 or 3 with timestamp
-        let timestamp_part = line.strip_prefix(": ")?;
-        let parts: Vec<&str> = timestamp_part.splitn(2, ';').collect();
-        if parts.len() < 2 {
-            return None;
-        }
-
-        // Get timestamp from first part (timestamp:0)
-        let ts_parts: Vec<&str> = parts[0].splitn(2, ':').collect();
-        let timestamp = ts_parts[0].parse::<i64>().ok()?;
-
-        // Get command and possibly directory
-        let cmd_dir_parts: Vec<&str> = parts[1].splitn(2, ':').collect();
-        let command = cmd_dir_parts[0].to_string();
-
-        // If we have a directory part
-        let directory = if cmd_dir_parts.len() > 1 {
-            Some(cmd_dir_parts[1].to_string())
-        } else {
-            None
-        };
-
-        if !command.is_empty() {
-            return Some(HistoryEntry {
-                timestamp,
-                command,
-                directory,
-                duration: None,
-                exit_code: None,
-            });
-        }
-    } else {
-        // Format 2: just the command
-        let command = line.trim().to_string();
-        if !command.is_empty() {
-            return Some(HistoryEntry {
-                timestamp: 0, // No timestamp available
-                command,
-                directory: None,
-                duration: None,
-                exit_code: None,
-            });
-        }
+    println!("This will permanently delete {} from:", what);
+    for path in data_files()? {
+        println!("  {}", path.display());
     }
+    print!("Continue? [y/N] ");
+    io::stdout().flush()?;
 
-    None
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
 }
 
-fn format_timestamp(timestamp: i64) -> String {
-    if timestamp == 0 {
-        return "Timestamp not available".to_string();
-    }
-    match Local.timestamp_opt(timestamp, 0) {
-        chrono::LocalResult::Single(dt) => dt.format("%b %d %Y at %I:%M %P").to_string(),
-        _ => "Invalid timestamp".to_string(),
+// What the stats and today views would show for `entries`, on stdout
+fn print_summary(view: &str, entries: &[HistoryEntry], format: OutputFormat, private: bool) {
+    let entries: Vec<&HistoryEntry> = entries.iter().collect();
+    let (mut summary, mut patterns) = timing::time("aggregate", || {
+        let bounds = period_bounds(chrono::Local::now(), -1);
+        let roots = ProjectRoots::resolve(entries.iter().copied());
+        (
+            summarize(&entries, &bounds, &roots),
+            time_patterns(&entries),
+        )
+    });
+    if private {
+        Privacy::from_env().summary(&mut summary, &mut patterns);
     }
+    print!("{}", summary_output(view, &summary, &patterns, format));
 }
 
-// Define box drawing characters
-const TOP_LEFT: &str = "┌";
-const TOP_RIGHT: &str = "┐";
-const BOTTOM_LEFT: &str = "└";
-const BOTTOM_RIGHT: &str = "┘";
-const HORIZONTAL: &str = "─";
-const VERTICAL: &str = "│";
-
-// Helper function to draw a box
-fn draw_box(
-    stdout: &mut io::Stdout,
-    x: u16,
-    y: u16,
-    width: u16,
-    height: u16,
-    title: Option<&str>,
-) -> Result<()> {
-    // Ensure minimum dimensions for a proper box
-    let width = width.max(4); // Minimum width to draw a proper box
-    let height = height.max(3); // Minimum height for a proper box
-
-    // Draw top border with optional title
-    execute!(stdout, cursor::MoveTo(x, y))?;
-    write!(stdout, "{}", TOP_LEFT)?;
-
-    if let Some(title_text) = title {
-        let title_display = format!(" {} ", title_text);
-        let title_width = title_display.width();
-        // Ensure we have enough space for title and borders
-        let remaining_width = width as usize - 2;
-
-        if title_width < remaining_width {
-            let left_border = (remaining_width - title_width) / 2;
-            let right_border = remaining_width - left_border - title_width;
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let error_format = cli.error_format;
 
-            write!(stdout, "{}", HORIZONTAL.repeat(left_border))?;
-            write!(stdout, "{}", title_display.cyan())?;
-            write!(stdout, "{}", HORIZONTAL.repeat(right_border))?;
-        } else {
-            // Title too long, just draw border
-            write!(stdout, "{}", HORIZONTAL.repeat(remaining_width))?;
-        }
-    } else {
-        write!(stdout, "{}", HORIZONTAL.repeat((width - 2) as usize))?;
+    let result = run(cli);
+    if let Some(report) = timing::report() {
+        eprint!("{}", report);
     }
-
-    write!(stdout, "{}", TOP_RIGHT)?;
-
-    // Draw sides
-    for i in 1..height - 1 {
-        execute!(stdout, cursor::MoveTo(x, y + i))?;
-        write!(stdout, "{}", VERTICAL)?;
-        execute!(stdout, cursor::MoveTo(x + width - 1, y + i))?;
-        write!(stdout, "{}", VERTICAL)?;
+    if let Err(err) = result {
+        eprintln!("{}", format_error(&err, error_format));
+        std::process::exit(ErrorKind::of(&err).exit_code());
     }
-
-    // Draw bottom
-    execute!(stdout, cursor::MoveTo(x, y + height - 1))?;
-    write!(stdout, "{}", BOTTOM_LEFT)?;
-    write!(stdout, "{}", HORIZONTAL.repeat((width - 2) as usize))?;
-    write!(stdout, "{}", BOTTOM_RIGHT)?;
-
-    Ok(())
 }
 
-// Write text inside a box area with an x offset
-fn write_in_box(stdout: &mut io::Stdout, x: u16, y: u16, text: &str, x_offset: u16) -> Result<()> {
-    execute!(stdout, cursor::MoveTo(x + 1 + x_offset, y))?;
-    write!(stdout, "{}", text)?;
-    Ok(())
-}
-
-fn display_detail_view(
-    stdout: &mut io::Stdout,
-    entry: &HistoryEntry,
-    entries: &[HistoryEntry],
-    current_index: usize,
-) -> Result<()> {
-    // Clear screen first
-    execute!(stdout, terminal::Clear(ClearType::All))?;
-
-    // Get terminal size
-    let (term_width, term_height) = terminal::size()?;
-
-    // Ensure minimum size requirements
-    let min_width = 80;
-    let min_height = 24;
-    if term_width < min_width || term_height < min_height {
-        execute!(stdout, cursor::MoveTo(0, 0))?;
-        write!(
-            stdout,
-            "Terminal too small. Please resize to at least {}x{}",
-            min_width, min_height
-        )?;
-        stdout.flush()?;
-        return Ok(());
+fn run(cli: Cli) -> Result<()> {
+    if cli.no_cache {
+        cache::disable();
     }
-
-    // Correctly assign previous and next commands
-    // Previous command comes before current (is newer, has lower index)
-    let prev_cmd = if current_index > 0 {
-        &entries[current_index - 1].command
-    } else {
-        "No previous command"
-    };
-
-    // Next command comes after current (is older, has higher index)
-    let next_cmd = if current_index < entries.len() - 1 {
-        &entries[current_index + 1].command
-    } else {
-        "No next command"
-    };
-
-    // Header
-    execute!(stdout, cursor::MoveTo(0, 0))?;
-    write!(
-        stdout,
-        "{}                                                                    {: <67}                                                               {}",
-        "CLI Wrapped".cyan().bold(),
-        "<esc>: back, ↑/↓: navigate".dark_grey(),
-        format!("history count: {}", entries.len()).cyan()
-    )?;
-
-    // Command navigation section - top row with 3 boxes
-    let box_height = 5;
-    let prev_width = term_width / 3;
-    let cmd_width = term_width / 3;
-    let next_width = term_width - prev_width - cmd_width;
-
-    // Previous command box (older command) - normal styling
-    draw_box(
-        stdout,
-        1,
-        2,
-        prev_width,
-        box_height,
-        Some("Previous command"),
-    )?;
-
-    // Write previous command with normal color
-    write_in_box(stdout, 1, 3, prev_cmd, 1)?;
-
-    // Current command box
-    draw_box(
-        stdout,
-        prev_width + 1,
-        2,
-        cmd_width,
-        box_height,
-        Some("Command"),
-    )?;
-    write_in_box(stdout, prev_width + 1, 3, &entry.command, 1)?;
-
-    // Next command box (newer command) - normal styling
-    draw_box(
-        stdout,
-        prev_width + cmd_width + 1,
-        2,
-        next_width - 1, // Adjust width to fix alignment
-        box_height,
-        Some("Next command"),
-    )?;
-
-    // Write next command with normal color
-    write_in_box(stdout, prev_width + cmd_width + 1, 3, next_cmd, 1)?;
-
-    // Calculate command stats
-    let username = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
-    let hostname = std::env::var("HOSTNAME")
-        .or_else(|_| std::env::var("HOST"))
-        .unwrap_or_else(|_| "unknown".to_string());
-
-    // Find the current working directory
-    let current_dir = if let Some(dir) = &entry.directory {
-        dir.clone()
-    } else {
-        std::env::current_dir()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|_| "unknown".to_string())
-    };
-
-    // Count how many times this command appears in history
-    let total_runs = entries
-        .iter()
-        .filter(|e| e.command == entry.command)
-        .count();
-
-    // Calculate command position in history (starting from 1 for oldest)
-    let history_position = current_index + 1;
-
-    // Gather real stats from the history entries and environment
-    let stats = [
-        ("History number", history_position.to_string()),
-        ("User", username),
-        ("Time", format_timestamp(entry.timestamp)),
-        ("Directory", current_dir),
-        ("Total runs", total_runs.to_string()),
-        (
-            "Recent runs",
-            format!(
-                "{}",
-                entries
-                    .iter()
-                    .filter(|e| e.command == entry.command && e.timestamp > entry.timestamp - 86400)
-                    .count()
-            ),
-        ),
-    ];
-
-    // Command stats box - left column
-    let stats_height = 14;
-    let stats_width = term_width / 2;
-    draw_box(
-        stdout,
-        1,
-        box_height + 2,
-        stats_width,
-        stats_height,
-        Some("Command stats"),
-    )?;
-
-    for (i, (key, value)) in stats.iter().enumerate() {
-        let line = box_height + 4 + i as u16;
-        execute!(stdout, cursor::MoveTo(3, line))?;
-        write!(stdout, "{:<14} {}", key.with(Color::DarkGrey), value)?;
+    if cli.present {
+        presentation::enable();
     }
-
-    // List of similar commands - right top
-    draw_box(
-        stdout,
-        stats_width + 1,
-        box_height + 2,
-        term_width - stats_width - 1,
-        5,
-        Some("Similar commands"),
-    )?;
-
-    // Find similar commands (commands that start with the same word)
-    let first_word = entry.command.split_whitespace().next().unwrap_or("");
-    let similar_commands: Vec<&HistoryEntry> = entries
-        .iter()
-        .filter(|e| {
-            let e_first_word = e.command.split_whitespace().next().unwrap_or("");
-            e_first_word == first_word && e.command != entry.command
-        })
-        .take(3)
-        .collect();
-
-    for (i, similar) in similar_commands.iter().enumerate() {
-        let line = box_height + 3 + i as u16;
-        let display = if similar.command.len() > 40 {
-            format!("{}...", &similar.command[..37])
+    if cli.timing {
+        timing::enable();
+    }
+    // `config` has to work on a file with mistakes in it, to fix them
+    if !matches!(cli.command, Some(Commands::Config { .. })) {
+        config::load()?;
+    }
+    theme::init(cli.theme);
+    let config = config::config();
+    // The config's history settings stand unless flags pick another history
+    let (source, shell, history_file) =
+        if cli.source.is_some() || cli.shell.is_some() || cli.history_file.is_some() {
+            (cli.source, cli.shell, cli.history_file.clone())
         } else {
-            similar.command.clone()
+            (config.source, config.shell, config.history_file.clone())
         };
-        write_in_box(stdout, stats_width + 1, line, &display, 1)?;
-    }
-
-    // Command frequency by hour - right middle
-    draw_box(
-        stdout,
-        stats_width + 1,
-        box_height + 7,
-        term_width - stats_width - 1,
-        4,
-        Some("Command frequency by hour"),
-    )?;
-
-    // Count commands by hour of day (based on timestamps)
-    let mut hour_counts = vec![0; 24];
-    for e in entries
-        .iter()
-        .filter(|e| e.command == entry.command && e.timestamp > 0)
-    {
-        let dt = Local.timestamp_opt(e.timestamp, 0);
-        if let chrono::LocalResult::Single(dt) = dt {
-            let hour = dt.hour() as usize;
-            if hour < 24 {
-                hour_counts[hour] += 1;
+    // The viewers' `d` only works on the stats log
+    allow_deleting(reads_stats_log(source, shell, history_file.as_deref()));
+    // Every command sees only the --since/--until window and the --last
+    // commands in it, without the commands the config ignores unless
+    // --include-ignored, tagged by the config's rules and with secrets
+    // masked. Untimed commands get their estimated time first, so the
+    // window can take them in.
+    let range = TimeRange {
+        since: cli.since,
+        until: cli.until,
+    };
+    let now = chrono::Local::now().timestamp();
+    let estimate = cli.estimate_timestamps.or(config.estimate_timestamps);
+    let in_range = |mut entries: Vec<HistoryEntry>| {
+        if let Some(how) = estimate {
+            estimate_timestamps(&mut entries, how);
+        }
+        if !range.is_unbounded() {
+            entries.retain(|e| range.contains(e.timestamp, now));
+        }
+        if !cli.include_ignored && !config.ignore.is_empty() {
+            entries.retain(|e| !config.ignores(&e.command));
+        }
+        if let Some(last) = cli.last {
+            entries.drain(..entries.len().saturating_sub(last));
+        }
+        let tagger = tagger();
+        if !tagger.is_empty() {
+            tagger.tag(&mut entries);
+        }
+        let redactor = redactor();
+        for entry in &mut entries {
+            if let Cow::Owned(command) = redactor.redact(&entry.command) {
+                entry.command = command;
             }
         }
-    }
-
-    // Find max for scaling
-    let max_count = hour_counts.iter().max().copied().unwrap_or(1);
-
-    // Calculate average usage
-    let total_usage: i32 = hour_counts.iter().sum();
-    let active_hours = hour_counts.iter().filter(|&&count| count > 0).count();
-    let avg_usage = if active_hours > 0 {
-        total_usage as f64 / active_hours as f64
-    } else {
-        0.0
+        entries
     };
-
-    // Create a simpler +/- visualization where + is above average and - is below
-    let mut hour_viz = String::new();
-    hour_viz.push_str("[Hours] ");
-    for i in 0..24 {
-        // Use - for below average, + for above average, · for zeros
-        let symbol = if hour_counts[i] == 0 {
-            "·"
-        } else if (hour_counts[i] as f64) < avg_usage {
-            "-"
-        } else {
-            "+"
-        };
-        hour_viz.push_str(symbol);
-    }
-
-    write_in_box(stdout, stats_width + 1, box_height + 8, &hour_viz, 1)?;
-    write_in_box(
-        stdout,
-        stats_width + 1,
-        box_height + 9,
-        &format!(
-            "Peak times: {}",
-            hour_counts
-                .iter()
-                .enumerate()
-                .filter(|(_, &count)| count > 2 * max_count / 3)
-                .map(|(hour, _)| format!("{:02}:00", hour))
-                .collect::<Vec<_>>()
-                .join(", ")
-        ),
-        1,
-    )?;
-
-    // Command usage over time - right bottom
-    draw_box(
-        stdout,
-        stats_width + 1,
-        box_height + 11,
-        term_width - stats_width - 1,
-        5,
-        Some("Command usage over time"),
-    )?;
-
-    // Group commands by day for a simple timeline
-    let mut days = std::collections::HashMap::new();
-    for e in entries
-        .iter()
-        .filter(|e| e.command == entry.command && e.timestamp > 0)
-    {
-        let dt = Local.timestamp_opt(e.timestamp, 0);
-        if let chrono::LocalResult::Single(dt) = dt {
-            let day = dt.format("%m/%d").to_string();
-            *days.entry(day).or_insert(0) += 1;
+    // Asked before loading a history with millions of commands
+    let narrowed = cli.last.is_some() || !range.is_unbounded();
+    let check_size = || check_history_size(source, shell, history_file.as_deref(), narrowed);
+    let load_history = || {
+        check_size()?;
+        get_history_entries(source, shell, history_file.as_deref()).map(in_range)
+    };
+    // The interactive views read from the database once there is one
+    let load_stored = || {
+        check_size()?;
+        stored_history(source, shell, history_file.as_deref()).map(in_range)
+    };
+    // `--dir` for the views
+    let load_in_dir = |dir: Option<&Path>| -> Result<Vec<HistoryEntry>> {
+        let mut entries = load_stored()?;
+        if let Some(dir) = dir {
+            let dir = project_dir(dir)?;
+            entries.retain(|e| ran_in(e, &dir));
+            if entries.is_empty() {
+                return Err(anyhow!("No commands recorded in {}", dir.display()))
+                    .context(ErrorKind::NoData);
+            }
         }
-    }
-
-    // Sort by date and take most recent
-    let mut days: Vec<(String, i32)> = days.into_iter().collect();
-    days.sort_by(|a, b| b.0.cmp(&a.0)); // Sort descending by date
-    days.truncate(7); // Keep only the 7 most recent days
-    days.reverse(); // Show oldest to newest
-
-    // Create a sparkline-style visualization
-    let max_day_count = days.iter().map(|(_, count)| *count).max().unwrap_or(1);
-    let days_viz = days
-        .iter()
-        .map(|(day, count)| {
-            let intensity = (*count as f64 / max_day_count as f64 * 5.0).round() as usize;
-            let symbol = match intensity {
-                0 => "▁",
-                1 => "▂",
-                2 => "▃",
-                3 => "▄",
-                4 => "▅",
-                _ => "▆",
+        Ok(entries)
+    };
+    // With no subcommand, the view the config names, or the history
+    let command = cli.command.unwrap_or(match config.default_view {
+        Some(Screen::Stats) => Commands::Stats {
+            period: None,
+            format: None,
+            plain: false,
+            dir: None,
+        },
+        Some(Screen::Today) => Commands::Today { format: None },
+        _ => Commands::History {
+            relative_time: false,
+            dir: None,
+            split: false,
+        },
+    });
+    match command {
+        // Printed instead of shown when a format is given
+        Commands::Stats {
+            period,
+            format,
+            plain,
+            dir,
+        } if plain || format.is_some() => {
+            let format = format.unwrap_or(OutputFormat::Table);
+            let mut entries = load_in_dir(dir.as_deref())?;
+            let view = match period {
+                Some(quarter) => {
+                    let (start, end) = quarter.bounds(cli.fiscal_year_start);
+                    entries.retain(|e| e.timestamp >= start && e.timestamp <= end);
+                    quarter.to_string()
+                }
+                None => "all-time".to_string(),
             };
-            format!("{}: {}", day, symbol)
-        })
-        .collect::<Vec<_>>()
-        .join("  ");
-
-    write_in_box(stdout, stats_width + 1, box_height + 12, &days_viz, 1)?;
-
-    // Show most frequent day
-    if !days.is_empty() {
-        let most_frequent = days
-            .iter()
-            .max_by_key(|(_, count)| *count)
-            .map(|(day, count)| format!("Most active: {} ({} times)", day, count))
-            .unwrap_or_else(|| "No data".to_string());
-        write_in_box(stdout, stats_width + 1, box_height + 13, &most_frequent, 1)?;
-    }
-
-    // Footer
-    execute!(stdout, cursor::MoveTo(1, term_height - 1))?;
-    write!(stdout, "Press ESC to go back to command list")?;
-
-    stdout.flush().context("Failed to flush stdout")
-}
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
-
-    match cli.command {
-        Commands::History => {
-            let entries = get_history_entries()?;
-            run_interactive_viewer(entries)?;
-        }
-        Commands::Stats => {
-            let entries = get_history_entries()?;
-            display_stats(&entries)?;
-        }
-        Commands::Today => {
-            let entries = get_history_entries()?;
-            display_today_stats(&entries)?;
+            print_summary(&view, &entries, format, cli.private);
+        }
+        Commands::Today {
+            format: Some(format),
+        } => {
+            let mut entries = load_stored()?;
+            let today_start = period_bounds(chrono::Local::now(), -1).today_start;
+            entries.retain(|e| e.timestamp >= today_start);
+            print_summary("today", &entries, format, cli.private);
+        }
+        // The today view's figures for a calendar week, month or year
+        Commands::Week { offset, format }
+        | Commands::Month { offset, format }
+        | Commands::Year { offset, format } => {
+            let period = match command {
+                Commands::Week { .. } => CalendarPeriod::Week,
+                Commands::Month { .. } => CalendarPeriod::Month,
+                _ => CalendarPeriod::Year,
+            };
+            let today = chrono::Local::now().date_naive();
+            let (start, end) = period.bounds(today, offset);
+            let mut entries = load_stored()?;
+            entries.retain(|e| e.timestamp >= start && e.timestamp <= end);
+            print_summary(&period.name(today, offset), &entries, format, cli.private);
+        }
+        // One TUI for all three, opened on the screen asked for
+        Commands::History { .. } | Commands::Stats { .. } | Commands::Today { .. } => {
+            let mut options = AppOptions {
+                fiscal_start: cli.fiscal_year_start,
+                split: config.history_layout == HistoryLayout::Split,
+                ..Default::default()
+            };
+            let (first, dir) = match command {
+                Commands::History {
+                    relative_time,
+                    dir,
+                    split,
+                } => {
+                    if relative_time {
+                        options.time_display = TimeDisplay::Relative;
+                    }
+                    options.split |= split;
+                    (Screen::History, dir)
+                }
+                Commands::Stats { period, dir, .. } => {
+                    options.period = period;
+                    (Screen::Stats, dir)
+                }
+                _ => (Screen::Today, None),
+            };
+            let entries = load_in_dir(dir.as_deref())?;
+            run_app(entries, first, options)?;
+        }
+        Commands::Search {
+            pattern,
+            fuzzy,
+            interactive,
+            format,
+        } => {
+            let matcher = Matcher::new(&pattern, fuzzy).context("Invalid search pattern")?;
+            let entries = load_history()?;
+            let matches = search(&entries, &matcher);
+            if matches.is_empty() {
+                return Err(anyhow!("No commands match '{}'", pattern)).context(ErrorKind::NoData);
+            }
+            if let Some(format) = format {
+                print!("{}", search_output(&matches, format));
+            } else if interactive {
+                let found: Vec<HistoryEntry> = entries
+                    .into_iter()
+                    .filter(|e| matcher.score(&e.command).is_some())
+                    .collect();
+                let options = AppOptions {
+                    fiscal_start: cli.fiscal_year_start,
+                    ..Default::default()
+                };
+                run_app(found, Screen::History, options)?;
+            } else {
+                print!("{}", search_report(&matches));
+            }
+        }
+        Commands::Top { limit, format } => {
+            let entries = load_history()?;
+            let mut top = top_commands(&entries, limit);
+            if cli.private {
+                top = Privacy::from_env().ranking(top);
+            }
+            if top.is_empty() {
+                return Err(anyhow!("No commands to rank")).context(ErrorKind::NoData);
+            }
+            print!("{}", top_output(&top, format));
+        }
+        Commands::Dirs { limit, format } => {
+            let dirs = directory_stats(&load_history()?, limit);
+            if dirs.is_empty() {
+                return Err(anyhow!("No commands with a recorded directory"))
+                    .context(ErrorKind::NoData);
+            }
+            print!("{}", dirs_output(&dirs, format));
+        }
+        Commands::Projects { limit, format } => {
+            let projects = project_stats(&load_history()?, limit);
+            if projects.is_empty() {
+                return Err(anyhow!("No commands recorded inside a git repository"))
+                    .context(ErrorKind::NoData);
+            }
+            print!("{}", projects_output(&projects, format));
+        }
+        Commands::Compare { a, b } => {
+            let comparison = compare_periods(&load_history()?, a.window(now), b.window(now));
+            if comparison.a.total + comparison.b.total == 0 {
+                return Err(anyhow!("No commands in either period")).context(ErrorKind::NoData);
+            }
+            print!("{}", compare_report(&comparison));
+        }
+        Commands::Jump { limit, format } => {
+            let list = jump_list(&load_history()?, now, limit);
+            if list.is_empty() {
+                return Err(anyhow!("No commands ran in a directory that still exists"))
+                    .context(ErrorKind::NoData);
+            }
+            print!("{}", jump_output(&list, format));
+        }
+        Commands::Streak => {
+            let active = day_counts(&load_history()?);
+            if active.is_empty() {
+                return Err(anyhow!("No commands with a timestamp")).context(ErrorKind::NoData);
+            }
+            print!(
+                "{}",
+                streak_report(&streaks(&active, chrono::Local::now().date_naive()))
+            );
+        }
+        Commands::Favorites => {
+            let favorites = load_favorites(&UserDirs::current()?)?;
+            if favorites.is_empty() {
+                return Err(anyhow!(
+                    "No favorites yet; press b on a command in the history view to pin it"
+                ))
+                .context(ErrorKind::NoData);
+            }
+            print!("{}", favorites_report(&favorites, &load_history()?));
+        }
+        Commands::Summary => {
+            print!("{}", brief_report(&load_history()?, chrono::Local::now()));
+        }
+        Commands::Heatmap { interactive } => {
+            let entries = load_history()?;
+            let active = day_counts(&entries);
+            if active.is_empty() {
+                return Err(anyhow!("No commands with a timestamp")).context(ErrorKind::NoData);
+            }
+            let heatmap = Heatmap::new(&active, chrono::Local::now().date_naive());
+            if interactive {
+                browse_heatmap(&heatmap, &daily_stats(&entries), &entries)?;
+            } else {
+                println!("{}\n", tr!("heatmap-title", total = heatmap.total()));
+                let color = io::stdout().is_terminal();
+                for line in heatmap_lines(&heatmap, color, None) {
+                    println!("{}", line);
+                }
+            }
+        }
+        Commands::Wrapped { year } => {
+            let year = year.unwrap_or_else(|| chrono::Datelike::year(&chrono::Local::now()));
+            let Some(mut recap) = Recap::new(&load_history()?, year) else {
+                return Err(anyhow!("No commands with a timestamp in {}", year))
+                    .context(ErrorKind::NoData);
+            };
+            if cli.private {
+                Privacy::from_env().recap(&mut recap);
+            }
+            let pages = recap_pages(&recap);
+            if io::stdout().is_terminal() {
+                browse_recap(&pages)?;
+            } else {
+                print!("{}", recap_text(&pages));
+            }
+        }
+        Commands::Export {
+            output,
+            format,
+            rollup,
+        } => {
+            let format = ExportFormat::choose(format, output.as_deref())?;
+            if rollup.is_some() && format != ExportFormat::Csv {
+                bail!("--rollup only writes CSV");
+            }
+            let entries = load_history()?;
+            let text = || match (rollup, format) {
+                (Some(rollup), _) => rollup_csv(&entries, rollup),
+                (None, ExportFormat::Json) => entries_json(&entries),
+                _ => entries_csv(&entries),
+            };
+            // SQLite is only chosen for a .db output file
+            match &output {
+                Some(path) if format == ExportFormat::Sqlite => write_sqlite(&entries, path)?,
+                Some(path) => fs::write(path, text())
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None => print!("{}", text()),
+            }
+            if let Some(path) = &output {
+                eprintln!("Exported {} entries to {}", entries.len(), path.display());
+            }
+        }
+        Commands::Snapshot { action } => {
+            let dirs = UserDirs::current()?;
+            let entries = load_stored()?;
+            let entries: Vec<&HistoryEntry> = entries.iter().collect();
+            let now = chrono::Local::now();
+            let roots = ProjectRoots::resolve(entries.iter().copied());
+            let summary = summarize(&entries, &period_bounds(now, -1), &roots);
+            let metrics = Metrics::new(&summary, now.timestamp());
+            match action {
+                SnapshotAction::Save { name } => {
+                    let path = save_metrics(&dirs, &name, &metrics)?;
+                    println!("Saved snapshot '{}' to {}", name, path.display());
+                }
+                SnapshotAction::Diff { name } => {
+                    let then = load_metrics(&dirs, &name)?;
+                    print!("{}", diff_report(&name, &then, &metrics));
+                }
+            }
+        }
+        Commands::Import { file, format } => {
+            let format = ImportFormat::choose(format, &file)?;
+            // Saved as they were read: the redactor, tags, ignore list and
+            // estimated times only change what the views show
+            let entries = read_import(&file, format)?;
+            let log = get_cli_stats_log_path()?;
+            let seeded = seed_log(&log, &UserDirs::current()?)?;
+            if seeded > 0 {
+                println!(
+                    "Started {} with the {} commands of your shell history, which the views read until now",
+                    log.display(),
+                    seeded
+                );
+            }
+            let summary = import_into_log(&log, entries)?;
+            println!(
+                "Added {} commands to {} ({} already there)",
+                summary.added,
+                log.display(),
+                summary.duplicates
+            );
+            if summary.undated > 0 {
+                println!(
+                    "Skipped {} commands without a timestamp, which can't be told apart from ones imported before",
+                    summary.undated
+                );
+            }
+        }
+        Commands::DiffSources { file, format } => {
+            let format = ImportFormat::choose(format, &file)?;
+            let there = in_range(read_import(&file, format)?);
+            let here = load_history()?;
+            if here.is_empty() || there.is_empty() {
+                return Err(anyhow!("No commands to compare on one side"))
+                    .context(ErrorKind::NoData);
+            }
+            print!(
+                "{}",
+                source_diff_report(&diff_sources(&here, &there), &file.display().to_string())
+            );
+        }
+        Commands::Ingest { watch: true } => {
+            let dirs = UserDirs::current()?;
+            let interval = ingest_interval();
+            println!(
+                "Importing into {} every {}s",
+                store_path(&dirs).display(),
+                interval.as_secs()
+            );
+            // Only files with new commands are mentioned, and pausing and
+            // resuming once each. A failed import, e.g. while another
+            // process holds the database, is tried again next time.
+            let mut paused = false;
+            loop {
+                match battery_level().filter(|&level| level < min_battery()) {
+                    Some(level) => {
+                        if !paused {
+                            println!("Paused on battery at {}%", level);
+                        }
+                        paused = true;
+                    }
+                    None => {
+                        if paused {
+                            println!("Resumed");
+                        }
+                        paused = false;
+                        match ingest_all(&dirs) {
+                            Ok(imported) => {
+                                for (path, count) in imported {
+                                    if count > 0 {
+                                        println!("{}: {} new commands", path.display(), count);
+                                    }
+                                }
+                            }
+                            Err(err) => eprintln!(
+                                "Import failed, trying again in {}s: {:#}",
+                                interval.as_secs(),
+                                err
+                            ),
+                        }
+                    }
+                }
+                std::thread::sleep(interval);
+            }
+        }
+        Commands::Ingest { watch: false } => {
+            let dirs = UserDirs::current()?;
+            let imported = ingest_all(&dirs)?;
+            if imported.is_empty() {
+                return Err(anyhow!("No history files found to import")).context(ErrorKind::NoData);
+            }
+            for (path, count) in imported {
+                println!("{}: {} new commands", path.display(), count);
+            }
+            println!("Saved to {}", store_path(&dirs).display());
+        }
+        Commands::Doctor => {
+            let check = hook_check(&UserDirs::current()?);
+            print!("{}", doctor_report(check.as_ref(), stale_hours()));
+        }
+        Commands::Config { action } => match action {
+            ConfigAction::Get { key } => match config::read_setting(&key)? {
+                Some(value) => println!("{}", value),
+                None => bail!("`{}` isn't set", key),
+            },
+            ConfigAction::Set { key, value } => {
+                let path = config::write_setting(&key, &value)?;
+                println!("Set {} in {}", key, path.display());
+            }
+            ConfigAction::Path => {
+                let path = config::config_path()
+                    .ok_or_else(|| anyhow!("Could not find home directory"))?;
+                println!("{}", path.display());
+            }
+        },
+        Commands::Init {
+            shell,
+            git_head,
+            context_command,
+        } => {
+            if shell != Shell::Zsh {
+                bail!("init only supports zsh so far");
+            }
+            let program =
+                std::env::current_exe().context("Could not find the cli-wrapped executable")?;
+            print!(
+                "{}",
+                zsh_hook(
+                    &program.to_string_lossy(),
+                    git_head,
+                    context_command.as_deref()
+                )
+            );
+        }
+        Commands::Record {
+            cmd,
+            cwd,
+            start,
+            duration,
+            exit,
+            session,
+            commit,
+            context,
+        } => {
+            // Nothing to record for a blank prompt
+            if cmd.trim().is_empty() {
+                return Ok(());
+            }
+            // The hook passes whatever the context command printed, nothing
+            // included. A label that isn't one is left out rather than
+            // losing the command, since no one sees the hook's errors.
+            let context = context
+                .map(|label| label.trim().to_string())
+                .filter(|label| !label.is_empty());
+            let context = match context {
+                Some(label) if !is_context_label(&label) => {
+                    eprintln!(
+                        "Left out context '{}': labels can only use letters, digits, '-', '_' and '.'",
+                        label
+                    );
+                    None
+                }
+                context => context,
+            };
+            let entry = HistoryEntry {
+                timestamp: start.unwrap_or_else(|| chrono::Local::now().timestamp()),
+                command: cmd,
+                directory: cwd,
+                duration,
+                exit_code: exit,
+                context,
+                session_id: session,
+                // The hook passes an empty HEAD outside a repository
+                commit: commit.filter(|sha| !sha.is_empty()),
+                host: None,
+                recorded_timestamp: None,
+            };
+            append_record(&get_cli_stats_log_path()?, &entry)?;
+        }
+        Commands::Timeline { command } => {
+            let entries = load_history()?;
+            let width = crossterm::terminal::size().map_or(80, |(width, _)| width as usize);
+            for line in timeline_lines(&entries, &command, width, 8) {
+                println!("{}", line);
+            }
+        }
+        Commands::Report { period, report } => {
+            let in_period = |mut entries: Vec<HistoryEntry>| {
+                if let Some(quarter) = period {
+                    let (start, end) = quarter.bounds(cli.fiscal_year_start);
+                    entries.retain(|e| e.timestamp >= start && e.timestamp <= end);
+                }
+                entries
+            };
+            let output = match report {
+                ReportKind::Loops => loops_report(&in_period(load_history()?)),
+                ReportKind::Branches => branches_report(&in_period(load_history()?)),
+                ReportKind::Retries => retries_report(&in_period(load_history()?)),
+                ReportKind::Sessions => sessions_report(&in_period(load_history()?)),
+                ReportKind::Panes => panes_report(&in_period(load_history()?)),
+                ReportKind::Commit { commit } => {
+                    commit_report(&in_period(load_history()?), &commit)
+                }
+                ReportKind::Users { homes, anonymize } => {
+                    // Users without readable history still get a row, saying why
+                    let users: Vec<(String, Result<Vec<HistoryEntry>, String>)> = homes
+                        .into_iter()
+                        .map(|home| {
+                            let user = home.file_name().map_or_else(
+                                || home.display().to_string(),
+                                |name| name.to_string_lossy().into_owned(),
+                            );
+                            let dirs = UserDirs::at(home);
+                            let entries =
+                                user_history(&dirs, source, shell, history_file.as_deref())
+                                    .map(|entries| in_period(in_range(entries)))
+                                    .map_err(|err| err.root_cause().to_string());
+                            (user, entries)
+                        })
+                        .collect();
+                    users_report(&users, anonymize)
+                }
+            };
+            print!("{}", output);
+        }
+        Commands::Suggest { .. } => {
+            // --functions is the only kind of suggestion so far
+            let entries = load_history()?;
+            print!("{}", functions_report(&entries));
+        }
+        Commands::Purge { matching, yes, .. } => {
+            // --all and --matching are mutually exclusive
+            let target = match matching {
+                Some(pattern) => PurgeTarget::Matching(
+                    Regex::new(&pattern).context("Invalid --matching pattern")?,
+                ),
+                None => PurgeTarget::All,
+            };
+            if !yes && !confirm_purge(&target)? {
+                println!("Nothing deleted.");
+                return Ok(());
+            }
+            print!("{}", format_summary(&purge(&target)?));
+        }
+        Commands::Scrub { pattern, yes } => {
+            let target = PurgeTarget::Commands(Regex::new(&pattern).context("Invalid pattern")?);
+            if !yes && !confirm_purge(&target)? {
+                println!("Nothing deleted.");
+                return Ok(());
+            }
+            print!("{}", format_summary(&purge(&target)?));
         }
     }
 
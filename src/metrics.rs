@@ -0,0 +1,344 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::aggregate::StatsSummary;
+use crate::error::ErrorKind;
+use crate::history::UserDirs;
+use crate::report::format_time;
+
+// Entries of each ranking kept in a snapshot
+const KEPT: usize = 25;
+
+// Rows of each ranking compared by `diff_report`
+const COMPARED: usize = 10;
+
+// The aggregate numbers saved by `snapshot save`, to compare against later
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metrics {
+    pub saved_at: i64,
+    pub total: usize,
+    pub unique: usize,
+    pub per_day: f64,
+    pub commands: Vec<(String, usize)>,
+    pub categories: Vec<(String, usize)>,
+}
+
+impl Metrics {
+    pub fn new(summary: &StatsSummary, saved_at: i64) -> Metrics {
+        let top = |counts: &[(String, usize)]| counts.iter().take(KEPT).cloned().collect();
+        Metrics {
+            saved_at,
+            total: summary.total,
+            unique: summary.unique,
+            per_day: summary.per_day(),
+            commands: top(&summary.commands),
+            categories: top(&summary.categories),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let ranking = |counts: &[(String, usize)]| -> Value {
+            counts
+                .iter()
+                .map(|(name, count)| json!({ "name": name, "count": count }))
+                .collect()
+        };
+        json!({
+            "saved_at": self.saved_at,
+            "total": self.total,
+            "unique": self.unique,
+            "per_day": self.per_day,
+            "commands": ranking(&self.commands),
+            "categories": ranking(&self.categories),
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Metrics> {
+        let ranking = |key: &str| -> Option<Vec<(String, usize)>> {
+            value[key]
+                .as_array()?
+                .iter()
+                .map(|row| {
+                    Some((
+                        row["name"].as_str()?.to_string(),
+                        row["count"].as_u64()? as usize,
+                    ))
+                })
+                .collect()
+        };
+        Some(Metrics {
+            saved_at: value["saved_at"].as_i64()?,
+            total: value["total"].as_u64()? as usize,
+            unique: value["unique"].as_u64()? as usize,
+            per_day: value["per_day"].as_f64()?,
+            commands: ranking("commands")?,
+            categories: ranking("categories")?,
+        })
+    }
+}
+
+// Percentage of all commands that `name` accounts for, if it was ranked
+fn metrics_share(
+    metrics: &Metrics,
+    ranking: fn(&Metrics) -> &[(String, usize)],
+    name: &str,
+) -> Option<f64> {
+    ranking(metrics)
+        .iter()
+        .find(|(ranked, _)| ranked == name)
+        .map(|(_, count)| *count as f64 * 100.0 / metrics.total.max(1) as f64)
+}
+
+pub fn snapshots_dir(dirs: &UserDirs) -> PathBuf {
+    dirs.data.join("cli-tracker/snapshots")
+}
+
+// Names become file names, so they're kept to something safe for one
+fn snapshot_path(dirs: &UserDirs, name: &str) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        bail!(
+            "Snapshot names can only use letters, digits, '-', '_' and '.', got '{}'",
+            name
+        );
+    }
+    Ok(snapshots_dir(dirs).join(format!("{}.json", name)))
+}
+
+// Save under `name`, replacing any snapshot already called that
+pub fn save_metrics(dirs: &UserDirs, name: &str, metrics: &Metrics) -> Result<PathBuf> {
+    let path = snapshot_path(dirs, name)?;
+    let dir = snapshots_dir(dirs);
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    fs::write(&path, format!("{:#}\n", metrics.to_json()))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+// Names of the saved snapshots, alphabetically
+pub fn saved_names(dirs: &UserDirs) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(snapshots_dir(dirs))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|file| {
+            let path = file.path();
+            let is_json = path
+                .extension()
+                .is_some_and(|extension| extension == "json");
+            is_json.then(|| path.file_stem()?.to_str().map(str::to_string))?
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+pub fn load_metrics(dirs: &UserDirs, name: &str) -> Result<Metrics> {
+    let path = snapshot_path(dirs, name)?;
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            let names = saved_names(dirs);
+            let saved = if names.is_empty() {
+                "none saved yet".to_string()
+            } else {
+                format!("saved: {}", names.join(", "))
+            };
+            return Err(anyhow!("No snapshot named '{}' ({})", name, saved))
+                .context(ErrorKind::NoData);
+        }
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    parse_metrics(&source, &path)
+}
+
+// Drop the commands `removes` picks from every saved snapshot, for `purge`.
+// The totals stay as they were. Returns how many were dropped and how many
+// are left.
+pub fn forget_commands(dirs: &UserDirs, removes: impl Fn(&str) -> bool) -> Result<(usize, usize)> {
+    let (mut removed, mut kept) = (0, 0);
+    for name in saved_names(dirs) {
+        let mut metrics = load_metrics(dirs, &name)?;
+        let before = metrics.commands.len();
+        metrics.commands.retain(|(command, _)| !removes(command));
+        kept += metrics.commands.len();
+        if metrics.commands.len() < before {
+            removed += before - metrics.commands.len();
+            save_metrics(dirs, &name, &metrics)?;
+        }
+    }
+    Ok((removed, kept))
+}
+
+fn parse_metrics(source: &str, path: &Path) -> Result<Metrics> {
+    serde_json::from_str(source)
+        .ok()
+        .and_then(|value| Metrics::from_json(&value))
+        .ok_or_else(|| anyhow!("{} is not a saved snapshot", path.display()))
+        .context(ErrorKind::Parse)
+}
+
+fn signed(change: f64, precision: usize) -> String {
+    format!("{:+.*}", precision, change)
+}
+
+fn shares_table(
+    out: &mut String,
+    title: &str,
+    then: &Metrics,
+    now: &Metrics,
+    ranking: fn(&Metrics) -> &[(String, usize)],
+) {
+    // Everything in either top list, biggest shift first
+    let mut names: Vec<&str> = Vec::new();
+    for (name, _) in ranking(now)
+        .iter()
+        .take(COMPARED)
+        .chain(ranking(then).iter().take(COMPARED))
+    {
+        if !names.contains(&name.as_str()) {
+            names.push(name);
+        }
+    }
+    let mut rows: Vec<(&str, Option<f64>, Option<f64>)> = names
+        .into_iter()
+        .map(|name| {
+            let share = |metrics| metrics_share(metrics, ranking, name);
+            (name, share(then), share(now))
+        })
+        .collect();
+    let change = |row: &(&str, Option<f64>, Option<f64>)| {
+        (row.2.unwrap_or(0.0) - row.1.unwrap_or(0.0)).abs()
+    };
+    rows.sort_by(|a, b| change(b).total_cmp(&change(a)));
+    rows.truncate(COMPARED);
+    if rows.is_empty() {
+        return;
+    }
+
+    let width = rows
+        .iter()
+        .map(|(name, _, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let _ = writeln!(out, "\n{} (share of all commands)", title);
+    let percent = |share: Option<f64>| share.map_or("-".to_string(), |s| format!("{:.1}%", s));
+    for (name, before, after) in rows {
+        let _ = writeln!(
+            out,
+            "  {:<width$}  {:>6}  {:>6}  {:>6}",
+            name,
+            percent(before),
+            percent(after),
+            signed(after.unwrap_or(0.0) - before.unwrap_or(0.0), 1),
+            width = width
+        );
+    }
+}
+
+// What changed between a saved snapshot and now
+pub fn diff_report(name: &str, then: &Metrics, now: &Metrics) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Since snapshot '{}' ({})\n",
+        name,
+        format_time(then.saved_at)
+    );
+    let _ = writeln!(
+        out,
+        "{:<10}  {:>8}  {:>8}  {:>8}",
+        "", "Then", "Now", "Change"
+    );
+    for (label, before, after) in [
+        ("Commands", then.total, now.total),
+        ("Unique", then.unique, now.unique),
+    ] {
+        let _ = writeln!(
+            out,
+            "{:<10}  {:>8}  {:>8}  {:>8}",
+            label,
+            before,
+            after,
+            signed(after as f64 - before as f64, 0)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{:<10}  {:>8.1}  {:>8.1}  {:>8}",
+        "Per day",
+        then.per_day,
+        now.per_day,
+        signed(now.per_day - then.per_day, 1)
+    );
+    shares_table(&mut out, "Most used commands", then, now, |m| &m.commands);
+    shares_table(&mut out, "Categories", then, now, |m| &m.categories);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+
+    fn metrics(total: usize, commands: &[(&str, usize)]) -> Metrics {
+        Metrics {
+            saved_at: 1_700_000_000,
+            total,
+            unique: commands.len(),
+            per_day: 10.0,
+            commands: commands
+                .iter()
+                .map(|(name, count)| (name.to_string(), *count))
+                .collect(),
+            categories: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn round_trips_and_compares_shares() {
+        let then = metrics(100, &[("git status", 50), ("ls", 20)]);
+        let parsed = parse_metrics(&then.to_json().to_string(), Path::new("x")).unwrap();
+        assert_eq!(parsed, then);
+        assert!(parse_metrics("{}", Path::new("x")).is_err());
+
+        let now = metrics(200, &[("git status", 60), ("cargo test", 50)]);
+        let report = diff_report("before", &then, &now);
+        assert!(report.contains("Commands         100       200      +100\n"));
+        // git status fell from half to under a third; ls dropped out
+        assert!(report.contains("  git status   50.0%   30.0%   -20.0\n"));
+        assert!(report.contains("  cargo test       -   25.0%   +25.0\n"));
+        assert!(report.contains("  ls           20.0%       -   -20.0\n"));
+    }
+
+    #[test]
+    fn names_must_be_plain_file_names() {
+        let dirs = UserDirs::at(PathBuf::from("/home/u"));
+        assert!(snapshot_path(&dirs, "before-move_2").is_ok());
+        assert!(snapshot_path(&dirs, "../x").is_err());
+        assert!(snapshot_path(&dirs, "").is_err());
+    }
+
+    #[test]
+    fn forgotten_commands_leave_every_snapshot() {
+        let home = ScratchDir::new("metrics");
+        let dirs = UserDirs::at(home.to_path_buf());
+        let saved = metrics(100, &[("git status", 50), ("export TOKEN=hunter2", 20)]);
+        save_metrics(&dirs, "before", &saved).unwrap();
+        save_metrics(&dirs, "after", &metrics(10, &[("ls", 10)])).unwrap();
+
+        let forgotten = forget_commands(&dirs, |command| command.contains("hunter2")).unwrap();
+        assert_eq!(forgotten, (1, 2));
+        let before = load_metrics(&dirs, "before").unwrap();
+        assert_eq!(before.commands, [("git status".to_string(), 50)]);
+        assert_eq!(before.total, 100);
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use std::{borrow::Cow, sync::OnceLock};
+
+use crate::config::config;
+use crate::error::ErrorKind;
+
+// One `PATTERN => REPLACEMENT` rule
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: Regex,
+    replacement: String,
+}
+
+// Rewrites applied to commands before they are counted, so that e.g. every
+// `git commit -m ...` ranks as one command
+#[derive(Debug, Clone, Default)]
+pub struct Rules {
+    rules: Vec<Rule>,
+}
+
+impl Rules {
+    // Each rule in turn, on what the one before left. The replacement can
+    // refer to capture groups as `$1` or `${name}`.
+    pub fn apply<'a>(&self, command: &'a str) -> Cow<'a, str> {
+        let mut command = Cow::Borrowed(command);
+        for rule in &self.rules {
+            if let Cow::Owned(replaced) = rule
+                .pattern
+                .replace_all(&command, rule.replacement.as_str())
+            {
+                command = Cow::Owned(replaced);
+            }
+        }
+        command
+    }
+}
+
+// `PATTERN => REPLACEMENT` rules from the config's [normalize] table
+pub fn parse_rules(rules: &[String]) -> Result<Rules> {
+    let rules = rules
+        .iter()
+        .map(|rule| {
+            let (pattern, replacement) = rule
+                .split_once(" => ")
+                .ok_or_else(|| anyhow!("Expected `PATTERN => REPLACEMENT`, not `{}`", rule))
+                .context(ErrorKind::Parse)?;
+            let pattern = Regex::new(pattern.trim_end())
+                .with_context(|| format!("Invalid normalize pattern {}", pattern))?;
+            Ok(Rule {
+                pattern,
+                replacement: replacement.trim_start().to_string(),
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Rules { rules })
+}
+
+static RULES: OnceLock<Rules> = OnceLock::new();
+
+// Built from the loaded config, whose rules were checked when it was read
+pub fn rules() -> &'static Rules {
+    RULES.get_or_init(|| parse_rules(&config().normalize).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_apply_in_order() {
+        let rules = parse_rules(&[
+            "^git commit -m .* => git commit -m <msg>".to_string(),
+            r"(^| )/\S+ => $1<path>".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(
+            rules.apply(r#"git commit -m "fix /tmp handling""#),
+            "git commit -m <msg>"
+        );
+        assert_eq!(rules.apply("ls -la /var/log /etc"), "ls -la <path> <path>");
+        assert!(matches!(rules.apply("make"), Cow::Borrowed("make")));
+
+        assert!(parse_rules(&["just a pattern".to_string()]).is_err());
+        assert!(parse_rules(&["( => x".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use crate::aggregate::{StatsSummary, TimePatterns};
+use crate::report::{dirs_report, format_time, projects_report, top_report, DirectoryStats};
+use crate::search::{search_report, SearchMatch};
+
+// How `stats`, `today`, `top`, `dirs`, `projects` and `search` print their
+// results when asked to instead of opening the TUI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+// Longest ranking printed as JSON or CSV. Every unique command would make
+// the output as large as the history.
+const RANKED: usize = 100;
+
+// Rows of each ranking in the plain table
+const TABLE_ROWS: usize = 10;
+
+// Weekday keys stay in English whatever the locale, for scripts
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+// Quoted when it holds a comma, quote or line break, as RFC 4180 has it
+pub fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+fn ranking_json(counts: &[(String, usize)], key: &str) -> Value {
+    counts
+        .iter()
+        .take(RANKED)
+        .map(|(name, count)| json!({ key: name, "count": count }))
+        .collect()
+}
+
+// The ranked commands from `top`
+pub fn top_output(top: &[(String, usize)], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Table => top_report(top),
+        OutputFormat::Json => format!("{:#}\n", ranking_json(top, "command")),
+        OutputFormat::Csv => {
+            let mut out = String::from("command,count\n");
+            for (command, count) in top {
+                let _ = writeln!(out, "{},{}", csv_field(command), count);
+            }
+            out
+        }
+    }
+}
+
+// The directories from `dirs`
+pub fn dirs_output(dirs: &[DirectoryStats], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Table => dirs_report(dirs),
+        _ => ranked_dirs_output(dirs, "directory", format),
+    }
+}
+
+// The repositories from `projects`
+pub fn projects_output(projects: &[DirectoryStats], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Table => projects_report(projects),
+        _ => ranked_dirs_output(projects, "project", format),
+    }
+}
+
+// JSON or CSV for either, with the path under `key`
+fn ranked_dirs_output(dirs: &[DirectoryStats], key: &str, format: OutputFormat) -> String {
+    if format == OutputFormat::Csv {
+        let mut out = format!("{},commands,unique,top_command,top_count\n", key);
+        for d in dirs {
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{}",
+                csv_field(&d.directory),
+                d.commands,
+                d.unique,
+                csv_field(&d.top.0),
+                d.top.1
+            );
+        }
+        return out;
+    }
+    let dirs: Vec<Value> = dirs
+        .iter()
+        .map(|d| {
+            json!({
+                key: d.directory,
+                "commands": d.commands,
+                "unique": d.unique,
+                "top_command": d.top.0,
+                "top_count": d.top.1,
+            })
+        })
+        .collect();
+    format!("{:#}\n", Value::Array(dirs))
+}
+
+// The matches from `search`. Last runs are Unix timestamps, 0 when unknown.
+pub fn search_output(matches: &[SearchMatch], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Table => search_report(matches),
+        OutputFormat::Json => {
+            let matches: Vec<Value> = matches
+                .iter()
+                .map(|m| {
+                    json!({
+                        "command": m.command,
+                        "count": m.count,
+                        "last_run": m.last_run,
+                        "score": m.score,
+                    })
+                })
+                .collect();
+            format!("{:#}\n", Value::Array(matches))
+        }
+        OutputFormat::Csv => {
+            let mut out = String::from("command,count,last_run,score\n");
+            for m in matches {
+                let _ = writeln!(
+                    out,
+                    "{},{},{},{}",
+                    csv_field(&m.command),
+                    m.count,
+                    m.last_run,
+                    m.score
+                );
+            }
+            out
+        }
+    }
+}
+
+// A ranking's section name, the key its names go under in JSON, and its
+// counts
+type Ranking<'a> = (&'static str, &'static str, &'a [(String, usize)]);
+
+// The rankings of a summary by the names JSON and CSV give them
+fn rankings(summary: &StatsSummary) -> [Ranking<'_>; 7] {
+    [
+        ("commands", "command", &summary.commands),
+        ("categories", "category", &summary.categories),
+        ("directories", "directory", &summary.directories),
+        ("projects", "project", &summary.projects),
+        ("pipeline_tools", "tool", &summary.pipeline_tools),
+        ("edited_extensions", "extension", &summary.edited_extensions),
+        ("contexts", "context", &summary.contexts),
+    ]
+}
+
+// What the stats and today views show for `view`. JSON is one object and
+// CSV one `section,name,count` row per figure.
+pub fn summary_output(
+    view: &str,
+    summary: &StatsSummary,
+    patterns: &TimePatterns,
+    format: OutputFormat,
+) -> String {
+    match format {
+        OutputFormat::Table => summary_table(view, summary, patterns),
+        OutputFormat::Json => {
+            let timestamp = |ts: i64| if ts > 0 { json!(ts) } else { Value::Null };
+            let mut object = json!({
+                "view": view,
+                "total": summary.total,
+                "unique": summary.unique,
+                "with_timestamps": summary.with_timestamps,
+                "first": timestamp(summary.oldest),
+                "last": timestamp(summary.newest),
+                "today": summary.today,
+                "this_week": summary.this_week,
+                "this_month": summary.this_month,
+                "hours": patterns.hour_counts,
+            });
+            let weekdays: Map<String, Value> = WEEKDAYS
+                .iter()
+                .zip(patterns.day_of_week_counts)
+                .map(|(day, count)| (day.to_string(), json!(count)))
+                .collect();
+            object["weekdays"] = Value::Object(weekdays);
+            for (name, key, counts) in rankings(summary) {
+                object[name] = ranking_json(counts, key);
+            }
+            format!("{:#}\n", object)
+        }
+        OutputFormat::Csv => {
+            let mut out = String::from("section,name,count\n");
+            for (name, count) in [
+                ("total", summary.total),
+                ("unique", summary.unique),
+                ("with_timestamps", summary.with_timestamps),
+                ("today", summary.today),
+                ("this_week", summary.this_week),
+                ("this_month", summary.this_month),
+            ] {
+                let _ = writeln!(out, "totals,{},{}", name, count);
+            }
+            for (hour, count) in patterns.hour_counts.iter().enumerate() {
+                let _ = writeln!(out, "hours,{:02},{}", hour, count);
+            }
+            for (day, count) in WEEKDAYS.iter().zip(patterns.day_of_week_counts) {
+                let _ = writeln!(out, "weekdays,{},{}", day, count);
+            }
+            for (section, _, counts) in rankings(summary) {
+                for (name, count) in counts.iter().take(RANKED) {
+                    let _ = writeln!(out, "{},{},{}", section, csv_field(name), count);
+                }
+            }
+            out
+        }
+    }
+}
+
+fn summary_table(view: &str, summary: &StatsSummary, patterns: &TimePatterns) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}\n", view);
+    let _ = writeln!(out, "Commands    {}", summary.total);
+    let _ = writeln!(out, "Unique      {}", summary.unique);
+    if summary.oldest > 0 {
+        let _ = writeln!(out, "First       {}", format_time(summary.oldest));
+        let _ = writeln!(out, "Last        {}", format_time(summary.newest));
+    }
+    // The three busiest hours, busiest first
+    let mut hours: Vec<(usize, usize)> = patterns
+        .hour_counts
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(_, count)| count > 0)
+        .collect();
+    hours.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    if !hours.is_empty() {
+        let peaks: Vec<String> = hours
+            .iter()
+            .take(3)
+            .map(|(hour, count)| format!("{:02}:00 ({})", hour, count))
+            .collect();
+        let _ = writeln!(out, "Peak hours  {}", peaks.join(", "));
+    }
+    for (title, counts) in [
+        ("Most used commands", &summary.commands),
+        ("Categories", &summary.categories),
+        ("Directories", &summary.directories),
+    ] {
+        if counts.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "\n{}", title);
+        let shown = &counts[..counts.len().min(TABLE_ROWS)];
+        for line in top_report(shown).lines() {
+            let _ = writeln!(out, "  {}", line);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_carry_the_same_rows() {
+        let top = vec![
+            ("git status".to_string(), 3),
+            ("echo \"a, b\"".to_string(), 1),
+        ];
+        assert_eq!(
+            top_output(&top, OutputFormat::Csv),
+            "command,count\ngit status,3\n\"echo \"\"a, b\"\"\",1\n"
+        );
+        let json: Value = serde_json::from_str(&top_output(&top, OutputFormat::Json)).unwrap();
+        assert_eq!(json[1]["command"], "echo \"a, b\"");
+        assert_eq!(json[0]["count"], 3);
+
+        let summary = StatsSummary {
+            total: 4,
+            unique: 2,
+            commands: top,
+            ..Default::default()
+        };
+        let mut patterns = TimePatterns::default();
+        patterns.hour_counts[9] = 4;
+        patterns.day_of_week_counts[4] = 4;
+        let json: Value = serde_json::from_str(&summary_output(
+            "all-time",
+            &summary,
+            &patterns,
+            OutputFormat::Json,
+        ))
+        .unwrap();
+        assert_eq!(json["total"], 4);
+        assert_eq!(json["first"], Value::Null);
+        assert_eq!(json["hours"][9], 4);
+        assert_eq!(json["weekdays"]["fri"], 4);
+        assert_eq!(json["commands"][0]["command"], "git status");
+
+        let table = summary_output("all-time", &summary, &patterns, OutputFormat::Table);
+        assert!(table.contains("\nPeak hours  09:00 (4)\n"));
+        assert!(table.contains("\nMost used commands\n  3  git status\n"));
+
+        let csv = summary_output("all-time", &summary, &patterns, OutputFormat::Csv);
+        assert!(csv.starts_with("section,name,count\ntotals,total,4\n"));
+        assert!(csv.contains("\nhours,09,4\n"));
+        assert!(csv.contains("\ncommands,git status,3\n"));
+    }
+}
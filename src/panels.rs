@@ -0,0 +1,301 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Cell, Padding, Paragraph, Row, Table};
+use unicode_width::UnicodeWidthStr;
+
+use crate::aggregate::{DataQuality, StatsSummary, TimePatterns};
+use crate::goals::{progress_bar, Goal, Limit};
+use crate::i18n::{weekday_initial, weekday_name};
+use crate::presentation::project_alias;
+use crate::render::{labeled, titled, Canvas};
+use crate::tr;
+use crate::ui_utils::{quality_title, truncate_end, truncate_middle};
+
+// Where the stats and today screens put each panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PanelAreas {
+    pub header: Rect,
+    pub general: Rect,
+    // Left out of the compact layout
+    pub rankings: Option<Rect>,
+    pub directories: Option<Rect>,
+    pub commands: Rect,
+    pub patterns: Rect,
+    pub goals: Rect,
+}
+
+// The header row, then rows of the given heights: general statistics beside
+// the rankings, directories beside commands, time patterns and goals. The
+// compact layout keeps only the left-hand general box, at full width, and
+// gives the commands the whole row.
+pub fn panel_areas(
+    screen: Rect,
+    [top, middle, bottom, goals]: [u16; 4],
+    compact: bool,
+) -> PanelAreas {
+    let [header, top, middle, patterns, goals] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(top),
+        Constraint::Length(middle),
+        Constraint::Length(bottom),
+        Constraint::Length(goals),
+    ])
+    .areas(screen);
+    let halves = Layout::horizontal([Constraint::Length(screen.width / 2), Constraint::Fill(1)]);
+    if compact {
+        return PanelAreas {
+            header,
+            general: top,
+            rankings: None,
+            directories: None,
+            commands: middle,
+            patterns,
+            goals,
+        };
+    }
+    let [general, rankings] = halves.areas(top);
+    let [directories, commands] = halves.areas(middle);
+    PanelAreas {
+        header,
+        general,
+        rankings: Some(rankings),
+        directories: Some(directories),
+        commands,
+        patterns,
+        goals,
+    }
+}
+
+// The view's name on the left of the header row, the controls in the middle
+// and the command count on the right
+pub fn render_header(canvas: &mut Canvas, area: Rect, title: &str, controls: &str, count: &str) {
+    canvas.render(Paragraph::new(title.light_cyan().bold()), area);
+    canvas.render(Paragraph::new(count.light_cyan()).right_aligned(), area);
+    // Controls shift right rather than cover the view name, and go on last so
+    // a long save message stays readable
+    let start = (area.width / 2)
+        .saturating_sub(controls.width() as u16 / 2)
+        .max(title.width() as u16 + 2);
+    let controls_area = Rect {
+        x: area.x + start.min(area.width),
+        width: area.width.saturating_sub(start),
+        ..area
+    };
+    canvas.render(Paragraph::new(controls.dark_gray()), controls_area);
+}
+
+// General Statistics, one labeled row each
+pub fn general_panel(rows: &[(String, String)]) -> Paragraph<'_> {
+    let lines: Vec<Line> = rows
+        .iter()
+        .map(|(key, value)| labeled(key, value.as_str()))
+        .collect();
+    Paragraph::new(lines).block(titled(&tr!("box-general")).padding(padding()))
+}
+
+// The first `limit` of a ranking, with directories shown as project
+// aliases when presenting, as the directory boxes show them
+pub fn ranking_rows(
+    counts: &[(String, usize)],
+    limit: usize,
+    directories: bool,
+    presenting: bool,
+) -> Vec<(String, usize)> {
+    counts
+        .iter()
+        .take(limit)
+        .map(|(name, count)| match directories && presenting {
+            true => (project_alias(name), *count),
+            false => (name.clone(), *count),
+        })
+        .collect()
+}
+
+// The rankings cycled with Tab: each name with a bar for its share, or just
+// its count when there are too few commands for shares to mean much
+pub fn ranking_panel<'a>(
+    title: &str,
+    shown: &'a [(String, usize)],
+    summary: &StatsSummary,
+    quality: DataQuality,
+    approx: &str,
+    width: u16,
+) -> Paragraph<'a> {
+    // Names get at least 10 columns, and up to a third of the box for longer
+    // ones like directory paths
+    let name_width = shown
+        .iter()
+        .map(|(name, _)| name.width())
+        .max()
+        .unwrap_or(0)
+        .clamp(10, (width as usize / 3).max(10));
+    let max_bar_width = (width as usize).saturating_sub(name_width + 10);
+    let lines: Vec<Line> = shown
+        .iter()
+        .map(|(name, count)| {
+            let name = truncate_end(name, name_width);
+            let padding = " ".repeat(name_width.saturating_sub(name.width()));
+            let value = if quality.shares_reliable() {
+                let percentage = summary.percentage(*count);
+                let bar_width = (percentage * max_bar_width / 100).min(max_bar_width);
+                format!("{} {}{}%", "█".repeat(bar_width), approx, percentage)
+            } else {
+                format!("{}{}", approx, count)
+            };
+            Line::from(format!("{}{} {}", name, padding, value))
+        })
+        .collect();
+    Paragraph::new(lines)
+        .block(titled(&quality_title(title, quality.shares_reliable())).padding(padding()))
+}
+
+// Numbered names with their counts lined up on the right, the selected one
+// highlighted, and a hint on the bottom border if given
+pub fn ranked_list<'a>(
+    title: &str,
+    rows: impl IntoIterator<Item = (String, String)>,
+    selected: Option<usize>,
+    hint: Option<&str>,
+) -> Table<'a> {
+    let rows = rows.into_iter().enumerate().map(|(i, (name, count))| {
+        let name = Cell::from(format!("{:2}. {} ", i + 1, name));
+        let name = if selected == Some(i) {
+            name.style(Style::new().bg(Color::Blue).bold())
+        } else {
+            name
+        };
+        Row::new([name, Cell::from(count.dark_gray())])
+    });
+    let mut block = titled(title).padding(padding());
+    if let Some(hint) = hint {
+        block = block.title_bottom(Line::from(hint.to_string()).dark_gray());
+    }
+    Table::new(rows, [Constraint::Fill(1), Constraint::Length(8)]).block(block)
+}
+
+// The width a ranked list's names may take in a box `width` wide: less the
+// borders, padding, number and count column
+pub fn ranked_name_width(width: u16) -> usize {
+    width.saturating_sub(18) as usize
+}
+
+// Peak hour and day, and how the week splits up
+pub fn time_patterns_panel(
+    patterns: &TimePatterns,
+    quality: DataQuality,
+    approx: &str,
+) -> Paragraph<'static> {
+    let peak_hour = match patterns.peak_hour() {
+        Some((hour, count)) if count > 0 => tr!(
+            "peak-hour",
+            hour = format!("{:02}:00", hour),
+            count = format!("{}{}", approx, count)
+        ),
+        _ => tr!("peak-hour-none"),
+    };
+    let peak_day = match patterns.peak_day() {
+        Some((day, count)) if count > 0 => tr!(
+            "peak-day",
+            day = weekday_name(day),
+            count = format!("{}{}", approx, count)
+        ),
+        _ => tr!("peak-day-none"),
+    };
+    // Percentages are adjusted to add up to exactly 100%. With few
+    // timestamps the counts are shown instead.
+    let percentages = patterns.day_percentages();
+    let mut distribution = vec![Span::from(format!("{:<19}", tr!("day-distribution") + " "))];
+    for (i, &pct) in percentages.iter().enumerate() {
+        let day = if quality.timing_reliable() {
+            format!("{}:{}%", weekday_initial(i), pct)
+        } else {
+            format!("{}:{}", weekday_initial(i), patterns.day_of_week_counts[i])
+        };
+        distribution.push(Span::from(format!("{:<7}", day)));
+    }
+    let lines = vec![
+        Line::from(peak_hour),
+        Line::from(peak_day),
+        Line::from(distribution),
+    ];
+    Paragraph::new(lines).block(
+        titled(&quality_title(
+            &tr!("box-time-patterns"),
+            quality.timing_reliable(),
+        ))
+        .padding(padding()),
+    )
+}
+
+// Each weekly goal with a bar toward it: green once met, yellow while an
+// at-least goal is short and red once an at-most limit is broken
+pub fn goals_panel(week: u32, goals: &[(&Goal, usize)], width: u16) -> Paragraph<'static> {
+    let name_width = (width as usize / 3).max(10);
+    let bar_width = (width as usize).saturating_sub(name_width + 24).max(5);
+    let lines: Vec<Line> = goals
+        .iter()
+        .map(|&(goal, count)| {
+            let name = truncate_middle(&goal.command, name_width);
+            let padding = " ".repeat(name_width.saturating_sub(name.width()));
+            let color = match (goal.met(count), goal.limit) {
+                (true, _) => Color::LightGreen,
+                (false, Limit::AtMost) => Color::LightRed,
+                (false, Limit::AtLeast) => Color::LightYellow,
+            };
+            Line::from(vec![
+                Span::from(format!("{}{} {:>5} ", name, padding, goal.label())),
+                Span::from(progress_bar(goal.progress(count), bar_width)).fg(color),
+                Span::from(format!(" {}/{}", count, goal.target)).fg(color),
+            ])
+        })
+        .collect();
+    Paragraph::new(lines).block(titled(&tr!("box-goals", week = week)).padding(padding()))
+}
+
+// Two columns between a panel's border and its text
+fn padding() -> Padding {
+    Padding::left(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panels_split_the_screen_in_rows_and_halves() {
+        let screen = Rect::new(0, 0, 101, 30);
+        let areas = panel_areas(screen, [8, 5, 5, 4], false);
+        assert_eq!(areas.header, Rect::new(0, 0, 101, 1));
+        assert_eq!(areas.general, Rect::new(0, 1, 50, 8));
+        assert_eq!(areas.rankings, Some(Rect::new(50, 1, 51, 8)));
+        assert_eq!(areas.commands, Rect::new(50, 9, 51, 5));
+        assert_eq!(areas.patterns, Rect::new(0, 14, 101, 5));
+        assert_eq!(areas.goals, Rect::new(0, 19, 101, 4));
+
+        let compact = panel_areas(screen, [8, 5, 5, 0], true);
+        assert_eq!(compact.general.width, 101);
+        assert_eq!((compact.rankings, compact.directories), (None, None));
+        assert_eq!(compact.commands, Rect::new(0, 9, 101, 5));
+        assert_eq!(compact.goals.height, 0);
+    }
+
+    #[test]
+    fn presented_directory_rankings_hide_their_paths() {
+        let counts = [
+            ("/home/me/secret-client".to_string(), 3),
+            ("/home/me/secret-client/src".to_string(), 2),
+            ("/etc".to_string(), 1),
+        ];
+        let rows = ranking_rows(&counts, 2, true, true);
+        assert_eq!(rows.len(), 2);
+        assert!(rows
+            .iter()
+            .all(|(name, _)| name.starts_with("project-") && !name.contains("secret")));
+        assert_eq!(rows[0].1, 3);
+
+        assert_eq!(ranking_rows(&counts, 2, true, false), counts[..2]);
+        // Other rankings, like categories, are shown as they are
+        assert_eq!(ranking_rows(&counts, 5, false, true), counts);
+    }
+}
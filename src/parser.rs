@@ -0,0 +1,643 @@
+use serde_json::Value;
+use std::fmt;
+
+use crate::history::HistoryEntry;
+
+// Version of the JSON lines written by `record`
+pub const RECORD_VERSION: u64 = 1;
+
+// How to treat lines that look like a structured record but don't parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    // Keep malformed lines as plain commands without a timestamp
+    #[default]
+    Tolerant,
+    // Report malformed lines as errors
+    Strict,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    // The timestamp field isn't a number
+    InvalidTimestamp(String),
+    // A `: <timestamp>:<elapsed>;` header without the `;` separator
+    MissingSeparator,
+    // The record has no command text
+    EmptyCommand,
+    // A `{...}` line that isn't a record `record` could have written
+    InvalidRecord(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidTimestamp(ts) => write!(f, "invalid timestamp '{}'", ts),
+            ParseError::MissingSeparator => write!(f, "missing ';' after timestamp header"),
+            ParseError::EmptyCommand => write!(f, "empty command"),
+            ParseError::InvalidRecord(reason) => write!(f, "invalid record: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Split a command line on `&&` into separate entries that share everything
+// but the command with `base`
+pub(crate) fn split_commands(
+    base: HistoryEntry,
+    command: &str,
+    mode: ParseMode,
+) -> Result<Vec<HistoryEntry>, ParseError> {
+    let entries: Vec<HistoryEntry> = command
+        .split("&&")
+        .map(str::trim)
+        .filter(|subcmd| !subcmd.is_empty())
+        .map(|subcmd| HistoryEntry {
+            command: subcmd.to_string(),
+            ..base.clone()
+        })
+        .collect();
+
+    if entries.is_empty() && mode == ParseMode::Strict {
+        return Err(ParseError::EmptyCommand);
+    }
+    Ok(entries)
+}
+
+fn stamped(timestamp: i64, directory: Option<&str>) -> HistoryEntry {
+    HistoryEntry {
+        timestamp,
+        directory: directory.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+// A line that isn't a structured record at all
+fn plain_command(line: &str, mode: ParseMode) -> Result<Vec<HistoryEntry>, ParseError> {
+    split_commands(HistoryEntry::default(), line.trim(), mode)
+}
+
+// Malformed records are an error in strict mode and a plain command otherwise
+fn malformed(
+    line: &str,
+    error: ParseError,
+    mode: ParseMode,
+) -> Result<Vec<HistoryEntry>, ParseError> {
+    match mode {
+        ParseMode::Strict => Err(error),
+        ParseMode::Tolerant => plain_command(line, ParseMode::Tolerant),
+    }
+}
+
+fn parse_timestamp(ts: &str) -> Result<i64, ParseError> {
+    ts.trim()
+        .parse()
+        .map_err(|_| ParseError::InvalidTimestamp(ts.trim().to_string()))
+}
+
+// Split a trailing directory off `rest` at the last `separator`, if what
+// follows it looks like a directory (or is empty)
+fn split_directory(rest: &str, separator: char) -> (&str, Option<&str>) {
+    match rest.rsplit_once(separator) {
+        Some((command, dir)) if dir.trim().is_empty() => (command, None),
+        Some((command, dir)) if is_valid_directory(dir.trim()) => (command, Some(dir.trim())),
+        _ => (rest, None),
+    }
+}
+
+// Context labels are short tags like `office` or `home-vpn`
+pub fn is_context_label(label: &str) -> bool {
+    !label.is_empty()
+        && label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+// Split a trailing `|<context>` off `<command>|<directory>|<context>`. The
+// label only counts when the field before it is a directory, so commands
+// that pipe into a program don't lose it.
+fn split_context(rest: &str) -> (&str, Option<&str>) {
+    if let Some((head, label)) = rest.rsplit_once('|') {
+        if let Some((_, dir)) = head.rsplit_once('|') {
+            if is_context_label(label.trim()) && is_valid_directory(dir.trim()) {
+                return (head, Some(label.trim()));
+            }
+        }
+    }
+    (rest, None)
+}
+
+// Leading run of ASCII digits followed by `separator`, as in `1681234567|...`
+fn numeric_prefix(line: &str, separator: char) -> Option<(&str, &str)> {
+    let (ts, rest) = line.split_once(separator)?;
+    if !ts.is_empty() && ts.chars().all(|c| c.is_ascii_digit()) {
+        Some((ts, rest))
+    } else {
+        None
+    }
+}
+
+// A line written by `record`, one JSON object per command:
+// {"v":1,"ts":1681234567,"cmd":"make","cwd":"/src","duration":3,"exit":0,"session":"...","commit":"..."}
+// JSON escaping means any command survives intact, separators and all. The
+// duration and exit code belong to the whole line, so it isn't split on `&&`.
+fn parse_record(line: &str, mode: ParseMode) -> Result<Vec<HistoryEntry>, ParseError> {
+    let invalid =
+        |reason: &str| malformed(line, ParseError::InvalidRecord(reason.to_string()), mode);
+    let Ok(Value::Object(record)) = serde_json::from_str::<Value>(line) else {
+        return invalid("not a JSON object");
+    };
+    // Records from a newer version are skipped rather than kept as a raw
+    // JSON command, since they're still well-formed
+    match record.get("v").and_then(Value::as_u64) {
+        Some(RECORD_VERSION) => {}
+        Some(version) if version > RECORD_VERSION && mode == ParseMode::Tolerant => {
+            return Ok(Vec::new())
+        }
+        _ => return invalid("unsupported version"),
+    }
+    let command = record
+        .get("cmd")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim();
+    if command.is_empty() {
+        return match mode {
+            ParseMode::Strict => Err(ParseError::EmptyCommand),
+            ParseMode::Tolerant => Ok(Vec::new()),
+        };
+    }
+
+    let text = |key: &str| record.get(key).and_then(Value::as_str);
+    let number = |key: &str| record.get(key).and_then(Value::as_i64);
+    Ok(vec![HistoryEntry {
+        timestamp: number("ts").unwrap_or(0),
+        command: command.to_string(),
+        directory: text("cwd")
+            .filter(|dir| is_valid_directory(dir))
+            .map(str::to_string),
+        duration: number("duration"),
+        exit_code: number("exit").and_then(|code| i32::try_from(code).ok()),
+        context: text("context").map(str::to_string),
+        session_id: text("session").map(str::to_string),
+        commit: text("commit").map(str::to_string),
+        host: None,
+        recorded_timestamp: None,
+    }])
+}
+
+// Parse a line of zsh history: `: <timestamp>:<elapsed>;<command>` with
+// EXTENDED_HISTORY, or just the command without it
+pub fn parse_zsh_line(line: &str, mode: ParseMode) -> Result<Vec<HistoryEntry>, ParseError> {
+    if line.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let Some(header) = line.strip_prefix(": ") else {
+        return plain_command(line, mode);
+    };
+    let Some((ts_part, command)) = header.split_once(';') else {
+        return malformed(line, ParseError::MissingSeparator, mode);
+    };
+    let ts = ts_part.split(':').next().unwrap_or_default();
+    match parse_timestamp(ts) {
+        Ok(timestamp) => split_commands(stamped(timestamp, None), command.trim(), mode),
+        Err(error) => malformed(line, error, mode),
+    }
+}
+
+// Parse a line of the stats log. Older hooks wrote one of:
+//   <timestamp>|<command>|<directory>[|<context>]
+//   <timestamp>:<command>:<directory>
+//   : <timestamp>:0;<command>:<directory>
+//   <command>
+// The directory is always the last field, so commands may themselves contain
+// `|` or `:` as long as the trailing field is recognisably a path.
+pub fn parse_stats_log_line(line: &str, mode: ParseMode) -> Result<Vec<HistoryEntry>, ParseError> {
+    if line.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // JSON lines from `record`
+    if line.starts_with('{') {
+        return parse_record(line, mode);
+    }
+
+    // Pipe-delimited format, optionally followed by a context label
+    if let Some((ts, rest)) = numeric_prefix(line, '|') {
+        let timestamp = match parse_timestamp(ts) {
+            Ok(timestamp) => timestamp,
+            Err(error) => return malformed(line, error, mode),
+        };
+        let (rest, context) = split_context(rest);
+        let (command, directory) = split_directory(rest, '|');
+        let base = HistoryEntry {
+            context: context.map(str::to_string),
+            ..stamped(timestamp, directory)
+        };
+        return split_commands(base, command, mode);
+    }
+
+    // Colon-delimited format
+    if let Some((ts, rest)) = numeric_prefix(line, ':') {
+        return match parse_timestamp(ts) {
+            Ok(timestamp) => {
+                let (command, directory) = split_directory(rest, ':');
+                split_commands(stamped(timestamp, directory), command, mode)
+            }
+            Err(error) => malformed(line, error, mode),
+        };
+    }
+
+    // Zsh history format, optionally followed by the directory
+    if let Some(header) = line.strip_prefix(": ") {
+        let Some((ts_part, rest)) = header.split_once(';') else {
+            return malformed(line, ParseError::MissingSeparator, mode);
+        };
+        let ts = ts_part.split(':').next().unwrap_or_default();
+        return match parse_timestamp(ts) {
+            Ok(timestamp) => {
+                let (command, directory) = split_directory(rest, ':');
+                split_commands(stamped(timestamp, directory), command, mode)
+            }
+            Err(error) => malformed(line, error, mode),
+        };
+    }
+
+    plain_command(line, mode)
+}
+
+// `#<timestamp>` comment line that bash writes before each command when
+// HISTTIMEFORMAT is set
+fn bash_timestamp(line: &str) -> Option<i64> {
+    let digits = line.trim_end().strip_prefix('#')?;
+    if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+// Parse a whole bash history file. Timestamps live on their own line, so
+// unlike the zsh and stats log formats this can't work a line at a time:
+// each `#<timestamp>` applies to the command lines after it. Files written
+// without HISTTIMEFORMAT have no such lines and give timestamp 0.
+pub fn parse_bash_history<S: AsRef<str>>(
+    lines: impl IntoIterator<Item = S>,
+    mode: ParseMode,
+) -> Result<Vec<HistoryEntry>, ParseError> {
+    let mut entries = Vec::new();
+    let mut timestamp = 0;
+    for line in lines {
+        let line = line.as_ref();
+        if let Some(ts) = bash_timestamp(line) {
+            timestamp = ts;
+        } else if !line.trim().is_empty() {
+            entries.extend(split_commands(stamped(timestamp, None), line.trim(), mode)?);
+        }
+    }
+    Ok(entries)
+}
+
+// Fish escapes backslashes and newlines inside `cmd:` values
+fn unescape_fish(command: &str) -> String {
+    let mut out = String::with_capacity(command.len());
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+// Parse a fish history file, a YAML-like list of records:
+//
+//   - cmd: git status
+//     when: 1681234567
+//     paths:
+//       - src/main.rs
+//
+// `paths` lists arguments that were files, not the working directory, so
+// it's skipped. Multi-line commands become one entry per line, as they do
+// in zsh history.
+pub fn parse_fish_history<S: AsRef<str>>(
+    lines: impl IntoIterator<Item = S>,
+    mode: ParseMode,
+) -> Result<Vec<HistoryEntry>, ParseError> {
+    let mut records: Vec<(String, i64)> = Vec::new();
+    for line in lines {
+        let line = line.as_ref();
+        if let Some(command) = line.strip_prefix("- cmd:") {
+            records.push((unescape_fish(command.trim()), 0));
+        } else if let (Some(when), Some(record)) =
+            (line.strip_prefix("  when:"), records.last_mut())
+        {
+            record.1 = match parse_timestamp(when) {
+                Ok(timestamp) => timestamp,
+                Err(error) if mode == ParseMode::Strict => return Err(error),
+                Err(_) => 0,
+            };
+        }
+    }
+
+    let mut entries = Vec::new();
+    for (command, timestamp) in records {
+        let base = stamped(timestamp, None);
+        let lines: Vec<&str> = command
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        if lines.is_empty() {
+            entries.extend(split_commands(base.clone(), "", mode)?);
+        }
+        for line in lines {
+            entries.extend(split_commands(base.clone(), line, mode)?);
+        }
+    }
+    Ok(entries)
+}
+
+// Parse a line of PowerShell's PSReadLine history (ConsoleHost_history.txt).
+// It's just the command: no timestamps, so every entry gets 0. Multi-line
+// commands end each line but the last with a backtick, and become one entry
+// per line as in zsh history. Windows files may have CRLF endings and a BOM.
+pub fn parse_powershell_line(line: &str, mode: ParseMode) -> Result<Vec<HistoryEntry>, ParseError> {
+    let line = line.trim_start_matches('\u{feff}').trim();
+    let command = line.strip_suffix('`').unwrap_or(line).trim_end();
+    if command.is_empty() {
+        return Ok(Vec::new());
+    }
+    split_commands(HistoryEntry::default(), command, mode)
+}
+
+// Helper function to validate if a string looks like a valid directory path
+pub fn is_valid_directory(path: &str) -> bool {
+    // Valid directories should:
+    // 1. Start with / (absolute path) or ~ (home directory)
+    // 2. Not look like the tail of a URL
+
+    if path.is_empty() {
+        return false;
+    }
+
+    // Check if starts with / or ~
+    if path.starts_with('/') || path.starts_with('~') {
+        // Do additional checks to exclude URLs, whose `//host/...` tail is
+        // what's left after splitting on the scheme's colon
+        if path.contains("://") || path.starts_with("//") || path.contains("github.com") {
+            return false;
+        }
+        return true;
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn commands(entries: &[HistoryEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.command.as_str()).collect()
+    }
+
+    #[test]
+    fn zsh_extended_history() {
+        let entries = parse_zsh_line(": 1681234567:0;git status && ls", ParseMode::Strict).unwrap();
+        assert_eq!(commands(&entries), ["git status", "ls"]);
+        assert!(entries.iter().all(|e| e.timestamp == 1681234567));
+    }
+
+    #[test]
+    fn malformed_zsh_header_is_kept_or_rejected() {
+        let line = ": notatime:0;ls";
+        assert_eq!(
+            parse_zsh_line(line, ParseMode::Strict),
+            Err(ParseError::InvalidTimestamp("notatime".to_string()))
+        );
+        let entries = parse_zsh_line(line, ParseMode::Tolerant).unwrap();
+        assert_eq!(commands(&entries), [line]);
+        assert_eq!(entries[0].timestamp, 0);
+
+        assert_eq!(
+            parse_zsh_line(": 1681234567:0", ParseMode::Strict),
+            Err(ParseError::MissingSeparator)
+        );
+
+        // Too big for a timestamp, in the stats log's own formats
+        for line in [
+            "99999999999999999999|ls|/tmp",
+            "99999999999999999999:ls:/tmp",
+        ] {
+            assert!(parse_stats_log_line(line, ParseMode::Strict).is_err());
+            let entries = parse_stats_log_line(line, ParseMode::Tolerant).unwrap();
+            assert_eq!(commands(&entries), [line]);
+        }
+    }
+
+    #[test]
+    fn stats_log_formats() {
+        let pipe = parse_stats_log_line("1681234567|cargo build|/home/me/repo", ParseMode::Strict)
+            .unwrap();
+        assert_eq!(pipe[0].command, "cargo build");
+        assert_eq!(pipe[0].directory.as_deref(), Some("/home/me/repo"));
+
+        let colon = parse_stats_log_line("1681234567:echo a:b:~/notes", ParseMode::Strict).unwrap();
+        assert_eq!(colon[0].command, "echo a:b");
+        assert_eq!(colon[0].directory.as_deref(), Some("~/notes"));
+
+        let zsh = parse_stats_log_line(": 1681234567:0;ls:/tmp", ParseMode::Strict).unwrap();
+        assert_eq!(zsh[0].command, "ls");
+        assert_eq!(zsh[0].directory.as_deref(), Some("/tmp"));
+
+        let plain = parse_stats_log_line("make", ParseMode::Strict).unwrap();
+        assert_eq!(plain[0].timestamp, 0);
+    }
+
+    #[test]
+    fn separators_inside_commands_are_preserved() {
+        let piped =
+            parse_stats_log_line("1681234567|ps aux | grep zsh|/tmp", ParseMode::Strict).unwrap();
+        assert_eq!(piped[0].command, "ps aux | grep zsh");
+
+        let url = parse_stats_log_line(
+            ": 1681234567:0;curl https://example.com/x",
+            ParseMode::Strict,
+        )
+        .unwrap();
+        assert_eq!(url[0].command, "curl https://example.com/x");
+        assert_eq!(url[0].directory, None);
+    }
+
+    #[test]
+    fn context_label_follows_the_directory() {
+        let tagged =
+            parse_stats_log_line("1681234567|make|/src/app|office", ParseMode::Strict).unwrap();
+        assert_eq!(tagged[0].command, "make");
+        assert_eq!(tagged[0].directory.as_deref(), Some("/src/app"));
+        assert_eq!(tagged[0].context.as_deref(), Some("office"));
+
+        let piped =
+            parse_stats_log_line("1681234567|ls /tmp | wc|/home", ParseMode::Strict).unwrap();
+        assert_eq!(piped[0].command, "ls /tmp | wc");
+        assert_eq!(piped[0].context, None);
+    }
+
+    #[test]
+    fn empty_records() {
+        assert_eq!(
+            parse_stats_log_line("   ", ParseMode::Strict),
+            Ok(Vec::new())
+        );
+        assert_eq!(
+            parse_stats_log_line("1681234567||/tmp", ParseMode::Strict),
+            Err(ParseError::EmptyCommand)
+        );
+        assert_eq!(
+            parse_stats_log_line("1681234567||/tmp", ParseMode::Tolerant),
+            Ok(Vec::new())
+        );
+    }
+
+    #[test]
+    fn bash_timestamp_lines_apply_to_the_next_command() {
+        let history = "#1681234567\ngit status && ls\n#1681234600\n#notatime\nmake\n";
+        let entries = parse_bash_history(history.lines(), ParseMode::Strict).unwrap();
+        assert_eq!(
+            commands(&entries),
+            ["git status", "ls", "#notatime", "make"]
+        );
+        assert_eq!(
+            entries.iter().map(|e| e.timestamp).collect::<Vec<_>>(),
+            [1681234567, 1681234567, 1681234600, 1681234600]
+        );
+
+        let plain = parse_bash_history(["cd /tmp", "", "ls"], ParseMode::Strict).unwrap();
+        assert_eq!(commands(&plain), ["cd /tmp", "ls"]);
+        assert!(plain.iter().all(|e| e.timestamp == 0));
+    }
+
+    #[test]
+    fn fish_records() {
+        let history = "- cmd: git status && ls\n  when: 1681234567\n  paths:\n    - src\n\
+                       - cmd: echo a\\\\b\\ncd /tmp\n  when: 1681234600\n";
+        let entries = parse_fish_history(history.lines(), ParseMode::Strict).unwrap();
+        assert_eq!(
+            commands(&entries),
+            ["git status", "ls", "echo a\\b", "cd /tmp"]
+        );
+        assert_eq!(
+            entries.iter().map(|e| e.timestamp).collect::<Vec<_>>(),
+            [1681234567, 1681234567, 1681234600, 1681234600]
+        );
+
+        let bad_time = ["- cmd: make", "  when: soon"];
+        assert_eq!(
+            parse_fish_history(bad_time, ParseMode::Strict),
+            Err(ParseError::InvalidTimestamp("soon".to_string()))
+        );
+        assert_eq!(
+            parse_fish_history(bad_time, ParseMode::Tolerant).unwrap()[0].timestamp,
+            0
+        );
+    }
+
+    #[test]
+    fn recorded_json_lines() {
+        let line = r#"{"v":1,"ts":1681234567,"cmd":"echo 'a|b:c' && ls","cwd":"/src","duration":3,"exit":1,"session":"4242-1681234000","commit":"9fceb02d0ae598e95dc970b74767f19372d61af8"}"#;
+        let entries = parse_stats_log_line(line, ParseMode::Strict).unwrap();
+        assert_eq!(
+            entries,
+            [HistoryEntry {
+                timestamp: 1681234567,
+                command: "echo 'a|b:c' && ls".to_string(),
+                directory: Some("/src".to_string()),
+                duration: Some(3),
+                exit_code: Some(1),
+                context: None,
+                session_id: Some("4242-1681234000".to_string()),
+                commit: Some("9fceb02d0ae598e95dc970b74767f19372d61af8".to_string()),
+                host: None,
+                recorded_timestamp: None,
+            }]
+        );
+
+        let future = r#"{"v":2,"cmd":"ls"}"#;
+        assert_eq!(
+            parse_stats_log_line(future, ParseMode::Strict),
+            Err(ParseError::InvalidRecord("unsupported version".to_string()))
+        );
+        assert!(parse_stats_log_line(future, ParseMode::Tolerant)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            commands(&parse_stats_log_line("{ ls; }", ParseMode::Tolerant).unwrap()),
+            ["{ ls; }"]
+        );
+    }
+
+    #[test]
+    fn powershell_lines() {
+        let history = "\u{feff}Get-ChildItem\r\ngit status && ls\r\nif ($x) {`\r\n  Write-Host hi`\r\n}\r\n\r\n";
+        let entries: Vec<HistoryEntry> = history
+            .lines()
+            .flat_map(|line| parse_powershell_line(line, ParseMode::Strict).unwrap())
+            .collect();
+        assert_eq!(
+            commands(&entries),
+            [
+                "Get-ChildItem",
+                "git status",
+                "ls",
+                "if ($x) {",
+                "Write-Host hi",
+                "}"
+            ]
+        );
+        assert!(entries.iter().all(|e| e.timestamp == 0));
+    }
+
+    proptest! {
+        #[test]
+        fn never_panics(line in any::<String>()) {
+            for mode in [ParseMode::Tolerant, ParseMode::Strict] {
+                let _ = parse_zsh_line(&line, mode);
+                let _ = parse_stats_log_line(&line, mode);
+                let _ = parse_bash_history(line.lines(), mode);
+                let _ = parse_fish_history(line.lines(), mode);
+                let _ = parse_powershell_line(&line, mode);
+            }
+        }
+
+        #[test]
+        fn tolerant_mode_keeps_every_command(
+            line in "(: [^&\n;]*;|[0-9]{1,25}[|:])?[a-z][^&\n]*"
+        ) {
+            prop_assert!(!parse_zsh_line(&line, ParseMode::Tolerant).unwrap().is_empty());
+            prop_assert!(!parse_stats_log_line(&line, ParseMode::Tolerant).unwrap().is_empty());
+        }
+
+        #[test]
+        fn pipe_records_round_trip(
+            ts in 1i64..4_000_000_000,
+            command in "[a-z][a-z |:=-]{0,30}",
+            dir in "(/[a-z_]{1,8}){1,3}",
+        ) {
+            let line = format!("{}|{}|{}", ts, command, dir);
+            let entries = parse_stats_log_line(&line, ParseMode::Strict).unwrap();
+            prop_assert_eq!(entries.len(), 1);
+            prop_assert_eq!(entries[0].timestamp, ts);
+            prop_assert_eq!(entries[0].command.as_str(), command.trim());
+            prop_assert_eq!(entries[0].directory.as_deref(), Some(dir.as_str()));
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::{fs, path::Path};
+
+// The charge left, in percent, while running on battery. None on mains power
+// or where Linux's /sys/class/power_supply isn't there to ask.
+pub fn battery_level() -> Option<u8> {
+    discharging_level(Path::new("/sys/class/power_supply"))
+}
+
+// The emptiest battery that is discharging, if any is
+fn discharging_level(supplies: &Path) -> Option<u8> {
+    fs::read_dir(supplies)
+        .ok()?
+        .flatten()
+        .filter_map(|supply| {
+            let path = supply.path();
+            let read = |name: &str| fs::read_to_string(path.join(name)).ok();
+            let discharging =
+                read("type")?.trim() == "Battery" && read("status")?.trim() == "Discharging";
+            discharging.then_some(())?;
+            read("capacity")?.trim().parse().ok()
+        })
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+
+    #[test]
+    fn only_a_discharging_battery_has_a_level() {
+        let dir = ScratchDir::new("power");
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            fs::create_dir_all(dir.join(name)).unwrap();
+            for (file, value) in files {
+                fs::write(dir.join(name).join(file), format!("{}\n", value)).unwrap();
+            }
+        };
+        supply("AC", &[("type", "Mains"), ("online", "1")]);
+        supply(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Charging"),
+                ("capacity", "15"),
+            ],
+        );
+        assert_eq!(discharging_level(&dir), None);
+
+        supply("BAT0", &[("status", "Discharging")]);
+        assert_eq!(discharging_level(&dir), Some(15));
+
+        assert_eq!(discharging_level(&dir.join("missing")), None);
+    }
+}
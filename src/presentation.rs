@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::history::repository_root;
+
+// Turned on with --present, for screenshots and screen sharing: the views
+// show times only relative to now, directories as project aliases and
+// neither the user nor the host name
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// Projects in the order they were first shown, so each keeps its alias for
+// the whole session
+static PROJECTS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+// `project-N` for the repository `path` is in, or for `path` itself outside
+// any, with `/…` for directories below its top
+pub fn project_alias(path: &str) -> String {
+    let path = Path::new(path);
+    let root = repository_root(path).unwrap_or(path);
+    alias_in(&mut PROJECTS.lock().unwrap(), root, path)
+}
+
+fn alias_in(projects: &mut Vec<PathBuf>, root: &Path, path: &Path) -> String {
+    let number = match projects.iter().position(|project| project == root) {
+        Some(index) => index + 1,
+        None => {
+            projects.push(root.to_path_buf());
+            projects.len()
+        }
+    };
+    if path == root {
+        format!("project-{}", number)
+    } else {
+        format!("project-{}/…", number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn projects_keep_the_alias_they_were_first_given() {
+        let mut projects = Vec::new();
+        let mut alias =
+            |root: &str, path: &str| alias_in(&mut projects, Path::new(root), Path::new(path));
+        assert_eq!(
+            alias("/home/me/secret-client", "/home/me/secret-client"),
+            "project-1"
+        );
+        assert_eq!(alias("/tmp", "/tmp"), "project-2");
+        assert_eq!(
+            alias("/home/me/secret-client", "/home/me/secret-client/src"),
+            "project-1/…"
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use anyhow::{Context, Result};
+use crossterm::{
+    cursor, execute,
+    style::{Color, Stylize},
+    terminal::{self, ClearType},
+};
+use std::io::{self, Write};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::error::ErrorKind;
+use crate::tr;
+use crate::ui_utils::{draw_box, screen_size, wait_to_dismiss};
+
+const PREVIEW_WIDTH: u16 = 100;
+
+// What a piece of a command is, for coloring it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    // The command each pipeline stage or list item runs
+    Program,
+    Flag,
+    // A quoted string, quotes included
+    Quoted,
+    // $NAME, ${NAME} and $(...)
+    Variable,
+    // Pipes, lists, redirections and the like
+    Operator,
+    Plain,
+}
+
+impl TokenKind {
+    fn color(self) -> Color {
+        match self {
+            TokenKind::Program => Color::Cyan,
+            TokenKind::Flag => Color::Yellow,
+            TokenKind::Quoted => Color::Green,
+            TokenKind::Variable => Color::Magenta,
+            TokenKind::Operator => Color::Red,
+            TokenKind::Plain => Color::White,
+        }
+    }
+}
+
+// Each character of a shell command with what it's part of. This only reads
+// far enough to color the command, not to run it.
+pub fn command_tokens(command: &str) -> Vec<(char, TokenKind)> {
+    let chars: Vec<char> = command.chars().collect();
+    let mut tokens = Vec::with_capacity(chars.len());
+    // The next word is a program, at the start and after an operator
+    let mut expect_program = true;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            if c == '\n' {
+                expect_program = true;
+            }
+            tokens.push((c, TokenKind::Plain));
+            i += 1;
+            continue;
+        }
+        if "|&;<>()".contains(c) {
+            // A file or descriptor follows a redirection, as in 2>&1
+            let redirect = "<>".contains(c) || (c == '&' && i > 0 && "<>".contains(chars[i - 1]));
+            expect_program = !redirect;
+            tokens.push((c, TokenKind::Operator));
+            i += 1;
+            continue;
+        }
+
+        // A word, up to unquoted whitespace or an operator
+        let start = tokens.len();
+        let mut word_kind = if expect_program {
+            TokenKind::Program
+        } else if c == '-' {
+            TokenKind::Flag
+        } else {
+            TokenKind::Plain
+        };
+        while i < chars.len() && !chars[i].is_whitespace() && !"|&;<>()".contains(chars[i]) {
+            match chars[i] {
+                quote @ ('\'' | '"') => {
+                    tokens.push((quote, TokenKind::Quoted));
+                    i += 1;
+                    while i < chars.len() && chars[i] != quote {
+                        if quote == '"' && chars[i] == '\\' && i + 1 < chars.len() {
+                            tokens.push((chars[i], TokenKind::Quoted));
+                            i += 1;
+                        }
+                        tokens.push((chars[i], TokenKind::Quoted));
+                        i += 1;
+                    }
+                    if i < chars.len() {
+                        tokens.push((chars[i], TokenKind::Quoted));
+                        i += 1;
+                    }
+                }
+                '$' => {
+                    tokens.push(('$', TokenKind::Variable));
+                    i += 1;
+                    let closing = match chars.get(i) {
+                        Some('{') => Some('}'),
+                        Some('(') => Some(')'),
+                        _ => None,
+                    };
+                    if let Some(closing) = closing {
+                        while i < chars.len() && chars[i] != closing {
+                            tokens.push((chars[i], TokenKind::Variable));
+                            i += 1;
+                        }
+                        if i < chars.len() {
+                            tokens.push((chars[i], TokenKind::Variable));
+                            i += 1;
+                        }
+                    } else {
+                        while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                            tokens.push((chars[i], TokenKind::Variable));
+                            i += 1;
+                        }
+                    }
+                }
+                other => {
+                    // NAME=value before a command leaves it still to come
+                    if other == '=' && word_kind == TokenKind::Program {
+                        word_kind = TokenKind::Plain;
+                        for token in &mut tokens[start..] {
+                            if token.1 == TokenKind::Program {
+                                token.1 = TokenKind::Plain;
+                            }
+                        }
+                    }
+                    tokens.push((other, word_kind));
+                    i += 1;
+                }
+            }
+        }
+        if word_kind == TokenKind::Program {
+            expect_program = false;
+        }
+    }
+    tokens
+}
+
+// The tokens in lines no wider than `width`, breaking after a space where
+// there is one and at the command's own line breaks
+pub fn wrap_tokens(tokens: &[(char, TokenKind)], width: usize) -> Vec<Vec<(char, TokenKind)>> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut line: Vec<(char, TokenKind)> = Vec::new();
+    let mut line_width = 0;
+    for &(c, kind) in tokens {
+        if c == '\n' {
+            lines.push(std::mem::take(&mut line));
+            line_width = 0;
+            continue;
+        }
+        let char_width = c.width().unwrap_or(0);
+        if line_width + char_width > width && !line.is_empty() {
+            // Carry the word over to the next line, unless it's the only one
+            let carried = match line.iter().rposition(|&(c, _)| c == ' ') {
+                Some(space) if space + 1 < line.len() => line.split_off(space + 1),
+                _ => Vec::new(),
+            };
+            lines.push(std::mem::replace(&mut line, carried));
+            line_width = line.iter().map(|&(c, _)| c.width().unwrap_or(0)).sum();
+        }
+        line.push((c, kind));
+        line_width += char_width;
+    }
+    lines.push(line);
+    lines
+}
+
+// Whether `command` is cut off in a list row `width` columns wide
+pub fn needs_preview(command: &str, width: usize) -> bool {
+    command.contains('\n') || command.width() > width
+}
+
+fn styled_line(line: &[(char, TokenKind)]) -> String {
+    line.iter()
+        .map(|&(c, kind)| c.with(kind.color()).to_string())
+        .collect()
+}
+
+// The whole command, wrapped and colored, in a box over the view below. Any
+// key closes it; a resize redraws it alone on a cleared screen, as the view
+// below no longer fits.
+pub fn show_preview(stdout: &mut io::Stdout, command: &str) -> Result<()> {
+    while !draw_preview(stdout, command).context(ErrorKind::Terminal)? {
+        execute!(stdout, terminal::Clear(ClearType::All))?;
+    }
+    Ok(())
+}
+
+// True once a key closes it
+fn draw_preview(stdout: &mut io::Stdout, command: &str) -> Result<bool> {
+    let (term_width, term_height) = screen_size()?;
+    let width = PREVIEW_WIDTH.min(term_width.saturating_sub(4)).max(10);
+    let mut lines = wrap_tokens(&command_tokens(command), width as usize - 4);
+    // Room for the borders, a blank line and the hint
+    let room = (term_height as usize).saturating_sub(6).max(1);
+    let cut = lines.len() > room;
+    lines.truncate(room);
+
+    let height = lines.len() as u16 + 4;
+    let x = term_width.saturating_sub(width) / 2;
+    let y = term_height.saturating_sub(height) / 2;
+
+    // Blank out the list underneath first
+    let blank = " ".repeat(width as usize);
+    for row in y..y + height {
+        execute!(stdout, cursor::MoveTo(x, row))?;
+        write!(stdout, "{}", blank)?;
+    }
+    draw_box(stdout, x, y, width, height, Some(&tr!("preview-title")))?;
+    for (i, line) in lines.iter().enumerate() {
+        execute!(stdout, cursor::MoveTo(x + 2, y + 1 + i as u16))?;
+        write!(stdout, "{}", styled_line(line))?;
+    }
+    if cut {
+        write!(stdout, "{}", "…".dark_grey())?;
+    }
+    let hint = tr!("preview-close");
+    let hint_x = x + width.saturating_sub(hint.width() as u16 + 2);
+    execute!(stdout, cursor::MoveTo(hint_x, y + height - 2))?;
+    write!(stdout, "{}", hint.dark_grey())?;
+    stdout.flush().context(ErrorKind::Terminal)?;
+    wait_to_dismiss()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(command: &str) -> String {
+        command_tokens(command)
+            .iter()
+            .map(|(_, kind)| match kind {
+                TokenKind::Program => 'p',
+                TokenKind::Flag => 'f',
+                TokenKind::Quoted => 'q',
+                TokenKind::Variable => 'v',
+                TokenKind::Operator => 'o',
+                TokenKind::Plain => '.',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn colors_and_wraps_a_pipeline() {
+        assert_eq!(
+            kinds("git log -n 3 | grep \"a b\" && echo $HOME"),
+            "ppp.....ff...o.pppp.qqqqq.oo.pppp.vvvvv"
+        );
+        assert_eq!(kinds("FOO=1 make"), "......pppp");
+        assert_eq!(kinds("make 2>&1 | less"), "pppp..oo..o.pppp");
+
+        let text = |lines: Vec<Vec<(char, TokenKind)>>| -> Vec<String> {
+            lines
+                .iter()
+                .map(|line| line.iter().map(|(c, _)| c).collect())
+                .collect()
+        };
+        let tokens = command_tokens("cargo build --release\necho done");
+        assert_eq!(
+            text(wrap_tokens(&tokens, 12)),
+            ["cargo build ", "--release", "echo done"]
+        );
+        // A word longer than the line is split
+        assert_eq!(
+            text(wrap_tokens(&command_tokens("abcdefgh"), 5)),
+            ["abcde", "fgh"]
+        );
+        assert!(needs_preview("ls\nls", 80));
+        assert!(!needs_preview("ls -la", 80));
+    }
+}
@@ -0,0 +1,173 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::aggregate::{StatsSummary, TimePatterns};
+use crate::wrapped::Recap;
+
+// How much each count may give away: the noise added has a scale of
+// 1/epsilon, so smaller is noisier
+const EPSILON: f64 = 1.0;
+
+// Ranked names seen fewer times than this, after the noise, are left out
+const MIN_COUNT: usize = 5;
+
+// Laplace noise for figures that will be shared, as differential privacy
+// adds it. Each count can change by one when a single command is run or
+// not, so noise with a scale of 1/epsilon hides whether any one command is
+// in the history. Rare names are dropped as well, since a command line run
+// once or twice is the likeliest to hold something personal.
+pub struct Privacy {
+    epsilon: f64,
+    min_count: usize,
+    state: u64,
+}
+
+impl Privacy {
+    pub fn new(epsilon: f64, min_count: usize, seed: u64) -> Privacy {
+        Privacy {
+            epsilon,
+            min_count,
+            // xorshift never leaves zero
+            state: seed | 1,
+        }
+    }
+
+    // CLI_TRACKER_PRIVACY_EPSILON and CLI_TRACKER_PRIVACY_MIN_COUNT, or 1
+    // and 5, with fresh noise each run
+    pub fn from_env() -> Privacy {
+        let epsilon = std::env::var("CLI_TRACKER_PRIVACY_EPSILON")
+            .ok()
+            .and_then(|epsilon| epsilon.parse().ok())
+            .filter(|&epsilon: &f64| epsilon > 0.0 && epsilon.is_finite())
+            .unwrap_or(EPSILON);
+        let min_count = std::env::var("CLI_TRACKER_PRIVACY_MIN_COUNT")
+            .ok()
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(MIN_COUNT);
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Privacy::new(epsilon, min_count, seed)
+    }
+
+    // Uniform in (-0.5, 0.5)
+    fn uniform(&mut self) -> f64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        ((self.state >> 11) as f64 + 0.5) / (1u64 << 53) as f64 - 0.5
+    }
+
+    fn laplace(&mut self) -> f64 {
+        let u = self.uniform();
+        -u.signum() * (1.0 - 2.0 * u.abs()).ln() / self.epsilon
+    }
+
+    // `count` with noise added, never below zero
+    pub fn count(&mut self, count: usize) -> usize {
+        (count as f64 + self.laplace()).round().max(0.0) as usize
+    }
+
+    // A ranking with noisy counts, without the names that came out rare,
+    // sorted again
+    pub fn ranking(&mut self, ranking: Vec<(String, usize)>) -> Vec<(String, usize)> {
+        let min_count = self.min_count;
+        let mut ranking: Vec<(String, usize)> = ranking
+            .into_iter()
+            .map(|(name, count)| (name, self.count(count)))
+            .filter(|&(_, count)| count >= min_count)
+            .collect();
+        ranking.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranking
+    }
+
+    // Every figure `stats --format` prints. First and last run times are
+    // dropped, as they're exact.
+    pub fn summary(&mut self, summary: &mut StatsSummary, patterns: &mut TimePatterns) {
+        for figure in [
+            &mut summary.total,
+            &mut summary.with_timestamps,
+            &mut summary.unique,
+            &mut summary.today,
+            &mut summary.this_week,
+            &mut summary.this_month,
+        ] {
+            *figure = self.count(*figure);
+        }
+        summary.oldest = 0;
+        summary.newest = 0;
+        for ranking in [
+            &mut summary.categories,
+            &mut summary.pipeline_tools,
+            &mut summary.edited_extensions,
+            &mut summary.edited_areas,
+            &mut summary.contexts,
+            &mut summary.directories,
+            &mut summary.projects,
+            &mut summary.commands,
+        ] {
+            *ranking = self.ranking(std::mem::take(ranking));
+        }
+        for count in patterns
+            .hour_counts
+            .iter_mut()
+            .chain(patterns.day_of_week_counts.iter_mut())
+        {
+            *count = self.count(*count);
+        }
+    }
+
+    // Every figure of a `wrapped` recap
+    pub fn recap(&mut self, recap: &mut Recap) {
+        recap.total = self.count(recap.total);
+        recap.unique = self.count(recap.unique);
+        recap.top = self.ranking(std::mem::take(&mut recap.top));
+        if let Some((_, count)) = &mut recap.busiest_day {
+            *count = self.count(*count);
+        }
+        if let Some((_, count)) = &mut recap.peak_hour {
+            *count = self.count(*count);
+        }
+        if let Some(new_tools) = &mut recap.new_tools {
+            *new_tools = self.ranking(std::mem::take(new_tools));
+        }
+        recap.longest_streak = self.count(recap.longest_streak);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_hides_counts_and_drops_rare_names() {
+        let ranking = vec![
+            ("git status".to_string(), 40),
+            ("cargo test".to_string(), 12),
+            ("ssh secret-host".to_string(), 1),
+        ];
+        // Next to no noise, so only the rare command goes
+        let mut exact = Privacy::new(1e9, 5, 42);
+        assert_eq!(exact.ranking(ranking.clone()), ranking[..2]);
+
+        let mut noisy = Privacy::new(0.5, 5, 42);
+        let counts: Vec<usize> = (0..200).map(|_| noisy.count(100)).collect();
+        assert!(counts.iter().any(|&count| count != 100));
+        // Scale 2 noise averages out close to the count
+        let mean = counts.iter().sum::<usize>() as f64 / counts.len() as f64;
+        assert!((mean - 100.0).abs() < 1.0, "mean {}", mean);
+        assert!((0..200).all(|_| noisy.count(0) < 100));
+
+        let mut summary = StatsSummary {
+            total: 53,
+            oldest: 1_700_000_000,
+            newest: 1_700_001_000,
+            commands: ranking,
+            ..Default::default()
+        };
+        let mut patterns = TimePatterns::default();
+        exact.summary(&mut summary, &mut patterns);
+        assert_eq!((summary.total, summary.oldest, summary.newest), (53, 0, 0));
+        assert_eq!(summary.commands.len(), 2);
+    }
+}
@@ -0,0 +1,471 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::cache::{cache_dir, forget};
+use crate::favorites::{favorites_path, load_favorites, save_favorites};
+use crate::history::{HistoryEntry, UserDirs};
+use crate::metrics::{forget_commands, snapshots_dir};
+use crate::parser::{parse_stats_log_line, ParseMode};
+use crate::record::format_record;
+use crate::redact::redactor;
+use crate::store::{store_path, Store};
+
+// What to remove
+pub enum PurgeTarget {
+    All,
+    // Entries whose command, directory or context label matches
+    Matching(Regex),
+    // Entries whose command matches, for `scrub`
+    Commands(Regex),
+}
+
+impl PurgeTarget {
+    // Whether a command goes, read from the stats log, the database or the
+    // cache. Its fields are matched rather than the line it was read from,
+    // so the keys of a JSON record never match and escaped characters match
+    // as they were typed.
+    fn removes_entry(&self, entry: &HistoryEntry) -> bool {
+        match self {
+            PurgeTarget::All => true,
+            PurgeTarget::Matching(pattern) => [
+                Some(&entry.command),
+                entry.directory.as_ref(),
+                entry.context.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            .any(|text| pattern.is_match(text)),
+            PurgeTarget::Commands(pattern) => pattern.is_match(&entry.command),
+        }
+    }
+}
+
+// What happened to one data file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSummary {
+    pub path: PathBuf,
+    pub removed: usize,
+    pub kept: usize,
+    // Lines of several commands that lost only some of them
+    pub shortened: usize,
+    pub deleted: bool,
+}
+
+// What becomes of one line of a file
+enum LineEdit {
+    Keep,
+    Remove,
+    Replace(String),
+}
+
+// A stats log line without the commands `removes` picks. A line like
+// `make && make install` that keeps some of its commands is written again as
+// a record of just those, so deleting one never takes the others with it.
+fn without_commands(line: &str, removes: impl FnMut(&HistoryEntry) -> bool) -> LineEdit {
+    let Ok(entries) = parse_stats_log_line(line, ParseMode::Tolerant) else {
+        return LineEdit::Keep;
+    };
+    let (removed, kept): (Vec<HistoryEntry>, Vec<HistoryEntry>) =
+        entries.into_iter().partition(removes);
+    match (removed.is_empty(), kept.first()) {
+        (true, _) => LineEdit::Keep,
+        (false, None) => LineEdit::Remove,
+        (false, Some(first)) => {
+            let commands: Vec<&str> = kept.iter().map(|e| e.command.as_str()).collect();
+            LineEdit::Replace(format_record(&HistoryEntry {
+                command: commands.join(" && "),
+                ..first.clone()
+            }))
+        }
+    }
+}
+
+// Every file the tracker writes. The shell's own history (e.g.
+// ~/.zsh_history) belongs to the shell and is never touched.
+pub fn data_files() -> Result<Vec<PathBuf>> {
+    let dirs = UserDirs::current()?;
+    let mut files = vec![
+        dirs.stats_log(),
+        store_path(&dirs),
+        snapshots_dir(&dirs),
+        favorites_path(&dirs),
+    ];
+    files.extend(cache_dir());
+    Ok(files)
+}
+
+fn count_lines(path: &Path) -> Result<usize> {
+    let file = fs::File::open(path)?;
+    Ok(BufReader::new(file).split(b'\n').count())
+}
+
+// Drop or replace the lines `edit` picks in a file. The file is rewritten
+// through a temporary file in the same directory so a crash never leaves it
+// half written. Missing files are reported as untouched.
+fn edit_lines(path: &Path, mut edit: impl FnMut(&str) -> LineEdit) -> Result<FileSummary> {
+    let mut summary = FileSummary {
+        path: path.to_path_buf(),
+        removed: 0,
+        kept: 0,
+        shortened: 0,
+        deleted: false,
+    };
+    if !path.exists() {
+        return Ok(summary);
+    }
+    let file =
+        fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let tmp_path = path.with_extension("purge-tmp");
+    let mut tmp = io::BufWriter::new(
+        fs::File::create(&tmp_path)
+            .with_context(|| format!("Failed to create {}", tmp_path.display()))?,
+    );
+    for line in BufReader::new(file).split(b'\n') {
+        let line = line?;
+        match edit(&String::from_utf8_lossy(&line)) {
+            LineEdit::Keep => {
+                summary.kept += 1;
+                tmp.write_all(&line)?;
+            }
+            LineEdit::Remove => {
+                summary.removed += 1;
+                continue;
+            }
+            LineEdit::Replace(replaced) => {
+                summary.shortened += 1;
+                tmp.write_all(replaced.as_bytes())?;
+            }
+        }
+        tmp.write_all(b"\n")?;
+    }
+    tmp.into_inner()?.sync_all()?;
+    fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(summary)
+}
+
+// `delete_entries` on the current user's stats log
+pub fn delete_from_stats_log(entries: &[HistoryEntry]) -> Result<FileSummary> {
+    delete_entries(&UserDirs::current()?.stats_log(), entries)
+}
+
+// Set when the viewers show the stats log, the only history `d` can delete
+// from. Shell histories and databases belong to other programs.
+static DELETABLE: AtomicBool = AtomicBool::new(false);
+
+pub fn allow_deleting(allowed: bool) {
+    DELETABLE.store(allowed, Ordering::Relaxed);
+}
+
+pub fn deleting_allowed() -> bool {
+    DELETABLE.load(Ordering::Relaxed)
+}
+
+// Remove matching lines from a single file, or the whole file for `All`
+pub fn purge_file(path: &Path, target: &PurgeTarget) -> Result<FileSummary> {
+    match target {
+        PurgeTarget::All if path.exists() => {
+            let removed = count_lines(path)?;
+            fs::remove_file(path)
+                .with_context(|| format!("Failed to delete {}", path.display()))?;
+            Ok(FileSummary {
+                path: path.to_path_buf(),
+                removed,
+                kept: 0,
+                shortened: 0,
+                deleted: true,
+            })
+        }
+        _ => edit_lines(path, |line| {
+            without_commands(line, |entry| target.removes_entry(entry))
+        }),
+    }
+}
+
+// The commands of the stats log at `path` that `entries` were read from,
+// one each, as the history view's `d` picked them. They may have been
+// redacted since, so commands are compared the same way, and given an
+// estimated time, so the time they were saved with is compared.
+pub fn delete_entries(path: &Path, entries: &[HistoryEntry]) -> Result<FileSummary> {
+    let redactor = redactor();
+    let mut remaining: Vec<&HistoryEntry> = entries.iter().collect();
+    edit_lines(path, |line| {
+        without_commands(line, |e| {
+            let found = remaining.iter().position(|wanted| {
+                e.timestamp == wanted.saved_timestamp()
+                    && redactor.redact(&e.command) == wanted.command
+            });
+            found.map(|i| remaining.swap_remove(i)).is_some()
+        })
+    })
+}
+
+// Delete the file or directory at `path`, for `PurgeTarget::All`
+fn delete_path(path: PathBuf) -> Result<FileSummary> {
+    let deleted = path.exists();
+    if path.is_dir() {
+        fs::remove_dir_all(&path)
+    } else if deleted {
+        fs::remove_file(&path)
+    } else {
+        Ok(())
+    }
+    .with_context(|| format!("Failed to delete {}", path.display()))?;
+    Ok(FileSummary {
+        path,
+        removed: 0,
+        kept: 0,
+        shortened: 0,
+        deleted,
+    })
+}
+
+// `All` deletes every data file. A pattern takes out only what it matches:
+// lines of the stats log, rows of the database, commands ranked in saved
+// snapshots, pins, and the cache files holding a match.
+pub fn purge(target: &PurgeTarget) -> Result<Vec<FileSummary>> {
+    let dirs = UserDirs::current()?;
+    let mut summaries = vec![purge_file(&dirs.stats_log(), target)?];
+    let db = store_path(&dirs);
+    let snapshots = snapshots_dir(&dirs);
+    let favorites = favorites_path(&dirs);
+    let cache = cache_dir();
+    let pattern = match target {
+        PurgeTarget::All => {
+            summaries.push(delete_path(db)?);
+            summaries.push(delete_path(snapshots)?);
+            summaries.push(delete_path(favorites)?);
+            summaries.extend(cache.map(delete_path).transpose()?);
+            return Ok(summaries);
+        }
+        PurgeTarget::Matching(pattern) | PurgeTarget::Commands(pattern) => pattern,
+    };
+    let summary = |path: PathBuf, (removed, kept): (usize, usize)| FileSummary {
+        path,
+        removed,
+        kept,
+        shortened: 0,
+        deleted: false,
+    };
+
+    // Copies of history lines, from the shells' histories as well
+    let removed = match db.exists() {
+        true => Store::open(&db)?.remove(|entry| target.removes_entry(entry))?,
+        false => (0, 0),
+    };
+    summaries.push(summary(db, removed));
+    // Saved stats name the commands that were run most
+    let removed = forget_commands(&dirs, |command| pattern.is_match(command))?;
+    summaries.push(summary(snapshots, removed));
+    // Pinned commands go with the commands they name
+    let mut pins = load_favorites(&dirs)?;
+    let before = pins.len();
+    pins.retain(|command| !pattern.is_match(command));
+    if pins.len() < before {
+        save_favorites(&dirs, &pins)?;
+    }
+    summaries.push(summary(favorites, (before - pins.len(), pins.len())));
+    // Parsed copies of every history read, the stats log included
+    if let Some(dir) = cache.filter(|dir| dir.exists()) {
+        let deleted = forget(&dir, |entry| target.removes_entry(entry))
+            .with_context(|| format!("Failed to clear {}", dir.display()))?;
+        summaries.extend(deleted.into_iter().map(|path| FileSummary {
+            path,
+            removed: 0,
+            kept: 0,
+            shortened: 0,
+            deleted: true,
+        }));
+    }
+    Ok(summaries)
+}
+
+pub fn format_summary(summaries: &[FileSummary]) -> String {
+    let mut out = String::new();
+    for summary in summaries {
+        let status = if summary.deleted && summary.removed == 0 {
+            "deleted".to_string()
+        } else if summary.deleted {
+            format!("deleted ({} lines)", summary.removed)
+        } else if summary.removed == 0 && summary.kept == 0 && summary.shortened == 0 {
+            "not found".to_string()
+        } else if summary.shortened > 0 {
+            format!(
+                "removed {} and shortened {} of {} lines",
+                summary.removed,
+                summary.shortened,
+                summary.removed + summary.kept + summary.shortened
+            )
+        } else {
+            format!(
+                "removed {} of {} lines",
+                summary.removed,
+                summary.removed + summary.kept
+            )
+        };
+        out.push_str(&format!("{}: {}\n", summary.path.display(), status));
+    }
+    let removed: usize = summaries.iter().map(|s| s.removed).sum();
+    let shortened: usize = summaries.iter().map(|s| s.shortened).sum();
+    if shortened > 0 {
+        out.push_str(&format!(
+            "{} lines removed and {} shortened in total\n",
+            removed, shortened
+        ));
+    } else {
+        out.push_str(&format!("{} lines removed in total\n", removed));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+
+    fn scratch_file(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn removes_only_matching_lines() {
+        let dir = ScratchDir::new("purge");
+        let path = scratch_file(
+            &dir,
+            "matching",
+            "1|ls|/tmp\n1|export TOKEN=abc|/tmp\n2|git push|/repo\n",
+        );
+        let pattern = PurgeTarget::Matching(Regex::new("TOKEN=").unwrap());
+        let summary = purge_file(&path, &pattern).unwrap();
+
+        assert_eq!(
+            (summary.removed, summary.kept, summary.deleted),
+            (1, 2, false)
+        );
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "1|ls|/tmp\n2|git push|/repo\n"
+        );
+    }
+
+    #[test]
+    fn matches_the_fields_of_json_records() {
+        let dir = ScratchDir::new("purge");
+        let path = scratch_file(
+            &dir,
+            "json",
+            concat!(
+                r#"{"cmd":"echo \"hi\"","cwd":"/tmp","ts":1,"v":1}"#,
+                "\n",
+                r#"{"cmd":"ls","cwd":"/work","ts":2,"v":1}"#,
+                "\n",
+            ),
+        );
+        // Only the record's keys say cmd, ts or v
+        let keys = PurgeTarget::Matching(Regex::new("cmd|ts|v").unwrap());
+        assert_eq!(purge_file(&path, &keys).unwrap().removed, 0);
+        // The quotes match as typed, not as escaped
+        let quoted = PurgeTarget::Matching(Regex::new(r#"echo "hi""#).unwrap());
+        assert_eq!(purge_file(&path, &quoted).unwrap().removed, 1);
+        let directory = PurgeTarget::Matching(Regex::new("^/work$").unwrap());
+        assert_eq!(purge_file(&path, &directory).unwrap().removed, 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+    }
+
+    #[test]
+    fn scrubs_commands_and_deletes_single_entries() {
+        let dir = ScratchDir::new("purge");
+        let path = scratch_file(
+            &dir,
+            "entries",
+            "1|ls|/tmp\n2|curl -u me:hunter2 x|/tmp\n3|ls|/tmp\n4|echo hunter2|/tmp\n",
+        );
+        // Only the command counts, not the directory
+        let scrub = PurgeTarget::Commands(Regex::new("hunter2|tmp").unwrap());
+        assert_eq!(purge_file(&path, &scrub).unwrap().removed, 2);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1|ls|/tmp\n3|ls|/tmp\n");
+        // Commands read back from the database or cache are picked alike
+        let copy = HistoryEntry {
+            command: "ls".to_string(),
+            directory: Some("/tmp".to_string()),
+            ..Default::default()
+        };
+        assert!(!scrub.removes_entry(&copy));
+        assert!(PurgeTarget::Matching(Regex::new("tmp").unwrap()).removes_entry(&copy));
+
+        let picked = HistoryEntry {
+            timestamp: 3,
+            command: "ls".to_string(),
+            ..Default::default()
+        };
+        let summary = delete_entries(&path, &[picked]).unwrap();
+        assert_eq!((summary.removed, summary.kept), (1, 1));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1|ls|/tmp\n");
+
+        // Saved without a time, and shown with an estimated one
+        fs::write(&path, "1|ls|/tmp\n0|make|/tmp\n").unwrap();
+        let estimated = HistoryEntry {
+            timestamp: 1,
+            command: "make".to_string(),
+            recorded_timestamp: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(delete_entries(&path, &[estimated]).unwrap().removed, 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1|ls|/tmp\n");
+    }
+
+    #[test]
+    fn deleting_part_of_a_line_keeps_its_other_commands() {
+        let dir = ScratchDir::new("purge");
+        let path = scratch_file(
+            &dir,
+            "chained",
+            "5|make && make install|/src|work\n6|ls && export TOKEN=abc|/tmp\n",
+        );
+        let picked = HistoryEntry {
+            timestamp: 5,
+            command: "make".to_string(),
+            ..Default::default()
+        };
+        let summary = delete_entries(&path, &[picked]).unwrap();
+        assert_eq!((summary.removed, summary.shortened), (0, 1));
+        let scrub = PurgeTarget::Commands(Regex::new("TOKEN=").unwrap());
+        assert_eq!(purge_file(&path, &scrub).unwrap().shortened, 1);
+
+        let left = fs::read_to_string(&path).unwrap();
+        let commands: Vec<HistoryEntry> = left
+            .lines()
+            .flat_map(|line| parse_stats_log_line(line, ParseMode::Strict).unwrap())
+            .collect();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(
+            (commands[0].timestamp, commands[0].command.as_str()),
+            (5, "make install")
+        );
+        assert_eq!(commands[0].directory.as_deref(), Some("/src"));
+        assert_eq!(commands[0].context.as_deref(), Some("work"));
+        assert_eq!(
+            (commands[1].timestamp, commands[1].command.as_str()),
+            (6, "ls")
+        );
+    }
+
+    #[test]
+    fn all_deletes_the_file() {
+        let dir = ScratchDir::new("purge");
+        let path = scratch_file(&dir, "all", "1|ls|/tmp\n2|pwd|/tmp\n");
+        let summary = purge_file(&path, &PurgeTarget::All).unwrap();
+
+        assert!(summary.deleted);
+        assert_eq!(summary.removed, 2);
+        assert!(!path.exists());
+        assert!(!purge_file(&path, &PurgeTarget::All).unwrap().deleted);
+    }
+}
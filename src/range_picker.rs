@@ -0,0 +1,372 @@
+use anyhow::{Context, Result};
+use chrono::{Datelike, Days, Local, Months, NaiveDate};
+use crossterm::{
+    cursor,
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    style::Stylize,
+};
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::error::ErrorKind;
+use crate::frames;
+use crate::history::HistoryEntry;
+use crate::i18n::{month_abbrev, weekday_initial};
+use crate::time_format::{local_midnight, Period};
+use crate::tr;
+use crate::ui_utils::{draw_box, screen_size};
+
+// Days picked in the date range picker, both included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayRange {
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+}
+
+impl DayRange {
+    // The days from one to the other, in whichever order they were picked
+    pub fn between(a: NaiveDate, b: NaiveDate) -> DayRange {
+        DayRange {
+            first: a.min(b),
+            last: a.max(b),
+        }
+    }
+
+    // First and last second covered
+    pub fn bounds(self) -> (i64, i64) {
+        let end = self
+            .last
+            .succ_opt()
+            .map_or(i64::MAX, |next| local_midnight(next) - 1);
+        (local_midnight(self.first), end)
+    }
+}
+
+impl fmt::Display for DayRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.first == self.last {
+            write!(f, "{}", self.first.format("%Y-%m-%d"))
+        } else {
+            write!(
+                f,
+                "{} – {}",
+                self.first.format("%Y-%m-%d"),
+                self.last.format("%Y-%m-%d")
+            )
+        }
+    }
+}
+
+// What the presets on the left of the picker cover
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Preset {
+    AllTime,
+    // The last n days, today included
+    LastDays(u64),
+    Period(Period),
+}
+
+impl Preset {
+    // The days covered, or None for all time
+    fn range(self, today: NaiveDate) -> Option<DayRange> {
+        match self {
+            Preset::AllTime => None,
+            Preset::LastDays(days) => {
+                let first = today.checked_sub_days(Days::new(days - 1))?;
+                Some(DayRange::between(first, today))
+            }
+            Preset::Period(period) => {
+                let (first, after) = period.days(today);
+                Some(DayRange::between(first, after.pred_opt()?))
+            }
+        }
+    }
+}
+
+const PRESETS: [(&str, Preset); 9] = [
+    ("range-all-time", Preset::AllTime),
+    ("range-today", Preset::Period(Period::Today)),
+    ("range-yesterday", Preset::Period(Period::Yesterday)),
+    ("range-last-7-days", Preset::LastDays(7)),
+    ("range-last-30-days", Preset::LastDays(30)),
+    ("range-this-week", Preset::Period(Period::ThisWeek)),
+    ("range-last-week", Preset::Period(Period::LastWeek)),
+    ("range-this-month", Preset::Period(Period::ThisMonth)),
+    ("range-last-month", Preset::Period(Period::LastMonth)),
+];
+
+// How the picker was closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Picked {
+    Cancelled,
+    AllTime,
+    Range(DayRange),
+}
+
+// The picker's state between key presses
+struct RangePicker {
+    today: NaiveDate,
+    // Keys go to the calendar instead of the presets, switched with Tab
+    on_calendar: bool,
+    preset: usize,
+    cursor: NaiveDate,
+    // First day marked with Space in the calendar, waiting for the last
+    marked: Option<NaiveDate>,
+    // What Enter applies; None is all time
+    selection: Option<DayRange>,
+}
+
+impl RangePicker {
+    fn new(current: Option<DayRange>, today: NaiveDate) -> RangePicker {
+        let preset = PRESETS
+            .iter()
+            .position(|(_, preset)| preset.range(today) == current)
+            .unwrap_or(0);
+        RangePicker {
+            today,
+            on_calendar: false,
+            preset,
+            cursor: current.map_or(today, |range| range.last),
+            marked: None,
+            selection: current,
+        }
+    }
+
+    // The days highlighted: while a first day is marked, up to the cursor
+    fn shown(&self) -> Option<DayRange> {
+        self.marked
+            .map(|first| DayRange::between(first, self.cursor))
+            .or(self.selection)
+    }
+
+    fn move_cursor(&mut self, moved: Option<NaiveDate>) {
+        self.cursor = moved.unwrap_or(self.cursor);
+    }
+
+    fn handle(&mut self, code: KeyCode) -> Option<Picked> {
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => return Some(Picked::Cancelled),
+            KeyCode::Enter => {
+                self.selection = self.shown();
+                return Some(self.selection.map_or(Picked::AllTime, Picked::Range));
+            }
+            KeyCode::Tab => self.on_calendar = !self.on_calendar,
+            KeyCode::Up | KeyCode::Char('k') if !self.on_calendar => {
+                self.preset = self.preset.saturating_sub(1);
+                self.selection = PRESETS[self.preset].1.range(self.today);
+            }
+            KeyCode::Down | KeyCode::Char('j') if !self.on_calendar => {
+                self.preset = (self.preset + 1).min(PRESETS.len() - 1);
+                self.selection = PRESETS[self.preset].1.range(self.today);
+            }
+            _ if !self.on_calendar => {}
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.move_cursor(self.cursor.checked_sub_days(Days::new(1)))
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.move_cursor(self.cursor.checked_add_days(Days::new(1)))
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.move_cursor(self.cursor.checked_sub_days(Days::new(7)))
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.move_cursor(self.cursor.checked_add_days(Days::new(7)))
+            }
+            KeyCode::PageUp | KeyCode::Char('[') => {
+                self.move_cursor(self.cursor.checked_sub_months(Months::new(1)))
+            }
+            KeyCode::PageDown | KeyCode::Char(']') => {
+                self.move_cursor(self.cursor.checked_add_months(Months::new(1)))
+            }
+            KeyCode::Char(' ') => match self.marked.take() {
+                Some(first) => self.selection = Some(DayRange::between(first, self.cursor)),
+                None => self.marked = Some(self.cursor),
+            },
+            _ => {}
+        }
+        None
+    }
+}
+
+// Width and height of the picker's box
+const PICKER_WIDTH: u16 = 60;
+const PICKER_HEIGHT: u16 = 14;
+
+// Where the calendar starts, from the box's left edge
+const CALENDAR_X: u16 = 24;
+
+fn draw_picker(
+    stdout: &mut io::Stdout,
+    picker: &RangePicker,
+    status: &str,
+    (term_width, term_height): (u16, u16),
+) -> Result<()> {
+    let width = PICKER_WIDTH.min(term_width);
+    let height = PICKER_HEIGHT.min(term_height);
+    let x = (term_width - width) / 2;
+    let y = (term_height - height) / 2;
+
+    // Blank the area the box covers, then draw it
+    for row in y..y + height {
+        execute!(stdout, cursor::MoveTo(x, row))?;
+        write!(stdout, "{}", " ".repeat(width as usize))?;
+    }
+    draw_box(stdout, x, y, width, height, Some(&tr!("range-title")))?;
+
+    for (i, (id, _)) in PRESETS.iter().enumerate() {
+        execute!(stdout, cursor::MoveTo(x + 2, y + 1 + i as u16))?;
+        let label = format!("{:<20}", tr!(id));
+        if i == picker.preset && !picker.on_calendar {
+            write!(stdout, "{}", label.black().on_cyan())?;
+        } else if i == picker.preset {
+            write!(stdout, "{}", label.cyan())?;
+        } else {
+            write!(stdout, "{}", label)?;
+        }
+    }
+
+    // The month the cursor is in
+    let calendar_x = x + CALENDAR_X;
+    let month = picker.cursor;
+    execute!(stdout, cursor::MoveTo(calendar_x, y + 1))?;
+    write!(
+        stdout,
+        "{}",
+        format!("{} {}", month_abbrev(month.month()), month.year()).bold()
+    )?;
+    for weekday in 0..7 {
+        execute!(stdout, cursor::MoveTo(calendar_x + weekday * 3 + 1, y + 2))?;
+        write!(stdout, "{}", weekday_initial(weekday as usize).dark_grey())?;
+    }
+    let shown = picker.shown();
+    let first_of_month = month.with_day(1).unwrap_or(month);
+    let lead = first_of_month.weekday().num_days_from_monday() as u16;
+    for day in first_of_month
+        .iter_days()
+        .take_while(|d| d.month() == month.month())
+    {
+        let cell = lead + day.day0() as u16;
+        let label = format!("{:>2}", day.day());
+        let in_range = shown.is_some_and(|range| day >= range.first && day <= range.last);
+        let styled = if day == picker.cursor && picker.on_calendar {
+            label.black().on_cyan().bold()
+        } else if in_range {
+            label.white().on_dark_blue()
+        } else if day == picker.today {
+            label.cyan().bold()
+        } else {
+            label.stylize()
+        };
+        execute!(
+            stdout,
+            cursor::MoveTo(calendar_x + (cell % 7) * 3, y + 3 + cell / 7)
+        )?;
+        write!(stdout, "{}", styled)?;
+    }
+
+    execute!(stdout, cursor::MoveTo(x + 2, y + height - 3))?;
+    write!(stdout, "{}", status)?;
+    let controls = if picker.on_calendar {
+        tr!("range-controls-calendar")
+    } else {
+        tr!("range-controls-presets")
+    };
+    execute!(stdout, cursor::MoveTo(x + 2, y + height - 2))?;
+    write!(stdout, "{}", controls.dark_grey())?;
+    stdout.flush().context(ErrorKind::Terminal)?;
+    Ok(())
+}
+
+// Pick the days the views cover, over whatever screen is showing. Starts
+// from `current`; ranges with no commands in `entries` can't be applied.
+pub fn pick_range(
+    stdout: &mut io::Stdout,
+    entries: &[HistoryEntry],
+    current: Option<DayRange>,
+) -> Result<Picked> {
+    let mut picker = RangePicker::new(current, Local::now().date_naive());
+    let mut empty = false;
+    loop {
+        let count = match picker.shown() {
+            Some(range) => {
+                let (start, end) = range.bounds();
+                entries
+                    .iter()
+                    .filter(|e| e.timestamp >= start && e.timestamp <= end)
+                    .count()
+            }
+            None => entries.len(),
+        };
+        let label = picker
+            .shown()
+            .map_or_else(|| tr!("range-all-time"), |range| range.to_string());
+        let status = if std::mem::take(&mut empty) {
+            tr!("range-empty")
+        } else {
+            tr!("range-commands", range = label, count = count)
+        };
+        draw_picker(stdout, &picker, &status, screen_size()?).context(ErrorKind::Terminal)?;
+
+        let Event::Key(KeyEvent {
+            code, modifiers, ..
+        }) = frames::next_event()?
+        else {
+            continue;
+        };
+        if code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
+            return Ok(Picked::Cancelled);
+        }
+        match picker.handle(code) {
+            Some(Picked::Range(_)) if count == 0 => empty = true,
+            Some(picked) => return Ok(picked),
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn presets_and_marked_days_make_the_range() {
+        // Wednesday
+        let today = date(2024, 3, 13);
+        let mut picker = RangePicker::new(None, today);
+        assert_eq!(picker.preset, 0);
+        picker.handle(KeyCode::Down);
+        picker.handle(KeyCode::Down);
+        picker.handle(KeyCode::Down);
+        assert_eq!(
+            picker.selection,
+            Some(DayRange::between(date(2024, 3, 7), today))
+        );
+
+        // Reopening on last month finds its preset
+        let last_month = DayRange::between(date(2024, 2, 1), date(2024, 2, 29));
+        assert_eq!(
+            RangePicker::new(Some(last_month), today).preset,
+            PRESETS.len() - 1
+        );
+
+        // On the calendar: mark the 13th, go back a week and a day, apply
+        picker.handle(KeyCode::Tab);
+        picker.handle(KeyCode::Char(' '));
+        picker.handle(KeyCode::Up);
+        picker.handle(KeyCode::Left);
+        assert_eq!(
+            picker.handle(KeyCode::Enter),
+            Some(Picked::Range(DayRange::between(
+                date(2024, 3, 5),
+                date(2024, 3, 13)
+            )))
+        );
+        assert_eq!(picker.handle(KeyCode::Esc), Some(Picked::Cancelled));
+        assert_eq!(last_month.to_string(), "2024-02-01 – 2024-02-29");
+    }
+}
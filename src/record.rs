@@ -0,0 +1,150 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use std::{fs::OpenOptions, io::Write, path::Path};
+
+use crate::history::HistoryEntry;
+use crate::parser::RECORD_VERSION;
+
+// One JSON line for the stats log, leaving out anything the hook didn't know
+pub fn format_record(entry: &HistoryEntry) -> String {
+    let mut record = Map::new();
+    record.insert("v".to_string(), json!(RECORD_VERSION));
+    record.insert("ts".to_string(), json!(entry.timestamp));
+    record.insert("cmd".to_string(), json!(entry.command));
+    if let Some(directory) = &entry.directory {
+        record.insert("cwd".to_string(), json!(directory));
+    }
+    if let Some(duration) = entry.duration {
+        record.insert("duration".to_string(), json!(duration));
+    }
+    if let Some(exit_code) = entry.exit_code {
+        record.insert("exit".to_string(), json!(exit_code));
+    }
+    if let Some(context) = &entry.context {
+        record.insert("context".to_string(), json!(context));
+    }
+    if let Some(session_id) = &entry.session_id {
+        record.insert("session".to_string(), json!(session_id));
+    }
+    if let Some(commit) = &entry.commit {
+        record.insert("commit".to_string(), json!(commit));
+    }
+    Value::Object(record).to_string()
+}
+
+// Appends are a single write of a whole line, so hooks in several shells
+// at once don't interleave records
+pub fn append_record(path: &Path, entry: &HistoryEntry) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(format!("{}\n", format_record(entry)).as_bytes())
+        .with_context(|| format!("Failed to write to {}", path.display()))
+}
+
+// Quote for a POSIX shell, e.g. a path with spaces
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+// Hook for .zshrc that records every command with `program record`. The
+// command and directory are captured before it runs; duration and exit
+// status once the prompt comes back. Recording runs in the background so
+// the prompt never waits for it. With `git_head`, the git HEAD of the
+// command's directory is recorded too, and with `context_command` the
+// context label it prints, both looked up in the background job.
+pub fn zsh_hook(program: &str, git_head: bool, context_command: Option<&str>) -> String {
+    let (mut init_args, commit) = if git_head {
+        (
+            " --git-head".to_string(),
+            " \\\n        --commit \"$(git -C \"$_cli_wrapped_cwd\" rev-parse -q --verify HEAD 2>/dev/null)\"",
+        )
+    } else {
+        (String::new(), "")
+    };
+    let context = match context_command {
+        Some(command) => {
+            init_args.push_str(&format!(" --context-command {}", shell_quote(command)));
+            format!(" \\\n        --context \"$({} 2>/dev/null)\"", command)
+        }
+        None => String::new(),
+    };
+    format!(
+        r#"# Record every command with cli-wrapped. Add to ~/.zshrc:
+#   eval "$({program} init zsh{init_args})"
+zmodload zsh/datetime
+autoload -Uz add-zsh-hook
+
+# Tells this shell's commands apart from those of other shells. Inside tmux
+# the session's name and the pane are added, for `report panes`.
+_cli_wrapped_session=$$-$EPOCHSECONDS
+if [[ -n $TMUX_PANE ]]; then
+    _cli_wrapped_session+="-tmux-$(tmux display-message -p -t "$TMUX_PANE" '#S' 2>/dev/null)$TMUX_PANE"
+fi
+
+_cli_wrapped_preexec() {{
+    _cli_wrapped_cmd=$1
+    _cli_wrapped_cwd=$PWD
+    _cli_wrapped_start=$EPOCHSECONDS
+}}
+
+_cli_wrapped_precmd() {{
+    local exit_code=$?
+    [[ -n $_cli_wrapped_start ]] || return
+    {program} record --cmd "$_cli_wrapped_cmd" --cwd "$_cli_wrapped_cwd" \
+        --start "$_cli_wrapped_start" --duration $(( EPOCHSECONDS - _cli_wrapped_start )) \
+        --exit "$exit_code" --session "$_cli_wrapped_session"{commit}{context} &!
+    unset _cli_wrapped_cmd _cli_wrapped_cwd _cli_wrapped_start
+}}
+
+add-zsh-hook preexec _cli_wrapped_preexec
+add-zsh-hook precmd _cli_wrapped_precmd
+"#,
+        program = shell_quote(program),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_stats_log_line, ParseMode};
+
+    #[test]
+    fn records_round_trip_through_the_parser() {
+        let entry = HistoryEntry {
+            timestamp: 1681234567,
+            command: "printf '%s|%s:%s\\n' \"a\" b c\nuptime".to_string(),
+            directory: Some("/home/me/my repo".to_string()),
+            duration: Some(4),
+            exit_code: Some(130),
+            context: Some("home-vpn".to_string()),
+            session_id: Some("4242-1681234000".to_string()),
+            commit: Some("9fceb02".to_string()),
+            host: None,
+            recorded_timestamp: None,
+        };
+        let line = format_record(&entry);
+        assert!(!line.contains('\n'));
+        assert_eq!(
+            parse_stats_log_line(&line, ParseMode::Strict).unwrap(),
+            [entry]
+        );
+    }
+
+    #[test]
+    fn hook_quotes_the_program_path() {
+        let hook = zsh_hook("/opt/it's here/cli-wrapped", false, None);
+        assert!(hook.contains(r"'/opt/it'\''s here/cli-wrapped' record --cmd"));
+        assert!(!hook.contains("--commit"));
+        assert!(!hook.contains("--context"));
+        assert!(hook.contains(r#"--session "$_cli_wrapped_session""#));
+        assert!(hook.contains(r#"_cli_wrapped_session+="-tmux-"#));
+        assert!(zsh_hook("cli-wrapped", true, None).contains("init zsh --git-head"));
+
+        let hook = zsh_hook("cli-wrapped", false, Some("~/bin/where-am-i"));
+        assert!(hook.contains(r#"--context "$(~/bin/where-am-i 2>/dev/null)" &!"#));
+        assert!(hook.contains("init zsh --context-command '~/bin/where-am-i'"));
+    }
+}
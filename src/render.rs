@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Widget};
+use std::io::{self, Write};
+use unicode_width::UnicodeWidthStr;
+
+use crate::error::ErrorKind;
+use crate::ui_utils::screen_size;
+
+// Marks cells no widget drew in, which are left as they are on screen
+const UNTOUCHED: &str = "\u{0}";
+
+// A frame drawn with ratatui widgets, then written over what's on screen in
+// one go. Widgets are clipped to the terminal, and what they leave out is
+// kept, so a view can mix them with text it writes itself.
+pub struct Canvas {
+    buffer: Buffer,
+}
+
+impl Canvas {
+    pub fn new(area: Rect) -> Canvas {
+        Canvas {
+            buffer: Buffer::filled(area, Cell::new(UNTOUCHED)),
+        }
+    }
+
+    // The whole terminal
+    pub fn screen() -> Result<Canvas> {
+        let (width, height) = screen_size()?;
+        Ok(Canvas::new(Rect::new(0, 0, width, height)))
+    }
+
+    pub fn area(&self) -> Rect {
+        self.buffer.area
+    }
+
+    pub fn render(&mut self, widget: impl Widget, area: Rect) {
+        let area = area.intersection(self.buffer.area);
+        if !area.is_empty() {
+            widget.render(area, &mut self.buffer);
+        }
+    }
+
+    pub fn flush(&self, stdout: &mut io::Stdout) -> Result<()> {
+        let mut backend = CrosstermBackend::new(&mut *stdout);
+        backend
+            .draw(drawn_cells(&self.buffer).into_iter())
+            .and_then(|_| Backend::flush(&mut backend))
+            .context(ErrorKind::Terminal)
+    }
+}
+
+// The cells widgets drew in, less those a wide character before them covers
+fn drawn_cells(buffer: &Buffer) -> Vec<(u16, u16, &Cell)> {
+    let mut cells = Vec::new();
+    let mut covered = 0;
+    for (i, cell) in buffer.content.iter().enumerate() {
+        let (x, y) = buffer.pos_of(i);
+        if x == buffer.area.x {
+            covered = 0;
+        }
+        if covered > 0 {
+            covered -= 1;
+            continue;
+        }
+        if cell.symbol() == UNTOUCHED {
+            continue;
+        }
+        covered = cell.symbol().width().saturating_sub(1);
+        cells.push((x, y, cell));
+    }
+    cells
+}
+
+// Draw one widget straight to the screen
+pub fn render_widget(stdout: &mut io::Stdout, widget: impl Widget, area: Rect) -> Result<()> {
+    let mut canvas = Canvas::screen()?;
+    canvas.render(widget, area);
+    canvas.flush(stdout)?;
+    stdout.flush().context(ErrorKind::Terminal)?;
+    Ok(())
+}
+
+// A box with its title centered in the top border, as every panel has
+pub fn titled(title: &str) -> Block<'static> {
+    Block::bordered().title(Line::from(format!(" {} ", title).light_cyan()).centered())
+}
+
+// `key` in grey, padded to line the values up, then `value`
+pub fn labeled<'a>(key: &str, value: impl Into<Span<'a>>) -> Line<'a> {
+    Line::from(vec![
+        Span::from(format!("{:<14} ", key)).fg(Color::DarkGray),
+        value.into(),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::widgets::Paragraph;
+
+    #[test]
+    fn only_drawn_cells_are_written() {
+        let mut canvas = Canvas::new(Rect::new(0, 0, 12, 3));
+        canvas.render(titled("Hi"), Rect::new(0, 0, 8, 3));
+        canvas.render(Paragraph::new("界a"), Rect::new(9, 1, 3, 1));
+        let cells = drawn_cells(&canvas.buffer);
+        let row = |y: u16| -> String {
+            cells
+                .iter()
+                .filter(|(_, cell_y, _)| *cell_y == y)
+                .map(|(_, _, cell)| cell.symbol())
+                .collect()
+        };
+        assert_eq!(row(0), "┌─ Hi ─┐");
+        // The box's inside is left alone, and the wide character covers two columns
+        assert_eq!(row(1), "││界a");
+        assert_eq!(row(2), "└──────┘");
+        // Widgets are cut off at the edge of the screen
+        canvas.render(Paragraph::new("overflowing"), Rect::new(10, 2, 20, 1));
+        assert_eq!(canvas.buffer[(11, 2)].symbol(), "v");
+    }
+}
@@ -0,0 +1,1653 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::aggregate::period_bounds;
+use crate::aggregate::{is_editor_command, program, sorted_counts};
+use crate::days::{day_counts, local_day, streaks, Streaks};
+use crate::history::{repository_root, HistoryEntry};
+use crate::normalize;
+use crate::ui_utils::home_relative;
+
+// Steps further apart than this belong to different cycles
+const MAX_CYCLE_GAP: i64 = 30 * 60;
+
+// Longer gaps between commands count as idle rather than active time
+const IDLE_GAP: i64 = 30 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Edit,
+    Build,
+    Test,
+}
+
+// One edit followed by build and/or test runs in the same directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    pub project: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Cycle {
+    pub fn duration(&self) -> i64 {
+        self.end - self.start
+    }
+}
+
+// Where a command fits in an edit -> build -> test loop, if at all
+fn step(command: &str) -> Option<Step> {
+    if is_editor_command(command) {
+        return Some(Step::Edit);
+    }
+
+    let mut words = command.split_whitespace();
+    let program = words.next()?;
+    let program = program.rsplit('/').next().unwrap_or(program);
+    let mut subcommand = words.next().unwrap_or("");
+    if subcommand == "run" && matches!(program, "npm" | "yarn" | "pnpm") {
+        subcommand = words.next().unwrap_or("");
+    }
+
+    match (program, subcommand) {
+        ("cargo", "test" | "t" | "nextest")
+        | ("go" | "dotnet" | "npm" | "yarn" | "pnpm" | "mvn" | "gradle" | "gradlew", "test")
+        | ("make", "test" | "check")
+        | ("pytest" | "jest" | "vitest" | "tox" | "ctest", _) => Some(Step::Test),
+        ("cargo", "build" | "b" | "check" | "c" | "clippy" | "run" | "r")
+        | ("go" | "dotnet" | "npm" | "yarn" | "pnpm" | "gradle" | "gradlew", "build")
+        | ("mvn", "compile" | "package" | "install")
+        | ("cmake", "--build")
+        | ("make" | "ninja" | "gcc" | "g++" | "clang" | "rustc" | "tsc" | "javac", _) => {
+            Some(Step::Build)
+        }
+        _ => None,
+    }
+}
+
+// Find edit -> build/test cycles. A cycle starts at the first edit after the
+// previous cycle and ends at the last build or test run before the next
+// edit; edits that are never followed by a run don't count.
+pub fn find_cycles(entries: &[HistoryEntry]) -> Vec<Cycle> {
+    let mut by_project: HashMap<&str, Vec<(i64, Step)>> = HashMap::new();
+    for entry in entries.iter().filter(|e| e.timestamp > 0) {
+        if let (Some(dir), Some(step)) = (&entry.directory, step(&entry.command)) {
+            by_project
+                .entry(dir)
+                .or_default()
+                .push((entry.timestamp, step));
+        }
+    }
+
+    let mut cycles = Vec::new();
+    for (project, mut steps) in by_project {
+        steps.sort_by_key(|&(ts, _)| ts);
+
+        // (start, last step, whether a build/test has run yet)
+        let mut current: Option<(i64, i64, bool)> = None;
+        let mut close = |current: &mut Option<(i64, i64, bool)>| {
+            if let Some((start, end, true)) = current.take() {
+                cycles.push(Cycle {
+                    project: project.to_string(),
+                    start,
+                    end,
+                });
+            }
+        };
+
+        for (ts, step) in steps {
+            if current.is_some_and(|(_, last, _)| ts - last > MAX_CYCLE_GAP) {
+                close(&mut current);
+            }
+            current = match (current, step) {
+                (Some((_, _, true)), Step::Edit) => {
+                    close(&mut current);
+                    Some((ts, ts, false))
+                }
+                (Some((start, _, _)), Step::Edit) => Some((start, ts, false)),
+                (None, Step::Edit) => Some((ts, ts, false)),
+                (Some((start, _, _)), Step::Build | Step::Test) => Some((start, ts, true)),
+                (None, Step::Build | Step::Test) => None,
+            };
+        }
+        close(&mut current);
+    }
+
+    cycles.sort_by(|a, b| {
+        a.start
+            .cmp(&b.start)
+            .then_with(|| a.project.cmp(&b.project))
+    });
+    cycles
+}
+
+// Compact duration such as "45s", "6m 30s" or "1h 05m"
+pub fn format_duration(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    if seconds < 60 {
+        format!("{}s", seconds)
+    } else if seconds < 3600 {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}h {:02}m", seconds / 3600, (seconds % 3600) / 60)
+    }
+}
+
+// Average cycle length and cycles per active day, per project
+pub fn loops_report(entries: &[HistoryEntry]) -> String {
+    let cycles = find_cycles(entries);
+    let mut out = String::new();
+    if cycles.is_empty() {
+        out.push_str("No edit/build/test cycles found.\n");
+        return out;
+    }
+
+    let mut projects: BTreeMap<&str, Vec<&Cycle>> = BTreeMap::new();
+    for cycle in &cycles {
+        projects.entry(&cycle.project).or_default().push(cycle);
+    }
+    let mut projects: Vec<(&str, Vec<&Cycle>)> = projects.into_iter().collect();
+    projects.sort_by_key(|(_, cycles)| std::cmp::Reverse(cycles.len()));
+
+    let total: i64 = cycles.iter().map(Cycle::duration).sum();
+    let _ = writeln!(
+        out,
+        "{} cycles, {} on average\n",
+        cycles.len(),
+        format_duration(total / cycles.len() as i64)
+    );
+
+    let width = projects
+        .iter()
+        .map(|(project, _)| project.len())
+        .max()
+        .unwrap_or(0)
+        .max("Project".len());
+    let _ = writeln!(
+        out,
+        "{:<width$}  {:>6}  {:>9}  {:>10}",
+        "Project",
+        "Cycles",
+        "Avg cycle",
+        "Cycles/day",
+        width = width
+    );
+    for (project, project_cycles) in projects {
+        let total: i64 = project_cycles.iter().map(|c| c.duration()).sum();
+        let days: HashSet<NaiveDate> = project_cycles
+            .iter()
+            .filter_map(|c| local_day(c.start))
+            .collect();
+        let _ = writeln!(
+            out,
+            "{:<width$}  {:>6}  {:>9}  {:>10.1}",
+            project,
+            project_cycles.len(),
+            format_duration(total / project_cycles.len() as i64),
+            project_cycles.len() as f64 / days.len().max(1) as f64,
+            width = width
+        );
+    }
+    out
+}
+
+// What a command does to the checked out branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BranchChange<'a> {
+    To(&'a str),
+    // `git checkout -` / `git switch -`
+    Previous,
+}
+
+fn branch_change(command: &str) -> Option<BranchChange<'_>> {
+    let mut words = command.split_whitespace();
+    if words.next() != Some("git") {
+        return None;
+    }
+    let subcommand = words.next()?;
+    if subcommand != "checkout" && subcommand != "switch" {
+        return None;
+    }
+
+    let mut target = None;
+    while let Some(word) = words.next() {
+        match word {
+            // Restoring files, not switching branches
+            "--" | "." => return None,
+            "-" => return Some(BranchChange::Previous),
+            "-b" | "-B" | "-c" | "-C" | "--orphan" => return words.next().map(BranchChange::To),
+            _ if word.starts_with('-') => {}
+            _ if target.is_none() => target = Some(word),
+            // `git checkout <branch> <path>` restores a path
+            _ => return None,
+        }
+    }
+    target.map(BranchChange::To)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchActivity {
+    pub branch: String,
+    pub project: String,
+    pub days: usize,
+    pub commands: usize,
+    pub active_seconds: i64,
+}
+
+// Attribute commands to branches. History doesn't record the branch, so it
+// is inferred per directory from the last `git checkout` / `git switch` run
+// there; commands before the first one aren't attributed.
+pub fn branch_activity(entries: &[HistoryEntry]) -> Vec<BranchActivity> {
+    let mut by_project: HashMap<&str, Vec<&HistoryEntry>> = HashMap::new();
+    for entry in entries.iter().filter(|e| e.timestamp > 0) {
+        if let Some(dir) = &entry.directory {
+            by_project.entry(dir).or_default().push(entry);
+        }
+    }
+
+    let mut activity: BTreeMap<(&str, &str), (HashSet<NaiveDate>, usize, i64)> = BTreeMap::new();
+    for (project, mut project_entries) in by_project {
+        project_entries.sort_by_key(|e| e.timestamp);
+
+        let mut current: Option<&str> = None;
+        let mut previous: Option<&str> = None;
+        let mut last_seen: Option<(&str, i64)> = None;
+        for entry in project_entries {
+            match branch_change(&entry.command) {
+                Some(BranchChange::To(branch)) => {
+                    previous = current.replace(branch);
+                }
+                Some(BranchChange::Previous) if previous.is_some() => {
+                    std::mem::swap(&mut previous, &mut current);
+                }
+                Some(BranchChange::Previous) => {}
+                None => {}
+            }
+
+            let Some(branch) = current else {
+                continue;
+            };
+            let (days, commands, active) = activity.entry((branch, project)).or_default();
+            days.extend(local_day(entry.timestamp));
+            *commands += 1;
+            if let Some((last_branch, last_ts)) = last_seen {
+                let gap = entry.timestamp - last_ts;
+                if last_branch == branch && gap <= IDLE_GAP {
+                    *active += gap;
+                }
+            }
+            last_seen = Some((branch, entry.timestamp));
+        }
+    }
+
+    let mut activity: Vec<BranchActivity> = activity
+        .into_iter()
+        .map(
+            |((branch, project), (days, commands, active_seconds))| BranchActivity {
+                branch: branch.to_string(),
+                project: project.to_string(),
+                days: days.len(),
+                commands,
+                active_seconds,
+            },
+        )
+        .collect();
+    activity.sort_by_key(|a| std::cmp::Reverse(a.commands));
+    activity
+}
+
+// Days, commands and active time per branch
+pub fn branches_report(entries: &[HistoryEntry]) -> String {
+    let activity = branch_activity(entries);
+    let mut out = String::new();
+    if activity.is_empty() {
+        out.push_str(
+            "No branch activity found. Branches are inferred from git checkout/switch commands.\n",
+        );
+        return out;
+    }
+
+    let branch_width = activity
+        .iter()
+        .map(|a| a.branch.len())
+        .max()
+        .unwrap_or(0)
+        .max("Branch".len());
+    let project_width = activity
+        .iter()
+        .map(|a| a.project.len())
+        .max()
+        .unwrap_or(0)
+        .max("Project".len());
+    let _ = writeln!(
+        out,
+        "{:<bw$}  {:<pw$}  {:>4}  {:>8}  {:>7}",
+        "Branch",
+        "Project",
+        "Days",
+        "Commands",
+        "Active",
+        bw = branch_width,
+        pw = project_width
+    );
+    for a in &activity {
+        let _ = writeln!(
+            out,
+            "{:<bw$}  {:<pw$}  {:>4}  {:>8}  {:>7}",
+            a.branch,
+            a.project,
+            a.days,
+            a.commands,
+            format_duration(a.active_seconds),
+            bw = branch_width,
+            pw = project_width
+        );
+    }
+    out
+}
+
+// Programs compared between users in `users_report`
+const COMPARED_PROGRAMS: usize = 10;
+
+// One user's activity on a shared host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserActivity {
+    pub user: String,
+    pub commands: usize,
+    pub unique: usize,
+    pub days: usize,
+    // Most used programs first
+    pub programs: Vec<(String, usize)>,
+}
+
+pub fn user_activity(user: &str, entries: &[HistoryEntry]) -> UserActivity {
+    let mut programs: HashMap<&str, usize> = HashMap::new();
+    for entry in entries {
+        *programs.entry(program(&entry.command)).or_insert(0) += 1;
+    }
+    let unique: HashSet<&str> = entries.iter().map(|e| e.command.as_str()).collect();
+    let days: HashSet<NaiveDate> = entries
+        .iter()
+        .filter_map(|e| local_day(e.timestamp))
+        .collect();
+    UserActivity {
+        user: user.to_string(),
+        commands: entries.len(),
+        unique: unique.len(),
+        days: days.len(),
+        programs: sorted_counts(programs),
+    }
+}
+
+// Per-user totals, a combined row, and the most used programs side by side.
+// Users are listed busiest first; anonymizing replaces their names with
+// "user-1", "user-2", ... in that order. A user whose history couldn't be
+// read comes with the reason, and is listed as unreadable rather than idle.
+pub fn users_report(
+    users: &[(String, Result<Vec<HistoryEntry>, String>)],
+    anonymize: bool,
+) -> String {
+    // Each user's activity, with why their history couldn't be read
+    let mut read: Vec<(UserActivity, Option<&str>)> = users
+        .iter()
+        .map(|(user, entries)| match entries {
+            Ok(entries) => (user_activity(user, entries), None),
+            Err(reason) => (user_activity(user, &[]), Some(reason.as_str())),
+        })
+        .collect();
+    read.sort_by(|(a, _), (b, _)| {
+        b.commands
+            .cmp(&a.commands)
+            .then_with(|| a.user.cmp(&b.user))
+    });
+    let (mut activity, reasons): (Vec<UserActivity>, Vec<Option<&str>>) = read.into_iter().unzip();
+    if anonymize {
+        for (i, user) in activity.iter_mut().enumerate() {
+            user.user = format!("user-{}", i + 1);
+        }
+    }
+    let all_entries: Vec<HistoryEntry> = users
+        .iter()
+        .filter_map(|(_, entries)| entries.as_ref().ok())
+        .flat_map(|entries| entries.iter().cloned())
+        .collect();
+    let all = user_activity("all", &all_entries);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{} users, {} commands\n", activity.len(), all.commands);
+
+    let width = activity
+        .iter()
+        .map(|a| a.user.len())
+        .max()
+        .unwrap_or(0)
+        .max("User".len());
+    let _ = writeln!(
+        out,
+        "{:<width$}  {:>8}  {:>6}  {:>4}  Top programs",
+        "User",
+        "Commands",
+        "Unique",
+        "Days",
+        width = width
+    );
+    for (a, reason) in activity.iter().zip(reasons).chain([(&all, None)]) {
+        let top = if let Some(reason) = reason {
+            format!("(unreadable: {})", reason)
+        } else if a.commands == 0 {
+            "(no history found)".to_string()
+        } else {
+            a.programs
+                .iter()
+                .take(3)
+                .map(|(program, _)| program.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let _ = writeln!(
+            out,
+            "{:<width$}  {:>8}  {:>6}  {:>4}  {}",
+            a.user,
+            a.commands,
+            a.unique,
+            a.days,
+            top,
+            width = width
+        );
+    }
+
+    if all.programs.is_empty() {
+        return out;
+    }
+    let program_width = all
+        .programs
+        .iter()
+        .take(COMPARED_PROGRAMS)
+        .map(|(program, _)| program.len())
+        .max()
+        .unwrap_or(0)
+        .max("Program".len());
+    let _ = write!(out, "\n{:<pw$}", "Program", pw = program_width);
+    for a in &activity {
+        let _ = write!(out, "  {:>w$}", a.user, w = a.user.len().max(5));
+    }
+    out.push('\n');
+    for (program, _) in all.programs.iter().take(COMPARED_PROGRAMS) {
+        let _ = write!(out, "{:<pw$}", program, pw = program_width);
+        for a in &activity {
+            let count = a
+                .programs
+                .iter()
+                .find(|(p, _)| p == program)
+                .map_or(0, |(_, count)| *count);
+            let _ = write!(out, "  {:>w$}", count, w = a.user.len().max(5));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// A re-run further than this after a failure is a fresh attempt, not a retry
+const RETRY_GAP: i64 = 10 * 60;
+
+// Exit status of a command interrupted with Ctrl-C, which says nothing
+// about whether it would have worked
+const INTERRUPTED: i32 = 130;
+
+fn failed(entry: &HistoryEntry) -> bool {
+    matches!(entry.exit_code, Some(code) if code != 0 && code != INTERRUPTED)
+}
+
+// How often one command was re-run straight after failing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryStats {
+    pub command: String,
+    // Failures that were immediately re-run
+    pub streaks: usize,
+    // Re-runs across all streaks
+    pub retries: usize,
+    // Streaks that ended with the command succeeding
+    pub recovered: usize,
+}
+
+// Find failures followed by the same command, without anything in between,
+// within RETRY_GAP. Consecutive re-runs form one streak, which recovers if
+// the last of them succeeds. Commands are followed per shell session where
+// one was recorded, since concurrent shells interleave in the log.
+pub fn retry_stats(entries: &[HistoryEntry]) -> Vec<RetryStats> {
+    let mut sessions: HashMap<Option<&str>, Vec<&HistoryEntry>> = HashMap::new();
+    for entry in entries.iter().filter(|e| e.timestamp > 0) {
+        sessions
+            .entry(entry.session_id.as_deref())
+            .or_default()
+            .push(entry);
+    }
+
+    let mut stats: HashMap<&str, RetryStats> = HashMap::new();
+    for mut session in sessions.into_values() {
+        session.sort_by_key(|e| e.timestamp);
+        let mut i = 0;
+        while i < session.len() {
+            let first = session[i];
+            let mut end = i;
+            while end + 1 < session.len()
+                && failed(session[end])
+                && session[end + 1].command == first.command
+                && session[end + 1].timestamp - session[end].timestamp <= RETRY_GAP
+            {
+                end += 1;
+            }
+            if end > i {
+                let command = stats.entry(&first.command).or_insert_with(|| RetryStats {
+                    command: first.command.clone(),
+                    streaks: 0,
+                    retries: 0,
+                    recovered: 0,
+                });
+                command.streaks += 1;
+                command.retries += end - i;
+                if session[end].exit_code == Some(0) {
+                    command.recovered += 1;
+                }
+            }
+            i = end + 1;
+        }
+    }
+
+    let mut stats: Vec<RetryStats> = stats.into_values().collect();
+    stats.sort_by(|a, b| {
+        b.streaks
+            .cmp(&a.streaks)
+            .then(b.retries.cmp(&a.retries))
+            .then_with(|| a.command.cmp(&b.command))
+    });
+    stats
+}
+
+// Commands most often retried after failing, with how the retries went
+pub fn retries_report(entries: &[HistoryEntry]) -> String {
+    let mut out = String::new();
+    if !entries.iter().any(|e| e.exit_code.is_some()) {
+        out.push_str(
+            "No exit codes recorded. Record them with `init zsh`, or read them with --source atuin or --source histdb.\n",
+        );
+        return out;
+    }
+    let stats = retry_stats(entries);
+    if stats.is_empty() {
+        out.push_str("No commands were re-run after failing.\n");
+        return out;
+    }
+
+    let width = stats
+        .iter()
+        .map(|s| s.command.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Command".len());
+    let _ = writeln!(
+        out,
+        "{:<width$}  {:>7}  {:>7}  {:>9}  {:>11}",
+        "Command",
+        "Retried",
+        "Retries",
+        "Recovered",
+        "Avg retries",
+        width = width
+    );
+    for s in &stats {
+        let _ = writeln!(
+            out,
+            "{:<width$}  {:>7}  {:>7}  {:>8}%  {:>11.1}",
+            s.command,
+            s.streaks,
+            s.retries,
+            s.recovered * 100 / s.streaks,
+            s.retries as f64 / s.streaks as f64,
+            width = width
+        );
+    }
+    out
+}
+
+// Git commits are shown abbreviated, as git does
+pub fn short_commit(commit: &str) -> &str {
+    commit.get(..7).unwrap_or(commit)
+}
+
+// Entries with a recorded HEAD in `directory`, oldest first
+fn recorded_heads<'a>(
+    entries: &'a [HistoryEntry],
+    directory: &Option<String>,
+) -> Vec<&'a HistoryEntry> {
+    let mut recorded: Vec<&HistoryEntry> = entries
+        .iter()
+        .filter(|e| e.commit.is_some() && e.directory == *directory)
+        .collect();
+    recorded.sort_by_key(|e| e.timestamp);
+    recorded
+}
+
+// The commit checked out when `entry` ran, and the next commit made after
+// it, going by the HEADs recorded in the same directory
+pub fn commits_around<'a>(
+    entries: &'a [HistoryEntry],
+    entry: &HistoryEntry,
+) -> (Option<&'a str>, Option<&'a str>) {
+    let recorded = recorded_heads(entries, &entry.directory);
+    let before = recorded
+        .iter()
+        .rev()
+        .find(|e| e.timestamp < entry.timestamp)
+        .and_then(|e| e.commit.as_deref());
+    let after = recorded
+        .iter()
+        .filter(|e| e.timestamp >= entry.timestamp)
+        .filter_map(|e| e.commit.as_deref())
+        .find(|&commit| Some(commit) != before);
+    (before, after)
+}
+
+// The command that left HEAD at a commit, and the commands run in the same
+// directory since HEAD last moved before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitWork<'a> {
+    pub made_by: &'a HistoryEntry,
+    pub previous: Option<&'a str>,
+    pub leading: Vec<&'a HistoryEntry>,
+}
+
+// `commit` may be abbreviated. The first recorded command with HEAD at the
+// commit is taken to have made it.
+pub fn commit_work<'a>(entries: &'a [HistoryEntry], commit: &str) -> Option<CommitWork<'a>> {
+    let made_by = entries
+        .iter()
+        .filter(|e| e.commit.as_deref().is_some_and(|c| c.starts_with(commit)))
+        .min_by_key(|e| e.timestamp)?;
+    let recorded = recorded_heads(entries, &made_by.directory);
+    let earlier: Vec<&HistoryEntry> = recorded
+        .into_iter()
+        .filter(|e| e.timestamp < made_by.timestamp)
+        .collect();
+    let previous = earlier.last().and_then(|e| e.commit.as_deref());
+    let mut leading: Vec<&HistoryEntry> = earlier
+        .iter()
+        .rev()
+        .take_while(|e| e.commit.as_deref() == previous)
+        .copied()
+        .collect();
+    leading.reverse();
+    Some(CommitWork {
+        made_by,
+        previous,
+        leading,
+    })
+}
+
+pub(crate) fn format_time(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+// Which command made a commit, and the work that led up to it
+pub fn commit_report(entries: &[HistoryEntry], commit: &str) -> String {
+    let mut out = String::new();
+    let Some(work) = commit_work(entries, commit) else {
+        let _ = writeln!(
+            out,
+            "No recorded command left HEAD at {}. HEADs are only recorded by `init zsh --git-head`.",
+            commit
+        );
+        return out;
+    };
+
+    let made = work.made_by;
+    let _ = writeln!(
+        out,
+        "{} was made by `{}` in {} at {}",
+        short_commit(made.commit.as_deref().unwrap_or(commit)),
+        made.command,
+        made.directory.as_deref().unwrap_or("an unknown directory"),
+        format_time(made.timestamp)
+    );
+    if let Some(previous) = work.previous {
+        let _ = writeln!(
+            out,
+            "\n{} commands since {}:",
+            work.leading.len(),
+            short_commit(previous)
+        );
+        for entry in &work.leading {
+            let _ = writeln!(out, "  {}  {}", format_time(entry.timestamp), entry.command);
+        }
+    }
+    out
+}
+
+// Sessions listed by `sessions_report`, most recent first
+const SESSIONS_SHOWN: usize = 20;
+
+// Directories named per session before the rest are only counted
+const SESSION_DIRECTORIES: usize = 3;
+
+// One shell session, as identified by the hook's --session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionActivity {
+    pub session: String,
+    pub start: i64,
+    // When the last command finished, where durations were recorded
+    pub end: i64,
+    pub commands: usize,
+    // In the order they were first visited
+    pub directories: Vec<String>,
+}
+
+impl SessionActivity {
+    pub fn duration(&self) -> i64 {
+        self.end - self.start
+    }
+}
+
+// Group timestamped commands by session, most recently started first.
+// Commands without a session aren't included.
+pub fn session_activity(entries: &[HistoryEntry]) -> Vec<SessionActivity> {
+    let mut sessions: HashMap<&str, Vec<&HistoryEntry>> = HashMap::new();
+    for entry in entries.iter().filter(|e| e.timestamp > 0) {
+        if let Some(session) = entry.session_id.as_deref() {
+            sessions.entry(session).or_default().push(entry);
+        }
+    }
+
+    let mut activity: Vec<SessionActivity> = sessions
+        .into_iter()
+        .map(|(session, mut commands)| {
+            commands.sort_by_key(|e| e.timestamp);
+            let mut directories: Vec<String> = Vec::new();
+            for directory in commands.iter().filter_map(|e| e.directory.as_deref()) {
+                if !directories.iter().any(|d| d == directory) {
+                    directories.push(directory.to_string());
+                }
+            }
+            SessionActivity {
+                session: session.to_string(),
+                start: commands[0].timestamp,
+                end: commands
+                    .iter()
+                    .map(|e| e.timestamp + e.duration.unwrap_or(0).max(0))
+                    .max()
+                    .unwrap_or(0),
+                commands: commands.len(),
+                directories,
+            }
+        })
+        .collect();
+    activity.sort_by(|a, b| {
+        b.start
+            .cmp(&a.start)
+            .then_with(|| a.session.cmp(&b.session))
+    });
+    activity
+}
+
+// Recent shell sessions with their length, commands and directories
+pub fn sessions_report(entries: &[HistoryEntry]) -> String {
+    let mut out = String::new();
+    let sessions = session_activity(entries);
+    if sessions.is_empty() {
+        out.push_str("No sessions recorded. Record them with `init zsh`.\n");
+        return out;
+    }
+
+    let total: usize = sessions.iter().map(|s| s.commands).sum();
+    let _ = writeln!(
+        out,
+        "{} sessions, {} commands, {} on average\n",
+        sessions.len(),
+        total,
+        format_duration(
+            sessions.iter().map(SessionActivity::duration).sum::<i64>() / sessions.len() as i64
+        )
+    );
+    let _ = writeln!(
+        out,
+        "{:<16}  {:>7}  {:>8}  Directories",
+        "Started", "Length", "Commands"
+    );
+    let home = home::home_dir();
+    for s in sessions.iter().take(SESSIONS_SHOWN) {
+        let mut directories = s
+            .directories
+            .iter()
+            .take(SESSION_DIRECTORIES)
+            .map(|directory| home_relative(directory, home.as_deref()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if s.directories.len() > SESSION_DIRECTORIES {
+            let _ = write!(
+                directories,
+                " and {} more",
+                s.directories.len() - SESSION_DIRECTORIES
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{:<16}  {:>7}  {:>8}  {}",
+            format_time(s.start),
+            format_duration(s.duration()),
+            s.commands,
+            directories
+        );
+    }
+    if sessions.len() > SESSIONS_SHOWN {
+        let _ = writeln!(
+            out,
+            "... and {} earlier sessions",
+            sessions.len() - SESSIONS_SHOWN
+        );
+    }
+    out
+}
+
+// Sessions started inside tmux have this, the tmux session's name and the
+// pane appended to their id by the `init zsh` hook
+const TMUX_MARK: &str = "-tmux-";
+
+// Days listed by `panes_report`, most recent first
+const PANE_DAYS_SHOWN: usize = 14;
+
+// Panes listed as the busiest by `panes_report`
+const PANES_SHOWN: usize = 10;
+
+// The tmux session and pane (`%3`) of a session id, for shells started
+// inside tmux
+pub fn tmux_pane(session_id: &str) -> Option<(&str, &str)> {
+    let (_, pane) = session_id.split_once(TMUX_MARK)?;
+    let at = pane.rfind('%')?;
+    Some(pane.split_at(at))
+}
+
+// The panes used on one day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaneDay {
+    pub day: NaiveDate,
+    pub panes: usize,
+    pub sessions: usize,
+    pub commands: usize,
+    // The pane that ran the most commands, and how many
+    pub busiest: (String, usize),
+}
+
+// One pane over the whole history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaneActivity {
+    pub pane: String,
+    pub commands: usize,
+    pub days: usize,
+}
+
+fn pane_name((session, pane): (&str, &str)) -> String {
+    format!("{} {}", session, pane)
+}
+
+// Commands per tmux pane, by day (most recent first) and over all days
+// (busiest first). Commands from outside tmux aren't included.
+pub fn pane_activity(entries: &[HistoryEntry]) -> (Vec<PaneDay>, Vec<PaneActivity>) {
+    let mut by_day: BTreeMap<NaiveDate, HashMap<(&str, &str), usize>> = BTreeMap::new();
+    for entry in entries {
+        let pane = entry.session_id.as_deref().and_then(tmux_pane);
+        if let (Some(pane), Some(day)) = (pane, local_day(entry.timestamp)) {
+            *by_day.entry(day).or_default().entry(pane).or_insert(0) += 1;
+        }
+    }
+
+    let mut totals: HashMap<(&str, &str), (usize, usize)> = HashMap::new();
+    let mut days = Vec::new();
+    for (day, panes) in by_day.into_iter().rev() {
+        for (&pane, &count) in &panes {
+            let total = totals.entry(pane).or_insert((0, 0));
+            *total = (total.0 + count, total.1 + 1);
+        }
+        let sessions: HashSet<&str> = panes.keys().map(|(session, _)| *session).collect();
+        let busiest = panes
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(&pane, &count)| (pane_name(pane), count))
+            .unwrap_or_default();
+        days.push(PaneDay {
+            day,
+            panes: panes.len(),
+            sessions: sessions.len(),
+            commands: panes.values().sum(),
+            busiest,
+        });
+    }
+
+    let mut panes: Vec<PaneActivity> = totals
+        .into_iter()
+        .map(|(pane, (commands, days))| PaneActivity {
+            pane: pane_name(pane),
+            commands,
+            days,
+        })
+        .collect();
+    panes.sort_by(|a, b| {
+        b.commands
+            .cmp(&a.commands)
+            .then_with(|| a.pane.cmp(&b.pane))
+    });
+    (days, panes)
+}
+
+// How many tmux panes were in use each day, and which ones did the work
+pub fn panes_report(entries: &[HistoryEntry]) -> String {
+    let mut out = String::new();
+    let (days, panes) = pane_activity(entries);
+    if days.is_empty() {
+        out.push_str("No tmux panes recorded. Record them with `init zsh` inside tmux.\n");
+        return out;
+    }
+
+    let sessions: HashSet<&str> = panes
+        .iter()
+        .filter_map(|p| p.pane.rsplit_once(' ').map(|(session, _)| session))
+        .collect();
+    let _ = writeln!(
+        out,
+        "{} panes in {} tmux sessions over {} days, {:.1} panes a day on average\n",
+        panes.len(),
+        sessions.len(),
+        days.len(),
+        days.iter().map(|d| d.panes).sum::<usize>() as f64 / days.len() as f64
+    );
+    let _ = writeln!(
+        out,
+        "{:<14}  {:>5}  {:>8}  {:>8}  Busiest pane",
+        "Day", "Panes", "Sessions", "Commands"
+    );
+    for d in days.iter().take(PANE_DAYS_SHOWN) {
+        let (pane, count) = &d.busiest;
+        let _ = writeln!(
+            out,
+            "{:<14}  {:>5}  {:>8}  {:>8}  {} ({}%)",
+            d.day.format("%Y-%m-%d %a"),
+            d.panes,
+            d.sessions,
+            d.commands,
+            pane,
+            count * 100 / d.commands.max(1)
+        );
+    }
+    if days.len() > PANE_DAYS_SHOWN {
+        let _ = writeln!(out, "... and {} earlier days", days.len() - PANE_DAYS_SHOWN);
+    }
+
+    let width = panes
+        .iter()
+        .take(PANES_SHOWN)
+        .map(|p| p.pane.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let _ = writeln!(
+        out,
+        "\n{:<width$}  {:>8}  {:>4}",
+        "Pane",
+        "Commands",
+        "Days",
+        width = width
+    );
+    for p in panes.iter().take(PANES_SHOWN) {
+        let _ = writeln!(
+            out,
+            "{:<width$}  {:>8}  {:>4}",
+            p.pane,
+            p.commands,
+            p.days,
+            width = width
+        );
+    }
+    out
+}
+
+// The most run commands, counted after the normalization rules like Most
+// Used Commands in `stats`
+pub fn top_commands(entries: &[HistoryEntry], limit: usize) -> Vec<(String, usize)> {
+    let rules = normalize::rules();
+    let mut counts: HashMap<Cow<str>, usize> = HashMap::new();
+    for entry in entries {
+        *counts.entry(rules.apply(&entry.command)).or_insert(0) += 1;
+    }
+    let mut top = sorted_counts(counts);
+    top.truncate(limit);
+    top
+}
+
+// Run counts and commands, one per line with no header, so the output can
+// be piped or cut up as is
+pub fn top_report(top: &[(String, usize)]) -> String {
+    let mut out = String::new();
+    let width = top
+        .iter()
+        .map(|(_, count)| count.to_string().len())
+        .max()
+        .unwrap_or(0);
+    for (command, count) in top {
+        let _ = writeln!(out, "{:>width$}  {}", count, command, width = width);
+    }
+    out
+}
+
+fn days(count: usize) -> String {
+    if count == 1 {
+        "1 day".to_string()
+    } else {
+        format!("{} days", count)
+    }
+}
+
+// `streaks`, as `streak` prints them
+pub fn streak_report(streaks: &Streaks) -> String {
+    let mut out = String::new();
+    let span = |from: Option<NaiveDate>, length: usize| match from {
+        Some(from) => {
+            let to = from + chrono::Days::new(length.saturating_sub(1) as u64);
+            format!("{} ({} – {})", days(length), from, to)
+        }
+        None => days(length),
+    };
+    let current = if streaks.current > 0 {
+        days(streaks.current)
+    } else {
+        "none, no commands yesterday or today".to_string()
+    };
+    let _ = writeln!(out, "Current streak  {}", current);
+    let _ = writeln!(
+        out,
+        "Longest streak  {}",
+        span(streaks.longest_from, streaks.longest)
+    );
+    let gap = if streaks.longest_gap > 0 {
+        span(streaks.gap_from, streaks.longest_gap)
+    } else {
+        "none".to_string()
+    };
+    let _ = writeln!(out, "Longest gap     {}", gap);
+    out
+}
+
+// `summary`: today's count, the current streak and this week's top command,
+// short and quick enough to print from a shell's startup file
+pub fn brief_report(entries: &[HistoryEntry], now: DateTime<Local>) -> String {
+    let bounds = period_bounds(now, 0);
+    let today = entries
+        .iter()
+        .filter(|e| e.timestamp >= bounds.today_start)
+        .count();
+    let streak = streaks(&day_counts(entries), now.date_naive()).current;
+    let (week_start, week_end) = bounds.week;
+    let this_week: Vec<HistoryEntry> = entries
+        .iter()
+        .filter(|e| e.timestamp >= week_start && e.timestamp < week_end)
+        .cloned()
+        .collect();
+
+    let mut out = String::new();
+    let commands = if today == 1 { "command" } else { "commands" };
+    let _ = writeln!(out, "Today      {} {}", today, commands);
+    let streak = if streak > 0 {
+        days(streak)
+    } else {
+        "none".to_string()
+    };
+    let _ = writeln!(out, "Streak     {}", streak);
+    let top = match top_commands(&this_week, 1).first() {
+        Some((command, count)) => format!("{} ({})", command, count),
+        None => "nothing yet".to_string(),
+    };
+    let _ = writeln!(out, "This week  {}", top);
+    out
+}
+
+// One working directory's commands, for `dirs`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryStats {
+    pub directory: String,
+    pub commands: usize,
+    pub unique: usize,
+    // The most run command there, and how often
+    pub top: (String, usize),
+}
+
+// Directories by how many commands ran in them, busiest first. Commands are
+// told apart after the normalization rules, as in `top`.
+pub fn directory_stats(entries: &[HistoryEntry], limit: usize) -> Vec<DirectoryStats> {
+    grouped_stats(entries, limit, |directory| Some(directory.to_string()))
+}
+
+// Projects ranked the same way. A project is the git repository a directory
+// is in, so its subdirectories count together; commands run outside any
+// repository are left out.
+pub fn project_stats(entries: &[HistoryEntry], limit: usize) -> Vec<DirectoryStats> {
+    let mut roots: HashMap<&str, Option<String>> = HashMap::new();
+    grouped_stats(entries, limit, |directory| {
+        roots
+            .entry(directory)
+            .or_insert_with(|| {
+                repository_root(Path::new(directory))
+                    .map(|root| root.to_string_lossy().into_owned())
+            })
+            .clone()
+    })
+}
+
+// Entries counted under the group `group` puts their directory in
+fn grouped_stats<'a>(
+    entries: &'a [HistoryEntry],
+    limit: usize,
+    mut group: impl FnMut(&'a str) -> Option<String>,
+) -> Vec<DirectoryStats> {
+    let rules = normalize::rules();
+    let mut by_directory: HashMap<String, HashMap<Cow<str>, usize>> = HashMap::new();
+    for entry in entries {
+        if let Some(directory) = entry.directory.as_deref().and_then(&mut group) {
+            *by_directory
+                .entry(directory)
+                .or_default()
+                .entry(rules.apply(&entry.command))
+                .or_insert(0) += 1;
+        }
+    }
+    let mut stats: Vec<DirectoryStats> = by_directory
+        .into_iter()
+        .map(|(directory, counts)| {
+            let unique = counts.len();
+            let commands = counts.values().sum();
+            let top = sorted_counts(counts).swap_remove(0);
+            DirectoryStats {
+                directory,
+                commands,
+                unique,
+                top,
+            }
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.commands
+            .cmp(&a.commands)
+            .then_with(|| a.directory.cmp(&b.directory))
+    });
+    stats.truncate(limit);
+    stats
+}
+
+pub fn dirs_report(dirs: &[DirectoryStats]) -> String {
+    ranked_dirs_table(dirs, "Directory")
+}
+
+pub fn projects_report(projects: &[DirectoryStats]) -> String {
+    ranked_dirs_table(projects, "Project")
+}
+
+fn ranked_dirs_table(dirs: &[DirectoryStats], heading: &str) -> String {
+    let mut out = String::new();
+    let home = home::home_dir();
+    let shown: Vec<String> = dirs
+        .iter()
+        .map(|d| home_relative(&d.directory, home.as_deref()))
+        .collect();
+    let width = shown
+        .iter()
+        .map(|directory| directory.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(9);
+    let _ = writeln!(
+        out,
+        "{:<width$}  {:>8}  {:>6}  Most used",
+        heading,
+        "Commands",
+        "Unique",
+        width = width
+    );
+    for (d, directory) in dirs.iter().zip(&shown) {
+        let _ = writeln!(
+            out,
+            "{:<width$}  {:>8}  {:>6}  {} ({})",
+            directory,
+            d.commands,
+            d.unique,
+            d.top.0,
+            d.top.1,
+            width = width
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+    use std::fs;
+
+    fn entry(timestamp: i64, command: &str, directory: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp,
+            command: command.to_string(),
+            directory: Some(directory.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn classifies_steps() {
+        assert_eq!(step("nvim src/main.rs"), Some(Step::Edit));
+        assert_eq!(step("cargo clippy --all-targets"), Some(Step::Build));
+        assert_eq!(step("npm run build"), Some(Step::Build));
+        assert_eq!(step("npm run test -- --watch"), Some(Step::Test));
+        assert_eq!(step("make check"), Some(Step::Test));
+        assert_eq!(step("git status"), None);
+    }
+
+    #[test]
+    fn cycles_end_at_the_next_edit() {
+        let entries = [
+            entry(1000, "vim a.rs", "/repo"),
+            entry(1010, "vim b.rs", "/repo"),
+            entry(1100, "cargo build", "/repo"),
+            entry(1150, "git diff", "/repo"),
+            entry(1200, "cargo test", "/repo"),
+            entry(1300, "vim a.rs", "/repo"),
+            entry(1400, "cargo test", "/repo"),
+            // Edited but never built
+            entry(1500, "vim c.rs", "/repo"),
+            // Elsewhere, and too far apart to be one cycle
+            entry(1000, "vim x.go", "/other"),
+            entry(1000 + MAX_CYCLE_GAP + 1, "go test ./...", "/other"),
+        ];
+        let cycles = find_cycles(&entries);
+        let spans: Vec<(&str, i64, i64)> = cycles
+            .iter()
+            .map(|c| (c.project.as_str(), c.start, c.end))
+            .collect();
+        assert_eq!(spans, [("/repo", 1000, 1200), ("/repo", 1300, 1400)]);
+    }
+
+    #[test]
+    fn parses_branch_changes() {
+        assert_eq!(
+            branch_change("git checkout main"),
+            Some(BranchChange::To("main"))
+        );
+        assert_eq!(
+            branch_change("git switch -c feature/login"),
+            Some(BranchChange::To("feature/login"))
+        );
+        assert_eq!(
+            branch_change("git checkout -b fix origin/main"),
+            Some(BranchChange::To("fix"))
+        );
+        assert_eq!(
+            branch_change("git checkout -"),
+            Some(BranchChange::Previous)
+        );
+        assert_eq!(branch_change("git checkout -- src/main.rs"), None);
+        assert_eq!(branch_change("git checkout main src/main.rs"), None);
+        assert_eq!(branch_change("git status"), None);
+    }
+
+    #[test]
+    fn attributes_commands_to_the_checked_out_branch() {
+        let entries = [
+            entry(900, "ls", "/repo"),
+            entry(1000, "git checkout -b feature/login", "/repo"),
+            entry(1060, "cargo test", "/repo"),
+            entry(1000 + 2 * IDLE_GAP, "cargo test", "/repo"),
+            entry(1000 + 2 * IDLE_GAP + 30, "git switch main", "/repo"),
+            entry(1000 + 2 * IDLE_GAP + 40, "git pull", "/repo"),
+            entry(1000 + 2 * IDLE_GAP + 50, "git checkout -", "/repo"),
+            entry(1000 + 2 * IDLE_GAP + 70, "git push", "/repo"),
+        ];
+        let activity = branch_activity(&entries);
+        let summary: Vec<(&str, usize, i64)> = activity
+            .iter()
+            .map(|a| (a.branch.as_str(), a.commands, a.active_seconds))
+            .collect();
+        // The long idle gap doesn't count towards active time
+        assert_eq!(summary, [("feature/login", 5, 60 + 20), ("main", 2, 10)]);
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(45), "45s");
+        assert_eq!(format_duration(390), "6m 30s");
+        assert_eq!(format_duration(3900), "1h 05m");
+    }
+
+    #[test]
+    fn users_are_compared_and_anonymized() {
+        let users = vec![
+            (
+                "alice".to_string(),
+                Ok(vec![
+                    entry(1681234567, "git status", "/a"),
+                    entry(0, "ls", "/a"),
+                ]),
+            ),
+            (
+                "bob".to_string(),
+                Ok(vec![
+                    entry(1681234567, "git push", "/b"),
+                    entry(1681321000, "git pull", "/b"),
+                    entry(1681321000, "git pull", "/b"),
+                ]),
+            ),
+            ("carol".to_string(), Ok(Vec::new())),
+            ("dave".to_string(), Err("Permission denied".to_string())),
+        ];
+
+        let bob = user_activity("bob", users[1].1.as_ref().unwrap());
+        assert_eq!((bob.commands, bob.unique, bob.days), (3, 2, 2));
+        assert_eq!(bob.programs, [("git".to_string(), 3)]);
+
+        let report = users_report(&users, true);
+        assert!(report.starts_with("4 users, 5 commands"));
+        assert!(!report.contains("alice") && !report.contains("bob"));
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines[3].starts_with("user-1") && lines[3].contains("git"));
+        assert!(lines[5].starts_with("user-3") && lines[5].contains("(no history found)"));
+        assert!(
+            lines[6].starts_with("user-4") && lines[6].contains("(unreadable: Permission denied)")
+        );
+        let git_row = lines
+            .iter()
+            .rev()
+            .find(|line| line.starts_with("git"))
+            .unwrap();
+        assert_eq!(
+            git_row.split_whitespace().collect::<Vec<_>>(),
+            ["git", "3", "1", "0", "0"]
+        );
+    }
+
+    #[test]
+    fn finds_retries_after_failures() {
+        let run = |timestamp: i64, command: &str, exit_code: i32, session: &str| HistoryEntry {
+            timestamp,
+            command: command.to_string(),
+            exit_code: Some(exit_code),
+            session_id: Some(session.to_string()),
+            ..Default::default()
+        };
+        let entries = [
+            run(100, "cargo test", 101, "a"),
+            // Another shell in between doesn't break the streak
+            run(110, "ls", 0, "b"),
+            run(120, "cargo test", 101, "a"),
+            run(150, "cargo test", 0, "a"),
+            run(200, "git push", 1, "a"),
+            run(210, "git pull", 0, "a"),
+            run(220, "git push", 1, "a"),
+            run(230, "git push", 1, "a"),
+            // Ctrl-C isn't a failure
+            run(300, "make", 130, "a"),
+            run(310, "make", 0, "a"),
+            // Too late to be a retry
+            run(400, "cargo test", 101, "a"),
+            run(400 + RETRY_GAP + 1, "cargo test", 0, "a"),
+        ];
+        assert_eq!(
+            retry_stats(&entries),
+            [
+                RetryStats {
+                    command: "cargo test".to_string(),
+                    streaks: 1,
+                    retries: 2,
+                    recovered: 1,
+                },
+                RetryStats {
+                    command: "git push".to_string(),
+                    streaks: 1,
+                    retries: 1,
+                    recovered: 0,
+                },
+            ]
+        );
+        assert!(retries_report(&entries).starts_with("Command "));
+        assert!(retries_report(&[entry(100, "ls", "/")]).starts_with("No exit codes"));
+    }
+
+    #[test]
+    fn commits_made_and_around_an_entry() {
+        let head = |timestamp: i64, command: &str, commit: &str| HistoryEntry {
+            commit: Some(commit.to_string()),
+            ..entry(timestamp, command, "/repo")
+        };
+        let entries = [
+            head(100, "git pull", "aaaaaaa1"),
+            head(110, "cargo test", "aaaaaaa1"),
+            entry(115, "ls", "/repo"),
+            head(120, "git commit -m fix", "bbbbbbb2"),
+            head(130, "git push", "bbbbbbb2"),
+            head(140, "git commit -m more", "ccccccc3"),
+            HistoryEntry {
+                directory: Some("/elsewhere".to_string()),
+                ..head(125, "git commit -m other", "ddddddd4")
+            },
+        ];
+
+        let work = commit_work(&entries, "bbbb").unwrap();
+        assert_eq!(work.made_by.command, "git commit -m fix");
+        assert_eq!(work.previous, Some("aaaaaaa1"));
+        let leading: Vec<&str> = work.leading.iter().map(|e| e.command.as_str()).collect();
+        assert_eq!(leading, ["git pull", "cargo test"]);
+        assert!(
+            commit_report(&entries, "bbbb").starts_with("bbbbbbb was made by `git commit -m fix`")
+        );
+        assert!(commit_work(&entries, "eeee").is_none());
+
+        assert_eq!(
+            commits_around(&entries, &entries[2]),
+            (Some("aaaaaaa1"), Some("bbbbbbb2"))
+        );
+        assert_eq!(
+            commits_around(&entries, &entries[4]),
+            (Some("bbbbbbb2"), Some("ccccccc3"))
+        );
+    }
+
+    #[test]
+    fn groups_commands_by_session() {
+        let run = |timestamp: i64, session: Option<&str>, directory: &str| HistoryEntry {
+            timestamp,
+            command: "ls".to_string(),
+            directory: Some(directory.to_string()),
+            duration: Some(5),
+            session_id: session.map(str::to_string),
+            ..Default::default()
+        };
+        let entries = [
+            run(1000, Some("a"), "/src"),
+            run(2000, Some("b"), "/tmp"),
+            run(1600, Some("a"), "/home"),
+            run(1300, Some("a"), "/src"),
+            run(1500, None, "/src"),
+        ];
+        let sessions = session_activity(&entries);
+        assert_eq!(
+            sessions[1],
+            SessionActivity {
+                session: "a".to_string(),
+                start: 1000,
+                end: 1605,
+                commands: 3,
+                directories: vec!["/src".to_string(), "/home".to_string()],
+            }
+        );
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(
+            (sessions[0].session.as_str(), sessions[0].duration()),
+            ("b", 5)
+        );
+    }
+
+    #[test]
+    fn panes_are_counted_per_day_and_overall() {
+        assert_eq!(
+            tmux_pane("4242-1681234000-tmux-my work%12"),
+            Some(("my work", "%12"))
+        );
+        assert_eq!(tmux_pane("4242-1681234000"), None);
+
+        let day = |d: u32, h: u32| {
+            Local
+                .with_ymd_and_hms(2024, 3, d, h, 0, 0)
+                .unwrap()
+                .timestamp()
+        };
+        let run = |timestamp: i64, session: &str| HistoryEntry {
+            timestamp,
+            command: "ls".to_string(),
+            session_id: Some(session.to_string()),
+            ..Default::default()
+        };
+        let entries = [
+            run(day(11, 9), "1-1-tmux-work%1"),
+            run(day(11, 10), "1-1-tmux-work%1"),
+            run(day(11, 11), "2-1-tmux-work%2"),
+            run(day(11, 12), "3-1-tmux-play%1"),
+            run(day(12, 9), "1-1-tmux-work%1"),
+            run(day(12, 9), "4-1"),
+        ];
+        let (days, panes) = pane_activity(&entries);
+        assert_eq!(days.len(), 2);
+        assert_eq!(
+            days[1],
+            PaneDay {
+                day: NaiveDate::from_ymd_opt(2024, 3, 11).unwrap(),
+                panes: 3,
+                sessions: 2,
+                commands: 4,
+                busiest: ("work %1".to_string(), 2),
+            }
+        );
+        assert_eq!(
+            panes[0],
+            PaneActivity {
+                pane: "work %1".to_string(),
+                commands: 3,
+                days: 2,
+            }
+        );
+        assert_eq!(panes.len(), 3);
+        assert!(panes_report(&entries)
+            .starts_with("3 panes in 2 tmux sessions over 2 days, 2.0 panes a day on average\n"));
+    }
+
+    #[test]
+    fn directories_rank_by_commands() {
+        let entries = [
+            entry(1, "cargo test", "/src/app"),
+            entry(2, "cargo test", "/src/app"),
+            entry(3, "git status", "/src/app"),
+            entry(4, "ls", "/tmp"),
+            HistoryEntry {
+                directory: None,
+                ..entry(5, "ls", "/")
+            },
+        ];
+        let dirs = directory_stats(&entries, 10);
+        assert_eq!(
+            dirs[0],
+            DirectoryStats {
+                directory: "/src/app".to_string(),
+                commands: 3,
+                unique: 2,
+                top: ("cargo test".to_string(), 2),
+            }
+        );
+        assert_eq!(dirs.len(), 2);
+        assert_eq!(directory_stats(&entries, 1).len(), 1);
+        assert!(dirs_report(&dirs).contains("\n/tmp              1       1  ls (1)\n"));
+    }
+
+    #[test]
+    fn projects_gather_their_subdirectories() {
+        let root = ScratchDir::new("projects");
+        let repo = root.join("foo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        let at = |dir: &Path| dir.to_str().unwrap().to_string();
+        let (src, tests) = (at(&repo.join("src")), at(&repo.join("tests")));
+        let entries = [
+            entry(1, "cargo build", &src),
+            entry(2, "cargo test", &tests),
+            entry(3, "cargo test", &tests),
+            entry(4, "ls", &at(&root)),
+        ];
+        let projects = project_stats(&entries, 10);
+        assert_eq!(
+            projects,
+            [DirectoryStats {
+                directory: at(&repo),
+                commands: 3,
+                unique: 2,
+                top: ("cargo test".to_string(), 2),
+            }]
+        );
+        assert!(projects_report(&projects).starts_with("Project"));
+    }
+
+    #[test]
+    fn top_commands_in_a_window() {
+        let entries = [
+            entry(100, "ls", "/"),
+            entry(200, "git status", "/"),
+            entry(300, "ls", "/"),
+            entry(400, "cargo test", "/"),
+            entry(500, "git status", "/"),
+            entry(600, "git status", "/"),
+        ];
+        let top = top_commands(&entries, 2);
+        assert_eq!(top, [("git status".to_string(), 3), ("ls".to_string(), 2)]);
+        assert_eq!(top_report(&top), "3  git status\n2  ls\n");
+
+        let recent = top_commands(&entries[3..], 10);
+        assert_eq!(
+            recent,
+            [("git status".to_string(), 2), ("cargo test".to_string(), 1)]
+        );
+        assert_eq!(top_report(&[]), "");
+    }
+
+    #[test]
+    fn brief_report_has_today_streak_and_top_of_the_week() {
+        // A Wednesday
+        let now = Local.with_ymd_and_hms(2024, 3, 13, 12, 0, 0).unwrap();
+        let at = |day: u32, command: &str| {
+            let time = Local.with_ymd_and_hms(2024, 3, day, 10, 0, 0).unwrap();
+            entry(time.timestamp(), command, "/")
+        };
+        let entries = [
+            at(8, "make"),
+            at(8, "make"),
+            at(8, "make"),
+            at(11, "git status"),
+            at(12, "git status"),
+            at(13, "ls"),
+        ];
+        assert_eq!(
+            brief_report(&entries, now),
+            "Today      1 command\nStreak     3 days\nThis week  git status (2)\n"
+        );
+        assert_eq!(
+            brief_report(&[], now),
+            "Today      0 commands\nStreak     none\nThis week  nothing yet\n"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::history::HistoryEntry;
+use crate::report::format_time;
+
+// How a search pattern is matched against commands
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    Regex(Regex),
+    // The pattern's characters in order, not necessarily next to each other
+    Fuzzy(String),
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, fuzzy: bool) -> Result<Matcher> {
+        Ok(if fuzzy {
+            Matcher::Fuzzy(pattern.to_lowercase())
+        } else {
+            Matcher::Regex(Regex::new(pattern)?)
+        })
+    }
+
+    // How well `command` matches, higher being better, or None if it doesn't.
+    // Every regex match is as good as any other.
+    pub fn score(&self, command: &str) -> Option<i64> {
+        match self {
+            Matcher::Regex(regex) => regex.is_match(command).then_some(0),
+            Matcher::Fuzzy(pattern) => fuzzy_score(pattern, command),
+        }
+    }
+}
+
+// Bonuses and penalties for `fuzzy_score`
+const MATCH: i64 = 16;
+const CONSECUTIVE: i64 = 24;
+const WORD_START: i64 = 20;
+const GAP: i64 = 1;
+
+// Match the lowercase `pattern` against `text` case-insensitively, taking
+// the earliest position for each character. Runs of adjacent characters and
+// characters at the start of a word score higher; skipped characters cost a
+// little, so tighter matches rank first.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    fuzzy_match(pattern, text).map(|(score, _)| score)
+}
+
+// The characters of `text` that `fuzzy_score` matched, by char index
+pub fn fuzzy_positions(pattern: &str, text: &str) -> Option<Vec<usize>> {
+    fuzzy_match(pattern, text).map(|(_, positions)| positions)
+}
+
+fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let mut score = 0;
+    let mut positions = Vec::new();
+    let mut wanted = pattern.chars().peekable();
+    let mut previous: Option<char> = None;
+    let mut matched_previous = false;
+    for (i, c) in text.chars().enumerate() {
+        let Some(&next) = wanted.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(next.to_lowercase()) {
+            score += MATCH;
+            if matched_previous {
+                score += CONSECUTIVE;
+            }
+            if previous.is_none_or(|p| !p.is_alphanumeric()) {
+                score += WORD_START;
+            }
+            wanted.next();
+            positions.push(i);
+            matched_previous = true;
+        } else {
+            if !positions.is_empty() {
+                score -= GAP;
+            }
+            matched_previous = false;
+        }
+        previous = Some(c);
+    }
+    wanted.peek().is_none().then_some((score, positions))
+}
+
+// One matching command, with every run of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub command: String,
+    pub count: usize,
+    pub last_run: i64,
+    pub score: i64,
+}
+
+// Matching commands, best match first, then the most recently run. Regex
+// matches all score the same, so they are simply the most recent first.
+pub fn search(entries: &[HistoryEntry], matcher: &Matcher) -> Vec<SearchMatch> {
+    let mut matches: HashMap<&str, SearchMatch> = HashMap::new();
+    for entry in entries {
+        if let Some(found) = matches.get_mut(entry.command.as_str()) {
+            found.count += 1;
+            found.last_run = found.last_run.max(entry.timestamp);
+            continue;
+        }
+        if let Some(score) = matcher.score(&entry.command) {
+            matches.insert(
+                &entry.command,
+                SearchMatch {
+                    command: entry.command.clone(),
+                    count: 1,
+                    last_run: entry.timestamp,
+                    score,
+                },
+            );
+        }
+    }
+    let mut matches: Vec<SearchMatch> = matches.into_values().collect();
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| b.last_run.cmp(&a.last_run))
+            .then_with(|| a.command.cmp(&b.command))
+    });
+    matches
+}
+
+// Each match with how often and when it last ran
+pub fn search_report(matches: &[SearchMatch]) -> String {
+    let mut out = String::new();
+    let runs: usize = matches.iter().map(|m| m.count).sum();
+    let _ = writeln!(out, "{} commands, {} runs\n", matches.len(), runs);
+    let width = matches
+        .iter()
+        .map(|m| m.count.to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max("Runs".len());
+    let _ = writeln!(
+        out,
+        "{:>width$}  {:<16}  Command",
+        "Runs",
+        "Last run",
+        width = width
+    );
+    for m in matches {
+        let last_run = if m.last_run > 0 {
+            format_time(m.last_run)
+        } else {
+            "-".to_string()
+        };
+        let _ = writeln!(
+            out,
+            "{:>width$}  {:<16}  {}",
+            m.count,
+            last_run,
+            m.command,
+            width = width
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(timestamp: i64, command: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp,
+            command: command.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fuzzy_matches_rank_tight_matches_first() {
+        assert!(fuzzy_score("gco", "git checkout main").is_some());
+        assert_eq!(
+            fuzzy_score("gco", "git commit"),
+            Some(MATCH * 3 + WORD_START * 2 + CONSECUTIVE - 3)
+        );
+        assert_eq!(fuzzy_score("xyz", "git status"), None);
+        assert_eq!(fuzzy_positions("gco", "git commit"), Some(vec![0, 4, 5]));
+
+        let entries = [
+            run(10, "cargo test"),
+            run(20, "git commit -m wip"),
+            run(30, "cat Cargo.toml"),
+            run(40, "cargo test"),
+        ];
+        let found = search(&entries, &Matcher::new("cargo", true).unwrap());
+        let commands: Vec<(&str, usize)> = found
+            .iter()
+            .map(|m| (m.command.as_str(), m.count))
+            .collect();
+        assert_eq!(commands, [("cargo test", 2), ("cat Cargo.toml", 1)]);
+        assert_eq!(found[0].last_run, 40);
+
+        // Regex matches are only ordered by when they last ran
+        let found = search(&entries, &Matcher::new("^ca", false).unwrap());
+        let commands: Vec<&str> = found.iter().map(|m| m.command.as_str()).collect();
+        assert_eq!(commands, ["cargo test", "cat Cargo.toml"]);
+        assert!(Matcher::new("(", false).is_err());
+    }
+}
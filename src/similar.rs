@@ -0,0 +1,211 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::history::HistoryEntry;
+use crate::tr;
+
+// Weights of the three signals in a command's similarity score
+const SPELLING_WEIGHT: f64 = 0.4;
+const ARGUMENTS_WEIGHT: f64 = 0.3;
+const TOGETHER_WEIGHT: f64 = 0.3;
+
+// Commands scoring below this aren't related enough to list
+const MIN_SCORE: f64 = 0.2;
+
+// Runs further apart than this don't count as run together
+const TOGETHER_SECONDS: i64 = 600;
+
+// Longer commands are compared by their start only, to keep edit distance
+// cheap enough to run on every redraw
+const SPELLING_CHARS: usize = 120;
+
+// Why a command was listed as similar: the signal that scored it highest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    // Few edits apart, e.g. a typo or a changed flag
+    Spelling,
+    // Shares arguments, such as a file or host
+    Arguments,
+    // Often run right before or after
+    Together,
+}
+
+impl Relation {
+    pub fn label(self) -> String {
+        match self {
+            Relation::Spelling => tr!("similar-spelling"),
+            Relation::Arguments => tr!("similar-arguments"),
+            Relation::Together => tr!("similar-together"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarCommand {
+    pub command: String,
+    pub score: f64,
+    pub relation: Relation,
+}
+
+// Levenshtein distance between `a` and `b`, by characters
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+// 1 for the same text, down to 0 for half the characters changed or more.
+// Unrelated commands are rarely further apart than that, so below it the
+// spelling says nothing.
+fn spelling_similarity(a: &[char], b: &str) -> f64 {
+    let b: Vec<char> = b.chars().take(SPELLING_CHARS).collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 0.0;
+    }
+    let same = 1.0 - edit_distance(a, &b) as f64 / longest as f64;
+    ((same - 0.5) * 2.0).max(0.0)
+}
+
+// Share of the arguments (words after the program) the two have in common
+fn argument_similarity(a: &HashSet<&str>, b: &str) -> f64 {
+    let b: HashSet<&str> = b.split_whitespace().skip(1).collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+// How often each other command ran right before or after `command`, as a
+// share of `command`'s runs
+fn run_together<'a>(entries: &'a [HistoryEntry], command: &str) -> HashMap<&'a str, f64> {
+    let mut together: HashMap<&str, usize> = HashMap::new();
+    let mut runs = 0;
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.command != command {
+            continue;
+        }
+        runs += 1;
+        let neighbours = [i.checked_sub(1), Some(i + 1)];
+        for neighbour in neighbours
+            .into_iter()
+            .flatten()
+            .filter_map(|j| entries.get(j))
+        {
+            let close = entry.timestamp == 0
+                || neighbour.timestamp == 0
+                || (entry.timestamp - neighbour.timestamp).abs() <= TOGETHER_SECONDS;
+            if close && neighbour.command != command {
+                *together.entry(neighbour.command.as_str()).or_insert(0) += 1;
+            }
+        }
+    }
+    together
+        .into_iter()
+        .map(|(other, count)| (other, (count as f64 / runs.max(1) as f64).min(1.0)))
+        .collect()
+}
+
+// The commands most like `command`, best first: a blend of how close they
+// are to it in spelling, the arguments they share and how often they run
+// next to it
+pub fn similar_commands(
+    entries: &[HistoryEntry],
+    command: &str,
+    limit: usize,
+) -> Vec<SimilarCommand> {
+    let spelling: Vec<char> = command.chars().take(SPELLING_CHARS).collect();
+    let arguments: HashSet<&str> = command.split_whitespace().skip(1).collect();
+    let together = run_together(entries, command);
+
+    let mut seen = HashSet::new();
+    let mut similar: Vec<SimilarCommand> = entries
+        .iter()
+        .map(|e| e.command.as_str())
+        .filter(|&other| other != command && seen.insert(other))
+        .filter_map(|other| {
+            let signals = [
+                (
+                    Relation::Spelling,
+                    SPELLING_WEIGHT * spelling_similarity(&spelling, other),
+                ),
+                (
+                    Relation::Arguments,
+                    ARGUMENTS_WEIGHT * argument_similarity(&arguments, other),
+                ),
+                (
+                    Relation::Together,
+                    TOGETHER_WEIGHT * together.get(other).copied().unwrap_or(0.0),
+                ),
+            ];
+            let score: f64 = signals.iter().map(|(_, part)| part).sum();
+            let (relation, _) = signals.into_iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+            (score >= MIN_SCORE).then(|| SimilarCommand {
+                command: other.to_string(),
+                score,
+                relation,
+            })
+        })
+        .collect();
+    similar.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.command.cmp(&b.command))
+    });
+    similar.truncate(limit);
+    similar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blends_spelling_arguments_and_neighbours() {
+        let run = |timestamp: i64, command: &str| HistoryEntry {
+            timestamp,
+            command: command.to_string(),
+            ..Default::default()
+        };
+        let entries = [
+            run(100, "cargo build --release"),
+            run(110, "./target/release/app"),
+            run(5000, "cargo build --relase"),
+            run(6000, "vim src/main.rs"),
+            run(7000, "cargo build --release"),
+            run(7010, "./target/release/app"),
+            run(8000, "cat src/main.rs"),
+            run(9000, "htop"),
+        ];
+        assert_eq!(edit_distance(&['a', 'b', 'c'], &['a', 'c']), 1);
+
+        let similar = similar_commands(&entries, "cargo build --release", 5);
+        let found: Vec<(&str, Relation)> = similar
+            .iter()
+            .map(|s| (s.command.as_str(), s.relation))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("cargo build --relase", Relation::Spelling),
+                ("./target/release/app", Relation::Together),
+            ]
+        );
+
+        let similar = similar_commands(&entries, "vim src/main.rs", 5);
+        assert_eq!(similar[0].command, "cat src/main.rs");
+        assert_eq!(similar[0].relation, Relation::Arguments);
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use crossterm::event::KeyCode;
+use serde_json::{json, Value};
+use std::{env, fmt::Write, fs, path::PathBuf};
+
+use crate::aggregate::{StatsSummary, TimePatterns};
+use crate::i18n::weekday_name;
+use crate::tr;
+
+// What a TUI view is showing, saved with `s` (Markdown) or `S` (JSON)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub view: String,
+    pub sections: Vec<Section>,
+}
+
+// One box of a view, as label and value pairs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub title: String,
+    pub rows: Vec<(String, String)>,
+    // Labels are commands, so Markdown shows them as code
+    pub code: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    Markdown,
+    Json,
+}
+
+impl SnapshotFormat {
+    pub fn for_key(code: KeyCode) -> Option<SnapshotFormat> {
+        match code {
+            KeyCode::Char('s') => Some(SnapshotFormat::Markdown),
+            KeyCode::Char('S') => Some(SnapshotFormat::Json),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            SnapshotFormat::Markdown => "md",
+            SnapshotFormat::Json => "json",
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn new(view: impl Into<String>) -> Snapshot {
+        Snapshot {
+            view: view.into(),
+            sections: Vec::new(),
+        }
+    }
+
+    pub fn section(mut self, title: impl Into<String>, rows: Vec<(String, String)>) -> Snapshot {
+        self.sections.push(Section {
+            title: title.into(),
+            rows,
+            code: false,
+        });
+        self
+    }
+
+    pub fn command_section(
+        mut self,
+        title: impl Into<String>,
+        rows: Vec<(String, String)>,
+    ) -> Snapshot {
+        self.sections.push(Section {
+            title: title.into(),
+            rows,
+            code: true,
+        });
+        self
+    }
+
+    pub fn to_markdown(&self, saved_at: DateTime<Local>) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# {}\n",
+            tr!("app-title-view", view = self.view.as_str())
+        );
+        let _ = writeln!(out, "{}", saved_at.format(&tr!("timestamp-format")));
+        for section in self.sections.iter().filter(|s| !s.rows.is_empty()) {
+            let _ = writeln!(out, "\n## {}\n", section.title);
+            for (label, value) in &section.rows {
+                let label = if section.code {
+                    format!("`{}`", label)
+                } else {
+                    label.clone()
+                };
+                if value.is_empty() {
+                    let _ = writeln!(out, "- {}", label);
+                } else {
+                    let _ = writeln!(out, "- {}: {}", label, value);
+                }
+            }
+        }
+        out
+    }
+
+    pub fn to_json(&self, saved_at: DateTime<Local>) -> Value {
+        let sections: Vec<Value> = self
+            .sections
+            .iter()
+            .map(|section| {
+                let rows: Vec<Value> = section
+                    .rows
+                    .iter()
+                    .map(|(label, value)| json!({ "label": label, "value": value }))
+                    .collect();
+                json!({ "title": section.title, "rows": rows })
+            })
+            .collect();
+        json!({
+            "view": self.view,
+            "saved_at": saved_at.to_rfc3339(),
+            "sections": sections,
+        })
+    }
+
+    // e.g. `cli-wrapped-week-42-oct-20261016-093000.md`. Views named after a
+    // long command are cut short.
+    fn file_name(&self, saved_at: DateTime<Local>, format: SnapshotFormat) -> String {
+        let mut slug = String::new();
+        for c in self.view.chars().flat_map(char::to_lowercase).take(40) {
+            if c.is_alphanumeric() {
+                slug.push(c);
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        let slug = slug.trim_end_matches('-');
+        format!(
+            "cli-wrapped-{}-{}.{}",
+            slug,
+            saved_at.format("%Y%m%d-%H%M%S"),
+            format.extension()
+        )
+    }
+}
+
+// The boxes shared by the stats and today views, limited to the rows on
+// screen. `approx` marks counts estimated from a sample.
+pub fn stats_snapshot(
+    view: &str,
+    general: &[(String, String)],
+    panel: (&str, &[(String, usize)]),
+    summary: &StatsSummary,
+    patterns: &TimePatterns,
+    shown: usize,
+    approx: &str,
+) -> Snapshot {
+    let (panel_title, panel_counts) = panel;
+    let ranking = |counts: &[(String, usize)]| -> Vec<(String, String)> {
+        counts
+            .iter()
+            .take(shown)
+            .map(|(name, count)| (name.clone(), format!("{}{}", approx, count)))
+            .collect()
+    };
+    let panel_rows = panel_counts
+        .iter()
+        .map(|(name, count)| {
+            let percentage = summary.percentage(*count);
+            (name.clone(), format!("{}{}%", approx, percentage))
+        })
+        .collect();
+
+    let mut pattern_rows = Vec::new();
+    if let Some((hour, count)) = patterns.peak_hour().filter(|(_, count)| *count > 0) {
+        pattern_rows.push((
+            tr!("snapshot-peak-hour"),
+            format!("{:02}:00 ({}{})", hour, approx, count),
+        ));
+    }
+    if let Some((day, count)) = patterns.peak_day().filter(|(_, count)| *count > 0) {
+        pattern_rows.push((
+            tr!("snapshot-peak-day"),
+            format!("{} ({}{})", weekday_name(day), approx, count),
+        ));
+    }
+    for (day, percentage) in patterns.day_percentages().iter().enumerate() {
+        pattern_rows.push((weekday_name(day), format!("{}%", percentage)));
+    }
+
+    Snapshot::new(view)
+        .section(tr!("box-general"), general.to_vec())
+        .section(panel_title, panel_rows)
+        .section(tr!("box-directories"), ranking(&summary.directories))
+        .command_section(tr!("box-commands"), ranking(&summary.commands))
+        .section(tr!("box-time-patterns"), pattern_rows)
+}
+
+// $CLI_TRACKER_EXPORT_DIR, or wherever the viewer was started
+fn export_dir() -> PathBuf {
+    env::var_os("CLI_TRACKER_EXPORT_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+pub fn save_snapshot(snapshot: &Snapshot, format: SnapshotFormat) -> Result<PathBuf> {
+    let saved_at = Local::now();
+    let dir = export_dir();
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create export directory {}", dir.display()))?;
+    let path = dir.join(snapshot.file_name(saved_at, format));
+    let contents = match format {
+        SnapshotFormat::Markdown => snapshot.to_markdown(saved_at),
+        SnapshotFormat::Json => format!("{:#}\n", snapshot.to_json(saved_at)),
+    };
+    fs::write(&path, contents)
+        .with_context(|| format!("Failed to write snapshot {}", path.display()))?;
+    Ok(path)
+}
+
+// Tells how the save went, shown by the view on its next redraw
+pub fn save_status(snapshot: &Snapshot, format: SnapshotFormat) -> String {
+    match save_snapshot(snapshot, format) {
+        Ok(path) => tr!("snapshot-saved", path = path.display().to_string()),
+        Err(err) => tr!("snapshot-failed", error = format!("{:#}", err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn snapshot() -> Snapshot {
+        Snapshot::new("Week 42 [Oct]")
+            .section(
+                "General Statistics",
+                vec![("Today".to_string(), "12".to_string())],
+            )
+            .command_section(
+                "Most Used Commands",
+                vec![("git status".to_string(), "30".to_string())],
+            )
+    }
+
+    #[test]
+    fn markdown_and_json_carry_every_section() {
+        let saved_at = Local.with_ymd_and_hms(2026, 10, 16, 9, 30, 0).unwrap();
+        let markdown = snapshot().to_markdown(saved_at);
+        assert!(markdown.starts_with("# CLI Wrapped: Week 42 [Oct]\n"));
+        assert!(markdown.contains("## General Statistics\n\n- Today: 12\n"));
+        assert!(markdown.contains("## Most Used Commands\n\n- `git status`: 30\n"));
+
+        let json = snapshot().to_json(saved_at);
+        assert_eq!(json["view"], "Week 42 [Oct]");
+        assert_eq!(json["sections"][1]["rows"][0]["label"], "git status");
+        assert_eq!(json["sections"][1]["rows"][0]["value"], "30");
+    }
+
+    #[test]
+    fn file_names_come_from_the_view_and_time() {
+        let saved_at = Local.with_ymd_and_hms(2026, 10, 16, 9, 30, 0).unwrap();
+        assert_eq!(
+            snapshot().file_name(saved_at, SnapshotFormat::Markdown),
+            "cli-wrapped-week-42-oct-20261016-093000.md"
+        );
+        assert_eq!(
+            Snapshot::new("All-time Stats").file_name(saved_at, SnapshotFormat::Json),
+            "cli-wrapped-all-time-stats-20261016-093000.json"
+        );
+    }
+}
@@ -1,800 +1,905 @@
-use anyhow::Result;
-use chrono::{Datelike, Local, TimeZone, Timelike};
+use anyhow::{Context, Result};
+use chrono::{Datelike, Local, NaiveDate, TimeZone};
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
-    style::{Color, Stylize},
     terminal::{self, ClearType},
 };
-use std::io::{self, Write};
-use unicode_width::UnicodeWidthStr;
-
-use crate::history::{format_timestamp, HistoryEntry};
-use crate::ui_utils::draw_box;
-
-pub fn display_stats(entries: &[HistoryEntry]) -> Result<()> {
-    let mut stdout = io::stdout();
-
-    // Set up terminal
-    execute!(stdout, terminal::EnterAlternateScreen)?;
-    terminal::enable_raw_mode()?;
-    execute!(stdout, cursor::Hide)?;
-
-    // Track current view: -1 = lifetime stats, 0 = current week, 1 = last week, etc.
-    let mut week_offset: i64 = -1;
-
-    loop {
-        // Get terminal size
-        let (term_width, term_height) = terminal::size()?;
-
-        // Check minimum terminal size requirements
-        let min_width = 100;
-        let min_height = 20;
-        if term_width < min_width || term_height < min_height {
-            execute!(
-                stdout,
-                terminal::Clear(ClearType::All),
-                cursor::MoveTo(0, 0)
-            )?;
-            write!(
-                stdout,
-                "Terminal too small. Please resize to at least {}x{}",
-                min_width, min_height
-            )?;
-            stdout.flush()?;
-
-            // Wait for input and check if terminal has been resized
-            if let Event::Key(KeyEvent {
-                code, modifiers, ..
-            }) = event::read()?
-            {
-                match code {
-                    KeyCode::Esc => break,
-                    KeyCode::Char('c') => {
-                        if modifiers.contains(KeyModifiers::CONTROL) {
-                            break;
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            continue;
-        }
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-        // Clear screen
-        execute!(stdout, terminal::Clear(ClearType::All))?;
-
-        // Calculate line allocation based on available height
-        // 1 line for header
-        // 6 lines for borders (3 box layers * 2 border lines each)
-        // Remaining lines for content
-
-        // Total available height
-        let usable_height = term_height;
-        let header_lines = 1;
-        let border_lines = 6; // 3 box layers * 2 border lines each
-
-        // Calculate remaining lines for content
-        let content_lines = usable_height
-            .saturating_sub(header_lines)
-            .saturating_sub(border_lines);
-
-        // Time patterns content (reduced from 4 to 2 since we removed a line)
-        let time_patterns_min = 2;
-        let time_patterns_max = 3;
-
-        // Middle layer content (start with 3, max 10)
-        let middle_layer_min = 3;
-        let middle_layer_max = 10;
-
-        // Apply priority-based allocation:
-        // 1. Ensure we have enough lines for minimum allocation
-        // 2. First allocate minimum to each layer
-        // 3. Then grow Time Patterns to max if possible
-        // 4. Then grow middle layer up to max
-        // 5. Any extra goes to top layer (though it's capped at its max)
-
-        // Update top layer max to 6 to accommodate additional content line
-        let top_layer_max = 5;
-
-        // When terminal height is limited, reduce middle box height
-        let adjusted_middle_layer_min = if term_height <= 20 {
-            2 // Reduce by 1 when height is limited
-        } else {
-            middle_layer_min
-        };
+use crate::aggregate::{
+    period_bounds, summarize, time_patterns, trend_weeks, usage_trend, CalendarPeriod,
+    PeriodBounds, ProjectRoots, Quarter, StatsSummary, TimePatterns, Trend,
+};
+use crate::app::{Screen, ScreenExit};
+use crate::config;
+use crate::days::{day_counts, local_day, streaks, Streaks};
+use crate::error::ErrorKind;
+use crate::frames;
+use crate::goals::Goal;
+use crate::help::{show_help, HelpView};
+use crate::history::HistoryEntry;
+use crate::i18n::month_abbrev;
+use crate::interactive::{browse_history, command_runs};
+use crate::panels::{
+    general_panel, goals_panel, panel_areas, ranked_list, ranked_name_width, ranking_panel,
+    ranking_rows, render_header, time_patterns_panel,
+};
+use crate::presentation;
+use crate::range_picker::DayRange;
+use crate::render::Canvas;
+use crate::snapshot::{save_status, stats_snapshot, SnapshotFormat};
+use crate::time_format::TimeDisplay;
+use crate::timing;
+use crate::tr;
+use crate::ui_utils::{
+    display_path, draw_banner, layout_thresholds, quality_title, screen_size, stats_layout,
+    truncate_end, StatsLayout,
+};
 
-        let adjusted_middle_layer_max = if term_height <= 20 {
-            middle_layer_max - 1 // Reduce max by 1 for limited height
-        } else {
-            middle_layer_max
-        };
+// Histories larger than this open the all-time view from a reservoir sample
+// while the exact counts are computed on a background thread
+const SAMPLE_THRESHOLD: usize = 250_000;
+const SAMPLE_SIZE: usize = 25_000;
 
-        // Start with minimum allocation using adjusted values
-        let base_allocation = top_layer_max + adjusted_middle_layer_min + time_patterns_min;
+// Everything the panels need for one view
+struct ViewData {
+    summary: StatsSummary,
+    patterns: TimePatterns,
+}
 
-        // Determine how many extra lines we have beyond base allocation
-        let extra_lines = content_lines.saturating_sub(base_allocation).min(20); // Cap extra at 20 to avoid excessive growth
+impl ViewData {
+    fn new(entries: &[&HistoryEntry], bounds: &PeriodBounds, roots: &ProjectRoots) -> Self {
+        ViewData {
+            summary: summarize(entries, bounds, roots),
+            patterns: time_patterns(entries),
+        }
+    }
+}
 
-        // Allocate additional lines according to priority
-        let time_patterns_extra = (time_patterns_max - time_patterns_min).min(extra_lines);
-        let time_patterns_content = time_patterns_min + time_patterns_extra;
+// What ←/→ step through, picked with w, m and y, or p for quarters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Step {
+    Week,
+    Month,
+    Year,
+    Quarter,
+}
 
-        let middle_extra = if extra_lines > time_patterns_extra {
-            (adjusted_middle_layer_max - adjusted_middle_layer_min)
-                .min(extra_lines - time_patterns_extra)
-        } else {
-            0
-        };
-        let middle_layer_content = adjusted_middle_layer_min + middle_extra;
-
-        // Top layer stays at max (already allocated in base_allocation)
-        let top_layer_content = top_layer_max;
-
-        // Calculate box heights (content + borders)
-        let top_box_height = top_layer_content + 2; // +2 for borders
-        let middle_box_height = middle_layer_content + 2; // +2 for borders
-        let bottom_box_height = time_patterns_content + 2; // +2 for borders
-
-        // Set command list limits based on available space
-        let commands_box_height = middle_box_height;
-        let max_commands = middle_layer_content as usize;
-        let max_categories = max_commands;
-
-        // Calculate widths to use the full terminal width
-        // Account for the border between columns (1 character)
-        let usable_width = term_width;
-        let half_width = usable_width / 2;
-
-        // Calculate precise widths for left and right boxes
-        let left_box_width = half_width;
-        let right_box_width = usable_width - half_width;
-
-        // Define the active entries based on current view
-        let (view_name, active_entries): (String, Vec<&HistoryEntry>) = if week_offset < 0 {
-            // Lifetime stats view
-            ("All-time Stats".to_string(), entries.iter().collect())
-        } else {
-            // Week-specific view
-            let now = chrono::Local::now();
-
-            // Calculate the start of the current week (Monday at 00:00:00)
-            let days_since_monday = now.weekday().num_days_from_monday() as i64;
-            let start_of_week = now
-                .date_naive()
-                .and_hms_opt(0, 0, 0)
-                .unwrap()
-                .and_local_timezone(chrono::Local)
-                .unwrap()
-                - chrono::Duration::days(days_since_monday)
-                - chrono::Duration::days(7 * week_offset);
-
-            // End of week is start of next week minus 1 second
-            let end_of_week =
-                start_of_week + chrono::Duration::days(7) - chrono::Duration::seconds(1);
-
-            // Get ISO week number of the year (1-52/53)
-            let week_number = start_of_week.iso_week().week();
-
-            // Format month abbreviation
-            let month_name = start_of_week.format("%b").to_string();
-
-            // Create view name in format "Week # [Month]"
-            let view_name = format!("Week {} [{}]", week_number, month_name);
-
-            // Filter entries for specific week
-            let week_entries = entries
-                .iter()
-                .filter(|e| {
-                    let ts = e.timestamp;
-                    ts >= start_of_week.timestamp() && ts <= end_of_week.timestamp()
-                })
-                .collect();
+// Entry indices bucketed by ISO week, plus per-day totals, so paging between
+// weeks never rescans the full history
+struct WeekIndex {
+    weeks: HashMap<(i32, u32), Vec<usize>>,
+    daily_counts: BTreeMap<NaiveDate, usize>,
+}
 
-            (view_name, week_entries)
-        };
+impl WeekIndex {
+    fn new(entries: &[HistoryEntry]) -> Self {
+        let mut weeks: HashMap<(i32, u32), Vec<usize>> = HashMap::new();
+        let mut daily_counts = BTreeMap::new();
 
-        // Header with view name
-        execute!(stdout, cursor::MoveTo(0, 0))?;
-
-        // Get the terminal width to properly center the controls text
-        let controls_text = "<←/h: prev, →/l: next, esc/q: exit>".dark_grey();
-        let left_text = format!("CLI Wrapped: {}", view_name).cyan().bold();
-        let right_text = format!("commands: {}", active_entries.len()).cyan();
-
-        // Calculate positions to ensure proper centering
-        let right_start = term_width.saturating_sub(right_text.to_string().width() as u16);
-        let center_start = half_width - (controls_text.to_string().width() as u16 / 2);
-
-        // Write the left part
-        write!(stdout, "{}", left_text)?;
-
-        // Write the centered controls
-        execute!(stdout, cursor::MoveTo(center_start, 0))?;
-        write!(stdout, "{}", controls_text)?;
-
-        // Write the right part
-        execute!(stdout, cursor::MoveTo(right_start, 0))?;
-        write!(stdout, "{}", right_text)?;
-
-        // Calculate time span and metrics for the active view
-        let oldest = active_entries
-            .iter()
-            .map(|e| e.timestamp)
-            .filter(|&ts| ts > 0)
-            .min()
-            .unwrap_or(0);
-        let newest = active_entries
-            .iter()
-            .map(|e| e.timestamp)
-            .filter(|&ts| ts > 0)
-            .max()
-            .unwrap_or(0);
-        let days = if newest > 0 && oldest > 0 {
-            ((newest - oldest) / 86400) + 1
-        } else if active_entries.len() > 0 {
-            // If we have entries but no valid timestamps, assume at least 1 day
-            1
-        } else {
-            0
-        };
+        for (i, entry) in entries.iter().enumerate() {
+            if entry.timestamp <= 0 {
+                continue;
+            }
+            if let chrono::LocalResult::Single(dt) = Local.timestamp_opt(entry.timestamp, 0) {
+                let week = dt.iso_week();
+                weeks.entry((week.year(), week.week())).or_default().push(i);
+                *daily_counts.entry(dt.date_naive()).or_insert(0) += 1;
+            }
+        }
 
-        // Count commands with valid timestamps
-        let commands_with_timestamps = active_entries.iter().filter(|e| e.timestamp > 0).count();
+        WeekIndex {
+            weeks,
+            daily_counts,
+        }
+    }
 
-        // Total commands including those without timestamps
-        let total_commands = active_entries.len();
+    // Indices of the entries in the week containing `day`
+    fn week(&self, day: chrono::DateTime<Local>) -> &[usize] {
+        let week = day.iso_week();
+        self.weeks
+            .get(&(week.year(), week.week()))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    // Number of entries on the local days spanned by the two timestamps
+    fn count_between(&self, start: i64, end: i64) -> usize {
+        let start = local_day(start).unwrap_or(NaiveDate::MIN);
+        let end = local_day(end).unwrap_or(NaiveDate::MAX);
+        self.daily_counts
+            .range(start..=end)
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
 
-        // Time metrics for the current week
-        let now = chrono::Local::now();
+// Algorithm R reservoir sample. The clock-seeded xorshift generator is plenty
+// for picking a representative preview.
+fn reservoir_sample(entries: &[HistoryEntry], size: usize) -> Vec<&HistoryEntry> {
+    let mut state = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+        | 1;
+    let mut next_random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    let mut sample: Vec<&HistoryEntry> = entries.iter().take(size).collect();
+    for (i, entry) in entries.iter().enumerate().skip(size) {
+        let j = (next_random() % (i as u64 + 1)) as usize;
+        if j < size {
+            sample[j] = entry;
+        }
+    }
+    sample
+}
 
-        // For specific week view, calculate the start/end of the selected week
-        let (this_week_start, this_week_end) = if week_offset >= 0 {
-            let days_since_monday = now.weekday().num_days_from_monday() as i64;
-            let start_of_week = now
-                .date_naive()
-                .and_hms_opt(0, 0, 0)
-                .unwrap()
-                .and_local_timezone(chrono::Local)
-                .unwrap()
-                - chrono::Duration::days(days_since_monday)
-                - chrono::Duration::days(7 * week_offset);
+// Opens on the all-time view, or on `period` if given. Quarters follow a
+// fiscal year starting in month `fiscal_start`.
+// The stats screen, kept while switching to other screens so its place
+// and the periods already aggregated survive
+pub struct StatsScreen {
+    entries: Arc<Vec<HistoryEntry>>,
+    roots: Arc<ProjectRoots>,
+    // Picked with r, and named in place of all time
+    range: Option<DayRange>,
+    fiscal_start: u32,
+    current_quarter: Quarter,
+    // Track current view: -1 = lifetime stats, 0 = current week or
+    // quarter, 1 = the one before, etc.
+    step: Step,
+    offset: i64,
+    // Which ranking the top right box shows
+    panel: usize,
+    // Row picked in Most Used Commands with ↑/↓, opened with Enter
+    selected: Option<usize>,
+    // Built on the first switch to a week view, then reused for every step
+    week_index: Option<WeekIndex>,
+    week_cache: HashMap<i64, ViewData>,
+    quarter_cache: HashMap<i64, ViewData>,
+    // Months and years, by step and offset
+    calendar_cache: HashMap<(Step, i64), ViewData>,
+    // All-time counts are computed once. Huge histories start from a
+    // sample and are swapped for the exact counts when the background
+    // pass finishes.
+    all_time: ViewData,
+    full_pass: Option<mpsc::Receiver<ViewData>>,
+    // Whatever the view, streaks run up to today
+    streaks: Streaks,
+}
 
-            let end_of_week =
-                start_of_week + chrono::Duration::days(7) - chrono::Duration::seconds(1);
+impl StatsScreen {
+    pub fn new(
+        entries: Arc<Vec<HistoryEntry>>,
+        roots: Arc<ProjectRoots>,
+        period: Option<Quarter>,
+        fiscal_start: u32,
+        range: Option<DayRange>,
+    ) -> StatsScreen {
+        let current_quarter = Quarter::containing(Local::now().date_naive(), fiscal_start);
+        let (step, offset) = match period {
+            Some(quarter) => (Step::Quarter, quarters_between(quarter, current_quarter)),
+            None => (Step::Week, -1),
+        };
 
-            (start_of_week.timestamp(), end_of_week.timestamp())
+        let lifetime_bounds = period_bounds(chrono::Local::now(), -1);
+        let mut full_pass = None;
+        let all_time = if entries.len() > SAMPLE_THRESHOLD {
+            let sample = reservoir_sample(&entries, SAMPLE_SIZE);
+            let factor = entries.len() as f64 / sample.len() as f64;
+            let sampled = ViewData::new(&sample, &lifetime_bounds, &roots);
+            let mut summary = sampled.summary.scaled(factor);
+            summary.total = entries.len();
+
+            let (tx, rx) = mpsc::channel();
+            let background_entries = Arc::clone(&entries);
+            let background_roots = Arc::clone(&roots);
+            thread::spawn(move || {
+                let all: Vec<&HistoryEntry> = background_entries.iter().collect();
+                // The receiver is gone if the user quit before we finished
+                let _ = tx.send(ViewData::new(&all, &lifetime_bounds, &background_roots));
+            });
+            full_pass = Some(rx);
+            ViewData {
+                summary,
+                patterns: sampled.patterns.scaled(factor),
+            }
         } else {
-            // For all-time view, use current week
-            let days_since_monday = now.weekday().num_days_from_monday() as i64;
-            let start_of_week = now
-                .date_naive()
-                .and_hms_opt(0, 0, 0)
-                .unwrap()
-                .and_local_timezone(chrono::Local)
-                .unwrap()
-                - chrono::Duration::days(days_since_monday);
-
-            let end_of_week =
-                start_of_week + chrono::Duration::days(7) - chrono::Duration::seconds(1);
-
-            (start_of_week.timestamp(), end_of_week.timestamp())
+            let all: Vec<&HistoryEntry> = entries.iter().collect();
+            ViewData::new(&all, &lifetime_bounds, &roots)
         };
 
-        // For specific week view, calculate the start/end of the month containing the selected week
-        let (this_month_start, this_month_end) = if week_offset >= 0 {
-            let days_since_monday = now.weekday().num_days_from_monday() as i64;
-            let selected_week_day = now
-                - chrono::Duration::days(days_since_monday)
-                - chrono::Duration::days(7 * week_offset);
-
-            let start_of_month = selected_week_day
-                .with_day(1)
-                .unwrap()
-                .with_hour(0)
-                .unwrap()
-                .with_minute(0)
-                .unwrap()
-                .with_second(0)
-                .unwrap();
-
-            // End of month is start of next month minus 1 second
-            let next_month = if start_of_month.month() == 12 {
-                start_of_month
-                    .with_month(1)
-                    .unwrap()
-                    .with_year(start_of_month.year() + 1)
-                    .unwrap()
+        let streaks = streaks(&day_counts(entries.iter()), Local::now().date_naive());
+        StatsScreen {
+            entries,
+            roots,
+            range,
+            fiscal_start,
+            current_quarter,
+            step,
+            offset,
+            panel: 0,
+            selected: None,
+            week_index: None,
+            week_cache: HashMap::new(),
+            quarter_cache: HashMap::new(),
+            calendar_cache: HashMap::new(),
+            all_time,
+            full_pass,
+            streaks,
+        }
+    }
+
+    pub fn run(&mut self, stdout: &mut io::Stdout) -> Result<ScreenExit> {
+        // Shown once in place of the controls after saving the view
+        let mut status: Option<String> = None;
+
+        // Smallest terminals for the full and compact layouts
+        let thresholds = layout_thresholds();
+        let goals = &config::config().goals;
+
+        loop {
+            // Get terminal size
+            let (term_width, term_height) = screen_size()?;
+
+            // Check minimum terminal size requirements. Terminals too small for
+            // every panel get the compact layout.
+            let [_, (min_width, min_height)] = thresholds;
+            let Some(layout) = stats_layout(term_width, term_height, thresholds) else {
+                execute!(
+                    stdout,
+                    terminal::Clear(ClearType::All),
+                    cursor::MoveTo(0, 0)
+                )?;
+                write!(
+                    stdout,
+                    "{}",
+                    tr!("terminal-too-small", width = min_width, height = min_height)
+                )?;
+                stdout.flush().context(ErrorKind::Terminal)?;
+
+                // Wait for input and check if terminal has been resized
+                if let Event::Key(KeyEvent {
+                    code, modifiers, ..
+                }) = frames::next_event()?
+                {
+                    if let Some(screen) = Screen::for_key(code) {
+                        return Ok(ScreenExit::Switch(screen));
+                    }
+                    match code {
+                        KeyCode::Esc => return Ok(ScreenExit::Back),
+                        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                            return Ok(ScreenExit::Quit)
+                        }
+                        _ => {}
+                    }
+                }
+                continue;
+            };
+            let compact = layout == StatsLayout::Compact;
+
+            // Clear screen
+            execute!(stdout, terminal::Clear(ClearType::All))?;
+
+            // Calculate line allocation based on available height
+            // 1 line for header
+            // 6 lines for borders (3 box layers * 2 border lines each)
+            // Remaining lines for content
+
+            // Total available height
+            let usable_height = term_height;
+            let header_lines = 1;
+            let border_lines = 6; // 3 box layers * 2 border lines each
+
+            // Calculate remaining lines for content
+            let content_lines = usable_height
+                .saturating_sub(header_lines)
+                .saturating_sub(border_lines);
+
+            // Time patterns content (reduced from 4 to 2 since we removed a line)
+            let time_patterns_min = 2;
+            let time_patterns_max = 3;
+
+            // Middle layer content (start with 3, max 10)
+            let middle_layer_min = 3;
+            let middle_layer_max = 10;
+
+            // Apply priority-based allocation:
+            // 1. Ensure we have enough lines for minimum allocation
+            // 2. First allocate minimum to each layer
+            // 3. Then grow Time Patterns to max if possible
+            // 4. Then grow middle layer up to max
+            // 5. Any extra goes to top layer (though it's capped at its max)
+
+            // Update top layer max to 6 to accommodate additional content line
+            let top_layer_max = 6;
+
+            // When terminal height is limited, reduce middle box height
+            let adjusted_middle_layer_min = if term_height <= 20 {
+                2 // Reduce by 1 when height is limited
             } else {
-                start_of_month
-                    .with_month(start_of_month.month() + 1)
-                    .unwrap()
+                middle_layer_min
             };
 
-            let end_of_month = next_month - chrono::Duration::seconds(1);
-
-            (start_of_month.timestamp(), end_of_month.timestamp())
-        } else {
-            // For all-time view, use current month
-            let start_of_month = now
-                .with_day(1)
-                .unwrap()
-                .with_hour(0)
-                .unwrap()
-                .with_minute(0)
-                .unwrap()
-                .with_second(0)
-                .unwrap();
-
-            // End of month is start of next month minus 1 second
-            let next_month = if start_of_month.month() == 12 {
-                start_of_month
-                    .with_month(1)
-                    .unwrap()
-                    .with_year(start_of_month.year() + 1)
-                    .unwrap()
+            let adjusted_middle_layer_max = if term_height <= 20 {
+                middle_layer_max - 1 // Reduce max by 1 for limited height
             } else {
-                start_of_month
-                    .with_month(start_of_month.month() + 1)
-                    .unwrap()
+                middle_layer_max
             };
 
-            let end_of_month = next_month - chrono::Duration::seconds(1);
-
-            (start_of_month.timestamp(), end_of_month.timestamp())
-        };
+            // Start with minimum allocation using adjusted values
+            let base_allocation = top_layer_max + adjusted_middle_layer_min + time_patterns_min;
 
-        // Get today's date for the "today" metric
-        let today_start = now
-            .date_naive()
-            .and_hms_opt(0, 0, 0)
-            .unwrap()
-            .and_local_timezone(chrono::Local)
-            .unwrap()
-            .timestamp();
-
-        // Count commands for different time periods, specific to the view
-        let commands_today = entries
-            .iter()
-            .filter(|e| e.timestamp >= today_start)
-            .count();
-
-        let commands_this_week = entries
-            .iter()
-            .filter(|e| e.timestamp >= this_week_start && e.timestamp <= this_week_end)
-            .count();
-
-        let commands_this_month = entries
-            .iter()
-            .filter(|e| e.timestamp >= this_month_start && e.timestamp <= this_month_end)
-            .count();
-
-        // Top Left Box - General Statistics
-        draw_box(
-            &mut stdout,
-            0,
-            1,
-            left_box_width,
-            top_box_height,
-            Some("General Statistics"),
-        )?;
-
-        // Different stats depending on view
-        let general_stats = if week_offset < 0 {
-            // Lifetime stats
-            [
-                ("Today", commands_today.to_string()),
-                ("This week", commands_this_week.to_string()),
-                ("This month", commands_this_month.to_string()),
-                ("Weekly average", {
-                    if days == 0 {
-                        "0".to_string()
-                    } else {
-                        // Calculate weeks since first command
-                        let weeks = (days as f64 / 7.0).ceil().max(1.0);
-                        // Use commands_with_timestamps for accurate time-based average
-                        format!("{:.1}", commands_with_timestamps as f64 / weeks)
-                    }
-                }),
-                (
-                    "Unique commands",
-                    active_entries
-                        .iter()
-                        .map(|e| &e.command)
-                        .collect::<std::collections::HashSet<_>>()
-                        .len()
-                        .to_string(),
-                ),
-            ]
-        } else {
-            // Weekly stats
-            [
-                ("Today", commands_today.to_string()),
-                ("This week", commands_this_week.to_string()),
-                ("This month", commands_this_month.to_string()),
-                ("Commands per day", {
-                    if days > 0 {
-                        format!("{:.1}", active_entries.len() as f64 / days as f64)
-                    } else {
-                        "0".to_string()
-                    }
-                }),
-                (
-                    "Unique commands",
-                    active_entries
-                        .iter()
-                        .map(|e| &e.command)
-                        .collect::<std::collections::HashSet<_>>()
-                        .len()
-                        .to_string(),
-                ),
-            ]
-        };
-
-        for (i, (key, value)) in general_stats.iter().enumerate() {
-            execute!(stdout, cursor::MoveTo(3, 2 + i as u16))?;
-            write!(stdout, "{:<14} {}", key.with(Color::DarkGrey), value)?;
-        }
-
-        // Top Right Box - Command Categories (Moved from Middle Right)
-        draw_box(
-            &mut stdout,
-            left_box_width,
-            1, // Moved to top row (y=1)
-            right_box_width,
-            top_box_height, // Use height of top row boxes
-            Some("Command Categories"),
-        )?;
-
-        let mut categories: std::collections::HashMap<&str, usize> =
-            std::collections::HashMap::new();
-        for entry in &active_entries {
-            let first_word = entry.command.split_whitespace().next().unwrap_or("other");
-            *categories.entry(first_word).or_insert(0) += 1;
-        }
-
-        // Sort by frequency
-        let mut categories: Vec<_> = categories.into_iter().collect();
-        categories.sort_by(|a, b| b.1.cmp(&a.1));
-
-        // Display top categories with percentage bars (limited by top_layer_content)
-        for (i, (category, count)) in categories
-            .iter()
-            .take(top_layer_content as usize)
-            .enumerate()
-        {
-            let percentage = if active_entries.is_empty() {
+            // Weekly goals get a box of their own under Time Patterns, taking
+            // lines from the middle layer, when it fits with the rest at their
+            // minimum
+            let goals_box_height =
+                (goals.len() as u16 + 2).min(content_lines.saturating_sub(base_allocation));
+            let goals_box_height = if goals.is_empty() || goals_box_height < 3 {
                 0
             } else {
-                (*count as f64 / active_entries.len() as f64 * 100.0) as usize
+                goals_box_height
             };
 
-            // Ensure we have a fixed width for the category name
-            let category_display = if category.len() > 10 {
-                format!("{}...", &category[..7])
+            // Determine how many extra lines we have beyond base allocation
+            let extra_lines = content_lines
+                .saturating_sub(base_allocation + goals_box_height)
+                .min(20); // Cap extra at 20 to avoid excessive growth
+
+            // Allocate additional lines according to priority
+            let time_patterns_extra = (time_patterns_max - time_patterns_min).min(extra_lines);
+            let time_patterns_content = time_patterns_min + time_patterns_extra;
+
+            let middle_extra = if extra_lines > time_patterns_extra {
+                (adjusted_middle_layer_max - adjusted_middle_layer_min)
+                    .min(extra_lines - time_patterns_extra)
             } else {
-                format!("{:<10}", category)
+                0
             };
+            let middle_layer_content = adjusted_middle_layer_min + middle_extra;
 
-            execute!(
-                stdout,
-                cursor::MoveTo(left_box_width + 3, 2 + i as u16) // Use top row y coordinate base (2)
-            )?;
-            write!(stdout, "{} ", category_display)?;
-
-            // Calculate bar width based on available space
-            let max_bar_width = (right_box_width as usize).saturating_sub(20);
-            let bar_width = (percentage * max_bar_width / 100).min(max_bar_width);
-            // Use a clearer bar character for better visibility
-            let dots = "█".repeat(bar_width);
-            write!(stdout, "{} {}%", dots, percentage)?;
-        }
-
-        // Middle Left Box - Most Used Directories (Moved from Middle Right)
-        draw_box(
-            &mut stdout,
-            0, // Moved to left column (x=0)
-            top_box_height + 1,
-            left_box_width, // Use width of left column
-            commands_box_height,
-            Some("Most Used Directories"),
-        )?;
-
-        // Count directory frequency
-        let mut directory_counts: std::collections::HashMap<String, usize> =
-            std::collections::HashMap::new();
-        for entry in &active_entries {
-            if let Some(dir) = &entry.directory {
-                *directory_counts.entry(dir.clone()).or_insert(0) += 1;
-            }
-        }
+            // Top layer stays at max (already allocated in base_allocation)
+            let top_layer_content = top_layer_max;
 
-        // Sort by frequency
-        let mut directory_counts: Vec<_> = directory_counts.into_iter().collect();
-        directory_counts.sort_by(|a, b| b.1.cmp(&a.1));
+            // Calculate box heights (content + borders)
+            let top_box_height = top_layer_content + 2; // +2 for borders
+            let middle_box_height = middle_layer_content + 2; // +2 for borders
+            let bottom_box_height = time_patterns_content + 2; // +2 for borders
 
-        // Display top directories (limited by max_commands)
-        for (i, (dir, count)) in directory_counts.iter().take(max_commands).enumerate() {
-            let display_width = left_box_width.saturating_sub(15) as usize; // Use left_box_width for truncation
-            let truncated_dir = if dir.len() > display_width {
-                format!("{}...", &dir[0..display_width - 3])
-            } else {
-                dir.to_string()
-            };
+            // Set command list limits based on available space
+            let commands_box_height = middle_box_height;
+            let max_commands = middle_layer_content as usize;
 
-            execute!(stdout, cursor::MoveTo(3, top_box_height + 2 + i as u16))?;
-            write!(stdout, "{:2}. {} ", i + 1, truncated_dir)?;
+            // Numbers in the all-time view are estimates until the full pass lands
+            let approximate = self.offset < 0 && self.full_pass.is_some();
+            let approx = if approximate { "~" } else { "" };
 
-            execute!(
-                stdout,
-                cursor::MoveTo(left_box_width - 10, top_box_height + 2 + i as u16) // Position count relative to left_box_width
-            )?;
-            write!(stdout, "{}", count.to_string().with(Color::DarkGrey))?;
-        }
+            // Aggregate the active entries based on current view
+            let (view_name, view, period): (String, &ViewData, Option<(i64, i64)>) = if self.offset
+                < 0
+            {
+                // Lifetime stats view
+                let view_name = match self.range {
+                    Some(range) => range.to_string(),
+                    None if approximate => tr!("view-all-time-approximate"),
+                    None => tr!("view-all-time"),
+                };
+                (view_name, &self.all_time, None)
+            } else if self.step == Step::Quarter {
+                let quarter = self.current_quarter.back(self.offset);
+                let view_name = if self.fiscal_start == 1 {
+                    tr!(
+                        "view-quarter",
+                        quarter = quarter.quarter,
+                        year = quarter.year
+                    )
+                } else {
+                    tr!(
+                        "view-fiscal-quarter",
+                        quarter = quarter.quarter,
+                        year = quarter.year
+                    )
+                };
+
+                let index = self
+                    .week_index
+                    .get_or_insert_with(|| WeekIndex::new(&self.entries));
+                let quarter_view = self.quarter_cache.entry(self.offset).or_insert_with(|| {
+                    let (start, end) = quarter.bounds(self.fiscal_start);
+                    let quarter_entries: Vec<&HistoryEntry> = self
+                        .entries
+                        .iter()
+                        .filter(|e| e.timestamp >= start && e.timestamp <= end)
+                        .collect();
+
+                    // Today, This week and This month are the current ones
+                    let bounds = period_bounds(Local::now(), 0);
+                    let mut view = ViewData::new(&quarter_entries, &bounds, &self.roots);
+                    view.summary.today = index.count_between(bounds.today_start, i64::MAX);
+                    view.summary.this_week = index.count_between(bounds.week.0, bounds.week.1);
+                    view.summary.this_month = index.count_between(bounds.month.0, bounds.month.1);
+                    view
+                });
 
-        // Middle Right Box - Most Used Commands (Moved from Middle Left)
-        draw_box(
-            &mut stdout,
-            left_box_width, // Moved to right column
-            top_box_height + 1,
-            right_box_width, // Use width of right column
-            commands_box_height,
-            Some("Most Used Commands"),
-        )?;
-
-        // Count command frequency
-        let mut command_counts: std::collections::HashMap<&str, usize> =
-            std::collections::HashMap::new();
-        for entry in &active_entries {
-            *command_counts.entry(&entry.command).or_insert(0) += 1;
-        }
+                (
+                    view_name,
+                    &*quarter_view,
+                    Some(quarter.bounds(self.fiscal_start)),
+                )
+            } else if matches!(self.step, Step::Month | Step::Year) {
+                let calendar = if self.step == Step::Month {
+                    CalendarPeriod::Month
+                } else {
+                    CalendarPeriod::Year
+                };
+                let today = Local::now().date_naive();
+                let first = calendar.first_day(today, self.offset as u32);
+                let view_name = match calendar {
+                    CalendarPeriod::Month => tr!(
+                        "view-month",
+                        month = month_abbrev(first.month()),
+                        year = first.year()
+                    ),
+                    _ => tr!("view-year", year = first.year()),
+                };
+                let (start, end) = calendar.bounds(today, self.offset as u32);
+
+                let index = self
+                    .week_index
+                    .get_or_insert_with(|| WeekIndex::new(&self.entries));
+                let calendar_view = self
+                    .calendar_cache
+                    .entry((self.step, self.offset))
+                    .or_insert_with(|| {
+                        let period_entries: Vec<&HistoryEntry> = self
+                            .entries
+                            .iter()
+                            .filter(|e| e.timestamp >= start && e.timestamp <= end)
+                            .collect();
+
+                        // Today, This week and This month are the current ones
+                        let bounds = period_bounds(Local::now(), 0);
+                        let mut view = ViewData::new(&period_entries, &bounds, &self.roots);
+                        view.summary.today = index.count_between(bounds.today_start, i64::MAX);
+                        view.summary.this_week = index.count_between(bounds.week.0, bounds.week.1);
+                        view.summary.this_month =
+                            index.count_between(bounds.month.0, bounds.month.1);
+                        view
+                    });
+
+                (view_name, &*calendar_view, Some((start, end)))
+            } else {
+                // Week-specific view
+                let now = chrono::Local::now();
+                let bounds = period_bounds(now, self.offset);
+                let start_of_week = Local.timestamp_opt(bounds.week.0, 0).unwrap();
+
+                // Get ISO week number of the year (1-52/53)
+                let week_number = start_of_week.iso_week().week();
+
+                // Format month abbreviation
+                let month_name = month_abbrev(start_of_week.month());
+
+                // Create view name in format "Week # [Month]"
+                let view_name = tr!("view-week", week = week_number, month = month_name);
+
+                // Weeks come from the pre-bucketed index and are aggregated once
+                let index = self
+                    .week_index
+                    .get_or_insert_with(|| WeekIndex::new(&self.entries));
+                let week_view = self.week_cache.entry(self.offset).or_insert_with(|| {
+                    let week_entries: Vec<&HistoryEntry> = index
+                        .week(start_of_week)
+                        .iter()
+                        .map(|&i| &self.entries[i])
+                        .collect();
 
-        // Sort by frequency
-        let mut command_counts: Vec<_> = command_counts.into_iter().collect();
-        command_counts.sort_by(|a, b| b.1.cmp(&a.1));
+                    // Today and This month reach outside the selected week
+                    let mut view = ViewData::new(&week_entries, &bounds, &self.roots);
+                    view.summary.today = index.count_between(bounds.today_start, i64::MAX);
+                    view.summary.this_month = index.count_between(bounds.month.0, bounds.month.1);
+                    view
+                });
 
-        // Display top commands (limited by max_commands)
-        for (i, (cmd, count)) in command_counts.iter().take(max_commands).enumerate() {
-            let display_width = right_box_width.saturating_sub(15) as usize; // Use right_box_width for truncation
-            let truncated_cmd = if cmd.len() > display_width {
-                format!("{}...", &cmd[0..display_width - 3])
+                (view_name, &*week_view, Some(bounds.week))
+            };
+            let counts = &view.summary;
+            let quality = counts.quality();
+            // Only weeks are compared with the weeks before them
+            let week_trend = match (self.step, period, &self.week_index) {
+                (Step::Week, Some(week), Some(index)) if quality.timing_reliable() => usage_trend(
+                    |start, end| index.count_between(start, end),
+                    week,
+                    Local::now().timestamp(),
+                    trend_weeks(),
+                ),
+                _ => None,
+            };
+            let view_name = quality_title(&view_name, quality.timing_reliable());
+            let selected_row = self
+                .selected
+                .map(|row| row.min(max_commands.min(counts.commands.len()).saturating_sub(1)));
+
+            // Header with view name
+            let controls_text = match status.take() {
+                Some(message) => message,
+                // The compact layout has no room for them beside the title
+                None if compact => String::new(),
+                None => tr!("stats-controls"),
+            };
+            let mut canvas = Canvas::screen()?;
+            let areas = panel_areas(
+                canvas.area(),
+                [
+                    top_box_height,
+                    commands_box_height,
+                    bottom_box_height,
+                    goals_box_height,
+                ],
+                compact,
+            );
+            render_header(
+                &mut canvas,
+                areas.header,
+                &tr!("app-title-view", view = view_name.as_str()),
+                &controls_text,
+                &tr!("commands-count", count = counts.total),
+            );
+
+            // Different stats depending on view
+            let general_stats = if self.offset < 0 {
+                // Lifetime stats
+                [
+                    (tr!("stat-today"), format!("{}{}", approx, counts.today)),
+                    (
+                        tr!("stat-this-week"),
+                        format!("{}{}", approx, counts.this_week),
+                    ),
+                    (
+                        tr!("stat-this-month"),
+                        format!("{}{}", approx, counts.this_month),
+                    ),
+                    (
+                        tr!("stat-weekly-average"),
+                        format!("{}{:.1}", approx, counts.weekly_average()),
+                    ),
+                    (tr!("stat-unique"), format!("{}{}", approx, counts.unique)),
+                    streak_row(&self.streaks),
+                ]
             } else {
-                cmd.to_string()
+                // Weekly and quarterly stats
+                [
+                    (tr!("stat-today"), counts.today.to_string()),
+                    (tr!("stat-this-week"), counts.this_week.to_string()),
+                    (tr!("stat-this-month"), counts.this_month.to_string()),
+                    (
+                        tr!("stat-per-day"),
+                        with_trend(format!("{:.1}", counts.per_day()), week_trend),
+                    ),
+                    (tr!("stat-unique"), counts.unique.to_string()),
+                    streak_row(&self.streaks),
+                ]
             };
+            canvas.render(general_panel(&general_stats), areas.general);
+
+            // Top Right Box - rankings, cycled with Tab
+            let panels = [
+                (tr!("panel-categories"), &counts.categories, false),
+                (tr!("panel-pipeline-tools"), &counts.pipeline_tools, false),
+                (tr!("panel-edited-types"), &counts.edited_extensions, false),
+                (tr!("panel-edited-areas"), &counts.edited_areas, true),
+                (tr!("panel-contexts"), &counts.contexts, false),
+                (tr!("panel-projects"), &counts.projects, true),
+            ];
+            let (panel_title, panel_counts, directories) = &panels[self.panel % panels.len()];
+            let shown = ranking_rows(
+                panel_counts,
+                top_layer_content as usize,
+                *directories,
+                presentation::enabled(),
+            );
+            if let Some(area) = areas.rankings {
+                canvas.render(
+                    ranking_panel(panel_title, &shown, counts, quality, approx, area.width),
+                    area,
+                );
+            }
 
-            execute!(
-                stdout,
-                cursor::MoveTo(left_box_width + 3, top_box_height + 2 + i as u16)
-            )?;
-            write!(stdout, "{:2}. {} ", i + 1, truncated_cmd)?;
-
-            execute!(
-                stdout,
-                cursor::MoveTo(
-                    left_box_width + right_box_width - 10,
-                    top_box_height + 2 + i as u16
-                ) // Position count relative to total width
-            )?;
-            write!(stdout, "{}", count.to_string().with(Color::DarkGrey))?;
-        }
-
-        // Bottom Box - Time Patterns
-        let bottom_y = 1 + top_box_height + commands_box_height;
-        draw_box(
-            &mut stdout,
-            0,
-            bottom_y,
-            usable_width, // Use the full width for the bottom box
-            bottom_box_height,
-            Some("Time Patterns"),
-        )?;
-
-        // Count by hour of day
-        let mut hour_counts = vec![0; 24];
-        for entry in active_entries.iter().filter(|e| e.timestamp > 0) {
-            let dt = Local.timestamp_opt(entry.timestamp, 0);
-            if let chrono::LocalResult::Single(dt) = dt {
-                let hour = dt.hour() as usize;
-                if hour < 24 {
-                    hour_counts[hour] += 1;
-                }
+            // Directories are left out of the compact layout
+            if let Some(area) = areas.directories {
+                let name_width = ranked_name_width(area.width);
+                let rows = counts
+                    .directories
+                    .iter()
+                    .take(max_commands)
+                    .map(|(dir, count)| {
+                        (
+                            display_path(dir, name_width),
+                            format!("{}{}", approx, count),
+                        )
+                    });
+                canvas.render(ranked_list(&tr!("box-directories"), rows, None, None), area);
             }
-        }
 
-        // Calculate average usage per hour
-        let total_usage: i32 = hour_counts.iter().sum();
-        let active_hours = hour_counts.iter().filter(|&&count| count > 0).count();
-        let avg_usage = if active_hours > 0 {
-            total_usage as f64 / active_hours as f64
-        } else {
-            0.0
-        };
+            // Most Used Commands, with how to open a command's runs on the
+            // box's bottom border
+            let name_width = ranked_name_width(areas.commands.width);
+            let rows = counts
+                .commands
+                .iter()
+                .take(max_commands)
+                .map(|(cmd, count)| {
+                    (
+                        truncate_end(cmd, name_width),
+                        format!("{}{}", approx, count),
+                    )
+                });
+            canvas.render(
+                ranked_list(
+                    &tr!("box-commands"),
+                    rows,
+                    selected_row,
+                    Some(&tr!("commands-hint")),
+                ),
+                areas.commands,
+            );
+
+            // Bottom Box - Time Patterns
+            let patterns = &view.patterns;
+            canvas.render(
+                time_patterns_panel(patterns, quality, approx),
+                areas.patterns,
+            );
+
+            // Weekly goals, for the week on screen or this week in the other views
+            if goals_box_height > 0 {
+                let week_offset = if self.step == Step::Week {
+                    self.offset.max(0)
+                } else {
+                    0
+                };
+                let bounds = period_bounds(Local::now(), week_offset);
+                let start_of_week = Local.timestamp_opt(bounds.week.0, 0).unwrap();
+                let index = self
+                    .week_index
+                    .get_or_insert_with(|| WeekIndex::new(&self.entries));
+                let week = index.week(start_of_week);
+                let progress: Vec<(&Goal, usize)> = goals
+                    .iter()
+                    .take(goals_box_height as usize - 2)
+                    .map(|goal| (goal, goal.count(week.iter().map(|&i| &self.entries[i]))))
+                    .collect();
+                canvas.render(
+                    goals_panel(
+                        start_of_week.iso_week().week(),
+                        &progress,
+                        areas.goals.width,
+                    ),
+                    areas.goals,
+                );
+            }
+            canvas.flush(stdout)?;
+            draw_banner(stdout).context(ErrorKind::Terminal)?;
+
+            // Wait for user input
+            stdout.flush().context(ErrorKind::Terminal)?;
+
+            let snapshot = stats_snapshot(
+                &view_name,
+                &general_stats,
+                (panel_title, &shown),
+                counts,
+                patterns,
+                max_commands,
+                approx,
+            );
+
+            // What Enter opens, taken now since the counts may be replaced below
+            let selected_command = counts
+                .commands
+                .get(selected_row.unwrap_or(0))
+                .map(|(command, _)| command.clone());
+
+            timing::frame_drawn();
+
+            // While the background pass is running, poll so the exact counts can
+            // replace the estimates as soon as they arrive
+            let next_event = loop {
+                if self.full_pass.is_none() || frames::poll(Duration::from_millis(100))? {
+                    break Some(frames::next_event()?);
+                }
+                if let Some(exact) = self.full_pass.as_ref().and_then(|rx| rx.try_recv().ok()) {
+                    self.all_time = exact;
+                    self.full_pass = None;
+                    break None;
+                }
+            };
+            let Some(next_event) = next_event else {
+                continue; // Redraw with the exact counts
+            };
 
-        // Find peak hour of day
-        let (peak_hour, peak_count) = hour_counts
-            .iter()
-            .enumerate()
-            .max_by_key(|&(_, count)| count)
-            .unwrap_or((0, &0));
-
-        // Find peak day of week
-        let mut day_of_week_counts = vec![0; 7];
-
-        // Filter to get only entries with valid timestamps
-        let entries_with_timestamps: Vec<&HistoryEntry> = active_entries
-            .iter()
-            .filter(|e| e.timestamp > 0)
-            .copied()
-            .collect();
-
-        for entry in &entries_with_timestamps {
-            let dt = Local.timestamp_opt(entry.timestamp, 0);
-            if let chrono::LocalResult::Single(dt) = dt {
-                let weekday = dt.weekday().num_days_from_monday() as usize;
-                if weekday < 7 {
-                    day_of_week_counts[weekday] += 1;
+            if let Event::Key(KeyEvent { code, .. }) = next_event {
+                if let Some(screen) = Screen::for_key(code) {
+                    return Ok(ScreenExit::Switch(screen));
+                }
+                if let Some(format) = SnapshotFormat::for_key(code) {
+                    status = Some(save_status(&snapshot, format));
+                    continue;
                 }
             }
-        }
-
-        let (peak_day_idx, peak_day_count) = day_of_week_counts
-            .iter()
-            .enumerate()
-            .max_by_key(|&(_, count)| count)
-            .unwrap_or((0, &0));
-
-        let weekdays = [
-            "Monday",
-            "Tuesday",
-            "Wednesday",
-            "Thursday",
-            "Friday",
-            "Saturday",
-            "Sunday",
-        ];
-        let peak_day = weekdays[peak_day_idx];
-
-        // Display peak times with consistent spacing
-        execute!(stdout, cursor::MoveTo(3, bottom_y + 1))?;
-        if *peak_count > 0 {
-            write!(
-                stdout,
-                "Peak hour: {:02}:00 ({} commands)",
-                peak_hour, peak_count
-            )?;
-        } else {
-            write!(stdout, "Peak hour: None")?;
-        }
 
-        execute!(stdout, cursor::MoveTo(3, bottom_y + 2))?;
-        if *peak_day_count > 0 {
-            write!(
-                stdout,
-                "Peak day: {} ({} commands)",
-                peak_day, peak_day_count
-            )?;
-        } else {
-            write!(stdout, "Peak day: None")?;
-        }
-
-        // Day of week distribution with better alignment
-        execute!(stdout, cursor::MoveTo(3, bottom_y + 3))?;
-        write!(stdout, "Day distribution: ")?;
-
-        let days = ["M", "T", "W", "T", "F", "S", "S"];
-        let distribution_start_x = 22; // Slightly adjust the starting position
-        let day_spacing = 7; // Consistent spacing between day percentages
-
-        // Calculate total from day_of_week_counts to ensure percentages add up to 100%
-        let total_days_count: usize = day_of_week_counts.iter().sum();
-        let mut percentages = vec![0; 7];
-        let mut float_percentages = vec![0.0; 7];
-        let mut sum = 0;
-
-        if total_days_count > 0 {
-            for (i, &count) in day_of_week_counts.iter().enumerate() {
-                let pct = (count as f64 / total_days_count as f64) * 100.0;
-                float_percentages[i] = pct;
-                percentages[i] = pct.round() as i32;
-                sum += percentages[i];
-            }
-            // Adjust so total is exactly 100%
-            if sum != 100 {
-                // Find the index with the largest fractional part
-                let mut diffs: Vec<(usize, f64)> = float_percentages
-                    .iter()
-                    .enumerate()
-                    .map(|(i, &f)| (i, f - (percentages[i] as f64)))
-                    .collect();
-                if sum < 100 {
-                    // Add to the day with the largest positive remainder
-                    diffs.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-                    for _ in 0..(100 - sum) {
-                        percentages[diffs[0].0] += 1;
+            // Handle key presses
+            match next_event {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc, ..
+                })
+                | Event::Key(KeyEvent {
+                    code: KeyCode::Char('q'),
+                    ..
+                }) => return Ok(ScreenExit::Back),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Left,
+                    ..
+                })
+                | Event::Key(KeyEvent {
+                    code: KeyCode::Char('h'),
+                    ..
+                }) => {
+                    // Go back (all-time -> current period -> previous periods)
+                    if self.offset < 0 {
+                        // When in all-time view, switch to current week or quarter
+                        self.offset = 0;
+                    } else {
+                        // When in a period view, go back one (increase offset)
+                        self.offset += 1;
                     }
-                } else if sum > 100 {
-                    // Subtract from the day with the smallest (most negative) remainder
-                    diffs.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-                    for _ in 0..(sum - 100) {
-                        percentages[diffs[0].0] -= 1;
+                    continue; // Force immediate refresh of the display
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Right,
+                    ..
+                })
+                | Event::Key(KeyEvent {
+                    code: KeyCode::Char('l'),
+                    ..
+                }) => {
+                    // Go forward (previous periods -> current period -> all-time)
+                    if self.offset > 0 {
+                        // When viewing past periods, move forward one (decrease offset)
+                        self.offset -= 1;
+                    } else if self.offset == 0 {
+                        // When viewing the current period, go to all-time view
+                        self.offset = -1;
                     }
+                    continue; // Force immediate refresh of the display
                 }
-            }
-        }
-
-        for (i, &pct) in percentages.iter().enumerate() {
-            execute!(
-                stdout,
-                cursor::MoveTo(distribution_start_x + i as u16 * day_spacing, bottom_y + 3)
-            )?;
-            write!(stdout, "{}:{}%", days[i], pct)?;
-        }
-
-        // Wait for user input
-        stdout.flush()?;
-
-        // Handle key presses
-        match event::read()? {
-            Event::Key(KeyEvent {
-                code: KeyCode::Esc, ..
-            })
-            | Event::Key(KeyEvent {
-                code: KeyCode::Char('q'),
-                ..
-            }) => break,
-            Event::Key(KeyEvent {
-                code: KeyCode::Left,
-                ..
-            })
-            | Event::Key(KeyEvent {
-                code: KeyCode::Char('h'),
-                ..
-            }) => {
-                // Go back (all-time -> current week -> previous weeks)
-                if week_offset < 0 {
-                    // When in all-time view, switch to current week
-                    week_offset = 0;
-                } else {
-                    // When in a week view, go back one week (increase offset)
-                    week_offset += 1;
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('p'),
+                    ..
+                }) => {
+                    // Switch between weeks and quarters, starting from the current one
+                    self.step = match self.step {
+                        Step::Quarter => Step::Week,
+                        _ => Step::Quarter,
+                    };
+                    self.offset = self.offset.min(0);
+                    continue;
                 }
-                continue; // Force immediate refresh of the display
-            }
-            Event::Key(KeyEvent {
-                code: KeyCode::Right,
-                ..
-            })
-            | Event::Key(KeyEvent {
-                code: KeyCode::Char('l'),
-                ..
-            }) => {
-                // Go forward (previous weeks -> current week -> all-time)
-                if week_offset > 0 {
-                    // When viewing past weeks, move forward one week (decrease offset)
-                    week_offset -= 1;
-                } else if week_offset == 0 {
-                    // When viewing current week, go to all-time view
-                    week_offset = -1;
+                Event::Key(KeyEvent {
+                    code: code @ KeyCode::Char('w' | 'm' | 'y'),
+                    ..
+                }) => {
+                    // Step by weeks, months or years, from the current one
+                    self.step = match code {
+                        KeyCode::Char('w') => Step::Week,
+                        KeyCode::Char('m') => Step::Month,
+                        _ => Step::Year,
+                    };
+                    self.offset = self.offset.min(0);
+                    continue;
                 }
-                continue; // Force immediate refresh of the display
-            }
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('c'),
-                modifiers,
-                ..
-            }) => {
-                if modifiers.contains(KeyModifiers::CONTROL) {
-                    break;
+                Event::Key(KeyEvent {
+                    code: KeyCode::Tab, ..
+                }) => {
+                    self.panel += 1;
+                    continue;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('r'),
+                    ..
+                }) => return Ok(ScreenExit::PickRange),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('?'),
+                    ..
+                }) => {
+                    show_help(stdout, HelpView::Stats)?;
+                    continue;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Up | KeyCode::Char('k'),
+                    ..
+                }) => {
+                    self.selected = Some(selected_row.unwrap_or(0).saturating_sub(1));
+                    continue;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Down | KeyCode::Char('j'),
+                    ..
+                }) => {
+                    self.selected = Some(selected_row.map_or(0, |row| row + 1));
+                    continue;
                 }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Enter,
+                    ..
+                }) => {
+                    // Every run of the command in this view, in the history viewer
+                    let Some(command) = &selected_command else {
+                        continue;
+                    };
+                    let runs = command_runs(&self.entries, command, period);
+                    if runs.is_empty() {
+                        continue;
+                    }
+                    match browse_history(stdout, &runs, TimeDisplay::Absolute)? {
+                        ScreenExit::Back => continue,
+                        exit => return Ok(exit),
+                    }
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers,
+                    ..
+                }) if modifiers.contains(KeyModifiers::CONTROL) => return Ok(ScreenExit::Quit),
+                _ => {}
             }
-            _ => {}
         }
     }
+}
 
-    // Clean up
-    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
-    terminal::disable_raw_mode()?;
+// The General Statistics row for the current and longest streak
+pub(crate) fn streak_row(streaks: &Streaks) -> (String, String) {
+    (
+        tr!("stat-streak"),
+        tr!(
+            "streak-value",
+            current = streaks.current,
+            longest = streaks.longest
+        ),
+    )
+}
+
+// `value` followed by how it compares with the weeks before, if known
+pub(crate) fn with_trend(value: String, trend: Option<Trend>) -> String {
+    match trend {
+        Some(trend) => {
+            let trend = tr!(
+                "trend-vs-average",
+                trend = trend.short(),
+                weeks = trend.weeks
+            );
+            format!("{} ({})", value, trend)
+        }
+        None => value,
+    }
+}
 
-    Ok(())
+// How many quarters `earlier` is before `later`. Future quarters open on the
+// current one.
+fn quarters_between(earlier: Quarter, later: Quarter) -> i64 {
+    let index = |q: Quarter| q.year as i64 * 4 + q.quarter as i64;
+    (index(later) - index(earlier)).max(0)
 }
@@ -0,0 +1,326 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::config::config;
+use crate::history::{
+    default_history_file, get_history_entries, parse_stats_log, resolve_directories, HistoryEntry,
+    Shell, Source, UserDirs,
+};
+use crate::timing;
+
+// Bytes kept from the end of each imported file. If they no longer match,
+// the file was rewritten (e.g. by zsh dropping duplicates or by `purge`)
+// and is imported again from the start.
+const TAIL_LENGTH: u64 = 256;
+
+// Seconds between the imports of `ingest --watch`
+const INGEST_INTERVAL: u64 = 300;
+
+// Battery charge, in percent, below which `ingest --watch` waits for mains
+// power
+const MIN_BATTERY: u8 = 20;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS files (
+        path TEXT PRIMARY KEY,
+        imported INTEGER NOT NULL,
+        tail BLOB NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS entries (
+        file TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        command TEXT NOT NULL,
+        directory TEXT,
+        duration INTEGER,
+        exit_code INTEGER,
+        context TEXT,
+        session_id TEXT,
+        git_commit TEXT
+    );
+    CREATE INDEX IF NOT EXISTS entries_by_file ON entries (file);
+";
+
+// Where `ingest` keeps its copy of the history
+pub fn store_path(dirs: &UserDirs) -> PathBuf {
+    dirs.data.join("cli-tracker/history.db")
+}
+
+// History files imported into SQLite, so the views don't have to parse
+// large files on every start. Entries keep their order within each file.
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Store> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open history database {}", path.display()))?;
+        Store::with_connection(conn)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Store> {
+        conn.execute_batch(SCHEMA)
+            .context("Failed to set up history database")?;
+        Ok(Store { conn })
+    }
+
+    // Import the lines added to `path` since the last time, in the format of
+    // `shell` or the stats log's. Returns how many commands were new.
+    pub fn ingest(&mut self, path: &Path, shell: Option<Shell>) -> Result<usize> {
+        let key = path.to_string_lossy();
+        let mut file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let length = file.metadata()?.len();
+
+        let known: Option<(u64, Vec<u8>)> = self
+            .conn
+            .query_row(
+                "SELECT imported, tail FROM files WHERE path = ?1",
+                [&key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let mut imported = 0;
+        if let Some((offset, tail)) = known {
+            if offset <= length {
+                let mut current = vec![0; tail.len()];
+                file.seek(SeekFrom::Start(offset - tail.len() as u64))?;
+                file.read_exact(&mut current)?;
+                if current == tail {
+                    imported = offset;
+                }
+            }
+        }
+
+        let mut new = Vec::new();
+        file.seek(SeekFrom::Start(imported))?;
+        file.read_to_end(&mut new)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        // A line still being written is left for next time
+        let complete = new.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        let new = &new[..complete];
+        let entries = match shell {
+            Some(shell) => shell.parse(new),
+            None => parse_stats_log(new),
+        };
+
+        let end = imported + complete as u64;
+        let tail_start = end.saturating_sub(TAIL_LENGTH);
+        let mut tail = vec![0; (end - tail_start) as usize];
+        file.seek(SeekFrom::Start(tail_start))?;
+        file.read_exact(&mut tail)?;
+
+        let tx = self.conn.transaction()?;
+        if imported == 0 {
+            tx.execute("DELETE FROM entries WHERE file = ?1", [&key])?;
+        }
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO entries (file, timestamp, command, directory, duration, exit_code,
+                    context, session_id, git_commit)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for entry in &entries {
+                insert.execute(params![
+                    key,
+                    entry.timestamp,
+                    entry.command,
+                    entry.directory,
+                    entry.duration,
+                    entry.exit_code,
+                    entry.context,
+                    entry.session_id,
+                    entry.commit,
+                ])?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO files (path, imported, tail) VALUES (?1, ?2, ?3)",
+            params![key, end, tail],
+        )?;
+        tx.commit()
+            .with_context(|| format!("Failed to save commands from {}", path.display()))?;
+        Ok(entries.len())
+    }
+
+    // Everything imported from `path`, in the order it was written
+    pub fn entries(&self, path: &Path) -> Result<Vec<HistoryEntry>> {
+        let mut statement = self.conn.prepare(
+            "SELECT timestamp, command, directory, duration, exit_code, context, session_id,
+                git_commit
+             FROM entries WHERE file = ?1 ORDER BY rowid",
+        )?;
+        let rows = statement.query_map([path.to_string_lossy()], stored_entry)?;
+        rows.collect::<rusqlite::Result<_>>()
+            .context("Failed to read history database")
+    }
+
+    // Remove the commands `removes` picks from every file, for `purge`.
+    // Where each file was imported up to is kept, so they don't come back
+    // with the next import unless the file itself still has them and is
+    // rewritten. Returns how many were removed and how many are left.
+    pub fn remove(&mut self, removes: impl Fn(&HistoryEntry) -> bool) -> Result<(usize, usize)> {
+        let tx = self.conn.transaction()?;
+        let mut removed = Vec::new();
+        let mut kept = 0;
+        {
+            let mut statement = tx.prepare(
+                "SELECT timestamp, command, directory, duration, exit_code, context, session_id,
+                    git_commit, rowid
+                 FROM entries",
+            )?;
+            let mut rows = statement.query([])?;
+            while let Some(row) = rows.next()? {
+                if removes(&stored_entry(row)?) {
+                    removed.push(row.get::<_, i64>(8)?);
+                } else {
+                    kept += 1;
+                }
+            }
+            let mut delete = tx.prepare("DELETE FROM entries WHERE rowid = ?1")?;
+            for rowid in &removed {
+                delete.execute([rowid])?;
+            }
+        }
+        tx.commit()
+            .context("Failed to remove commands from history database")?;
+        Ok((removed.len(), kept))
+    }
+}
+
+// A row of `entries`, from its timestamp to its git commit
+fn stored_entry(row: &rusqlite::Row) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        timestamp: row.get(0)?,
+        command: row.get(1)?,
+        directory: row.get(2)?,
+        duration: row.get(3)?,
+        exit_code: row.get(4)?,
+        context: row.get(5)?,
+        session_id: row.get(6)?,
+        commit: row.get(7)?,
+        host: None,
+        recorded_timestamp: None,
+    })
+}
+
+// Import every history file the views could read: the stats log and each
+// shell's history. Returns each file found with its count of new commands.
+pub fn ingest_all(dirs: &UserDirs) -> Result<Vec<(PathBuf, usize)>> {
+    let mut store = Store::open(&store_path(dirs))?;
+    let files = std::iter::once((dirs.stats_log(), None))
+        .chain(Shell::ALL.map(|shell| (shell.history_path(dirs), Some(shell))));
+    let mut imported = Vec::new();
+    for (path, shell) in files.filter(|(path, _)| path.exists()) {
+        let count = store.ingest(&path, shell)?;
+        imported.push((path, count));
+    }
+    Ok(imported)
+}
+
+// ingest.interval from the config, or five minutes
+pub fn ingest_interval() -> Duration {
+    Duration::from_secs(config().ingest_interval.unwrap_or(INGEST_INTERVAL))
+}
+
+// ingest.min_battery from the config, or 20%. 0 never pauses.
+pub fn min_battery() -> u8 {
+    config().min_battery.unwrap_or(MIN_BATTERY)
+}
+
+// Like `get_history_entries`, but once `ingest` has created the database the
+// default history comes from there, after importing anything new. Explicit
+// sources and files are always read directly.
+pub fn stored_history(
+    source: Option<Source>,
+    shell: Option<Shell>,
+    history_file: Option<&Path>,
+) -> Result<Vec<HistoryEntry>> {
+    if source.is_some() || history_file.is_some() {
+        return get_history_entries(source, shell, history_file);
+    }
+    let dirs = UserDirs::current()?;
+    let db = store_path(&dirs);
+    let file = default_history_file(&dirs, shell, Shell::detect());
+    let (Some((path, format)), true) = (file, db.exists()) else {
+        return get_history_entries(source, shell, history_file);
+    };
+    let mut store = Store::open(&db)?;
+    timing::time("index new lines", || store.ingest(&path, format))?;
+    let mut entries = timing::time("read", || store.entries(&path))?;
+    timing::time("resolve directories", || {
+        resolve_directories(&mut entries, &dirs.home)
+    });
+    if entries.is_empty() {
+        // Leave it to the file readers to fall back or explain
+        return get_history_entries(source, shell, history_file);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+
+    fn commands(entries: &[HistoryEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.command.as_str()).collect()
+    }
+
+    #[test]
+    fn imports_only_new_lines_and_redoes_rewritten_files() {
+        let dir = ScratchDir::new("store");
+        let path = dir.join("zsh_history");
+        let mut store = Store::with_connection(Connection::open_in_memory().unwrap()).unwrap();
+
+        fs::write(&path, ": 1700000000:0;ls\n: 1700000060:0;git status\n").unwrap();
+        assert_eq!(store.ingest(&path, Some(Shell::Zsh)).unwrap(), 2);
+
+        // The half-written last line waits for the next import
+        let mut more = fs::read(&path).unwrap();
+        more.extend_from_slice(b": 1700000120:0;make\n: 1700000180:0;ma");
+        fs::write(&path, &more).unwrap();
+        assert_eq!(store.ingest(&path, Some(Shell::Zsh)).unwrap(), 1);
+        more.extend_from_slice(b"ke test\n");
+        fs::write(&path, &more).unwrap();
+        assert_eq!(store.ingest(&path, Some(Shell::Zsh)).unwrap(), 1);
+        assert_eq!(
+            commands(&store.entries(&path).unwrap()),
+            ["ls", "git status", "make", "make test"]
+        );
+
+        // Rewritten in place, e.g. with duplicates removed
+        fs::write(&path, ": 1700000000:0;pwd\n: 1700000060:0;git status\n").unwrap();
+        assert_eq!(store.ingest(&path, Some(Shell::Zsh)).unwrap(), 2);
+        assert_eq!(
+            commands(&store.entries(&path).unwrap()),
+            ["pwd", "git status"]
+        );
+    }
+
+    #[test]
+    fn removes_only_the_picked_commands() {
+        let dir = ScratchDir::new("store");
+        let path = dir.join("bash_history");
+        let mut store = Store::with_connection(Connection::open_in_memory().unwrap()).unwrap();
+        fs::write(&path, "ls\nexport TOKEN=hunter2\nmake\n").unwrap();
+        assert_eq!(store.ingest(&path, Some(Shell::Bash)).unwrap(), 3);
+
+        let removed = store.remove(|e| e.command.contains("hunter2")).unwrap();
+        assert_eq!(removed, (1, 2));
+        assert_eq!(commands(&store.entries(&path).unwrap()), ["ls", "make"]);
+        // The file is unchanged, so nothing is imported again
+        assert_eq!(store.ingest(&path, Some(Shell::Bash)).unwrap(), 0);
+        assert_eq!(commands(&store.entries(&path).unwrap()), ["ls", "make"]);
+    }
+}
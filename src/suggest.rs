@@ -0,0 +1,249 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::history::HistoryEntry;
+
+// Shorter commands aren't worth wrapping in a function
+const MIN_LENGTH: usize = 25;
+
+// A command has to come back this often to be worth a function
+const MIN_RUNS: usize = 3;
+
+// Templates with more varying words than this are different commands that
+// happen to share a start, not one command with arguments
+const MAX_ARGS: usize = 2;
+
+const SUGGESTIONS_SHOWN: usize = 10;
+
+// A long command retyped with a few words changing, which could become a
+// shell function taking those words as arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSuggestion {
+    pub name: String,
+    // Fixed words, with None where an argument goes
+    pub template: Vec<Option<String>>,
+    pub runs: usize,
+    // The most recent matching command
+    pub example: String,
+    // Characters no longer typed across all runs if the function had existed
+    pub saved: usize,
+}
+
+impl FunctionSuggestion {
+    pub fn args(&self) -> usize {
+        self.template.iter().filter(|word| word.is_none()).count()
+    }
+
+    // A ready-to-paste zsh function, headed by why it was suggested
+    pub fn stub(&self) -> String {
+        let mut arg = 0;
+        let body: Vec<String> = self
+            .template
+            .iter()
+            .map(|word| match word {
+                Some(word) => word.clone(),
+                None => {
+                    arg += 1;
+                    format!("\"${}\"", arg)
+                }
+            })
+            .collect();
+        format!(
+            "# {} runs, about {} characters saved\n# e.g. {}\n{}() {{\n    {}\n}}\n",
+            self.runs,
+            self.saved,
+            self.example,
+            self.name,
+            body.join(" ")
+        )
+    }
+}
+
+// A matching command's timestamp, text and words
+type Run<'a> = (i64, &'a str, Vec<&'a str>);
+
+// Quoting and escaping would need a real shell parser to split into words,
+// so those commands are left out
+fn words(command: &str) -> Option<Vec<&str>> {
+    if command.contains(['\'', '"', '\\', '`', '\n']) {
+        return None;
+    }
+    Some(command.split_whitespace().collect())
+}
+
+// Initials of the fixed words that aren't options, e.g. `kgp` for
+// `kubectl get pods -n "$1"`
+fn function_name(template: &[Option<String>]) -> String {
+    let name: String = template
+        .iter()
+        .flatten()
+        .filter(|word| !word.starts_with('-'))
+        .filter_map(|word| word.chars().find(char::is_ascii_alphabetic))
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if name.is_empty() {
+        "run".to_string()
+    } else {
+        name
+    }
+}
+
+// Group long, repeated commands by program, subcommand and word count, and
+// turn the words that vary within a group into arguments. Groups are ranked
+// by characters saved, which grows with both how often and how long the
+// command is typed, and shrinks with every argument still to be typed.
+pub fn function_suggestions(entries: &[HistoryEntry]) -> Vec<FunctionSuggestion> {
+    let mut groups: HashMap<(&str, &str, usize), Vec<Run>> = HashMap::new();
+    for entry in entries.iter().filter(|e| e.command.len() >= MIN_LENGTH) {
+        let Some(words) = words(&entry.command) else {
+            continue;
+        };
+        if words.len() < 2 {
+            continue;
+        }
+        groups
+            .entry((words[0], words[1], words.len()))
+            .or_default()
+            .push((entry.timestamp, &entry.command, words));
+    }
+
+    let mut suggestions: Vec<FunctionSuggestion> = Vec::new();
+    for runs in groups.into_values().filter(|runs| runs.len() >= MIN_RUNS) {
+        let (_, first_command, first_words) = &runs[0];
+        let template: Vec<Option<String>> = first_words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                runs.iter()
+                    .all(|(_, _, words)| words[i] == *word)
+                    .then(|| word.to_string())
+            })
+            .collect();
+        let args = template.iter().filter(|word| word.is_none()).count();
+        if args > MAX_ARGS {
+            continue;
+        }
+
+        let name = function_name(&template);
+        let saved: usize = runs
+            .iter()
+            .map(|(_, command, words)| {
+                let args: Vec<&str> = words
+                    .iter()
+                    .zip(&template)
+                    .filter(|(_, fixed)| fixed.is_none())
+                    .map(|(word, _)| *word)
+                    .collect();
+                let call = name.len() + args.iter().map(|arg| arg.len() + 1).sum::<usize>();
+                command.len().saturating_sub(call)
+            })
+            .sum();
+        let example = runs
+            .iter()
+            .max_by_key(|(timestamp, _, _)| *timestamp)
+            .map_or(*first_command, |(_, command, _)| *command);
+        suggestions.push(FunctionSuggestion {
+            name,
+            template,
+            runs: runs.len(),
+            example: example.to_string(),
+            saved,
+        });
+    }
+
+    suggestions.sort_by(|a, b| {
+        b.saved
+            .cmp(&a.saved)
+            .then_with(|| a.example.cmp(&b.example))
+    });
+    suggestions.truncate(SUGGESTIONS_SHOWN);
+
+    // Names only need to be unique among the suggestions themselves
+    let mut taken = HashSet::new();
+    for suggestion in &mut suggestions {
+        let base = suggestion.name.clone();
+        let mut n = 1;
+        while !taken.insert(suggestion.name.clone()) {
+            n += 1;
+            suggestion.name = format!("{}{}", base, n);
+        }
+    }
+    suggestions
+}
+
+// Function stubs for the commands most worth turning into functions
+pub fn functions_report(entries: &[HistoryEntry]) -> String {
+    let suggestions = function_suggestions(entries);
+    let mut out = String::new();
+    if suggestions.is_empty() {
+        out.push_str("# No long commands are retyped often enough to suggest functions.\n");
+        return out;
+    }
+    let _ = writeln!(
+        out,
+        "# Functions for commands you retype often. Check the names don't clash\n\
+         # with anything on your PATH, then paste into ~/.zshrc.\n"
+    );
+    for (i, suggestion) in suggestions.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(&suggestion.stub());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(timestamp: i64, command: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp,
+            command: command.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn varying_words_become_arguments() {
+        let entries = [
+            run(1, "kubectl logs -f deploy/api -n staging"),
+            run(2, "kubectl logs -f deploy/web -n staging"),
+            run(3, "kubectl logs -f deploy/api -n production"),
+            // Too varied to be one command
+            run(4, "git log --oneline --graph -20 main"),
+            run(5, "git log --stat --reverse -5 dev"),
+            run(6, "git log --patch --all -1 topic"),
+            // Too short to bother with
+            run(7, "ls -la"),
+            run(8, "ls -la"),
+            run(9, "ls -la"),
+            // Quoted, so not split into words
+            run(10, "git commit -m 'a long commit message'"),
+        ];
+        let suggestions = function_suggestions(&entries);
+        assert_eq!(suggestions.len(), 1);
+        let logs = &suggestions[0];
+        assert_eq!((logs.name.as_str(), logs.runs, logs.args()), ("kl", 3, 2));
+        assert_eq!(logs.example, "kubectl logs -f deploy/api -n production");
+        assert!(logs
+            .stub()
+            .contains("kl() {\n    kubectl logs -f \"$1\" -n \"$2\"\n}"));
+    }
+
+    #[test]
+    fn names_are_made_unique() {
+        let mut entries = Vec::new();
+        for i in 0..3 {
+            entries.push(run(i, "git push origin main --force-with-lease"));
+            entries.push(run(i, "git pull origin main --rebase --autostash"));
+        }
+        let mut names: Vec<String> = function_suggestions(&entries)
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["gpom", "gpom2"]);
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::config::config;
+use crate::history::{ran_in, HistoryEntry};
+use crate::parser::is_context_label;
+
+// `<rule> => <tag>`, as the config's [tags] lists write them
+pub fn split_rule(rule: &str) -> Result<(&str, &str)> {
+    let (matcher, tag) = rule
+        .rsplit_once("=>")
+        .ok_or_else(|| anyhow!("Tag rule `{}` needs `=> <tag>` at the end", rule))?;
+    let (matcher, tag) = (matcher.trim(), tag.trim());
+    if matcher.is_empty() {
+        return Err(anyhow!("Tag rule `{}` has nothing to match", rule));
+    }
+    if !is_context_label(tag) {
+        return Err(anyhow!(
+            "Tag `{}` may only have letters, digits, -, _ and .",
+            tag
+        ));
+    }
+    Ok((matcher, tag))
+}
+
+// `~/work` as a path under the home directory
+fn expand_home(dir: &str) -> PathBuf {
+    match dir.strip_prefix("~/") {
+        Some(rest) => home::home_dir().unwrap_or_default().join(rest),
+        None => PathBuf::from(dir),
+    }
+}
+
+// Tags for commands the hook recorded without one, from the config's rules.
+// The first rule that matches wins, commands before directories.
+pub struct Tagger {
+    commands: Vec<(Regex, String)>,
+    directories: Vec<(PathBuf, String)>,
+}
+
+impl Tagger {
+    pub fn new(commands: &[String], directories: &[String]) -> Result<Tagger> {
+        let commands = commands
+            .iter()
+            .map(|rule| {
+                let (pattern, tag) = split_rule(rule)?;
+                let regex = Regex::new(pattern)
+                    .with_context(|| format!("Invalid tag pattern {}", pattern))?;
+                Ok((regex, tag.to_string()))
+            })
+            .collect::<Result<_>>()?;
+        let directories = directories
+            .iter()
+            .map(|rule| {
+                let (dir, tag) = split_rule(rule)?;
+                Ok((expand_home(dir), tag.to_string()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Tagger {
+            commands,
+            directories,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty() && self.directories.is_empty()
+    }
+
+    pub fn tag_for(&self, entry: &HistoryEntry) -> Option<&str> {
+        let by_command = self
+            .commands
+            .iter()
+            .find(|(regex, _)| regex.is_match(&entry.command))
+            .map(|(_, tag)| tag);
+        by_command
+            .or_else(|| {
+                self.directories
+                    .iter()
+                    .find(|(dir, _)| ran_in(entry, dir))
+                    .map(|(_, tag)| tag)
+            })
+            .map(String::as_str)
+    }
+
+    // Fill in the tag of every untagged entry a rule matches. A tag the
+    // hook recorded always stays.
+    pub fn tag(&self, entries: &mut [HistoryEntry]) {
+        for entry in entries.iter_mut().filter(|e| e.context.is_none()) {
+            entry.context = self.tag_for(entry).map(str::to_string);
+        }
+    }
+}
+
+static TAGGER: OnceLock<Tagger> = OnceLock::new();
+
+// Built from the loaded config, whose rules were checked when it was read
+pub fn tagger() -> &'static Tagger {
+    TAGGER.get_or_init(|| {
+        let config = config();
+        Tagger::new(&config.tag_commands, &config.tag_directories)
+            .unwrap_or_else(|_| Tagger::new(&[], &[]).expect("no rules is valid"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_tag_untagged_entries() {
+        let tagger = Tagger::new(
+            &["^(kubectl|helm) => ops".to_string()],
+            &["/home/me/work => work".to_string()],
+        )
+        .unwrap();
+        let run = |command: &str, directory: &str, context: Option<&str>| HistoryEntry {
+            command: command.to_string(),
+            directory: Some(directory.to_string()),
+            context: context.map(str::to_string),
+            ..Default::default()
+        };
+        let mut entries = vec![
+            run("kubectl get pods", "/home/me/work/api", None),
+            run("make", "/home/me/work/api", None),
+            run("make", "/home/me/workshop", None),
+            run("helm list", "/tmp", Some("home")),
+        ];
+        tagger.tag(&mut entries);
+        let tags: Vec<Option<&str>> = entries.iter().map(|e| e.context.as_deref()).collect();
+        assert_eq!(tags, [Some("ops"), Some("work"), None, Some("home")]);
+
+        assert!(split_rule("^git").is_err());
+        assert!(split_rule("^git => two words").is_err());
+        assert!(Tagger::new(&["( => x".to_string()], &[]).is_err());
+    }
+}
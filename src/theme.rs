@@ -0,0 +1,167 @@
+use anyhow::{anyhow, Result};
+use crossterm::style::{self, Color};
+use std::sync::OnceLock;
+
+use crate::config::{config, Theme};
+
+// The colors of the history viewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub header: Color,
+    // Line numbers, times, separators and the controls
+    pub muted: Color,
+    // Commands outside any category
+    pub command: Color,
+    pub pinned: Color,
+    // Characters a search matched
+    pub matched: Color,
+    pub selected: Color,
+    pub selected_text: Color,
+}
+
+// The keys the config's [theme] table takes
+pub const KEYS: &[&str] = &[
+    "header",
+    "muted",
+    "command",
+    "pinned",
+    "match",
+    "selected",
+    "selected_text",
+];
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+impl Palette {
+    pub fn preset(theme: Theme) -> Palette {
+        match theme {
+            Theme::Default => Palette {
+                header: Color::Cyan,
+                muted: Color::DarkGrey,
+                command: Color::White,
+                pinned: Color::Yellow,
+                matched: Color::Yellow,
+                selected: Color::DarkBlue,
+                selected_text: Color::White,
+            },
+            Theme::Solarized => Palette {
+                header: rgb(0x26, 0x8b, 0xd2),
+                muted: rgb(0x58, 0x6e, 0x75),
+                command: rgb(0x83, 0x94, 0x96),
+                pinned: rgb(0xb5, 0x89, 0x00),
+                matched: rgb(0xcb, 0x4b, 0x16),
+                selected: rgb(0x07, 0x36, 0x42),
+                selected_text: rgb(0x93, 0xa1, 0xa1),
+            },
+            Theme::Mono => Palette {
+                header: Color::Reset,
+                muted: Color::Reset,
+                command: Color::Reset,
+                pinned: Color::Reset,
+                matched: Color::Reset,
+                selected: Color::Reset,
+                selected_text: Color::Reset,
+            },
+        }
+    }
+
+    // Change one color, by its key in [theme]
+    pub fn set(&mut self, key: &str, color: Color) -> Result<()> {
+        let slot = match key {
+            "header" => &mut self.header,
+            "muted" => &mut self.muted,
+            "command" => &mut self.command,
+            "pinned" => &mut self.pinned,
+            "match" => &mut self.matched,
+            "selected" => &mut self.selected,
+            "selected_text" => &mut self.selected_text,
+            _ => {
+                return Err(anyhow!(
+                    "Unknown color `{}`; one of {}",
+                    key,
+                    KEYS.join(", ")
+                ))
+            }
+        };
+        *slot = color;
+        Ok(())
+    }
+
+    // Without a background color the selected row is drawn reversed, so it
+    // still stands out
+    pub fn selection_reversed(&self) -> bool {
+        self.selected == Color::Reset
+    }
+}
+
+// A color name such as `dark_blue`, or `#rrggbb`
+pub fn parse_color(value: &str) -> Option<Color> {
+    match value.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 => {
+            let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+            Some(rgb(channel(0)?, channel(2)?, channel(4)?))
+        }
+        Some(_) => None,
+        None => Color::try_from(value).ok(),
+    }
+}
+
+// --theme, then monochrome when NO_COLOR is set, then the config's theme
+fn chosen(flag: Option<Theme>, no_color: bool, configured: Theme) -> Theme {
+    flag.unwrap_or(if no_color { Theme::Mono } else { configured })
+}
+
+static PALETTE: OnceLock<Palette> = OnceLock::new();
+
+// Pick the theme once at startup, with the config's [theme] colors over
+// its preset. Monochrome turns colors off everywhere, not just in the
+// history viewer.
+pub fn init(flag: Option<Theme>) {
+    let config = config();
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let theme = chosen(flag, no_color, config.theme);
+    let mut palette = Palette::preset(theme);
+    if theme != Theme::Mono {
+        for (key, color) in &config.colors {
+            // Checked when the config was read
+            let _ = palette.set(key, *color);
+        }
+    }
+    style::force_color_output(theme != Theme::Mono);
+    let _ = PALETTE.set(palette);
+}
+
+pub fn palette() -> &'static Palette {
+    PALETTE.get_or_init(|| Palette::preset(Theme::Default))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_colors_and_no_color() {
+        let mut palette = Palette::preset(Theme::Default);
+        assert_eq!(palette.selected, Color::DarkBlue);
+        palette
+            .set("match", parse_color("#cb4b16").unwrap())
+            .unwrap();
+        assert_eq!(palette.matched, rgb(0xcb, 0x4b, 0x16));
+        assert!(palette.set("background", Color::Red).is_err());
+        assert!(Palette::preset(Theme::Mono).selection_reversed());
+
+        assert_eq!(parse_color("dark_grey"), Some(Color::DarkGrey));
+        assert_eq!(parse_color("#12345"), None);
+        assert_eq!(parse_color("#12345g"), None);
+        assert_eq!(parse_color("mauve"), None);
+
+        assert_eq!(chosen(None, true, Theme::Solarized), Theme::Mono);
+        assert_eq!(chosen(None, false, Theme::Solarized), Theme::Solarized);
+        assert_eq!(
+            chosen(Some(Theme::Default), true, Theme::Mono),
+            Theme::Default
+        );
+    }
+}
@@ -0,0 +1,378 @@
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, TimeZone};
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::config;
+use crate::presentation;
+use crate::tr;
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+// Whether timestamps are shown as dates or as time elapsed since then
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeDisplay {
+    #[default]
+    Absolute,
+    Relative,
+}
+
+impl TimeDisplay {
+    pub fn toggled(self) -> Self {
+        match self {
+            TimeDisplay::Absolute => TimeDisplay::Relative,
+            TimeDisplay::Relative => TimeDisplay::Absolute,
+        }
+    }
+
+    // Always relative while presenting, so no exact time is shown
+    fn shown(self) -> Self {
+        if presentation::enabled() {
+            TimeDisplay::Relative
+        } else {
+            self
+        }
+    }
+
+    pub fn format(self, timestamp: i64, now: i64) -> String {
+        match self.shown() {
+            TimeDisplay::Absolute => format_timestamp(timestamp),
+            TimeDisplay::Relative => format_relative(timestamp, now),
+        }
+    }
+
+    // Short enough for a list column
+    pub fn format_compact(self, timestamp: i64, now: i64) -> String {
+        match self.shown() {
+            TimeDisplay::Absolute => {
+                format_with(timestamp, "timestamp-format-compact", "%m/%d %H:%M")
+            }
+            TimeDisplay::Relative => format_relative(timestamp, now),
+        }
+    }
+}
+
+fn local_time(timestamp: i64) -> Option<DateTime<Local>> {
+    match Local.timestamp_opt(timestamp, 0) {
+        chrono::LocalResult::Single(dt) => Some(dt),
+        _ => None,
+    }
+}
+
+// Format with the strftime pattern from the translation. A broken pattern
+// falls back to `default` rather than panicking.
+fn format_with(timestamp: i64, pattern_id: &str, default: &str) -> String {
+    if timestamp == 0 {
+        return tr!("timestamp-unavailable");
+    }
+    let Some(dt) = local_time(timestamp) else {
+        return tr!("timestamp-invalid");
+    };
+    // The config's date format, if it sets one, over the locale's
+    let pattern = match &config::config().date_format {
+        Some(pattern) if pattern_id == "timestamp-format" => pattern.clone(),
+        _ => tr!(pattern_id),
+    };
+    let mut formatted = String::new();
+    if write!(formatted, "{}", dt.format(&pattern)).is_err() {
+        formatted = dt.format(default).to_string();
+    }
+    formatted
+}
+
+pub fn format_timestamp(timestamp: i64) -> String {
+    format_with(timestamp, "timestamp-format", "%b %d %Y at %I:%M %P")
+}
+
+// `2024-03-01`, or how long ago that day was while presenting
+pub fn format_day(day: NaiveDate) -> String {
+    if presentation::enabled() {
+        format_relative(local_midnight(day), Local::now().timestamp())
+    } else {
+        day.format("%Y-%m-%d").to_string()
+    }
+}
+
+// "just now", "5m ago", "2h ago", "3d ago", ... Timestamps slightly in the
+// future (clock skew between machines) count as just now.
+pub fn format_relative(timestamp: i64, now: i64) -> String {
+    if timestamp == 0 {
+        return tr!("timestamp-unavailable");
+    }
+    let elapsed = now - timestamp;
+    let (id, count) = match elapsed {
+        _ if elapsed < MINUTE => return tr!("relative-just-now"),
+        _ if elapsed < HOUR => ("relative-minutes", elapsed / MINUTE),
+        _ if elapsed < DAY => ("relative-hours", elapsed / HOUR),
+        _ if elapsed < 7 * DAY => ("relative-days", elapsed / DAY),
+        _ if elapsed < 30 * DAY => ("relative-weeks", elapsed / (7 * DAY)),
+        _ if elapsed < 365 * DAY => ("relative-months", elapsed / (30 * DAY)),
+        _ => ("relative-years", elapsed / (365 * DAY)),
+    };
+    tr!(id, count = count)
+}
+
+// A stretch of time counted back from now, like `7d` given to --since
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeSpan {
+    pub seconds: i64,
+}
+
+impl TimeSpan {
+    // When the span started, seen from `now`
+    pub fn start(self, now: i64) -> i64 {
+        now - self.seconds
+    }
+}
+
+// A whole number followed by m, h, d or w
+impl FromStr for TimeSpan {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected a span like 30m, 12h, 7d or 2w, got '{}'", s);
+        let unit = match s.chars().last() {
+            Some('m') => MINUTE,
+            Some('h') => HOUR,
+            Some('d') => DAY,
+            Some('w') => 7 * DAY,
+            _ => return Err(invalid()),
+        };
+        let count: i64 = s[..s.len() - 1].parse().map_err(|_| invalid())?;
+        if count <= 0 {
+            return Err(invalid());
+        }
+        Ok(TimeSpan {
+            seconds: count.checked_mul(unit).ok_or_else(invalid)?,
+        })
+    }
+}
+
+// Calendar periods named relative to today. Weeks start on Monday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Today,
+    Yesterday,
+    ThisWeek,
+    LastWeek,
+    ThisMonth,
+    LastMonth,
+}
+
+impl Period {
+    const NAMES: [(&'static str, Period); 6] = [
+        ("today", Period::Today),
+        ("yesterday", Period::Yesterday),
+        ("this-week", Period::ThisWeek),
+        ("last-week", Period::LastWeek),
+        ("this-month", Period::ThisMonth),
+        ("last-month", Period::LastMonth),
+    ];
+
+    // The period's first day and the day after its last
+    pub(crate) fn days(self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let monday = today - Days::new(u64::from(today.weekday().num_days_from_monday()));
+        let first_of_month = today.with_day(1).unwrap_or(today);
+        match self {
+            Period::Today => (today, today + Days::new(1)),
+            Period::Yesterday => (today - Days::new(1), today),
+            Period::ThisWeek => (monday, monday + Days::new(7)),
+            Period::LastWeek => (monday - Days::new(7), monday),
+            Period::ThisMonth => (first_of_month, first_of_month + Months::new(1)),
+            Period::LastMonth => (first_of_month - Months::new(1), first_of_month),
+        }
+    }
+}
+
+// One end of a time range: a local date, a span before now, or a named
+// period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeBound {
+    Date(NaiveDate),
+    Ago(TimeSpan),
+    Period(Period),
+}
+
+impl TimeBound {
+    // The first and last day covered, for the bounds that are whole days
+    fn days(self, now: i64) -> Option<(NaiveDate, NaiveDate)> {
+        match self {
+            TimeBound::Date(date) => Some((date, date.succ_opt()?)),
+            TimeBound::Ago(_) => None,
+            TimeBound::Period(period) => {
+                let today = Local.timestamp_opt(now, 0).single()?.date_naive();
+                Some(period.days(today))
+            }
+        }
+    }
+
+    // The first second covered, i.e. a date's midnight
+    pub fn start(self, now: i64) -> i64 {
+        match (self, self.days(now)) {
+            (TimeBound::Ago(span), _) => span.start(now),
+            (_, Some((first, _))) => local_midnight(first),
+            (_, None) => i64::MIN,
+        }
+    }
+
+    // The last second covered, so an end date includes the whole day
+    pub fn end(self, now: i64) -> i64 {
+        match (self, self.days(now)) {
+            (TimeBound::Ago(span), _) => span.start(now),
+            (_, Some((_, after))) => local_midnight(after) - 1,
+            (_, None) => i64::MAX,
+        }
+    }
+
+    // The stretch of time the bound names on its own: a date's day, a
+    // period, or for a span the time from its start to now
+    pub fn window(self, now: i64) -> (i64, i64) {
+        match self {
+            TimeBound::Ago(span) => (span.start(now), now),
+            _ => (self.start(now), self.end(now)),
+        }
+    }
+}
+
+// The `--since` and `--until` window that every command narrows the
+// history to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeRange {
+    pub since: Option<TimeBound>,
+    pub until: Option<TimeBound>,
+}
+
+impl TimeRange {
+    pub fn is_unbounded(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+
+    // Whether a command run at `timestamp` is inside the window. Undated
+    // commands can't be placed, so they're only inside an unbounded one.
+    pub fn contains(&self, timestamp: i64, now: i64) -> bool {
+        if self.is_unbounded() {
+            return true;
+        }
+        timestamp > 0
+            && self.since.is_none_or(|since| timestamp >= since.start(now))
+            && self.until.is_none_or(|until| timestamp <= until.end(now))
+    }
+}
+
+pub(crate) fn local_midnight(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map_or(0, |dt| dt.timestamp())
+}
+
+// `2024-03-01`, a span like `7d`, or a period like `last-month`
+impl FromStr for TimeBound {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(TimeBound::Date(date));
+        }
+        if let Some((_, period)) = Period::NAMES.iter().find(|(name, _)| *name == s) {
+            return Ok(TimeBound::Period(*period));
+        }
+        s.parse().map(TimeBound::Ago).map_err(|_| {
+            format!(
+                "expected a date like 2024-03-01, a span like 7d or a period like last-month, got '{}'",
+                s
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_buckets() {
+        let now = 1_700_000_000;
+        assert_eq!(format_relative(now - 30, now), "just now");
+        assert_eq!(format_relative(now + 120, now), "just now");
+        assert_eq!(format_relative(now - 5 * MINUTE, now), "5m ago");
+        assert_eq!(format_relative(now - 2 * HOUR - 59, now), "2h ago");
+        assert_eq!(format_relative(now - 3 * DAY, now), "3d ago");
+        assert_eq!(format_relative(now - 15 * DAY, now), "2w ago");
+        assert_eq!(format_relative(now - 90 * DAY, now), "3mo ago");
+        assert_eq!(format_relative(now - 800 * DAY, now), "2y ago");
+        assert_eq!(format_relative(0, now), "Timestamp not available");
+    }
+
+    #[test]
+    fn toggling_switches_formats() {
+        let display = TimeDisplay::default();
+        assert_eq!(display.toggled(), TimeDisplay::Relative);
+        assert_eq!(display.toggled().toggled(), display);
+        assert_eq!(
+            TimeDisplay::Relative.format(1_700_000_000 - HOUR, 1_700_000_000),
+            "1h ago"
+        );
+    }
+
+    #[test]
+    fn parses_spans() {
+        assert_eq!("7d".parse(), Ok(TimeSpan { seconds: 7 * DAY }));
+        assert_eq!(
+            "30m".parse(),
+            Ok(TimeSpan {
+                seconds: 30 * MINUTE
+            })
+        );
+        assert_eq!("2w".parse::<TimeSpan>().unwrap().start(100 * DAY), 86 * DAY);
+        assert!("7".parse::<TimeSpan>().is_err());
+        assert!("0d".parse::<TimeSpan>().is_err());
+        assert!("-1h".parse::<TimeSpan>().is_err());
+        assert!("d".parse::<TimeSpan>().is_err());
+    }
+
+    #[test]
+    fn bounds_cover_whole_days() {
+        let bound: TimeBound = "2024-03-01".parse().unwrap();
+        let midnight = Local
+            .with_ymd_and_hms(2024, 3, 1, 0, 0, 0)
+            .unwrap()
+            .timestamp();
+        assert_eq!(bound.start(0), midnight);
+        assert_eq!(bound.end(0), midnight + DAY - 1);
+        assert_eq!("3d".parse::<TimeBound>().unwrap().end(10 * DAY), 7 * DAY);
+        assert!("2024-13-01".parse::<TimeBound>().is_err());
+    }
+
+    #[test]
+    fn named_periods_cover_whole_weeks_and_months() {
+        // Wednesday 2024-03-13, midday
+        let now = Local
+            .with_ymd_and_hms(2024, 3, 13, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        let midnight = |y, m, d| {
+            Local
+                .with_ymd_and_hms(y, m, d, 0, 0, 0)
+                .unwrap()
+                .timestamp()
+        };
+        let last_month: TimeBound = "last-month".parse().unwrap();
+        assert_eq!(last_month.start(now), midnight(2024, 2, 1));
+        assert_eq!(last_month.end(now), midnight(2024, 3, 1) - 1);
+        let this_week: TimeBound = "this-week".parse().unwrap();
+        assert_eq!(this_week.start(now), midnight(2024, 3, 11));
+        assert!("next-month".parse::<TimeBound>().is_err());
+
+        let range = TimeRange {
+            since: Some(TimeBound::Period(Period::LastWeek)),
+            until: Some("2d".parse().unwrap()),
+        };
+        assert!(range.contains(midnight(2024, 3, 4), now));
+        assert!(range.contains(midnight(2024, 3, 11) - 1, now));
+        assert!(!range.contains(midnight(2024, 3, 3), now));
+        assert!(!range.contains(now - DAY, now));
+        assert!(!range.contains(0, now));
+        assert!(TimeRange::default().contains(0, now));
+    }
+}
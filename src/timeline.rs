@@ -0,0 +1,327 @@
+use chrono::NaiveDate;
+
+use crate::days::day_counts;
+use crate::history::HistoryEntry;
+use crate::time_format::format_day;
+use crate::tr;
+
+// Longest gaps called out under the chart
+const GAPS_SHOWN: usize = 3;
+
+const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// Runs of one command over its whole lifetime, bucketed to fit a chart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+    // Days covered by each bucket, the first starting on `first`
+    pub bucket_days: i64,
+    pub counts: Vec<usize>,
+    // Stretches without a run as (last run before, next run after),
+    // longest first
+    pub gaps: Vec<(NaiveDate, NaiveDate)>,
+}
+
+// `git` matches every git command, `git push` every push, and so on
+pub fn matches_command(command: &str, query: &str) -> bool {
+    let query = query.trim();
+    command == query
+        || command
+            .strip_prefix(query)
+            .is_some_and(|rest| rest.starts_with(' '))
+}
+
+// Timeline of the commands matching `query`, with at most `columns`
+// buckets. None when no matching run has a timestamp.
+pub fn build_timeline(entries: &[HistoryEntry], query: &str, columns: usize) -> Option<Timeline> {
+    let days = day_counts(
+        entries
+            .iter()
+            .filter(|e| matches_command(&e.command, query)),
+    );
+    let (&first, _) = days.first_key_value()?;
+    let (&last, _) = days.last_key_value()?;
+
+    let span = (last - first).num_days() + 1;
+    let columns = columns.max(1) as i64;
+    let bucket_days = (span + columns - 1) / columns;
+    let mut counts = vec![0; ((span + bucket_days - 1) / bucket_days) as usize];
+    for (day, count) in &days {
+        counts[((*day - first).num_days() / bucket_days) as usize] += count;
+    }
+
+    let used: Vec<NaiveDate> = days.keys().copied().collect();
+    let mut gaps: Vec<(NaiveDate, NaiveDate)> = used
+        .windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .filter(|(before, after)| (*after - *before).num_days() > 1)
+        .collect();
+    gaps.sort_by_key(|(before, after)| (std::cmp::Reverse(*after - *before), *before));
+    gaps.truncate(GAPS_SHOWN);
+
+    Some(Timeline {
+        first,
+        last,
+        bucket_days,
+        counts,
+        gaps,
+    })
+}
+
+impl Timeline {
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    pub fn bucket_start(&self, bucket: usize) -> NaiveDate {
+        self.first + chrono::Duration::days(bucket as i64 * self.bucket_days)
+    }
+
+    fn bucket_of(&self, day: NaiveDate) -> usize {
+        ((day - self.first).num_days() / self.bucket_days) as usize
+    }
+
+    // The busiest bucket, earliest on ties
+    pub fn peak(&self) -> (usize, usize) {
+        self.counts
+            .iter()
+            .copied()
+            .enumerate()
+            .fold(
+                (0, 0),
+                |best, (i, count)| {
+                    if count > best.1 {
+                        (i, count)
+                    } else {
+                        best
+                    }
+                },
+            )
+    }
+
+    // Average runs per bucket after the peak, and that as a share of the
+    // peak. None when the peak is the latest bucket.
+    pub fn since_peak(&self) -> Option<(f64, usize)> {
+        let (peak_bucket, peak_count) = self.peak();
+        let after = &self.counts[peak_bucket + 1..];
+        if after.is_empty() || peak_count == 0 {
+            return None;
+        }
+        let average = after.iter().sum::<usize>() as f64 / after.len() as f64;
+        Some((
+            average,
+            (average * 100.0 / peak_count as f64).round() as usize,
+        ))
+    }
+}
+
+fn bucket_label(days: i64) -> String {
+    match days {
+        1 => tr!("timeline-bucket-day"),
+        7 => tr!("timeline-bucket-week"),
+        _ => tr!("timeline-bucket-days", days = days),
+    }
+}
+
+// A label in the summary under the chart, padded so the values line up
+fn summary_line(label: &str, value: String) -> String {
+    format!("{:<12} {}", label, value)
+}
+
+// Bars `height` rows tall, one column per bucket, drawn with eighth blocks.
+// Buckets with any runs always show at least the lowest block.
+fn chart_rows(counts: &[usize], height: usize) -> Vec<String> {
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    let eighths: Vec<usize> = counts
+        .iter()
+        .map(|&count| {
+            let scaled = count * height * 8 / max;
+            if count > 0 {
+                scaled.max(1)
+            } else {
+                0
+            }
+        })
+        .collect();
+    (0..height)
+        .map(|row| {
+            let floor = (height - 1 - row) * 8;
+            eighths
+                .iter()
+                .map(|&e| BLOCKS[e.saturating_sub(floor).min(8)])
+                .collect()
+        })
+        .collect()
+}
+
+// The chart with its axis, gap markers and annotations, as plain lines
+pub fn render_timeline(query: &str, timeline: &Timeline, height: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let per = bucket_label(timeline.bucket_days);
+    lines.push(tr!(
+        "timeline-summary",
+        query = query,
+        count = timeline.total(),
+        first = format_day(timeline.first),
+        last = format_day(timeline.last),
+        bucket = per.as_str()
+    ));
+    lines.push(String::new());
+
+    let (peak_bucket, peak_count) = timeline.peak();
+    let label_width = peak_count.to_string().len();
+    for (i, row) in chart_rows(&timeline.counts, height).into_iter().enumerate() {
+        let label = if i == 0 {
+            peak_count.to_string()
+        } else {
+            String::new()
+        };
+        lines.push(format!("{:>w$} │{}", label, row, w = label_width));
+    }
+    let columns = timeline.counts.len();
+    lines.push(format!(
+        "{:>w$} └{}",
+        "",
+        "─".repeat(columns),
+        w = label_width
+    ));
+
+    // Number the longest gaps under the middle of the stretch they cover
+    let mut markers = vec![' '; columns];
+    for (n, (before, after)) in timeline.gaps.iter().enumerate() {
+        let middle = (timeline.bucket_of(*before) + timeline.bucket_of(*after)) / 2;
+        markers[middle] = char::from_digit(n as u32 + 1, 10).unwrap_or('*');
+    }
+    let markers: String = markers.into_iter().collect();
+    lines.push(format!(
+        "{:>w$}  {}",
+        "",
+        markers.trim_end(),
+        w = label_width
+    ));
+
+    let first = format_day(timeline.first);
+    let last = format_day(timeline.last);
+    let padding = (columns + 2)
+        .saturating_sub(first.len() + last.len())
+        .max(1);
+    lines.push(format!(
+        "{:>w$}  {}{}{}",
+        "",
+        first,
+        " ".repeat(padding),
+        last,
+        w = label_width
+    ));
+    lines.push(String::new());
+
+    lines.push(summary_line(&tr!("timeline-first-used"), first));
+    let peak_start = format_day(timeline.bucket_start(peak_bucket));
+    let peak = if timeline.bucket_days == 1 {
+        peak_start
+    } else {
+        tr!(
+            "timeline-bucket-of",
+            bucket = per.as_str(),
+            start = peak_start
+        )
+    };
+    lines.push(summary_line(
+        &tr!("timeline-peak"),
+        tr!("timeline-peak-value", peak = peak, count = peak_count),
+    ));
+    let since_peak = match timeline.since_peak() {
+        Some((average, share)) => tr!(
+            "timeline-since-peak-value",
+            average = format!("{:.1}", average),
+            bucket = per.as_str(),
+            share = share
+        ),
+        None => tr!("timeline-still-at-peak"),
+    };
+    lines.push(summary_line(&tr!("timeline-since-peak"), since_peak));
+    lines.push(summary_line(&tr!("timeline-last-used"), last));
+
+    if !timeline.gaps.is_empty() {
+        lines.push(tr!("timeline-gaps"));
+        for (n, (before, after)) in timeline.gaps.iter().enumerate() {
+            lines.push(format!(
+                "  {}",
+                tr!(
+                    "timeline-gap",
+                    number = n + 1,
+                    days = (*after - *before).num_days() - 1,
+                    from = format_day(*before),
+                    to = format_day(*after)
+                )
+            ));
+        }
+    }
+    lines
+}
+
+// The rendered timeline for `query` in about `width` columns
+pub fn timeline_lines(
+    entries: &[HistoryEntry],
+    query: &str,
+    width: usize,
+    height: usize,
+) -> Vec<String> {
+    // Leave room for the count label and axis
+    match build_timeline(entries, query, width.saturating_sub(8)) {
+        Some(timeline) => render_timeline(query, &timeline, height),
+        None => vec![tr!("timeline-no-runs", query = query)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn run(day: u32, command: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: Local
+                .with_ymd_and_hms(2024, 3, day, 12, 0, 0)
+                .unwrap()
+                .timestamp(),
+            command: command.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matches_whole_words() {
+        assert!(matches_command("git push origin", "git"));
+        assert!(matches_command("git push", "git push"));
+        assert!(!matches_command("gitk", "git"));
+    }
+
+    #[test]
+    fn buckets_peak_and_gaps() {
+        let entries = [
+            run(1, "make"),
+            run(2, "make test"),
+            run(2, "make"),
+            run(3, "ls"),
+            run(10, "make"),
+            run(30, "make"),
+        ];
+        let timeline = build_timeline(&entries, "make", 10).unwrap();
+
+        assert_eq!(timeline.bucket_days, 3);
+        assert_eq!(timeline.counts, [3, 0, 0, 1, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(timeline.peak(), (0, 3));
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        assert_eq!(timeline.gaps, [(day(10), day(30)), (day(2), day(10))]);
+        assert_eq!(timeline.since_peak(), Some((2.0 / 9.0, 7)));
+
+        assert_eq!(build_timeline(&entries, "cargo", 10), None);
+    }
+
+    #[test]
+    fn chart_scales_to_the_busiest_bucket() {
+        assert_eq!(chart_rows(&[4, 0, 1, 2], 2), ["█   ", "█ ▄█"]);
+    }
+}
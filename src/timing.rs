@@ -0,0 +1,143 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Turned on with --timing
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// Where startup time went so far. Only startup is of interest, so nothing
+// more is recorded once the first frame is on screen.
+struct Timings {
+    started: Instant,
+    // Phases in the order they first ran, with their total time
+    phases: Vec<(&'static str, Duration)>,
+    // When the last phase finished, which the first render counts from
+    last: Instant,
+    rendered: bool,
+}
+
+static TIMINGS: Mutex<Option<Timings>> = Mutex::new(None);
+
+pub fn enable() {
+    let now = Instant::now();
+    *TIMINGS.lock().unwrap() = Some(Timings {
+        started: now,
+        phases: Vec::new(),
+        last: now,
+        rendered: false,
+    });
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+fn add(phase: &'static str, took: Duration) {
+    let mut timings = TIMINGS.lock().unwrap();
+    let Some(timings) = timings.as_mut().filter(|timings| !timings.rendered) else {
+        return;
+    };
+    match timings.phases.iter_mut().find(|(name, _)| *name == phase) {
+        Some((_, total)) => *total += took,
+        None => timings.phases.push((phase, took)),
+    }
+    timings.last = Instant::now();
+}
+
+// Run `f`, adding how long it took to `phase` when timing startup
+pub fn time<T>(phase: &'static str, f: impl FnOnce() -> T) -> T {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    add(phase, start.elapsed());
+    result
+}
+
+// Called by the screens once a frame is drawn. The first one ends startup.
+pub fn frame_drawn() {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let since_last = match TIMINGS.lock().unwrap().as_ref() {
+        Some(timings) if !timings.rendered => timings.last.elapsed(),
+        _ => return,
+    };
+    add("first render", since_last);
+    if let Some(timings) = TIMINGS.lock().unwrap().as_mut() {
+        timings.rendered = true;
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+// The phases and their times, for stderr once the command is done. None
+// without --timing.
+pub fn report() -> Option<String> {
+    let timings = TIMINGS.lock().unwrap();
+    let timings = timings.as_ref()?;
+    let mut out = String::from("Startup timing\n");
+    let width = timings
+        .phases
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    for (name, took) in &timings.phases {
+        let _ = writeln!(
+            out,
+            "  {:<width$}  {:>10}",
+            name,
+            millis(*took),
+            width = width
+        );
+    }
+    // Up to the first frame for the TUI, or to now for everything else
+    let total = if timings.rendered {
+        timings.last - timings.started
+    } else {
+        timings.started.elapsed()
+    };
+    let _ = writeln!(
+        out,
+        "  {:<width$}  {:>10}",
+        "total",
+        millis(total),
+        width = width
+    );
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phases_add_up_until_the_first_frame() {
+        enable();
+        time("parse", || std::thread::sleep(Duration::from_millis(2)));
+        time("parse", || std::thread::sleep(Duration::from_millis(2)));
+        frame_drawn();
+        // Later frames and phases aren't startup
+        time("aggregate", || ());
+        frame_drawn();
+
+        // Other tests may time phases of their own meanwhile
+        let report = report().unwrap();
+        assert!(report.starts_with("Startup timing\n"));
+        let took = |phase: &str| -> f64 {
+            let line = report
+                .lines()
+                .find(|line| line.trim_start().starts_with(phase))
+                .unwrap();
+            let millis = line.trim_end_matches(" ms").rsplit(' ').next().unwrap();
+            millis.parse().unwrap()
+        };
+        assert!(took("parse") >= 4.0);
+        assert!(took("first render") >= 0.0);
+        assert!(took("total") >= took("parse"));
+        assert!(!report.contains("aggregate"));
+    }
+}
@@ -0,0 +1,510 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, TimeZone};
+use crossterm::{
+    cursor,
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    terminal::{self, ClearType},
+};
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+use std::io::{self, Write};
+use unicode_width::UnicodeWidthStr;
+
+use crate::aggregate::{
+    activity_level, context_switches, count_between, month_day_counts, period_bounds, summarize,
+    time_patterns, trend_weeks, usage_trend, DataQuality, ProjectRoots,
+};
+use crate::app::{Screen, ScreenExit};
+use crate::days::{day_counts, streaks};
+use crate::error::ErrorKind;
+use crate::frames;
+use crate::help::{show_help, HelpView};
+use crate::history::HistoryEntry;
+use crate::i18n::{month_abbrev, weekday_initial};
+use crate::panels::{
+    general_panel, panel_areas, ranked_list, ranked_name_width, ranking_panel, ranking_rows,
+    render_header, time_patterns_panel,
+};
+use crate::presentation;
+use crate::render::Canvas;
+use crate::snapshot::{save_status, stats_snapshot, SnapshotFormat};
+use crate::stats::{streak_row, with_trend};
+use crate::timing;
+use crate::tr;
+use crate::ui_utils::{
+    display_path, draw_banner, layout_thresholds, quality_title, screen_size, stats_layout,
+    truncate_end, StatsLayout,
+};
+
+// The today screen, kept while switching to other screens
+pub struct TodayScreen {
+    // Track current view: -1 = lifetime stats, 0 = current week, 1 = last week, etc.
+    week_offset: i64,
+    // Which ranking the top right box shows
+    panel: usize,
+}
+
+impl Default for TodayScreen {
+    fn default() -> Self {
+        TodayScreen {
+            week_offset: -1,
+            panel: 0,
+        }
+    }
+}
+
+impl TodayScreen {
+    pub fn run(
+        &mut self,
+        stdout: &mut io::Stdout,
+        entries: &[HistoryEntry],
+        roots: &ProjectRoots,
+    ) -> Result<ScreenExit> {
+        // Shown once in place of the controls after saving the view
+        let mut status: Option<String> = None;
+
+        // Smallest terminals for the full and compact layouts
+        let thresholds = layout_thresholds();
+
+        loop {
+            // Get terminal size
+            let (term_width, term_height) = screen_size()?;
+
+            // Check minimum terminal size requirements. Terminals too small for
+            // every panel get the compact layout.
+            let [_, (min_width, min_height)] = thresholds;
+            let Some(layout) = stats_layout(term_width, term_height, thresholds) else {
+                execute!(
+                    stdout,
+                    terminal::Clear(ClearType::All),
+                    cursor::MoveTo(0, 0)
+                )?;
+                write!(
+                    stdout,
+                    "{}",
+                    tr!("terminal-too-small", width = min_width, height = min_height)
+                )?;
+                stdout.flush().context(ErrorKind::Terminal)?;
+
+                // Wait for input and check if terminal has been resized
+                if let Event::Key(KeyEvent {
+                    code, modifiers, ..
+                }) = frames::next_event()?
+                {
+                    if let Some(screen) = Screen::for_key(code) {
+                        return Ok(ScreenExit::Switch(screen));
+                    }
+                    match code {
+                        KeyCode::Esc => return Ok(ScreenExit::Back),
+                        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                            return Ok(ScreenExit::Quit)
+                        }
+                        _ => {}
+                    }
+                }
+                continue;
+            };
+            let compact = layout == StatsLayout::Compact;
+
+            // Clear screen
+            execute!(stdout, terminal::Clear(ClearType::All))?;
+
+            // Calculate line allocation based on available height
+            // 1 line for header
+            // 6 lines for borders (3 box layers * 2 border lines each)
+            // Remaining lines for content
+
+            // Total available height
+            let usable_height = term_height;
+            let header_lines = 1;
+            let border_lines = 6; // 3 box layers * 2 border lines each
+
+            // Calculate remaining lines for content
+            let content_lines = usable_height
+                .saturating_sub(header_lines)
+                .saturating_sub(border_lines);
+
+            // Time patterns content (reduced from 4 to 2 since we removed a line)
+            let time_patterns_min = 2;
+            let time_patterns_max = 3;
+
+            // Middle layer content (start with 3, max 10)
+            let middle_layer_min = 3;
+            let middle_layer_max = 10;
+
+            // Apply priority-based allocation:
+            // 1. Ensure we have enough lines for minimum allocation
+            // 2. First allocate minimum to each layer
+            // 3. Then grow Time Patterns to max if possible
+            // 4. Then grow middle layer up to max
+            // 5. Any extra goes to top layer (though it's capped at its max)
+
+            // Top layer max is 7 so the month calendar fits a weekday header and
+            // six weeks
+            let top_layer_max = 7;
+
+            // When terminal height is limited, reduce middle box height
+            let adjusted_middle_layer_min = if term_height <= 20 {
+                2 // Reduce by 1 when height is limited
+            } else {
+                middle_layer_min
+            };
+
+            let adjusted_middle_layer_max = if term_height <= 20 {
+                middle_layer_max - 1 // Reduce max by 1 for limited height
+            } else {
+                middle_layer_max
+            };
+
+            // Start with minimum allocation using adjusted values
+            let base_allocation = top_layer_max + adjusted_middle_layer_min + time_patterns_min;
+
+            // Determine how many extra lines we have beyond base allocation
+            let extra_lines = content_lines.saturating_sub(base_allocation).min(20); // Cap extra at 20 to avoid excessive growth
+
+            // Allocate additional lines according to priority
+            let time_patterns_extra = (time_patterns_max - time_patterns_min).min(extra_lines);
+            let time_patterns_content = time_patterns_min + time_patterns_extra;
+
+            let middle_extra = if extra_lines > time_patterns_extra {
+                (adjusted_middle_layer_max - adjusted_middle_layer_min)
+                    .min(extra_lines - time_patterns_extra)
+            } else {
+                0
+            };
+            let middle_layer_content = adjusted_middle_layer_min + middle_extra;
+
+            // Top layer stays at max (already allocated in base_allocation)
+            let top_layer_content = top_layer_max;
+
+            // Calculate box heights (content + borders)
+            let top_box_height = top_layer_content + 2; // +2 for borders
+            let middle_box_height = middle_layer_content + 2; // +2 for borders
+            let bottom_box_height = time_patterns_content + 2; // +2 for borders
+
+            // Set command list limits based on available space
+            let commands_box_height = middle_box_height;
+            let max_commands = middle_layer_content as usize;
+
+            // Define the active entries based on current view
+            let now = chrono::Local::now();
+            let bounds = period_bounds(now, self.week_offset);
+            let (view_name, active_entries): (String, Vec<&HistoryEntry>) = if self.week_offset < 0
+            {
+                // Lifetime stats view
+                (tr!("view-all-time"), entries.iter().collect())
+            } else {
+                // Week-specific view
+                let start_of_week = Local.timestamp_opt(bounds.week.0, 0).unwrap();
+
+                // Get ISO week number of the year (1-52/53)
+                let week_number = start_of_week.iso_week().week();
+
+                // Format month abbreviation
+                let month_name = month_abbrev(start_of_week.month());
+
+                // Create view name in format "Week # [Month]"
+                let view_name = tr!("view-week", week = week_number, month = month_name);
+
+                // Filter entries for specific week
+                let week_entries = entries
+                    .iter()
+                    .filter(|e| e.timestamp >= bounds.week.0 && e.timestamp <= bounds.week.1)
+                    .collect();
+
+                (view_name, week_entries)
+            };
+
+            // Today and This month reach outside the selected week
+            let mut counts =
+                timing::time("aggregate", || summarize(&active_entries, &bounds, roots));
+            counts.today = count_between(entries.iter(), bounds.today_start, i64::MAX);
+            counts.this_month = count_between(entries.iter(), bounds.month.0, bounds.month.1);
+            let patterns = time_patterns(&active_entries);
+            let quality = counts.quality();
+            let view_name = quality_title(&view_name, quality.timing_reliable());
+            let weeks = trend_weeks();
+            let switches = context_switches(entries, bounds.today_start, weeks);
+            // Every command today has a timestamp, so only the count matters
+            let today_reliable = DataQuality::of(counts.today, counts.today).timing_reliable();
+            let trend = match switches.trend() {
+                Some(trend) if today_reliable => format!(" {}", trend.short()),
+                _ => String::new(),
+            };
+            let streaks = streaks(&day_counts(entries.iter()), now.date_naive());
+            let switches_row = (
+                tr!("stat-project-switches"),
+                format!("{} ({:.1}/h{})", switches.today, switches.per_hour, trend),
+            );
+            let today_trend = if today_reliable {
+                usage_trend(
+                    |start, end| count_between(entries.iter(), start, end),
+                    (bounds.today_start, bounds.today_start + 24 * 3600 - 1),
+                    now.timestamp(),
+                    weeks,
+                )
+            } else {
+                None
+            };
+            let today_row = (
+                tr!("stat-today"),
+                with_trend(counts.today.to_string(), today_trend),
+            );
+
+            // Header with view name
+            let controls_text = match status.take() {
+                Some(message) => message,
+                // The compact layout has no room for them beside the title
+                None if compact => String::new(),
+                None => tr!("today-controls"),
+            };
+            let mut canvas = Canvas::screen()?;
+            let areas = panel_areas(
+                canvas.area(),
+                [top_box_height, commands_box_height, bottom_box_height, 0],
+                compact,
+            );
+            render_header(
+                &mut canvas,
+                areas.header,
+                &tr!("app-title-view", view = view_name.as_str()),
+                &controls_text,
+                &tr!("commands-count", count = counts.total),
+            );
+
+            // Different stats depending on view
+            let general_stats = if self.week_offset < 0 {
+                // Lifetime stats
+                [
+                    today_row.clone(),
+                    (tr!("stat-this-week"), counts.this_week.to_string()),
+                    (tr!("stat-this-month"), counts.this_month.to_string()),
+                    (
+                        tr!("stat-weekly-average"),
+                        format!("{:.1}", counts.weekly_average()),
+                    ),
+                    (tr!("stat-unique"), counts.unique.to_string()),
+                    switches_row,
+                    streak_row(&streaks),
+                ]
+            } else {
+                // Weekly stats
+                [
+                    today_row,
+                    (tr!("stat-this-week"), counts.this_week.to_string()),
+                    (tr!("stat-this-month"), counts.this_month.to_string()),
+                    (tr!("stat-per-day"), format!("{:.1}", counts.per_day())),
+                    (tr!("stat-unique"), counts.unique.to_string()),
+                    switches_row,
+                    streak_row(&streaks),
+                ]
+            };
+            canvas.render(general_panel(&general_stats), areas.general);
+
+            // Current month calendar beside the general stats, when there's room
+            let stats_width = general_stats
+                .iter()
+                .map(|(key, value)| key.width().max(14) + 1 + value.width())
+                .max()
+                .unwrap_or(0) as u16
+                + 3;
+            let calendar_x = areas.general.width.saturating_sub(CALENDAR_WIDTH + 2);
+            if calendar_x >= stats_width + 2 {
+                let day_counts = month_day_counts(entries.iter(), now);
+                canvas.render(
+                    month_calendar(now, &day_counts),
+                    Rect::new(calendar_x, areas.general.y + 1, CALENDAR_WIDTH, 7),
+                );
+            }
+
+            // Top Right Box - rankings, cycled with Tab
+            let panels = [
+                (tr!("panel-categories"), &counts.categories, false),
+                (tr!("panel-pipeline-tools"), &counts.pipeline_tools, false),
+                (tr!("panel-edited-types"), &counts.edited_extensions, false),
+                (tr!("panel-edited-areas"), &counts.edited_areas, true),
+                (tr!("panel-contexts"), &counts.contexts, false),
+                (tr!("panel-projects"), &counts.projects, true),
+            ];
+            let (panel_title, panel_counts, directories) = &panels[self.panel % panels.len()];
+            let shown = ranking_rows(
+                panel_counts,
+                top_layer_content as usize,
+                *directories,
+                presentation::enabled(),
+            );
+            if let Some(area) = areas.rankings {
+                canvas.render(
+                    ranking_panel(panel_title, &shown, &counts, quality, "", area.width),
+                    area,
+                );
+            }
+
+            // Directories are left out of the compact layout
+            if let Some(area) = areas.directories {
+                let name_width = ranked_name_width(area.width);
+                let rows = counts
+                    .directories
+                    .iter()
+                    .take(max_commands)
+                    .map(|(dir, count)| (display_path(dir, name_width), count.to_string()));
+                canvas.render(ranked_list(&tr!("box-directories"), rows, None, None), area);
+            }
+
+            // Most Used Commands
+            let name_width = ranked_name_width(areas.commands.width);
+            let rows = counts
+                .commands
+                .iter()
+                .take(max_commands)
+                .map(|(cmd, count)| (truncate_end(cmd, name_width), count.to_string()));
+            canvas.render(
+                ranked_list(&tr!("box-commands"), rows, None, None),
+                areas.commands,
+            );
+
+            // Bottom Box - Time Patterns
+            canvas.render(time_patterns_panel(&patterns, quality, ""), areas.patterns);
+            canvas.flush(stdout)?;
+            draw_banner(stdout).context(ErrorKind::Terminal)?;
+
+            // Wait for user input
+            stdout.flush().context(ErrorKind::Terminal)?;
+
+            let snapshot = stats_snapshot(
+                &view_name,
+                &general_stats,
+                (panel_title, &shown),
+                &counts,
+                &patterns,
+                max_commands,
+                "",
+            );
+
+            timing::frame_drawn();
+            let next_event = frames::next_event()?;
+            if let Event::Key(KeyEvent { code, .. }) = next_event {
+                if let Some(screen) = Screen::for_key(code) {
+                    return Ok(ScreenExit::Switch(screen));
+                }
+                if let Some(format) = SnapshotFormat::for_key(code) {
+                    status = Some(save_status(&snapshot, format));
+                    continue;
+                }
+            }
+
+            // Handle key presses
+            match next_event {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc, ..
+                })
+                | Event::Key(KeyEvent {
+                    code: KeyCode::Char('q'),
+                    ..
+                }) => return Ok(ScreenExit::Back),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Left,
+                    ..
+                })
+                | Event::Key(KeyEvent {
+                    code: KeyCode::Char('h'),
+                    ..
+                }) => {
+                    // Go back (all-time -> current week -> previous weeks)
+                    if self.week_offset < 0 {
+                        // When in all-time view, switch to current week
+                        self.week_offset = 0;
+                    } else {
+                        // When in a week view, go back one week (increase offset)
+                        self.week_offset += 1;
+                    }
+                    continue; // Force immediate refresh of the display
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Right,
+                    ..
+                })
+                | Event::Key(KeyEvent {
+                    code: KeyCode::Char('l'),
+                    ..
+                }) => {
+                    // Go forward (previous weeks -> current week -> all-time)
+                    if self.week_offset > 0 {
+                        // When viewing past weeks, move forward one week (decrease offset)
+                        self.week_offset -= 1;
+                    } else if self.week_offset == 0 {
+                        // When viewing current week, go to all-time view
+                        self.week_offset = -1;
+                    }
+                    continue; // Force immediate refresh of the display
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Tab, ..
+                }) => {
+                    self.panel += 1;
+                    continue;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('?'),
+                    ..
+                }) => {
+                    show_help(stdout, HelpView::Today)?;
+                    continue;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers,
+                    ..
+                }) if modifiers.contains(KeyModifiers::CONTROL) => return Ok(ScreenExit::Quit),
+                _ => {}
+            }
+        }
+    }
+}
+
+// Seven columns of a two-digit day plus a space
+const CALENDAR_WIDTH: u16 = 7 * 3;
+
+// Month grid starting on Monday, with weekday initials at `y` and up to six
+// weeks below them. Days are shaded by how many commands ran
+// on them and today is highlighted.
+// This month's days under their weekday initials, each colored by how busy
+// it was, with today highlighted
+fn month_calendar(today: DateTime<Local>, day_counts: &[usize]) -> Paragraph<'static> {
+    let header: Vec<Span> = (0..7)
+        .map(|weekday| Span::styled(format!(" {} ", weekday_initial(weekday)), grey()))
+        .collect();
+    let mut lines = vec![Line::from(header)];
+
+    let first_weekday = today.with_day(1).unwrap().weekday().num_days_from_monday() as usize;
+    let busiest = day_counts.iter().copied().max().unwrap_or(0);
+    let mut week = vec![Span::from("   ".repeat(first_weekday))];
+    for (day0, &count) in day_counts.iter().enumerate() {
+        let style = if day0 as u32 == today.day0() {
+            Style::new().black().on_light_cyan().bold()
+        } else {
+            match activity_level(count, busiest) {
+                0 => grey(),
+                1 => Style::new().on_green(),
+                2 => Style::new().black().on_light_green(),
+                _ => Style::new().black().on_light_yellow(),
+            }
+        };
+        week.push(Span::styled(format!("{:>2}", day0 + 1), style));
+        week.push(Span::from(" "));
+        if (first_weekday + day0) % 7 == 6 {
+            lines.push(Line::from(std::mem::take(&mut week)));
+        }
+    }
+    if !week.is_empty() {
+        lines.push(Line::from(week));
+    }
+    Paragraph::new(lines)
+}
+
+fn grey() -> Style {
+    Style::new().dark_gray()
+}
@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use crossterm::{cursor, event::KeyCode, execute, style::Stylize};
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::error::ErrorKind;
+use crate::tr;
+use crate::ui_utils::{draw_box, screen_size};
+
+// Message for each step, in order
+const STEPS: [&str; 3] = ["tour-detail", "tour-list", "tour-stats"];
+
+const TOUR_WIDTH: u16 = 64;
+
+// The first-run tour, drawn over whichever history view is showing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tour {
+    step: usize,
+}
+
+// Marks the tour as seen. Kept next to user translations.
+fn seen_marker() -> Option<PathBuf> {
+    home::home_dir().map(|home| home.join(".config/cli-tracker/tour-seen"))
+}
+
+// Failing to save this only means the tour comes back next time
+pub fn mark_seen() {
+    if let Some(path) = seen_marker() {
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(path, "");
+    }
+}
+
+impl Tour {
+    // The tour, unless it has been finished or skipped before
+    pub fn start() -> Option<Tour> {
+        let seen = seen_marker().is_none_or(|path| path.exists());
+        (!seen).then_some(Tour { step: 0 })
+    }
+
+    // The tour after a key press, or None once it's finished or skipped
+    pub fn on_key(self, code: KeyCode) -> Option<Tour> {
+        match code {
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char(' ') => {
+                let step = self.step + 1;
+                (step < STEPS.len()).then_some(Tour { step })
+            }
+            KeyCode::Left => Some(Tour {
+                step: self.step.saturating_sub(1),
+            }),
+            KeyCode::Esc | KeyCode::Char('q') => None,
+            _ => Some(self),
+        }
+    }
+
+    // A box in the middle of the screen over the current view
+    pub fn draw(self, stdout: &mut io::Stdout) -> Result<()> {
+        let (term_width, term_height) = screen_size()?;
+        let mut lines: Vec<String> = tr!(STEPS[self.step]).lines().map(str::to_string).collect();
+        lines.push(String::new());
+        let controls = tr!("tour-controls");
+
+        let width = TOUR_WIDTH.min(term_width.saturating_sub(4));
+        let height = lines.len() as u16 + 3;
+        let x = (term_width - width) / 2;
+        let y = term_height.saturating_sub(height) / 2;
+
+        // Blank out the view underneath first
+        let blank = " ".repeat(width as usize);
+        for row in y..y + height {
+            execute!(stdout, cursor::MoveTo(x, row))?;
+            write!(stdout, "{}", blank)?;
+        }
+        let title = tr!("tour-title", step = self.step + 1, steps = STEPS.len());
+        draw_box(stdout, x, y, width, height, Some(&title))?;
+        for (i, line) in lines.iter().enumerate() {
+            execute!(stdout, cursor::MoveTo(x + 2, y + 1 + i as u16))?;
+            write!(stdout, "{}", line)?;
+        }
+        let controls_x = x + width.saturating_sub(controls.width() as u16 + 2);
+        execute!(stdout, cursor::MoveTo(controls_x, y + height - 2))?;
+        write!(stdout, "{}", controls.dark_grey())?;
+        stdout.flush().context(ErrorKind::Terminal)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_forward_back_and_out() {
+        let tour = Tour { step: 0 };
+        let second = tour.on_key(KeyCode::Enter).unwrap();
+        assert_eq!(second.step, 1);
+        assert_eq!(second.on_key(KeyCode::Left), Some(tour));
+        assert_eq!(second.on_key(KeyCode::Char('x')), Some(second));
+        assert_eq!(second.on_key(KeyCode::Esc), None);
+
+        let last = Tour {
+            step: STEPS.len() - 1,
+        };
+        assert_eq!(last.on_key(KeyCode::Right), None);
+    }
+}
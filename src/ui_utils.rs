@@ -1,15 +1,48 @@
-use anyhow::Result;
-use crossterm::{cursor, execute, style::Stylize};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use crossterm::event::Event;
+use crossterm::{cursor, execute, style::Stylize, terminal};
+use ratatui::layout::Rect;
+use ratatui::widgets::Block;
 use std::io::{self, Write};
-use unicode_width::UnicodeWidthStr;
+use std::path::Path;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-// Define box drawing characters
-pub const TOP_LEFT: &str = "┌";
-pub const TOP_RIGHT: &str = "┐";
-pub const BOTTOM_LEFT: &str = "└";
-pub const BOTTOM_RIGHT: &str = "┘";
-pub const HORIZONTAL: &str = "─";
-pub const VERTICAL: &str = "│";
+use crate::config::config;
+use crate::doctor;
+use crate::error::ErrorKind;
+use crate::frames;
+use crate::presentation::{self, project_alias};
+use crate::render::{render_widget, titled};
+use crate::tr;
+
+// What a screen that any key closes does with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dismiss {
+    Close,
+    // The terminal changed size, so it's drawn again to fit
+    Redraw,
+    Ignore,
+}
+
+pub fn dismiss_on(event: &Event) -> Dismiss {
+    match event {
+        Event::Key(_) => Dismiss::Close,
+        Event::Resize(..) => Dismiss::Redraw,
+        _ => Dismiss::Ignore,
+    }
+}
+
+// Wait until a key closes the screen, true, or a resize needs it redrawn
+pub fn wait_to_dismiss() -> Result<bool> {
+    loop {
+        match dismiss_on(&frames::next_event()?) {
+            Dismiss::Close => return Ok(true),
+            Dismiss::Redraw => return Ok(false),
+            Dismiss::Ignore => {}
+        }
+    }
+}
 
 // Helper function to draw a box
 pub fn draw_box(
@@ -21,62 +54,248 @@ pub fn draw_box(
     title: Option<&str>,
 ) -> Result<()> {
     // Ensure minimum dimensions for a proper box
-    let width = width.max(4); // Minimum width to draw a proper box
-    let height = height.max(3); // Minimum height for a proper box
-
-    // Draw top border with optional title
-    execute!(stdout, cursor::MoveTo(x, y))?;
-    write!(stdout, "{}", TOP_LEFT)?;
-
-    if let Some(title_text) = title {
-        let title_display = format!(" {} ", title_text);
-        let title_width = title_display.width();
-        // Ensure we have enough space for title and borders
-        let remaining_width = width as usize - 2;
-
-        if title_width < remaining_width {
-            let left_border = (remaining_width - title_width) / 2;
-            let right_border = remaining_width - left_border - title_width;
-
-            write!(stdout, "{}", HORIZONTAL.repeat(left_border))?;
-            write!(stdout, "{}", title_display.cyan())?;
-            write!(stdout, "{}", HORIZONTAL.repeat(right_border))?;
-        } else {
-            // Title too long, just draw border
-            write!(stdout, "{}", HORIZONTAL.repeat(remaining_width))?;
-        }
+    let area = Rect::new(x, y, width.max(4), height.max(3));
+    let block = match title {
+        Some(title) => titled(title),
+        None => Block::bordered(),
+    };
+    render_widget(stdout, block, area)
+}
+
+// A panel's title, marked when there's too little data behind it
+pub fn quality_title(title: &str, reliable: bool) -> String {
+    if reliable {
+        title.to_string()
     } else {
-        write!(stdout, "{}", HORIZONTAL.repeat((width - 2) as usize))?;
+        format!("{} · {}", title, tr!("low-data"))
     }
+}
 
-    write!(stdout, "{}", TOP_RIGHT)?;
+// The terminal's width and height, for drawing
+pub fn screen_size() -> Result<(u16, u16)> {
+    terminal::size().context(ErrorKind::Terminal)
+}
 
-    // Draw sides
-    for i in 1..height - 1 {
-        execute!(stdout, cursor::MoveTo(x, y + i))?;
-        write!(stdout, "{}", VERTICAL)?;
-        execute!(stdout, cursor::MoveTo(x + width - 1, y + i))?;
-        write!(stdout, "{}", VERTICAL)?;
+// The logging hook warning across the last row, while there is one.
+// Returns whether it was drawn.
+pub fn draw_banner(stdout: &mut io::Stdout) -> Result<bool> {
+    let Some(banner) = doctor::banner() else {
+        return Ok(false);
+    };
+    let (width, height) = screen_size()?;
+    let text = format!(" ⚠ {}", banner);
+    let text = truncate_middle(&text, width as usize);
+    let padded = format!("{:<width$}", text, width = width as usize);
+    execute!(stdout, cursor::MoveTo(0, height.saturating_sub(1)))?;
+    write!(stdout, "{}", padded.black().on_yellow())?;
+    Ok(true)
+}
+
+// Layouts of the stats and today views, from most to least detailed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsLayout {
+    // Two columns with every panel
+    Full,
+    // One column without the rankings panel, directories or controls
+    Compact,
+}
+
+// Smallest terminal for each layout, as (width, height)
+const FULL_SIZE: (u16, u16) = (100, 20);
+const COMPACT_SIZE: (u16, u16) = (80, 20);
+
+// A terminal size such as `120x30`
+pub fn parse_size(value: &str) -> Option<(u16, u16)> {
+    let (width, height) = value.trim().split_once(['x', 'X'])?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+// Minimum sizes for the full and compact layouts, from ui.full_size and
+// ui.compact_size in the config when set
+pub fn layout_thresholds() -> [(u16, u16); 2] {
+    let config = config();
+    [
+        config.full_size.unwrap_or(FULL_SIZE),
+        config.compact_size.unwrap_or(COMPACT_SIZE),
+    ]
+}
+
+// The most detailed layout that fits, or None if the terminal is too small
+// for any
+pub fn stats_layout(
+    width: u16,
+    height: u16,
+    [full, compact]: [(u16, u16); 2],
+) -> Option<StatsLayout> {
+    let fits = |(min_width, min_height): (u16, u16)| width >= min_width && height >= min_height;
+    if fits(full) {
+        Some(StatsLayout::Full)
+    } else if fits(compact) {
+        Some(StatsLayout::Compact)
+    } else {
+        None
     }
+}
 
-    // Draw bottom
-    execute!(stdout, cursor::MoveTo(x, y + height - 1))?;
-    write!(stdout, "{}", BOTTOM_LEFT)?;
-    write!(stdout, "{}", HORIZONTAL.repeat((width - 2) as usize))?;
-    write!(stdout, "{}", BOTTOM_RIGHT)?;
+// How paths that don't fit are shortened, set with ui.path_style
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum PathStyle {
+    // Cut out of the middle, keeping both ends
+    #[default]
+    Truncate,
+    // Fish-style, every directory but the last cut to its first letter
+    #[value(name = "fish")]
+    Abbreviate,
+}
 
-    Ok(())
+pub fn path_style() -> PathStyle {
+    config().path_style
 }
 
-// Write text inside a box area with an x offset
-pub fn write_in_box(
-    stdout: &mut io::Stdout,
-    x: u16,
-    y: u16,
-    text: &str,
-    x_offset: u16,
-) -> Result<()> {
-    execute!(stdout, cursor::MoveTo(x + 1 + x_offset, y))?;
-    write!(stdout, "{}", text)?;
-    Ok(())
+// `/home/me/src` as `~/src`
+pub fn home_relative(path: &str, home: Option<&Path>) -> String {
+    let Some(home) = home.and_then(Path::to_str).filter(|home| !home.is_empty()) else {
+        return path.to_string();
+    };
+    match path.strip_prefix(home) {
+        Some("") => "~".to_string(),
+        Some(rest) if rest.starts_with('/') => format!("~{}", rest),
+        _ => path.to_string(),
+    }
+}
+
+// `~/src/cli-tracker/src` as `~/s/c/src`. Hidden directories keep their dot.
+fn abbreviate_path(path: &str) -> String {
+    let Some((parents, last)) = path.rsplit_once('/') else {
+        return path.to_string();
+    };
+    let parents: Vec<String> = parents
+        .split('/')
+        .map(|part| {
+            let keep = if part.starts_with('.') { 2 } else { 1 };
+            part.chars().take(keep).collect()
+        })
+        .collect();
+    format!("{}/{}", parents.join("/"), last)
+}
+
+// Both ends of `text` with `...` between, in at most `width` columns
+pub fn truncate_middle(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    if width <= 3 {
+        return ".".repeat(width);
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let fits = |chars: &[char]| chars.iter().collect::<String>().width();
+    let (mut head, mut tail) = (0, 0);
+    // Alternate, so the end (usually the most telling part) gets any spare
+    loop {
+        let grow_tail = tail <= head;
+        let (next_head, next_tail) = if grow_tail {
+            (head, tail + 1)
+        } else {
+            (head + 1, tail)
+        };
+        let used = fits(&chars[..next_head]) + fits(&chars[chars.len() - next_tail..]);
+        if used + 3 > width {
+            break;
+        }
+        (head, tail) = (next_head, next_tail);
+    }
+    format!(
+        "{}...{}",
+        chars[..head].iter().collect::<String>(),
+        chars[chars.len() - tail..].iter().collect::<String>()
+    )
+}
+
+// A directory as it should appear in a column `width` wide: relative to
+// home, then shortened in `style` if that still doesn't fit
+pub fn fit_path(path: &str, width: usize, home: Option<&Path>, style: PathStyle) -> String {
+    let mut path = home_relative(path, home);
+    if path.width() > width && style == PathStyle::Abbreviate {
+        path = abbreviate_path(&path);
+    }
+    truncate_middle(&path, width)
+}
+
+// `text` cut to `width` columns, ending in `...`, if it's any wider
+pub fn truncate_end(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut used = 0;
+    let head: String = text
+        .chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used + 3 <= width
+        })
+        .collect();
+    format!("{}...", head)
+}
+
+// `fit_path` for the current user and path style, or the project's alias
+// when presenting
+pub fn display_path(path: &str, width: usize) -> String {
+    if presentation::enabled() {
+        return truncate_middle(&project_alias(path), width);
+    }
+    fit_path(path, width, home::home_dir().as_deref(), path_style())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_close_and_resizes_redraw() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let key = Event::Key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE));
+        assert_eq!(dismiss_on(&key), Dismiss::Close);
+        assert_eq!(dismiss_on(&Event::Resize(120, 40)), Dismiss::Redraw);
+        assert_eq!(dismiss_on(&Event::FocusLost), Dismiss::Ignore);
+    }
+
+    #[test]
+    fn picks_the_most_detailed_layout_that_fits() {
+        let thresholds = [FULL_SIZE, COMPACT_SIZE];
+        assert_eq!(stats_layout(120, 40, thresholds), Some(StatsLayout::Full));
+        assert_eq!(stats_layout(80, 24, thresholds), Some(StatsLayout::Compact));
+        assert_eq!(stats_layout(100, 19, thresholds), None);
+
+        assert_eq!(parse_size("90x22"), Some((90, 22)));
+        assert_eq!(parse_size("wide"), None);
+        let custom = [parse_size("130X30").unwrap(), COMPACT_SIZE];
+        assert_eq!(stats_layout(120, 40, custom), Some(StatsLayout::Compact));
+    }
+
+    #[test]
+    fn paths_are_shown_from_home_and_shortened() {
+        let home = Some(Path::new("/home/me"));
+        assert_eq!(home_relative("/home/me", home), "~");
+        assert_eq!(home_relative("/home/me/src", home), "~/src");
+        assert_eq!(home_relative("/home/merge", home), "/home/merge");
+
+        let path = "/home/me/src/cli-tracker/target/debug";
+        assert_eq!(
+            fit_path(path, 40, home, PathStyle::Truncate),
+            "~/src/cli-tracker/target/debug"
+        );
+        assert_eq!(
+            fit_path(path, 20, home, PathStyle::Truncate),
+            "~/src/cl...get/debug"
+        );
+        assert_eq!(
+            fit_path(path, 20, home, PathStyle::Abbreviate),
+            "~/s/c/t/debug"
+        );
+        assert_eq!(abbreviate_path("/etc/.config/nvim"), "/e/.c/nvim");
+        assert_eq!(truncate_middle("abcdef", 2), "..");
+        assert_eq!(truncate_end("/home/me/src", 12), "/home/me/src");
+        assert_eq!(truncate_end("/home/me/src", 8), "/home...");
+        assert_eq!(truncate_end("界界界界", 7), "界界...");
+    }
 }
@@ -0,0 +1,310 @@
+use anyhow::{Context, Result};
+use crossterm::{
+    cursor,
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    style::{self, Color, Stylize},
+    terminal::{self, ClearType},
+};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::{Screen, ScreenExit};
+use crate::compare::percent_change;
+use crate::days::local_day;
+use crate::error::ErrorKind;
+use crate::frames;
+use crate::help::{show_help, HelpView};
+use crate::history::HistoryEntry;
+use crate::interactive::browse_history;
+use crate::time_format::{format_day, TimeDisplay};
+use crate::tr;
+use crate::ui_utils::{screen_size, truncate_middle};
+
+// Trends compare this many days up to now with as many before them
+const TREND_DAYS: i64 = 28;
+
+// Count, first, last and trend, with the spaces between them
+const FIGURES_WIDTH: usize = 7 + 12 + 12 + 8;
+
+// One command of the vocabulary, however many times it ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniqueCommand {
+    pub command: String,
+    pub count: usize,
+    // First and last runs with a timestamp, 0 when none had one
+    pub first: i64,
+    pub last: i64,
+    // Runs in the last TREND_DAYS days and the TREND_DAYS before those
+    pub recent: usize,
+    pub previous: usize,
+}
+
+impl UniqueCommand {
+    // `+50%`, `new`, or nothing for a command unused in both windows
+    pub fn trend(&self) -> String {
+        if self.recent == 0 && self.previous == 0 {
+            String::new()
+        } else {
+            percent_change(self.previous as f64, self.recent as f64)
+        }
+    }
+
+    // The change the trend shows, with new commands above any rise
+    fn trend_order(&self) -> f64 {
+        match (self.previous, self.recent) {
+            (0, 0) => 0.0,
+            (0, _) => f64::INFINITY,
+            (previous, recent) => recent as f64 / previous as f64 - 1.0,
+        }
+    }
+}
+
+// What the vocabulary is sorted by, picked with 1 to 5
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortColumn {
+    Count,
+    First,
+    Last,
+    Trend,
+    Command,
+}
+
+impl SortColumn {
+    const ALL: [SortColumn; 5] = [
+        SortColumn::Count,
+        SortColumn::First,
+        SortColumn::Last,
+        SortColumn::Trend,
+        SortColumn::Command,
+    ];
+
+    fn heading(self) -> String {
+        match self {
+            SortColumn::Count => tr!("vocabulary-count"),
+            SortColumn::First => tr!("vocabulary-first"),
+            SortColumn::Last => tr!("vocabulary-last"),
+            SortColumn::Trend => tr!("vocabulary-trend"),
+            SortColumn::Command => tr!("vocabulary-command"),
+        }
+    }
+}
+
+// Each command in `entries` once, busiest first
+pub fn unique_commands(entries: &[HistoryEntry], now: i64) -> Vec<UniqueCommand> {
+    let recent_start = now - TREND_DAYS * 86400;
+    let previous_start = recent_start - TREND_DAYS * 86400;
+    let mut commands: HashMap<&str, UniqueCommand> = HashMap::new();
+    for entry in entries {
+        let unique = commands
+            .entry(entry.command.as_str())
+            .or_insert_with(|| UniqueCommand {
+                command: entry.command.clone(),
+                count: 0,
+                first: 0,
+                last: 0,
+                recent: 0,
+                previous: 0,
+            });
+        unique.count += 1;
+        if entry.timestamp > 0 {
+            if unique.first == 0 || entry.timestamp < unique.first {
+                unique.first = entry.timestamp;
+            }
+            unique.last = unique.last.max(entry.timestamp);
+            if entry.timestamp > now {
+                continue;
+            } else if entry.timestamp >= recent_start {
+                unique.recent += 1;
+            } else if entry.timestamp >= previous_start {
+                unique.previous += 1;
+            }
+        }
+    }
+    let mut commands: Vec<UniqueCommand> = commands.into_values().collect();
+    sort_commands(&mut commands, SortColumn::Count, true);
+    commands
+}
+
+// Sorted by `column`, ties broken by the command itself
+pub fn sort_commands(commands: &mut [UniqueCommand], column: SortColumn, descending: bool) {
+    commands.sort_by(|a, b| {
+        let order = match column {
+            SortColumn::Count => a.count.cmp(&b.count),
+            SortColumn::First => a.first.cmp(&b.first),
+            SortColumn::Last => a.last.cmp(&b.last),
+            SortColumn::Trend => a.trend_order().total_cmp(&b.trend_order()),
+            SortColumn::Command => a.command.cmp(&b.command),
+        };
+        let order = if descending { order.reverse() } else { order };
+        order.then_with(|| a.command.cmp(&b.command))
+    });
+}
+
+fn day(timestamp: i64) -> String {
+    local_day(timestamp).map_or_else(|| "-".to_string(), format_day)
+}
+
+// Every command once, with how often, since when, until when and which way
+// it's going. Enter opens the runs of the selected one.
+pub fn browse_vocabulary(stdout: &mut io::Stdout, entries: &[HistoryEntry]) -> Result<ScreenExit> {
+    let mut commands = unique_commands(entries, chrono::Local::now().timestamp());
+    let (mut column, mut descending) = (SortColumn::Count, true);
+    let mut selected: usize = 0;
+
+    loop {
+        let (term_width, term_height) = screen_size()?;
+        execute!(
+            stdout,
+            terminal::Clear(ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+        let title = tr!("vocabulary-title", count = commands.len());
+        write!(
+            stdout,
+            "{} {}",
+            title.cyan().bold(),
+            tr!("vocabulary-controls").dark_grey()
+        )?;
+
+        // Headings, the sorted one marked with its direction
+        let heading = |c: SortColumn| {
+            let arrow = match (c == column, descending) {
+                (false, _) => "",
+                (true, true) => "▼",
+                (true, false) => "▲",
+            };
+            format!("{}{}", c.heading(), arrow)
+        };
+        let headings = format!(
+            "{:>6} {:<11} {:<11} {:>7}  {}",
+            heading(SortColumn::Count),
+            heading(SortColumn::First),
+            heading(SortColumn::Last),
+            heading(SortColumn::Trend),
+            heading(SortColumn::Command)
+        );
+        execute!(stdout, cursor::MoveTo(0, 2))?;
+        write!(stdout, "{}", headings.dark_grey())?;
+
+        let rows = term_height.saturating_sub(4).max(1) as usize;
+        let start = selected
+            .saturating_sub(rows / 2)
+            .min(commands.len().saturating_sub(rows));
+        let command_width = (term_width as usize).saturating_sub(FIGURES_WIDTH + 1);
+        for (row, unique) in commands.iter().enumerate().skip(start).take(rows) {
+            execute!(stdout, cursor::MoveTo(0, 3 + (row - start) as u16))?;
+            let command = truncate_middle(&unique.command.replace('\n', " "), command_width);
+            let line = format!(
+                "{:>6} {:<11} {:<11} {:>7}  {}",
+                unique.count,
+                day(unique.first),
+                day(unique.last),
+                unique.trend(),
+                command
+            );
+            if row == selected {
+                let padding = (term_width as usize).saturating_sub(line.width());
+                execute!(stdout, style::SetBackgroundColor(Color::DarkBlue))?;
+                write!(stdout, "{}{}", line.white(), " ".repeat(padding))?;
+                execute!(stdout, style::ResetColor)?;
+            } else {
+                write!(stdout, "{}", line)?;
+            }
+        }
+        stdout.flush().context(ErrorKind::Terminal)?;
+
+        let Event::Key(KeyEvent {
+            code, modifiers, ..
+        }) = frames::next_event()?
+        else {
+            continue;
+        };
+        if let Some(screen) = Screen::for_key(code) {
+            return Ok(ScreenExit::Switch(screen));
+        }
+        match code {
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(ScreenExit::Quit)
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(ScreenExit::Back),
+            KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                selected = (selected + 1).min(commands.len().saturating_sub(1))
+            }
+            KeyCode::PageUp => selected = selected.saturating_sub(rows),
+            KeyCode::PageDown => selected = (selected + rows).min(commands.len().saturating_sub(1)),
+            KeyCode::Char('?') => show_help(stdout, HelpView::Vocabulary)?,
+            // Picking the sorted column again turns it around
+            KeyCode::Char(key @ '1'..='5') => {
+                let picked = SortColumn::ALL[key as usize - '1' as usize];
+                descending = if picked == column {
+                    !descending
+                } else {
+                    picked != SortColumn::Command
+                };
+                column = picked;
+                sort_commands(&mut commands, column, descending);
+                selected = 0;
+            }
+            KeyCode::Enter => {
+                let Some(unique) = commands.get(selected) else {
+                    continue;
+                };
+                let runs: Vec<HistoryEntry> = entries
+                    .iter()
+                    .filter(|e| e.command == unique.command)
+                    .cloned()
+                    .collect();
+                match browse_history(stdout, &runs, TimeDisplay::Absolute)? {
+                    ScreenExit::Back => continue,
+                    exit => return Ok(exit),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_each_command_once_with_its_trend() {
+        let now = 100 * 86400;
+        let run = |days_ago: i64, command: &str| HistoryEntry {
+            timestamp: now - days_ago * 86400,
+            command: command.to_string(),
+            ..Default::default()
+        };
+        let entries = [
+            run(90, "make"),
+            run(40, "make"),
+            run(30, "make"),
+            run(2, "make"),
+            run(1, "ls"),
+            run(70, "vim"),
+        ];
+        let mut commands = unique_commands(&entries, now);
+        let names: Vec<&str> = commands.iter().map(|c| c.command.as_str()).collect();
+        assert_eq!(names, ["make", "ls", "vim"]);
+        assert_eq!(commands[0].count, 4);
+        assert_eq!(
+            (commands[0].first, commands[0].last),
+            (now - 90 * 86400, now - 2 * 86400)
+        );
+        assert_eq!((commands[0].recent, commands[0].previous), (1, 2));
+        assert_eq!(commands[0].trend(), "-50%");
+        assert_eq!(commands[1].trend(), "new");
+        assert_eq!(commands[2].trend(), "");
+
+        sort_commands(&mut commands, SortColumn::Trend, true);
+        assert_eq!(commands[0].command, "ls");
+        sort_commands(&mut commands, SortColumn::First, false);
+        assert_eq!(commands[0].command, "make");
+        sort_commands(&mut commands, SortColumn::Command, false);
+        assert_eq!(commands[2].command, "vim");
+    }
+}
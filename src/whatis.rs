@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+// One-line descriptions of common programs, so the detail view can say what
+// a tool does without man pages installed. Kept sorted by name.
+const SUMMARIES: &[(&str, &str)] = &[
+    ("apt", "install and manage Debian packages"),
+    ("awk", "pattern scanning and text processing language"),
+    ("bat", "cat with syntax highlighting and git integration"),
+    ("brew", "the missing package manager for macOS"),
+    ("cargo", "Rust's package manager and build tool"),
+    ("cat", "concatenate files and print them"),
+    ("cd", "change the working directory"),
+    ("chmod", "change file mode bits"),
+    ("chown", "change file owner and group"),
+    ("cp", "copy files and directories"),
+    ("curl", "transfer data from or to a server"),
+    ("cut", "remove sections from each line of files"),
+    ("df", "report file system disk space usage"),
+    ("diff", "compare files line by line"),
+    ("docker", "build, run and manage containers"),
+    ("du", "estimate file space usage"),
+    ("echo", "display a line of text"),
+    ("env", "run a program in a modified environment"),
+    ("exa", "a modern replacement for ls"),
+    ("export", "set environment variables for child processes"),
+    ("fd", "a simple, fast alternative to find"),
+    ("find", "search for files in a directory hierarchy"),
+    ("fzf", "command-line fuzzy finder"),
+    ("gcc", "GNU C and C++ compiler"),
+    ("gh", "GitHub on the command line"),
+    ("git", "the stupid content tracker"),
+    ("go", "tool for managing Go source code"),
+    ("grep", "print lines that match patterns"),
+    ("gzip", "compress or expand files"),
+    ("head", "output the first part of files"),
+    ("helm", "the Kubernetes package manager"),
+    ("htop", "interactive process viewer"),
+    ("jq", "command-line JSON processor"),
+    ("kill", "send a signal to a process"),
+    ("kubectl", "control Kubernetes clusters"),
+    ("less", "page through text one screenful at a time"),
+    ("ln", "make links between files"),
+    ("ls", "list directory contents"),
+    ("make", "maintain groups of programs"),
+    ("man", "an interface to the system reference manuals"),
+    ("mkdir", "make directories"),
+    ("mv", "move (rename) files"),
+    ("nano", "a small, friendly text editor"),
+    ("node", "server-side JavaScript runtime"),
+    ("npm", "JavaScript package manager"),
+    ("nvim", "Neovim, a Vim-based text editor"),
+    ("pip", "install Python packages"),
+    ("ps", "report a snapshot of the current processes"),
+    ("pwd", "print name of the current working directory"),
+    ("python", "the Python interpreter"),
+    ("python3", "the Python interpreter"),
+    ("rg", "ripgrep: recursively search for a regex pattern"),
+    ("rm", "remove files or directories"),
+    ("rsync", "fast, versatile remote and local file copying"),
+    ("rustc", "the Rust compiler"),
+    ("rustup", "the Rust toolchain installer"),
+    ("scp", "secure copy between hosts"),
+    ("sed", "stream editor for filtering and transforming text"),
+    ("sort", "sort lines of text files"),
+    ("source", "run commands from a file in the current shell"),
+    ("ssh", "OpenSSH remote login client"),
+    ("sudo", "execute a command as another user"),
+    (
+        "systemctl",
+        "control the systemd system and service manager",
+    ),
+    ("tail", "output the last part of files"),
+    ("tar", "an archiving utility"),
+    ("terraform", "build, change and version infrastructure"),
+    ("tmux", "terminal multiplexer"),
+    ("top", "display Linux processes"),
+    ("touch", "change file timestamps, creating missing files"),
+    ("uniq", "report or omit repeated lines"),
+    ("vi", "a screen-oriented text editor"),
+    ("vim", "Vi IMproved, a programmer's text editor"),
+    ("wc", "print newline, word and byte counts"),
+    ("wget", "non-interactive network downloader"),
+    ("which", "locate a command"),
+    (
+        "xargs",
+        "build and execute command lines from standard input",
+    ),
+    ("yarn", "JavaScript package manager"),
+    ("z", "jump to a frequently used directory"),
+    ("zip", "package and compress files"),
+];
+
+// The program a command line runs, skipping `NAME=value` prefixes, sudo
+// and the path it was called by
+pub fn program_name(command: &str) -> Option<&str> {
+    let program = command
+        .split_whitespace()
+        .find(|word| !word.contains('=') && *word != "sudo")?;
+    program.rsplit('/').next().filter(|name| !name.is_empty())
+}
+
+// What `whatis` printed, e.g. `ls (1) - list directory contents`
+fn parse_whatis(output: &str) -> Option<String> {
+    let line = output.lines().next()?;
+    let (_, summary) = line.split_once(" - ")?;
+    Some(summary.trim().to_string()).filter(|summary| !summary.is_empty())
+}
+
+// Asked once per program, as the detail view redraws on every key
+static LOOKUPS: Mutex<Option<HashMap<String, Option<String>>>> = Mutex::new(None);
+
+fn lookup(program: &str) -> Option<String> {
+    // CLI_TRACKER_WHATIS=off keeps to the bundled descriptions
+    if std::env::var("CLI_TRACKER_WHATIS").is_ok_and(|value| value == "off") {
+        return None;
+    }
+    let mut lookups = LOOKUPS.lock().ok()?;
+    lookups
+        .get_or_insert_with(HashMap::new)
+        .entry(program.to_string())
+        .or_insert_with(|| {
+            let output = Command::new("whatis")
+                .arg(program)
+                .stderr(Stdio::null())
+                .output()
+                .ok()?;
+            parse_whatis(&String::from_utf8_lossy(&output.stdout))
+        })
+        .clone()
+}
+
+// A one-line description of the program `command` runs: the bundled one, or
+// what `whatis` says when it's installed
+pub fn summary(command: &str) -> Option<String> {
+    let program = program_name(command)?;
+    match SUMMARIES.binary_search_by(|(name, _)| name.cmp(&program)) {
+        Ok(i) => Some(SUMMARIES[i].1.to_string()),
+        Err(_) => lookup(program),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_the_program_a_command_runs() {
+        assert!(SUMMARIES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(
+            program_name("sudo RUST_LOG=debug /usr/bin/ls -la"),
+            Some("ls")
+        );
+        assert_eq!(program_name("  "), None);
+        assert_eq!(
+            summary("git status").as_deref(),
+            Some("the stupid content tracker")
+        );
+        assert_eq!(
+            parse_whatis("ls (1)               - list directory contents\n").as_deref(),
+            Some("list directory contents")
+        );
+        assert_eq!(parse_whatis("frobnicate: nothing appropriate.\n"), None);
+    }
+}
@@ -0,0 +1,453 @@
+use anyhow::{Context, Result};
+use chrono::{Datelike, Local, NaiveDate, TimeZone, Timelike};
+use crossterm::{
+    cursor,
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    style::{Color, Stylize},
+    terminal::{self, ClearType},
+};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::Duration;
+use unicode_width::UnicodeWidthStr;
+
+use crate::aggregate::{program, sorted_counts};
+use crate::days::{local_day, streaks};
+use crate::error::ErrorKind;
+use crate::frames;
+use crate::help::{show_help, HelpView};
+use crate::history::HistoryEntry;
+use crate::i18n::{month_abbrev, weekday_name};
+use crate::report::top_commands;
+use crate::tr;
+use crate::ui_utils::screen_size;
+
+// How many top commands and new tools the recap lists
+const RECAP_LIST: usize = 5;
+
+// Frames the big number takes to count up, and the time between them
+const COUNT_FRAMES: usize = 15;
+const FRAME: Duration = Duration::from_millis(40);
+
+// Pause before each of a page's lines appears
+const LINE_DELAY: Duration = Duration::from_millis(120);
+
+// A page's accent, in turn
+const ACCENTS: [Color; 6] = [
+    Color::Magenta,
+    Color::Green,
+    Color::Yellow,
+    Color::Cyan,
+    Color::Blue,
+    Color::Red,
+];
+
+// One year of history, as `wrapped` tells it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recap {
+    pub year: i32,
+    pub total: usize,
+    pub unique: usize,
+    pub top: Vec<(String, usize)>,
+    pub busiest_day: Option<(NaiveDate, usize)>,
+    // Hour of the day (0-23) with the most commands
+    pub peak_hour: Option<(u32, usize)>,
+    // Programs first run in the year, by how often they ran in it. None when
+    // the history starts that year, as everything would be new.
+    pub new_tools: Option<Vec<(String, usize)>>,
+    pub longest_streak: usize,
+    pub streak_from: Option<NaiveDate>,
+}
+
+impl Recap {
+    // None when nothing with a timestamp ran in `year`
+    pub fn new(entries: &[HistoryEntry], year: i32) -> Option<Recap> {
+        let in_year: Vec<HistoryEntry> = entries
+            .iter()
+            .filter(|e| local_day(e.timestamp).is_some_and(|day| day.year() == year))
+            .cloned()
+            .collect();
+        if in_year.is_empty() {
+            return None;
+        }
+
+        let mut days: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        let mut hours = [0; 24];
+        let mut commands: HashMap<&str, usize> = HashMap::new();
+        let mut programs: HashMap<&str, usize> = HashMap::new();
+        for entry in &in_year {
+            if let Some(time) = Local.timestamp_opt(entry.timestamp, 0).single() {
+                *days.entry(time.date_naive()).or_insert(0) += 1;
+                hours[time.hour() as usize] += 1;
+            }
+            *commands.entry(entry.command.as_str()).or_insert(0) += 1;
+            *programs.entry(program(&entry.command)).or_insert(0) += 1;
+        }
+
+        // A program is new if nothing before the year ran it
+        let before: Vec<&HistoryEntry> = entries
+            .iter()
+            .filter(|e| local_day(e.timestamp).is_some_and(|day| day.year() < year))
+            .collect();
+        let new_tools = (!before.is_empty()).then(|| {
+            let known: Vec<&str> = before.iter().map(|e| program(&e.command)).collect();
+            programs.retain(|program, _| !known.contains(program));
+            sorted_counts(programs)
+        });
+
+        let busiest_day = days
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(&day, &count)| (day, count));
+        let peak_hour = (0..24u32)
+            .max_by(|&a, &b| hours[a as usize].cmp(&hours[b as usize]).then(b.cmp(&a)))
+            .map(|hour| (hour, hours[hour as usize]));
+        let last_day = NaiveDate::from_ymd_opt(year, 12, 31).unwrap_or_default();
+        let streaks = streaks(&days, last_day);
+
+        Some(Recap {
+            year,
+            total: in_year.len(),
+            unique: commands.len(),
+            top: top_commands(&in_year, RECAP_LIST),
+            busiest_day,
+            peak_hour,
+            new_tools,
+            longest_streak: streaks.longest,
+            streak_from: streaks.longest_from,
+        })
+    }
+}
+
+// One screen of the recap: a title, a big number counted up to with a
+// caption, then a few lines shown one by one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub title: String,
+    pub figure: Option<usize>,
+    pub caption: String,
+    pub lines: Vec<String>,
+}
+
+fn date(day: NaiveDate) -> String {
+    format!(
+        "{} {} {}",
+        weekday_name(day.weekday().num_days_from_monday() as usize),
+        day.day(),
+        month_abbrev(day.month())
+    )
+}
+
+fn ranked(list: &[(String, usize)]) -> Vec<String> {
+    list.iter()
+        .enumerate()
+        .map(|(i, (name, count))| format!("{}. {}  ({})", i + 1, name, count))
+        .collect()
+}
+
+pub fn recap_pages(recap: &Recap) -> Vec<Page> {
+    let mut pages = vec![Page {
+        title: tr!("wrapped-intro", year = recap.year),
+        figure: Some(recap.total),
+        caption: tr!("wrapped-total"),
+        lines: vec![tr!("wrapped-unique", count = recap.unique)],
+    }];
+    if let Some((command, count)) = recap.top.first() {
+        pages.push(Page {
+            title: tr!("wrapped-top"),
+            figure: Some(*count),
+            caption: tr!("wrapped-top-runs", command = command.as_str()),
+            lines: ranked(&recap.top),
+        });
+    }
+    if let Some((day, count)) = recap.busiest_day {
+        pages.push(Page {
+            title: tr!("wrapped-busiest-day"),
+            figure: Some(count),
+            caption: tr!("wrapped-busiest-day-on", date = date(day)),
+            lines: Vec::new(),
+        });
+    }
+    if let Some((hour, count)) = recap.peak_hour {
+        pages.push(Page {
+            title: tr!("wrapped-peak-hour"),
+            figure: Some(count),
+            caption: tr!(
+                "wrapped-peak-hour-at",
+                from = format!("{:02}:00", hour),
+                to = format!("{:02}:00", (hour + 1) % 24)
+            ),
+            lines: Vec::new(),
+        });
+    }
+    pages.push(match &recap.new_tools {
+        Some(tools) => Page {
+            title: tr!("wrapped-new-tools"),
+            figure: Some(tools.len()),
+            caption: tr!("wrapped-new-tools-count"),
+            lines: ranked(&tools[..tools.len().min(RECAP_LIST)]),
+        },
+        None => Page {
+            title: tr!("wrapped-new-tools"),
+            figure: None,
+            caption: tr!("wrapped-new-tools-unknown", year = recap.year),
+            lines: Vec::new(),
+        },
+    });
+    let streak_lines = match recap.streak_from {
+        Some(from) if recap.longest_streak > 1 => {
+            let to = from + chrono::Days::new(recap.longest_streak as u64 - 1);
+            vec![tr!("wrapped-streak-span", from = date(from), to = date(to))]
+        }
+        _ => Vec::new(),
+    };
+    pages.push(Page {
+        title: tr!("wrapped-streak"),
+        figure: Some(recap.longest_streak),
+        caption: tr!("wrapped-streak-days"),
+        lines: streak_lines,
+    });
+    pages
+}
+
+// Every page one after another, for when stdout isn't a terminal
+pub fn recap_text(pages: &[Page]) -> String {
+    let mut out = String::new();
+    for page in pages {
+        let _ = writeln!(out, "{}", page.title);
+        match page.figure {
+            Some(figure) => {
+                let _ = writeln!(out, "  {} {}", figure, page.caption);
+            }
+            None => {
+                let _ = writeln!(out, "  {}", page.caption);
+            }
+        }
+        for line in &page.lines {
+            let _ = writeln!(out, "  {}", line);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// The figure `frame` frames into counting up to it, slowing towards the end
+pub fn counted(figure: usize, frame: usize) -> usize {
+    if frame >= COUNT_FRAMES {
+        return figure;
+    }
+    let progress = frame as f64 / COUNT_FRAMES as f64;
+    let eased = 1.0 - (1.0 - progress).powi(3);
+    (figure as f64 * eased).round() as usize
+}
+
+// The recap a page at a time, in the alternate screen
+pub fn browse_recap(pages: &[Page]) -> Result<()> {
+    let mut stdout = io::stdout();
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)
+        .and_then(|_| terminal::enable_raw_mode())
+        .context(ErrorKind::Terminal)?;
+    let result = recap_loop(&mut stdout, pages);
+    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)
+        .and_then(|_| terminal::disable_raw_mode())
+        .context(ErrorKind::Terminal)?;
+    result
+}
+
+fn write_centered(stdout: &mut io::Stdout, y: u16, text: &str, styled: String) -> Result<()> {
+    let width = screen_size()?.0;
+    let x = width.saturating_sub(text.width() as u16) / 2;
+    execute!(stdout, cursor::MoveTo(x, y))?;
+    write!(stdout, "{}", styled)?;
+    Ok(())
+}
+
+// Draws `page` with its number at `figure` and `lines` of its lines
+fn draw_page(
+    stdout: &mut io::Stdout,
+    pages: &[Page],
+    index: usize,
+    figure: Option<usize>,
+    lines: usize,
+) -> Result<()> {
+    let page = &pages[index];
+    let accent = ACCENTS[index % ACCENTS.len()];
+    let (width, height) = screen_size()?;
+    execute!(
+        stdout,
+        terminal::Clear(ClearType::All),
+        cursor::MoveTo(0, 0)
+    )?;
+    let top = height.saturating_sub(8 + page.lines.len() as u16) / 2;
+    write_centered(
+        stdout,
+        top,
+        &page.title,
+        page.title.as_str().with(accent).bold().to_string(),
+    )?;
+    if let Some(figure) = figure {
+        let figure = figure.to_string();
+        write_centered(
+            stdout,
+            top + 2,
+            &figure,
+            figure.as_str().with(accent).bold().to_string(),
+        )?;
+    }
+    write_centered(
+        stdout,
+        top + 3,
+        &page.caption,
+        page.caption.as_str().bold().to_string(),
+    )?;
+    // The lines line up on the left, as a block in the middle
+    let block = page
+        .lines
+        .iter()
+        .map(|line| line.width())
+        .max()
+        .unwrap_or(0);
+    let left = width.saturating_sub(block as u16) / 2;
+    for (i, line) in page.lines.iter().take(lines).enumerate() {
+        execute!(stdout, cursor::MoveTo(left, top + 5 + i as u16))?;
+        write!(stdout, "{}", line)?;
+    }
+
+    // A dot per page, the current one filled
+    let dots: Vec<&str> = (0..pages.len())
+        .map(|i| if i == index { "●" } else { "○" })
+        .collect();
+    let dots = dots.join(" ");
+    write_centered(
+        stdout,
+        height.saturating_sub(3),
+        &dots,
+        dots.as_str().with(accent).to_string(),
+    )?;
+    let controls = tr!("wrapped-controls");
+    write_centered(
+        stdout,
+        height.saturating_sub(2),
+        &controls,
+        controls.as_str().dark_grey().to_string(),
+    )?;
+    stdout.flush().context(ErrorKind::Terminal)?;
+    Ok(())
+}
+
+// Plays the page's animation, stopping early when a key comes in
+fn animate_page(stdout: &mut io::Stdout, pages: &[Page], index: usize) -> Result<()> {
+    let page = &pages[index];
+    if let Some(figure) = page.figure {
+        // No faster than ui.fps allows
+        let frame_time = FRAME.max(frames::frame_interval());
+        for frame in 0..COUNT_FRAMES {
+            draw_page(stdout, pages, index, Some(counted(figure, frame)), 0)
+                .context(ErrorKind::Terminal)?;
+            if frames::poll(frame_time)? {
+                return Ok(());
+            }
+        }
+    }
+    for lines in 0..page.lines.len() {
+        draw_page(stdout, pages, index, page.figure, lines).context(ErrorKind::Terminal)?;
+        if frames::poll(LINE_DELAY)? {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+fn recap_loop(stdout: &mut io::Stdout, pages: &[Page]) -> Result<()> {
+    let mut index = 0;
+    let mut animate = true;
+    loop {
+        if animate {
+            animate_page(stdout, pages, index)?;
+        }
+        let page = &pages[index];
+        draw_page(stdout, pages, index, page.figure, page.lines.len())
+            .context(ErrorKind::Terminal)?;
+
+        let Event::Key(KeyEvent {
+            code, modifiers, ..
+        }) = frames::next_event()?
+        else {
+            animate = false;
+            continue;
+        };
+        let next = match code {
+            KeyCode::Right | KeyCode::Char('l' | ' ') | KeyCode::Enter => {
+                (index + 1 < pages.len()).then_some(index + 1)
+            }
+            KeyCode::Left | KeyCode::Char('h') => index.checked_sub(1),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Char('?') => {
+                show_help(stdout, HelpView::Wrapped)?;
+                None
+            }
+            _ => None,
+        };
+        animate = next.is_some();
+        index = next.unwrap_or(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(year: i32, month: u32, day: u32, hour: u32, command: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: Local
+                .with_ymd_and_hms(year, month, day, hour, 0, 0)
+                .unwrap()
+                .timestamp(),
+            command: command.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn recaps_one_year() {
+        let entries = vec![
+            run(2023, 6, 1, 9, "git status"),
+            run(2024, 3, 4, 9, "git status"),
+            run(2024, 3, 5, 14, "cargo build"),
+            run(2024, 3, 5, 14, "cargo test"),
+            run(2024, 3, 6, 14, "git status"),
+            run(2024, 7, 1, 22, "rg todo"),
+            run(2025, 1, 1, 9, "ls"),
+        ];
+        let recap = Recap::new(&entries, 2024).unwrap();
+        assert_eq!((recap.total, recap.unique), (5, 4));
+        assert_eq!(recap.top[0], ("git status".to_string(), 2));
+        assert_eq!(
+            recap.busiest_day,
+            Some((NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(), 2))
+        );
+        assert_eq!(recap.peak_hour, Some((14, 3)));
+        assert_eq!(
+            recap.new_tools,
+            Some(vec![("cargo".to_string(), 2), ("rg".to_string(), 1)])
+        );
+        assert_eq!(recap.longest_streak, 3);
+        assert_eq!(recap.streak_from, NaiveDate::from_ymd_opt(2024, 3, 4));
+
+        // Nothing before 2023 to compare with
+        assert_eq!(Recap::new(&entries, 2023).unwrap().new_tools, None);
+        assert_eq!(Recap::new(&entries, 2022), None);
+
+        let pages = recap_pages(&recap);
+        assert_eq!(pages.len(), 6);
+        assert_eq!(pages[0].figure, Some(5));
+        assert!(recap_text(&pages).contains("2. cargo build  (1)"));
+
+        assert_eq!(counted(200, 0), 0);
+        assert!(counted(200, COUNT_FRAMES / 2) > 100);
+        assert_eq!(counted(200, COUNT_FRAMES), 200);
+    }
+}